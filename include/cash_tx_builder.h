@@ -0,0 +1,118 @@
+#ifndef CASH_TX_BUILDER_H
+#define CASH_TX_BUILDER_H
+
+/* Generated by cbindgen. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Succeeded
+ */
+#define CASH_TX_BUILDER_OK 0
+
+/**
+ * Null pointer or invalid argument
+ */
+#define CASH_TX_BUILDER_INVALID_ARGUMENT -1
+
+/**
+ * Operation failed
+ */
+#define CASH_TX_BUILDER_FAILED -2
+
+/**
+ * Opaque transaction builder handle
+ */
+typedef struct CashTxBuilder CashTxBuilder;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Construct new transaction builder
+ * # Returns
+ * * handle, must be released by `cash_tx_builder_free`
+ */
+CashTxBuilder *cash_tx_builder_new(void);
+
+/**
+ * Release transaction builder
+ * # Safety
+ * `builder` must be returned by `cash_tx_builder_new` (or null) and not used after this call.
+ */
+void cash_tx_builder_free(CashTxBuilder *builder);
+
+/**
+ * Add input
+ * # Arguments
+ * * `txid` - 32 bytes previous transaction hash in serialized (not reversed) order
+ * * `index` - previous txout-index
+ * * `value` - previous value
+ * * `script`, `script_len` - previous `scriptPubKey`
+ * * `sequence_no` - sequence number
+ * # Safety
+ * `builder` must be valid handle, `txid` must point to 32 bytes and `script` to `script_len` bytes.
+ */
+int32_t cash_tx_builder_add_input(CashTxBuilder *builder,
+                                  const uint8_t *txid,
+                                  uint32_t index,
+                                  uint64_t value,
+                                  const uint8_t *script,
+                                  uintptr_t script_len,
+                                  uint32_t sequence_no);
+
+/**
+ * Set `scriptSig` of input
+ * # Safety
+ * `builder` must be valid handle and `script` must point to `script_len` bytes.
+ */
+int32_t cash_tx_builder_set_script_sig(CashTxBuilder *builder,
+                                       uintptr_t index,
+                                       const uint8_t *script,
+                                       uintptr_t script_len);
+
+/**
+ * Add output
+ * # Safety
+ * `builder` must be valid handle and `script` must point to `script_len` bytes.
+ */
+int32_t cash_tx_builder_add_output(CashTxBuilder *builder,
+                                   uint64_t value,
+                                   const uint8_t *script,
+                                   uintptr_t script_len);
+
+/**
+ * Get BIP143 sighash of input with previous output
+ * # Arguments
+ * * `hash_type` - sighash type (`FORKID` is required)
+ * * `out` - buffer of 32 bytes to receive sighash
+ * # Safety
+ * `builder` must be valid handle and `out` must point to writable 32 bytes.
+ */
+int32_t cash_tx_builder_sighash(const CashTxBuilder *builder,
+                                uint32_t hash_type,
+                                uint32_t index,
+                                uint8_t *out);
+
+/**
+ * Serialize transaction
+ *
+ * Call with null `out` to get required size.
+ * # Arguments
+ * * `out`, `out_len` - buffer to receive serialized transaction
+ * # Returns
+ * * serialized size (nothing is written if it exceeds `out_len`), `0` if `builder` is null
+ * # Safety
+ * `builder` must be valid handle and `out` must point to writable `out_len` bytes.
+ */
+uintptr_t cash_tx_builder_serialize(const CashTxBuilder *builder, uint8_t *out, uintptr_t out_len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CASH_TX_BUILDER_H */
@@ -0,0 +1,353 @@
+//! Bitcoin Cash addresses
+//! [cashaddr spec](https://github.com/bitcoincashorg/bitcoincash.org/blob/master/spec/cashaddr.md)
+//!
+//! Only 160 bit P2PKH and P2SH addresses are supported (token-aware types are not).
+
+use std::fmt;
+use std::iter;
+use std::str::FromStr;
+use super::script::{self, p2pkh, p2sh, ScriptType, ToAddressScript};
+use super::base58;
+use super::network::Network;
+use super::error::{Error, Result};
+
+/// Characters of cashaddr payload
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Length of cashaddr checksum in 5 bit groups
+const CHECKSUM_LEN: usize = 8;
+
+/// Network encoded in address (test networks share `Testnet`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddressNetwork {
+    /// `bitcoincash:`
+    Mainnet,
+    /// `bchtest:`
+    Testnet,
+    /// `bchreg:` (legacy addresses are shared with testnet)
+    Regtest,
+}
+
+impl AddressNetwork {
+    const ALL: [AddressNetwork; 3] = [AddressNetwork::Mainnet, AddressNetwork::Testnet, AddressNetwork::Regtest];
+
+    /// Prefix of cashaddr
+    pub fn cashaddr_prefix(self) -> &'static str {
+        match self {
+            AddressNetwork::Mainnet => "bitcoincash",
+            AddressNetwork::Testnet => "bchtest",
+            AddressNetwork::Regtest => "bchreg",
+        }
+    }
+
+    /// Version bytes of legacy P2PKH and P2SH address
+    fn legacy_versions(self) -> (u8, u8) {
+        match self {
+            AddressNetwork::Mainnet => (0x00, 0x05),
+            AddressNetwork::Testnet | AddressNetwork::Regtest => (0x6f, 0xc4),
+        }
+    }
+}
+
+/// P2PKH or P2SH address
+/// # Example
+/// ```
+/// # use cash_tx_builder::address::{Address, AddressNetwork};
+/// let address: Address = "bitcoincash:qph5kuz78czq00e3t85ugpgd7xmer5kr7c5f6jdpwk".parse()?;
+/// assert_eq!(address.network(), AddressNetwork::Mainnet);
+/// assert!(address.is_p2pkh());
+/// assert_eq!(address.to_legacy(), "1B9UNtBfkkpgt8kVbwLN9ktE62QKnMbDzR");
+/// assert_eq!(address, "1B9UNtBfkkpgt8kVbwLN9ktE62QKnMbDzR".parse()?);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Address {
+    /// pay to public key hash
+    P2PKH(AddressNetwork, [u8; 20]),
+    /// pay to script hash
+    P2SH(AddressNetwork, [u8; 20]),
+}
+
+impl Address {
+    /// Parse cashaddr, prefix can be omitted
+    /// # Arguments
+    /// * `s` - cashaddr, e.g. `"bitcoincash:qph5kuz78czq00e3t85ugpgd7xmer5kr7c5f6jdpwk"`
+    pub fn from_cashaddr(s: &str) -> Result<Address> {
+        let invalid = || Error::InvalidAddress(s.to_string());
+        if s.bytes().any(|b| b.is_ascii_lowercase()) && s.bytes().any(|b| b.is_ascii_uppercase()) {
+            return Err(invalid());
+        }
+        let lower = s.to_ascii_lowercase();
+
+        let (networks, payload) = match lower.find(':') {
+            Some(i) => {
+                let network = AddressNetwork::ALL.iter().cloned()
+                    .find(|n| n.cashaddr_prefix() == &lower[..i])
+                    .ok_or_else(invalid)?;
+                (vec![network], &lower[i + 1..])
+            },
+            None => (AddressNetwork::ALL.to_vec(), &lower[..]),
+        };
+        let data = payload.bytes()
+            .map(|c| CHARSET.iter().position(|&d| d == c).map(|d| d as u8))
+            .collect::<Option<Vec<_>>>()
+            .filter(|data| data.len() > CHECKSUM_LEN)
+            .ok_or_else(invalid)?;
+
+        let network = networks.into_iter()
+            .find(|n| polymod(&[&expand_prefix(n.cashaddr_prefix())[..], &data].concat()) == 0)
+            .ok_or_else(invalid)?;
+        let payload = convert_bits(&data[..data.len() - CHECKSUM_LEN], 5, 8, false).ok_or_else(invalid)?;
+
+        // version byte: type in bits 3-6, size in bits 0-2 (`0` for 160 bit)
+        match payload.split_first() {
+            Some((0x00, hash)) => Address::from_hash(network, true, hash).ok_or_else(invalid),
+            Some((0x08, hash)) => Address::from_hash(network, false, hash).ok_or_else(invalid),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Parse legacy (base58) address
+    ///
+    /// Testnet and regtest share version bytes, so `AddressNetwork::Testnet` is returned for both.
+    /// # Arguments
+    /// * `s` - legacy address, e.g. `"1B9UNtBfkkpgt8kVbwLN9ktE62QKnMbDzR"`
+    pub fn from_legacy(s: &str) -> Result<Address> {
+        let invalid = || Error::InvalidAddress(s.to_string());
+        let v = base58::decode_check(s).ok_or_else(invalid)?;
+        let (&version, hash) = v.split_first().ok_or_else(invalid)?;
+
+        let (network, is_pkh) = [AddressNetwork::Mainnet, AddressNetwork::Testnet].iter()
+            .find_map(|&n| match n.legacy_versions() {
+                (pkh, _) if pkh == version => Some((n, true)),
+                (_, sh) if sh == version => Some((n, false)),
+                _ => None,
+            })
+            .ok_or_else(invalid)?;
+
+        Address::from_hash(network, is_pkh, hash).ok_or_else(invalid)
+    }
+
+    /// Get address of `scriptPubKey`
+    /// # Arguments
+    /// * `script` - `scriptPubKey`
+    /// * `network` - network
+    /// # Returns
+    /// * address (`None` if neither P2PKH nor P2SH)
+    pub fn from_script(script: &[u8], network: AddressNetwork) -> Option<Address> {
+        match script::script_type(script) {
+            ScriptType::PubKeyHash => Address::from_hash(network, true, &script[3..23]),
+            ScriptType::ScriptHash => Address::from_hash(network, false, &script[2..22]),
+            _ => None,
+        }
+    }
+
+    fn from_hash(network: AddressNetwork, is_pkh: bool, hash: &[u8]) -> Option<Address> {
+        if hash.len() != 20 {
+            return None;
+        }
+        let mut v = [0; 20];
+        v.copy_from_slice(hash);
+
+        Some(if is_pkh { Address::P2PKH(network, v) } else { Address::P2SH(network, v) })
+    }
+
+    /// Network
+    pub fn network(&self) -> AddressNetwork {
+        match self {
+            Address::P2PKH(network, _) | Address::P2SH(network, _) => *network,
+        }
+    }
+
+    /// `public key hash` or `script hash`
+    pub fn hash(&self) -> &[u8; 20] {
+        match self {
+            Address::P2PKH(_, hash) | Address::P2SH(_, hash) => hash,
+        }
+    }
+
+    /// `true` if P2PKH
+    pub fn is_p2pkh(&self) -> bool {
+        matches!(self, Address::P2PKH(..))
+    }
+
+    /// Build `scriptPubKey`
+    pub fn script_pub_key(&self) -> Result<Vec<u8>> {
+        match self {
+            Address::P2PKH(_, hash) => p2pkh::script_pub_key(hash),
+            Address::P2SH(_, hash) => p2sh::script_pub_key(hash),
+        }
+    }
+
+    /// Format as cashaddr with prefix
+    pub fn to_cashaddr(&self) -> String {
+        let prefix = self.network().cashaddr_prefix();
+        let version = if self.is_p2pkh() { 0x00 } else { 0x08 };
+        let mut data = convert_bits(&[&[version][..], self.hash()].concat(), 8, 5, true).unwrap_or_default();
+
+        let checksum = polymod(&[&expand_prefix(prefix)[..], &data, &[0; CHECKSUM_LEN]].concat());
+        data.extend((0..CHECKSUM_LEN).rev().map(|i| (checksum >> (5 * i)) as u8 & 0x1f));
+
+        let payload = data.iter().map(|&d| char::from(CHARSET[d as usize])).collect::<String>();
+        format!("{}:{}", prefix, payload)
+    }
+
+    /// Format as legacy (base58) address
+    pub fn to_legacy(&self) -> String {
+        let (pkh, sh) = self.network().legacy_versions();
+        let version = if self.is_p2pkh() { pkh } else { sh };
+
+        base58::encode_check(&[&[version][..], self.hash()].concat())
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    /// Parse cashaddr or legacy address
+    fn from_str(s: &str) -> Result<Address> {
+        Address::from_cashaddr(s).or_else(|_| Address::from_legacy(s))
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_cashaddr())
+    }
+}
+
+impl ToAddressScript for Address {
+    fn to_address_script<F>(&self, _parser: &F) -> Result<Vec<u8>>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        self.script_pub_key()
+    }
+
+    fn is_for_network(&self, network: Network) -> bool {
+        network.accepts(self.network())
+    }
+}
+
+/// Address parser for `TxBuilder::new`
+///
+/// Accepts cashaddr and legacy addresses of any network.
+/// # Example
+/// ```
+/// # use cash_tx_builder::{TxBuilder, Amount, address};
+/// let mut txb = TxBuilder::new(address::parse);
+/// txb.add_address_output(Amount::from_sat(1000), "bitcoincash:qph5kuz78czq00e3t85ugpgd7xmer5kr7c5f6jdpwk")?;
+/// txb.add_address_output(Amount::from_sat(1000), "1B9UNtBfkkpgt8kVbwLN9ktE62QKnMbDzR")?;
+/// assert!(txb.add_address_output(Amount::from_sat(1000), "1B9UNtBfkkpgt8kVbwLN9ktE62QKnMbDzS").is_err());
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn parse(address: &str) -> Option<(Vec<u8>, bool)> {
+    let address = address.parse::<Address>().ok()?;
+
+    Some((address.hash().to_vec(), address.is_p2pkh()))
+}
+
+/// Lower 5 bits of prefix characters followed by separator
+fn expand_prefix(prefix: &str) -> Vec<u8> {
+    prefix.bytes().map(|b| b & 0x1f).chain(iter::once(0)).collect()
+}
+
+/// BCH code checksum of cashaddr (`0` if valid)
+fn polymod(v: &[u8]) -> u64 {
+    const GENERATORS: [u64; 5] = [0x98_f2bc_8e61, 0x79_b76d_99e2, 0xf3_3e5f_b3c4, 0xae_2eab_e2a8, 0x1e_4f43_e470];
+
+    v.iter().fold(1, |c, &d| {
+        let c0 = c >> 35;
+        let c = ((c & 0x07_ffff_ffff) << 5) ^ u64::from(d);
+        GENERATORS.iter().enumerate()
+            .filter(|(i, _)| c0 & (1 << i) != 0)
+            .fold(c, |c, (_, g)| c ^ g)
+    }) ^ 1
+}
+
+/// Regroup bits (`None` if padding is invalid)
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let max = (1 << to) - 1;
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut v = vec![];
+    for &d in data {
+        acc = ((acc << from) | u32::from(d)) & ((1 << (from + to - 1)) - 1);
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            v.push(((acc >> bits) & max) as u8);
+        }
+    }
+
+    if pad {
+        if bits > 0 {
+            v.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || (acc << (to - bits)) & max != 0 {
+        return None;
+    }
+
+    Some(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cashaddr() -> Result<()> {
+        let hash = hex!("f5bf48b397dae70be82b3cca4793f8eb2b6cdac9");
+        let vectors = [
+            (Address::P2PKH(AddressNetwork::Mainnet, hash), "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2"),
+            (Address::P2SH(AddressNetwork::Testnet, hash), "bchtest:pr6m7j9njldwwzlg9v7v53unlr4jkmx6eyvwc0uz5t"),
+        ];
+        for (address, s) in &vectors {
+            assert_eq!(address.to_cashaddr(), *s);
+            assert_eq!(Address::from_cashaddr(s)?, *address);
+            assert_eq!(Address::from_cashaddr(&s.to_ascii_uppercase())?, *address);
+            assert_eq!(Address::from_cashaddr(&s[s.find(':').unwrap() + 1..])?, *address);
+        }
+
+        let regtest = Address::P2PKH(AddressNetwork::Regtest, hash);
+        assert_eq!(regtest.to_cashaddr().parse::<Address>()?, regtest);
+
+        for invalid in &[
+            "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg3",
+            "bchtest:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2",
+            "bitcoincash:Qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekg2",
+            "bitcoincash:qr6m7j9njldwwzlg9v7v53unlr4jkmx6eylep8ekgb",
+            "bitcoincash:",
+        ] {
+            assert!(Address::from_cashaddr(invalid).is_err(), "{}", invalid);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn legacy() -> Result<()> {
+        let p2pkh: Address = "bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a".parse()?;
+        assert_eq!(p2pkh.to_legacy(), "1BpEi6DfDAUFd7GtittLSdBeYJvcoaVggu");
+        let p2sh: Address = "bitcoincash:ppm2qsznhks23z7629mms6s4cwef74vcwvn0h829pq".parse()?;
+        assert_eq!(p2sh.to_legacy(), "3CWFddi6m4ndiGyKqzYvsFYagqDLPVMTzC");
+        assert_eq!(Address::from_legacy("3CWFddi6m4ndiGyKqzYvsFYagqDLPVMTzC")?, p2sh);
+
+        let testnet = Address::P2PKH(AddressNetwork::Testnet, *p2pkh.hash());
+        assert_eq!(Address::from_legacy(&testnet.to_legacy())?, testnet);
+        assert_eq!(Address::from_legacy(&Address::P2PKH(AddressNetwork::Regtest, *p2pkh.hash()).to_legacy())?, testnet);
+        assert!(Address::from_legacy("1BpEi6DfDAUFd7GtittLSdBeYJvcoaVggv").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn script() -> Result<()> {
+        let address: Address = "bitcoincash:pph5kuz78czq00e3t85ugpgd7xmer5kr7crv8a2z4t".parse()?;
+        let script = address.script_pub_key()?;
+        assert_eq!(script, hex!("a9146f4b705e3e0407bf3159e9c4050df1b791d2c3f687"));
+        assert_eq!(Address::from_script(&script, AddressNetwork::Mainnet), Some(address));
+        assert_eq!(Address::from_script(&[0x51], AddressNetwork::Mainnet), None);
+        assert_eq!(script::address_to_script(&address, &|_: &str| None)?, script);
+
+        Ok(())
+    }
+}
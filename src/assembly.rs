@@ -0,0 +1,419 @@
+//! Multi-party transaction assembly (CashFusion like)
+//!
+//! Every participant contributes inputs and outputs as components, optionally blinded
+//! by salted commitments until they are revealed. Components are ordered by their hash,
+//! so every participant assembles the same transaction and positions do not tell
+//! who contributed what. Each participant then signs only its own inputs.
+
+use std::collections::HashSet;
+
+use super::tx_builder::TxBuilder;
+use super::tx_builder::sig_hash::SigHashType;
+use super::types::VarInt;
+use super::types::transaction::{OutPoint, Output};
+use super::hash::{self, SigHash};
+use super::error::{Error, Result};
+#[cfg(feature = "sign")]
+use super::signer::Signer;
+
+/// Input contributed by a participant
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputComponent {
+    /// previous outpoint
+    pub outpoint: OutPoint,
+    /// previous output
+    pub prev_output: Output,
+    /// sequence no
+    pub sequence_no: u32,
+    /// `redeem script` of P2SH input
+    pub redeem_script: Option<Vec<u8>>,
+}
+
+/// Input or output contributed by a participant
+#[derive(Debug, Clone, PartialEq)]
+pub enum Component {
+    /// input with its previous output
+    Input(InputComponent),
+    /// output
+    Output(Output),
+}
+
+impl Component {
+    /// Serialize component (the preimage of its hash and commitment)
+    pub fn to_vec(&self) -> Vec<u8> {
+        match self {
+            Component::Input(input) => {
+                let redeem_script = input.redeem_script.as_deref().unwrap_or_default();
+                [
+                    &[0x00][..],
+                    &Vec::from(&input.outpoint),
+                    &input.sequence_no.to_le_bytes(),
+                    &input.prev_output.to_vec(),
+                    &Vec::from(VarInt::from(redeem_script.len() as u64)),
+                    redeem_script,
+                ].concat()
+            },
+            Component::Output(output) => [&[0x01][..], &output.to_vec()].concat(),
+        }
+    }
+
+    /// Hash which decides position in the assembled transaction
+    pub fn hash(&self) -> [u8; 32] {
+        to_array(&hash::sha256(&self.to_vec()))
+    }
+
+    /// Salted commitment of component
+    /// # Arguments
+    /// * `salt` - random salt kept secret until the component is revealed
+    pub fn commitment(&self, salt: &[u8; 32]) -> [u8; 32] {
+        to_array(&hash::sha256(&[&salt[..], &self.to_vec()].concat()))
+    }
+}
+
+fn to_array(hash: &[u8]) -> [u8; 32] {
+    let mut v = [0; 32];
+    v.copy_from_slice(hash);
+    v
+}
+
+/// Component revealed with the salt of its earlier commitment
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlindedComponent {
+    /// commitment submitted before reveal
+    pub commitment: [u8; 32],
+    /// salt of `commitment`
+    pub salt: [u8; 32],
+    /// revealed component
+    pub component: Component,
+}
+
+impl BlindedComponent {
+    /// Blind component with `salt`
+    /// # Arguments
+    /// * `component` - component
+    /// * `salt` - random salt
+    pub fn new(component: Component, salt: [u8; 32]) -> BlindedComponent {
+        BlindedComponent {
+            commitment: component.commitment(&salt),
+            salt,
+            component,
+        }
+    }
+
+    /// Check that the revealed component matches `commitment`
+    pub fn verify(&self) -> bool {
+        self.component.commitment(&self.salt) == self.commitment
+    }
+}
+
+/// Contributions of participants
+/// # Example
+/// ```
+/// # use cash_tx_builder::{Amount, sig_hash};
+/// # use cash_tx_builder::assembly::{Assembly, Component, InputComponent};
+/// # use cash_tx_builder::types::transaction::{OutPoint, Output};
+/// # use cash_tx_builder::script::p2pkh;
+/// let contribution = |i: u8| vec![
+///     Component::Input(InputComponent {
+///         outpoint: OutPoint { txid: [i; 32].into(), n: 0 },
+///         prev_output: Output::new(Amount::from_sat(10_000), &p2pkh::script_pub_key(&[i; 20]).unwrap()),
+///         sequence_no: 0xffff_ffff,
+///         redeem_script: None,
+///     }),
+///     Component::Output(Output::new(Amount::from_sat(9_000), &p2pkh::script_pub_key(&[i + 0x10; 20]).unwrap())),
+/// ];
+/// let mut assembly = Assembly::new();
+/// let alice = assembly.add_party(contribution(1));
+/// let bob = assembly.add_party(contribution(2));
+///
+/// let assembled = assembly.build(|_: &str| None)?;
+/// assert_eq!(assembled.txb.input_count(), 2);
+/// let alice_inputs = assembled.inputs_of(alice)?;
+/// assert_eq!(alice_inputs.len(), 1);
+/// assert_eq!(assembled.owner_of(alice_inputs[0]), Some(alice));
+/// assert_eq!(assembled.sighashes(bob, sig_hash::ALL | sig_hash::FORKID)?.len(), 1);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Assembly {
+    parties: Vec<Vec<Component>>,
+}
+
+impl Assembly {
+    /// Construct empty `Assembly`
+    pub fn new() -> Assembly {
+        Assembly::default()
+    }
+
+    /// Add contribution of a participant
+    /// # Arguments
+    /// * `components` - inputs and outputs of the participant
+    /// # Returns
+    /// * party index
+    pub fn add_party(&mut self, components: Vec<Component>) -> usize {
+        self.parties.push(components);
+        self.parties.len() - 1
+    }
+
+    /// Add contribution of a participant from revealed components
+    /// # Arguments
+    /// * `components` - revealed components of the participant
+    /// # Returns
+    /// * party index (error with position of the first component not matching its commitment)
+    pub fn add_blinded_party(&mut self, components: Vec<BlindedComponent>) -> Result<usize> {
+        if let Some(i) = components.iter().position(|c| !c.verify()) {
+            return Err(Error::InvalidCommitment(i));
+        }
+
+        Ok(self.add_party(components.into_iter().map(|c| c.component).collect()))
+    }
+
+    /// Number of participants
+    pub fn party_count(&self) -> usize {
+        self.parties.len()
+    }
+
+    /// Assemble transaction
+    ///
+    /// Inputs and outputs are ordered by component hash.
+    /// # Arguments
+    /// * `address_parser` - address parser of the builder
+    pub fn build<F>(&self, address_parser: F) -> Result<Assembled<F>>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        for (party, components) in self.parties.iter().enumerate() {
+            for component in components {
+                match component {
+                    Component::Input(input) => inputs.push((component.hash(), party, input)),
+                    Component::Output(output) => outputs.push((component.hash(), output)),
+                }
+            }
+        }
+        inputs.sort_by_key(|i| i.0);
+        outputs.sort_by_key(|o| o.0);
+
+        let mut outpoints = HashSet::new();
+        let mut txb = TxBuilder::new(address_parser);
+        let mut owners = Vec::with_capacity(inputs.len());
+        let mut redeem_scripts = Vec::with_capacity(inputs.len());
+        for (index, (_, party, input)) in inputs.into_iter().enumerate() {
+            let outpoint = &input.outpoint;
+            if !outpoints.insert(Vec::from(outpoint)) {
+                return Err(Error::DuplicateOutPoint(outpoint.txid, outpoint.n));
+            }
+            txb.add_input(outpoint.txid, outpoint.n, None, None, Some(input.sequence_no))?;
+            txb.set_prev_output(index, input.prev_output.clone())?;
+            if let Some(script) = &input.redeem_script {
+                txb.set_redeem_script(index, script)?;
+            }
+            owners.push(party);
+            redeem_scripts.push(input.redeem_script.clone());
+        }
+        for (_, output) in outputs {
+            match &output.token {
+                Some(token) => txb.add_token_output(output.value, &output.script, token.clone()),
+                None => txb.add_output(output.value, &output.script),
+            }
+        }
+
+        let mut signers = vec![Vec::new(); self.parties.len()];
+        for (index, &party) in owners.iter().enumerate() {
+            signers[party].push(index);
+        }
+
+        Ok(Assembled { txb, owners, signers, redeem_scripts })
+    }
+}
+
+/// Assembled transaction with the inputs each participant must sign
+#[derive(Debug)]
+pub struct Assembled<F>
+    where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+    /// builder of assembled transaction
+    pub txb: TxBuilder<F>,
+    owners: Vec<usize>,
+    signers: Vec<Vec<usize>>,
+    redeem_scripts: Vec<Option<Vec<u8>>>,
+}
+
+impl<F> Assembled<F>
+    where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+    /// Input indexes contributed by `party`
+    /// # Arguments
+    /// * `party` - party index
+    pub fn inputs_of(&self, party: usize) -> Result<&[usize]> {
+        self.signers.get(party).map(Vec::as_slice).ok_or(Error::InvalidIndex(party))
+    }
+
+    /// Party which contributed input
+    /// # Arguments
+    /// * `index` - input index
+    pub fn owner_of(&self, index: usize) -> Option<usize> {
+        self.owners.get(index).copied()
+    }
+
+    /// Sighashes `party` must sign
+    /// # Arguments
+    /// * `party` - party index
+    /// * `hash_type` - sighash type
+    /// # Returns
+    /// * input index and sighash of each input of `party`
+    pub fn sighashes(&self, party: usize, hash_type: SigHashType) -> Result<Vec<(usize, SigHash)>> {
+        self.inputs_of(party)?.iter().map(|&index| {
+            let sighash = match &self.redeem_scripts[index] {
+                Some(script) => self.txb.p2sh_sighash(index, hash_type, script)?,
+                None => self.txb.witness_v0_hash(hash_type, index as u32, None, None)?,
+            };
+            Ok((index, sighash))
+        }).collect()
+    }
+
+    /// Sign P2PKH inputs of `party`
+    ///
+    /// P2SH inputs of `party` are skipped,
+    /// set their `scriptSig` on `txb` with signatures of `sighashes`.
+    /// # Arguments
+    /// * `party` - party index
+    /// * `signer` - signer of the party
+    /// * `hash_type` - sighash type
+    #[cfg(feature = "sign")]
+    pub fn sign_party<S: Signer>(&mut self, party: usize, signer: &S, hash_type: SigHashType) -> Result<()> {
+        for index in self.inputs_of(party)?.to_vec() {
+            if self.redeem_scripts[index].is_none() {
+                self.txb.sign_input(index, signer, hash_type)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::types::Amount;
+    use super::super::tx_builder::sig_hash;
+    use super::super::script::{p2pkh, p2sh};
+
+    fn input(i: u8, prev_script: &[u8], redeem_script: Option<Vec<u8>>) -> Component {
+        Component::Input(InputComponent {
+            outpoint: OutPoint { txid: [i; 32].into(), n: u32::from(i) },
+            prev_output: Output::new(Amount::from_sat(u64::from(i) * 1000), prev_script),
+            sequence_no: 0xffff_fffe,
+            redeem_script,
+        })
+    }
+
+    #[test]
+    fn assemble() -> Result<()> {
+        let redeem_script = vec![0x51];
+        let p2sh_script = p2sh::script_pub_key(&hash::hash160(&redeem_script))?;
+        let p2pkh_script = p2pkh::script_pub_key(&[0x01; 20])?;
+
+        let mut assembly = Assembly::new();
+        let a = assembly.add_party(vec![
+            input(1, &p2pkh_script, None),
+            input(2, &p2pkh_script, None),
+            Component::Output(Output::new(Amount::from_sat(2500), &p2pkh_script)),
+        ]);
+        let b = assembly.add_blinded_party(vec![
+            BlindedComponent::new(input(3, &p2sh_script, Some(redeem_script.clone())), [0x01; 32]),
+            BlindedComponent::new(Component::Output(Output::new(Amount::from_sat(2500), &p2sh_script)), [0x02; 32]),
+        ])?;
+        assert_eq!(assembly.party_count(), 2);
+
+        let assembled = assembly.build(|_: &str| None)?;
+        assert_eq!(assembled.txb.input_count(), 3);
+        assert_eq!(assembled.txb.output_count(), 2);
+        assert_eq!(assembled.txb.fee()?, Amount::from_sat(1000));
+        assert_eq!(assembled.inputs_of(a)?.len(), 2);
+        assert_eq!(assembled.inputs_of(b)?.len(), 1);
+        for party in &[a, b] {
+            for &index in assembled.inputs_of(*party)? {
+                assert_eq!(assembled.owner_of(index), Some(*party));
+            }
+        }
+
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        let sighashes = assembled.sighashes(b, hash_type)?;
+        assert_eq!(sighashes.len(), 1);
+        let (index, sighash) = &sighashes[0];
+        assert_eq!(sighash, &assembled.txb.p2sh_sighash(*index, hash_type, &redeem_script)?);
+
+        // order does not depend on order of parties
+        let mut reversed = Assembly::new();
+        reversed.add_party(assembly.parties[1].clone());
+        reversed.add_party(assembly.parties[0].clone());
+        assert_eq!(reversed.build(|_: &str| None)?.txb.txid(), assembled.txb.txid());
+
+        match assembled.inputs_of(2) {
+            Err(Error::InvalidIndex(2)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_contribution() {
+        let script = [0x51];
+        let mut blinded = BlindedComponent::new(input(1, &script, None), [0x01; 32]);
+        blinded.salt = [0x02; 32];
+        let mut assembly = Assembly::new();
+        match assembly.add_blinded_party(vec![BlindedComponent::new(input(2, &script, None), [0x01; 32]), blinded]) {
+            Err(Error::InvalidCommitment(1)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        assembly.add_party(vec![input(1, &script, None)]);
+        assembly.add_party(vec![input(1, &script, None)]);
+        match assembly.build(|_: &str| None) {
+            Err(Error::DuplicateOutPoint(_, 1)) => {},
+            r => panic!("unexpected result: {:?}", r.map(|_| ())),
+        }
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn sign_party() -> Result<()> {
+        use std::convert::TryFrom;
+        use secp256k1::SecretKey;
+        use super::super::types::transaction::Transaction;
+
+        let keys = [SecretKey::from_slice(&[0x01; 32])?, SecretKey::from_slice(&[0x02; 32])?];
+        let redeem_script = vec![0x51];
+        let p2sh_script = p2sh::script_pub_key(&hash::hash160(&redeem_script))?;
+        let mut assembly = Assembly::new();
+        for (i, key) in keys.iter().enumerate() {
+            let prev_script = p2pkh::script_pub_key(&hash::hash160(&Signer::public_key(key)))?;
+            assembly.add_party(vec![
+                input(i as u8 + 1, &prev_script, None),
+                Component::Output(Output::new(Amount::from_sat(500), &prev_script)),
+            ]);
+        }
+        assembly.add_party(vec![
+            input(3, &p2sh_script, Some(redeem_script.clone())),
+            input(4, &p2pkh::script_pub_key(&hash::hash160(&Signer::public_key(&keys[0])))?, None),
+        ]);
+
+        let mut assembled = assembly.build(|_: &str| None)?;
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        for (party, key) in keys.iter().enumerate() {
+            assembled.sign_party(party, key, hash_type)?;
+        }
+        // P2SH input is left to the party
+        assembled.sign_party(2, &keys[0], hash_type)?;
+        let p2sh_index = *assembled.inputs_of(2)?.iter()
+            .find(|&&i| assembled.txb.prev_output(i).map(|o| &o.script) == Some(&p2sh_script))
+            .expect("P2SH input");
+        let tx = Transaction::try_from(&assembled.txb.to_vec()[..])?;
+        assert!(tx.inputs[p2sh_index].script.is_empty());
+        assembled.txb.set_script_sig(p2sh_index, &p2sh::script_sig(&[], &redeem_script)?)?;
+        for index in 0..assembled.txb.input_count() {
+            assembled.txb.validate_input(index)?;
+        }
+
+        Ok(())
+    }
+}
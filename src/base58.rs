@@ -0,0 +1,64 @@
+//! Base58Check used by extended keys and legacy addresses
+
+use super::hash;
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Encode `data` with 4 bytes checksum
+pub(crate) fn encode_check(data: &[u8]) -> String {
+    let v = [data, &hash::hash256(data)[..4]].concat();
+
+    // base 58 digits in little endian
+    let mut digits: Vec<u8> = vec![];
+    for &byte in &v {
+        let mut carry = u32::from(byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let zeros = v.iter().take_while(|&&b| b == 0).count();
+    std::iter::repeat_n(ALPHABET[0], zeros)
+        .chain(digits.iter().rev().map(|&d| ALPHABET[d as usize]))
+        .map(char::from)
+        .collect()
+}
+
+/// Decode string and verify checksum
+/// # Returns
+/// * data without checksum (`None` if invalid)
+pub(crate) fn decode_check(s: &str) -> Option<Vec<u8>> {
+    // bytes in little endian
+    let mut bytes: Vec<u8> = vec![];
+    for c in s.bytes() {
+        let mut carry = ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let zeros = s.bytes().take_while(|&c| c == ALPHABET[0]).count();
+    let v = std::iter::repeat_n(0, zeros).chain(bytes.into_iter().rev()).collect::<Vec<_>>();
+    if v.len() < 4 {
+        return None;
+    }
+
+    let (data, checksum) = v.split_at(v.len() - 4);
+    if hash::hash256(data)[..4] != *checksum {
+        return None;
+    }
+
+    Some(data.to_vec())
+}
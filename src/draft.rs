@@ -0,0 +1,227 @@
+//! Draft transaction for edit-before-send flows
+//!
+//! Inputs are locked once the draft is created, outputs stay editable.
+//! Every revision increments the sequence of all inputs,
+//! so a later revision replaces an earlier one which has not been mined.
+
+use std::convert::TryFrom;
+
+use super::tx_builder::TxBuilder;
+use super::script::ToAddressScript;
+use super::types::{Amount, FeeRate, Sequence};
+use super::types::transaction::Transaction;
+use super::error::{Error, Result};
+
+/// Draft of transaction with its revisions
+/// # Example
+/// ```
+/// # use cash_tx_builder::{TxBuilder, Amount, FeeRate};
+/// # use cash_tx_builder::draft::DraftTx;
+/// # use cash_tx_builder::script::p2pkh;
+/// let prev_script = p2pkh::script_pub_key(&[0x01; 20])?;
+/// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+/// let mut txb = TxBuilder::new(|_: &str| None);
+/// txb.add_input(prev_txid, 0, Some(100_000), Some(&prev_script), Some(0))?;
+/// txb.add_output(Amount::from_sat(99_000), &prev_script);
+///
+/// let mut draft = DraftTx::new(txb)?;
+/// draft.add_output(Amount::from_sat(10_000), &p2pkh::script_pub_key(&[0x02; 20])?);
+/// draft.bump_fee(0, FeeRate::from_sat_per_byte(2))?;
+/// assert_eq!(draft.revise()?, 1);
+/// assert_eq!(draft.revisions()[1].inputs[0].sequence_no, 1);
+///
+/// let tx = draft.finalize()?;
+/// assert_eq!(tx.inputs[0].sequence_no, 0xffff_ffff);
+/// assert_eq!(tx.outputs.len(), 2);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct DraftTx<F>
+    where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+    txb: TxBuilder<F>,
+    revisions: Vec<Transaction>,
+}
+
+impl<F> DraftTx<F>
+    where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+    /// Construct draft from builder, recording it as revision `0`
+    /// # Arguments
+    /// * `txb` - builder with all inputs (previous outputs are required to bump fee)
+    pub fn new(txb: TxBuilder<F>) -> Result<DraftTx<F>> {
+        let tx = Transaction::try_from(&txb.to_vec()[..])?;
+
+        Ok(DraftTx { txb, revisions: vec![tx] })
+    }
+
+    /// Builder of the current state, e.g. to sign it
+    pub fn builder(&self) -> &TxBuilder<F> {
+        &self.txb
+    }
+
+    /// Take builder of the current state
+    pub fn into_builder(self) -> TxBuilder<F> {
+        self.txb
+    }
+
+    /// Recorded revisions, the oldest first
+    pub fn revisions(&self) -> &[Transaction] {
+        &self.revisions
+    }
+
+    /// Add output
+    /// # Arguments
+    /// * `value` - value
+    /// * `script` - `scriptPubKey`
+    pub fn add_output(&mut self, value: Amount, script: &[u8]) {
+        self.txb.add_output(value, script);
+    }
+
+    /// Add output by bitcoin address
+    /// # Arguments
+    /// * `value` - value
+    /// * `address` - bitcoin address (string or `address::Address`)
+    pub fn add_address_output<A: ToAddressScript + ?Sized>(&mut self, value: Amount, address: &A) -> Result<()> {
+        self.txb.add_address_output(value, address)
+    }
+
+    /// Replace output
+    /// # Arguments
+    /// * `index` - output index
+    /// * `value` - value
+    /// * `script` - `scriptPubKey`
+    pub fn replace_output(&mut self, index: usize, value: Amount, script: &[u8]) -> Result<()> {
+        self.txb.replace_output(index, value, script)
+    }
+
+    /// Remove output
+    /// # Arguments
+    /// * `index` - output index
+    pub fn remove_output(&mut self, index: usize) -> Result<()> {
+        self.txb.remove_output(index)
+    }
+
+    /// Pay fee at `fee_rate` from change output
+    /// # Arguments
+    /// * `change_index` - index of change output
+    /// * `fee_rate` - fee rate (of signed transaction)
+    /// # Returns
+    /// * new fee
+    pub fn bump_fee(&mut self, change_index: usize, fee_rate: FeeRate) -> Result<Amount> {
+        let tx = Transaction::try_from(&self.txb.to_vec()[..])?;
+        let change = tx.outputs.get(change_index).ok_or(Error::InvalidIndex(change_index))?;
+        let size = self.txb.estimated_size_with_signatures()?;
+        let report = self.txb.balance_report();
+        if let Some(index) = report.inputs.iter().position(Option::is_none) {
+            return Err(Error::MissingPrevOutput(index));
+        }
+        let overflow = || Error::InvalidAmount(format!("sum of values exceeds {}", Amount::MAX_MONEY));
+        let input_value = report.input_total.ok_or_else(overflow)?;
+        let others = tx.outputs.iter().enumerate()
+            .filter(|(i, _)| *i != change_index)
+            .try_fold(Amount::ZERO, |sum, (_, o)| sum.checked_add(o.value))
+            .ok_or_else(overflow)?;
+        let available = input_value.checked_sub(others)
+            .ok_or(Error::InsufficientFunds(input_value.to_sat(), others.to_sat()))?;
+
+        let fee = fee_rate.fee(size);
+        if fee > available {
+            return Err(Error::InsufficientFunds(available.to_sat(), fee.to_sat()));
+        }
+        let value = available.checked_sub(fee).expect("fee is not greater than available");
+        self.txb.replace_output(change_index, value, &change.script)?;
+
+        Ok(fee)
+    }
+
+    /// Record the current state as a new revision, incrementing sequences of all inputs
+    /// # Returns
+    /// * revision number
+    pub fn revise(&mut self) -> Result<usize> {
+        let tx = Transaction::try_from(&self.txb.to_vec()[..])?;
+        if let Some(index) = tx.inputs.iter().position(|i| Sequence::from(i.sequence_no) == Sequence::FINAL) {
+            return Err(Error::FinalSequence(index));
+        }
+        for (index, input) in tx.inputs.iter().enumerate() {
+            self.txb.set_input_sequence(index, Sequence::from(input.sequence_no + 1))?;
+        }
+        self.revisions.push(Transaction::try_from(&self.txb.to_vec()[..])?);
+
+        Ok(self.revisions.len() - 1)
+    }
+
+    /// Set sequences of all inputs final and record the last revision
+    ///
+    /// The final revision can not be revised any more.
+    /// # Returns
+    /// * final transaction (unsigned, sign it with `builder`)
+    pub fn finalize(&mut self) -> Result<Transaction> {
+        for index in 0..self.txb.input_count() {
+            self.txb.set_input_sequence(index, Sequence::FINAL)?;
+        }
+        let tx = Transaction::try_from(&self.txb.to_vec()[..])?;
+        self.revisions.push(tx.clone());
+
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::script::p2pkh;
+
+    type Parser = fn(&str) -> Option<(Vec<u8>, bool)>;
+
+    fn draft() -> Result<DraftTx<Parser>> {
+        let prev_script = p2pkh::script_pub_key(&[0x01; 20])?;
+        let mut txb = TxBuilder::<Parser>::new(|_| None);
+        for i in 0..2 {
+            txb.add_input([0x01; 32].into(), i, Some(10_000), Some(&prev_script), Some(i))?;
+        }
+        txb.add_output(Amount::from_sat(20_000), &prev_script);
+
+        DraftTx::new(txb)
+    }
+
+    #[test]
+    fn revise() -> Result<()> {
+        let mut draft = draft()?;
+        assert_eq!(draft.bump_fee(0, FeeRate::MIN_RELAY)?, Amount::from_sat(draft.builder().estimated_size_with_signatures()? as u64));
+        let fee = draft.bump_fee(0, FeeRate::from_sat_per_byte(3))?;
+        assert_eq!(draft.builder().fee()?, fee);
+        assert_eq!(draft.revise()?, 1);
+        assert_eq!(draft.revise()?, 2);
+
+        let sequences = draft.revisions().iter()
+            .map(|tx| tx.inputs.iter().map(|i| i.sequence_no).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(sequences, vec![vec![0, 1], vec![1, 2], vec![2, 3]]);
+        assert_eq!(draft.revisions()[0].outputs[0].value, Amount::from_sat(20_000));
+        assert_eq!(draft.revisions()[1].outputs[0].value, Amount::from_sat(20_000 - fee.to_sat()));
+
+        draft.finalize()?;
+        match draft.revise() {
+            Err(Error::FinalSequence(0)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(draft.revisions().len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn bump_fee() -> Result<()> {
+        let mut draft = draft()?;
+        match draft.bump_fee(1, FeeRate::MIN_RELAY) {
+            Err(Error::InvalidIndex(1)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match draft.bump_fee(0, FeeRate::from_sat_per_byte(1000)) {
+            Err(Error::InsufficientFunds(20_000, _)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(draft.builder().fee()?, Amount::ZERO);
+
+        Ok(())
+    }
+}
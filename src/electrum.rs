@@ -0,0 +1,212 @@
+//! Electrum (Fulcrum) server client
+//! [spec](https://electrum-cash-protocol.readthedocs.io/en/latest/protocol-methods.html)
+//!
+//! Only plain TCP connections are supported.
+
+use std::fmt::Display;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use super::tx_builder::TxBuilder;
+use super::types::Txid;
+use super::types::transaction::Transaction;
+use super::hash;
+use super::error::{Error, Result};
+
+fn electrum_error<E: Display>(err: E) -> Error {
+    Error::ElectrumError(err.to_string())
+}
+
+/// Get script hash used by Electrum protocol to look up `scriptPubKey`
+/// # Arguments
+/// * `script` - `scriptPubKey`
+/// # Returns
+/// * reversed sha256 of `script` in hex
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::electrum::script_hash;
+/// let script = hex!("76a91432b57f34861bcbe33a701be9ac3a50288fbc0a3d88ac");
+/// assert_eq!(script_hash(&script).len(), 64);
+/// ```
+pub fn script_hash(script: &[u8]) -> String {
+    let mut hash = hash::sha256(script);
+    hash.reverse();
+    hex::encode(hash)
+}
+
+/// Unspent output returned by `blockchain.scripthash.listunspent`
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Utxo {
+    pub tx_hash: Txid,
+    pub tx_pos: u32,
+    /// `0` (or negative) if unconfirmed
+    pub height: i64,
+    pub value: u64,
+}
+
+/// Electrum client over a single connection
+#[derive(Debug)]
+pub struct Client {
+    stream: BufReader<TcpStream>,
+    next_id: u64,
+}
+
+impl Client {
+    /// Connect to server
+    /// # Arguments
+    /// * `addr` - server address, e.g. `"fulcrum.example.com:50001"`
+    pub async fn connect<A: ToSocketAddrs>(addr: A) -> Result<Client> {
+        let stream = TcpStream::connect(addr).await.map_err(electrum_error)?;
+
+        Ok(Client {
+            stream: BufReader::new(stream),
+            next_id: 0,
+        })
+    }
+
+    /// Send JSON-RPC request and wait for its response
+    /// # Arguments
+    /// * `method` - method name
+    /// * `params` - parameters
+    /// # Returns
+    /// * `result` of response
+    pub async fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let mut line = serde_json::to_vec(&request).map_err(electrum_error)?;
+        line.push(b'\n');
+        self.stream.write_all(&line).await.map_err(electrum_error)?;
+
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await.map_err(electrum_error)? == 0 {
+                return Err(Error::ElectrumError("connection closed".to_string()));
+            }
+            let mut response: Value = serde_json::from_str(&line).map_err(electrum_error)?;
+
+            // skip subscription notifications and stale responses
+            if response["id"] != id {
+                continue;
+            }
+            if !response["error"].is_null() {
+                return Err(Error::ElectrumError(response["error"].to_string()));
+            }
+
+            return Ok(response["result"].take());
+        }
+    }
+
+    /// List unspent outputs of `scriptPubKey`
+    /// # Arguments
+    /// * `script` - `scriptPubKey`
+    pub async fn list_unspent(&mut self, script: &[u8]) -> Result<Vec<Utxo>> {
+        let result = self.request("blockchain.scripthash.listunspent", json!([script_hash(script)])).await?;
+
+        serde_json::from_value(result).map_err(electrum_error)
+    }
+
+    /// Get transaction
+    /// # Arguments
+    /// * `txid` - txid
+    pub async fn get_transaction(&mut self, txid: Txid) -> Result<Transaction> {
+        let result = self.request("blockchain.transaction.get", json!([txid])).await?;
+        let hex = result.as_str().ok_or_else(|| electrum_error(&result))?;
+
+        Transaction::from_hex(hex)
+    }
+
+    /// Broadcast transaction
+    /// # Arguments
+    /// * `tx` - signed transaction
+    /// # Returns
+    /// * txid accepted by server
+    pub async fn broadcast(&mut self, tx: &Transaction) -> Result<Txid> {
+        let result = self.request("blockchain.transaction.broadcast", json!([tx.to_hex()])).await?;
+        let txid = result.as_str().ok_or_else(|| electrum_error(&result))?;
+
+        txid.parse()
+    }
+
+    /// Add all unspent outputs of `scriptPubKey` as inputs
+    ///
+    /// Previous transactions are fetched so that previous outputs
+    /// (including token data) are known to `txb`.
+    /// # Arguments
+    /// * `txb` - transaction builder
+    /// * `script` - `scriptPubKey`
+    /// # Returns
+    /// * total value of added inputs
+    pub async fn add_inputs<F>(&mut self, txb: &mut TxBuilder<F>, script: &[u8]) -> Result<u64>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        let mut total = 0;
+        for utxo in self.list_unspent(script).await? {
+            let prev_tx = self.get_transaction(utxo.tx_hash).await?;
+            let prev_output = prev_tx.outputs.get(utxo.tx_pos as usize)
+                .filter(|o| o.script == script && o.value.to_sat() == utxo.value)
+                .ok_or_else(|| Error::ElectrumError(format!("unexpected output: {}:{}", utxo.tx_hash, utxo.tx_pos)))?;
+
+            txb.add_input(utxo.tx_hash, utxo.tx_pos, None, None, None)?;
+            txb.set_prev_output(txb.input_count() - 1, prev_output.clone())?;
+            total += utxo.value;
+        }
+
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use tokio::net::TcpListener;
+    use super::*;
+    use super::super::types::Amount;
+
+    const RAW_TX: &str = "0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000";
+
+    async fn serve(listener: TcpListener) {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        let txid = Transaction::from_hex(RAW_TX).unwrap().txid();
+
+        let mut line = String::new();
+        while stream.read_line(&mut line).await.unwrap() > 0 {
+            let request: Value = serde_json::from_str(&line).unwrap();
+            let result = match request["method"].as_str().unwrap() {
+                "blockchain.scripthash.listunspent" => json!([{ "tx_hash": txid, "tx_pos": 1, "height": 100, "value": 10000 }]),
+                "blockchain.transaction.get" => json!(RAW_TX),
+                "blockchain.transaction.broadcast" => json!(txid),
+                _ => Value::Null,
+            };
+            let notification = json!({ "jsonrpc": "2.0", "method": "blockchain.headers.subscribe", "params": [] });
+            let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+            let v = format!("{}\n{}\n", notification, response);
+            stream.write_all(v.as_bytes()).await.unwrap();
+            line.clear();
+        }
+    }
+
+    #[tokio::test]
+    async fn client() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.map_err(electrum_error)?;
+        let addr = listener.local_addr().map_err(electrum_error)?;
+        tokio::spawn(serve(listener));
+
+        let tx = Transaction::try_from(&hex::decode(RAW_TX).unwrap()[..])?;
+        let script = tx.outputs[1].script.clone();
+
+        let mut client = Client::connect(addr).await?;
+        let mut txb = TxBuilder::new(|_: &str| None);
+        assert_eq!(client.add_inputs(&mut txb, &script).await?, 10000);
+        assert_eq!(txb.input_count(), 1);
+        txb.add_output(Amount::from_sat(9000), &script);
+        assert_eq!(txb.fee()?, Amount::from_sat(1000));
+
+        assert_eq!(client.broadcast(&tx).await?, tx.txid());
+
+        Ok(())
+    }
+}
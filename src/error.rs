@@ -1,47 +1,405 @@
 use std::result;
 
-use failure::Fail;
-use super::types;
+use thiserror::Error;
+use super::interpreter;
+use super::network::Network;
+use super::types::Txid;
 
 /// Alias of `Result` used by cash_tx_builder.
 pub type Result<T> = result::Result<T, Error>;
 
 /// Errors
-#[derive(Debug, Fail)]
+#[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
     /// Invalid opcode.
     /// # Arguments
     /// * opcode
-    #[fail(display = "Invalid opcode: {}", 0)]
+    #[error("Invalid opcode: {0}")]
     InvalidOpCode(u8),
 
+    /// Script ends in the middle of push.
+    #[error("Unexpected end of script at {offset}: {needed} bytes needed, {available} available")]
+    UnexpectedEndOfScript {
+        /// bytes needed by the push
+        needed: usize,
+        /// bytes left in script
+        available: usize,
+        /// byte offset of the push
+        offset: usize,
+    },
+
+    /// Unknown opcode name.
+    /// # Arguments
+    /// * name
+    #[error("Unknown opcode: {0}")]
+    UnknownOpCode(String),
+
     /// Invalid input/output index.
     /// # Arguments
     /// * index
-    #[fail(display = "Invalid index: {}", 0)]
+    #[error("Invalid index: {0}")]
     InvalidIndex(usize),
 
     /// Invalid length script.
     /// # Arguments
     /// * data length
-    #[fail(display = "Invalid lenght data: {}", 0)]
+    #[error("Invalid lenght data: {0}")]
     InvalidLengthData(usize),
 
+    /// Script exceeds size limit.
+    /// # Arguments
+    /// * script size
+    /// * limit
+    #[error("Script is too large: {0} > {1}")]
+    ScriptTooLarge(usize, usize),
+
+    /// Transaction exceeds size limit.
+    /// # Arguments
+    /// * estimated size
+    /// * limit
+    #[error("Transaction is too large: {0} > {1}")]
+    TxTooLarge(usize, usize),
+
     /// Invalid bitcoin address.
     /// # Arguments
     /// * address
-    #[fail(display = "Invalid address: {}", 0)]
+    #[error("Invalid address: {0}")]
     InvalidAddress(String),
 
-    /// type error
+    /// Invalid multisig parameters.
+    /// # Arguments
+    /// * required signatures
+    /// * public keys
+    #[error("Invalid multisig: {0} of {1}")]
+    InvalidMultisig(usize, usize),
+
+    /// Invalid script number.
+    /// # Arguments
+    /// * encoded number
+    #[error("Invalid script number: {0:?}")]
+    InvalidScriptNum(Vec<u8>),
+
+    /// Invalid script ASM.
+    /// # Arguments
+    /// * invalid token
+    #[error("Invalid ASM: {0}")]
+    InvalidAsm(String),
+
+    /// Invalid SLP message.
+    /// # Arguments
+    /// * invalid field
+    #[error("Invalid SLP message: {0}")]
+    InvalidSlp(String),
+
+    /// Invalid memo.cash message.
+    /// # Arguments
+    /// * invalid field
+    #[error("Invalid memo message: {0}")]
+    InvalidMemo(String),
+
+    /// Invalid partially signed transaction.
+    /// # Arguments
+    /// * reason
+    #[error("Invalid partially signed transaction: {0}")]
+    InvalidPst(String),
+
+    /// Input does not have enough signatures.
+    /// # Arguments
+    /// * input index
+    #[error("Incomplete input: {0}")]
+    IncompleteInput(usize),
+
+    /// Previous output of the input is unknown.
+    /// # Arguments
+    /// * input index
+    #[error("Missing previous output: {0}")]
+    MissingPrevOutput(usize),
+
+    /// Input spends null outpoint, which only coinbase can spend.
+    /// # Arguments
+    /// * input index
+    #[error("Null outpoint: {0}")]
+    NullOutPoint(usize),
+
+    /// `redeem script` of P2SH input is unknown.
+    /// # Arguments
+    /// * input index
+    #[error("Missing redeem script: {0}")]
+    MissingRedeemScript(usize),
+
+    /// `redeem script` does not match P2SH `scriptPubKey` of previous output.
+    /// # Arguments
+    /// * input index
+    #[error("Invalid redeem script: {0}")]
+    InvalidRedeemScript(usize),
+
+    /// Previous `scriptPubKey` of input cannot be signed by single signature,
+    /// e.g. P2SH or multisig.
+    /// # Arguments
+    /// * input index
+    #[error("Unsupported script: {0}")]
+    UnsupportedScript(usize),
+
+    /// Sighash type is not allowed.
+    /// # Arguments
+    /// * sighash type
+    #[error("Invalid sighash type: {0:#x}")]
+    InvalidSigHashType(u32),
+
+    /// Signature is not strict DER with low S (nor Schnorr).
+    /// # Arguments
+    /// * signature
+    #[error("Invalid signature: {0:?}")]
+    InvalidSignature(Vec<u8>),
+
+    /// Less signatures than required.
+    /// # Arguments
+    /// * number of signatures
+    /// * required number
+    #[error("Missing signatures: {0} of {1}")]
+    MissingSignatures(usize, usize),
+
+    /// Public key is not SEC1 encoded.
+    /// # Arguments
+    /// * public key
+    #[error("Invalid public key: {0:?}")]
+    InvalidPublicKey(Vec<u8>),
+
+    /// Secret does not match hash lock.
+    /// # Arguments
+    /// * secret
+    #[error("Invalid secret: {0:?}")]
+    InvalidSecret(Vec<u8>),
+
+    /// Inputs do not cover outputs and fee.
+    /// # Arguments
+    /// * available value
+    /// * required value
+    #[error("Insufficient funds: available {0}, required {1}")]
+    InsufficientFunds(u64, u64),
+
+    /// Null data exceeds standard size.
+    /// # Arguments
+    /// * data size
+    #[error("Non-standard null data size: {0}")]
+    NonStandardDataSize(usize),
+
+    /// Address belongs to other network.
+    /// # Arguments
+    /// * network of transaction
+    #[error("Address is not for network: {0}")]
+    NetworkMismatch(Network),
+
+    /// Unknown network name.
+    /// # Arguments
+    /// * name
+    #[error("Invalid network: {0}")]
+    InvalidNetwork(String),
+
+    /// Amount is malformed or out of range.
+    /// # Arguments
+    /// * amount
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+
+    /// Fee rate is malformed or out of range.
+    /// # Arguments
+    /// * fee rate
+    #[error("Invalid fee rate: {0}")]
+    InvalidFeeRate(String),
+
+    /// UTXO provider or transport error.
+    /// # Arguments
+    /// * reason
+    #[error("Provider error: {0}")]
+    ProviderError(String),
+
+    /// Transaction parse error
+    /// # Arguments
+    /// * error index
+    /// * raw transaction
+    #[error("Transaction parse error: at {0}, {1:?}")]
+    TxParseError(usize, Vec<u8>),
+
+    /// Block parse error
+    /// # Arguments
+    /// * error index
+    #[error("Block parse error: at {0}")]
+    BlockParseError(usize),
+
+    /// Convert error
+    #[error("VarInt convert error")]
+    TryFromVarIntError,
+
+    /// Invalid token data
+    /// # Arguments
+    /// * serialized token prefix
+    #[error("Invalid token data: {0:?}")]
+    InvalidTokenData(Vec<u8>),
+
+    /// Token genesis or minting breaks CashTokens rules.
+    /// # Arguments
+    /// * reason
+    #[error("Invalid token issuance: {0}")]
+    InvalidTokenIssuance(String),
+
+    /// Invalid length of 256 bit value
+    /// # Arguments
+    /// * length in bytes
+    #[error("Invalid uint256 length: {0}")]
+    InvalidUint256Length(usize),
+
+    /// Invalid txid
+    /// # Arguments
+    /// * txid string
+    #[error("Invalid txid: {0}")]
+    InvalidTxid(String),
+
+    /// Invalid outpoint
+    /// # Arguments
+    /// * outpoint string
+    #[error("Invalid outpoint: {0}")]
+    InvalidOutPoint(String),
+
+    /// Invalid payment URI
+    /// # Arguments
+    /// * URI string
+    #[error("Invalid URI: {0}")]
+    InvalidUri(String),
+
+    /// hex library's error
+    /// # Arguments
+    /// * error
+    #[error("hex error: {0}")]
+    HexError(#[source] hex::FromHexError),
+
+    /// I/O error
+    /// # Arguments
+    /// * error
+    #[error("I/O error: {0}")]
+    IoError(#[source] std::io::Error),
+
+    /// script evaluation error
+    /// # Arguments
+    /// * error
+    #[error("script error: {0}")]
+    ScriptError(#[source] interpreter::ScriptError),
+
+    /// Outpoint spent twice.
+    /// # Arguments
+    /// * txid
+    /// * output index
+    #[error("Duplicate outpoint: {0}:{1}")]
+    DuplicateOutPoint(Txid, u32),
+
+    /// Sequence of input is final and can not be incremented.
+    /// # Arguments
+    /// * input index
+    #[error("Sequence is final: {0}")]
+    FinalSequence(usize),
+
+    /// Revealed component does not match its commitment.
+    /// # Arguments
+    /// * component index
+    #[error("Invalid commitment: {0}")]
+    InvalidCommitment(usize),
+
+    /// Error of an input.
+    /// # Arguments
+    /// * input index
+    /// * error
+    #[error("input {0}: {1}")]
+    InputError(usize, #[source] Box<Error>),
+
+    /// Errors of rows in batch.
+    /// # Arguments
+    /// * row index and error of each failed row
+    #[error("batch error: {}", format_rows(.0))]
+    BatchError(Vec<(usize, Error)>),
+
+    /// secp256k1 library's error
     /// # Arguments
     /// * error
-    #[fail(display = "type error: {}", 0)]
-    TypeError(types::TypeError),
+    #[cfg(feature = "sign")]
+    #[error("secp256k1 error: {0}")]
+    Secp256k1Error(#[source] secp256k1::Error),
+
+    /// Electrum server or transport error.
+    /// # Arguments
+    /// * reason
+    #[cfg(feature = "electrum")]
+    #[error("Electrum error: {0}")]
+    ElectrumError(String),
+
+    /// Node JSON-RPC or transport error.
+    /// # Arguments
+    /// * reason
+    #[cfg(feature = "rpc")]
+    #[error("RPC error: {0}")]
+    RpcError(String),
+
+    /// Invalid extended key or derivation.
+    /// # Arguments
+    /// * reason
+    #[cfg(feature = "hd")]
+    #[error("Invalid HD key: {0}")]
+    InvalidHdKey(String),
+
+    /// Invalid derivation path.
+    /// # Arguments
+    /// * path
+    #[cfg(feature = "hd")]
+    #[error("Invalid derivation path: {0}")]
+    InvalidDerivationPath(String),
+
+    /// Invalid contract template or argument.
+    /// # Arguments
+    /// * reason
+    #[cfg(feature = "template")]
+    #[error("Invalid template: {0}")]
+    InvalidTemplate(String),
+
+    /// Invalid payment request or payment ACK.
+    /// # Arguments
+    /// * reason
+    #[cfg(feature = "payment-protocol")]
+    #[error("Invalid payment request: {0}")]
+    InvalidPaymentRequest(String),
+
+    /// Invalid BIP39 mnemonic.
+    /// # Arguments
+    /// * reason
+    #[cfg(feature = "hd")]
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+}
+
+fn format_rows(rows: &[(usize, Error)]) -> String {
+    rows.iter().map(|(i, e)| format!("row {}: {}", i, e)).collect::<Vec<_>>().join(", ")
+}
+
+impl From<hex::FromHexError> for Error {
+    fn from(err: hex::FromHexError) -> Error {
+        Error::HexError(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Error {
+        Error::IoError(err)
+    }
+}
+
+impl From<interpreter::ScriptError> for Error {
+    fn from(err: interpreter::ScriptError) -> Error {
+        Error::ScriptError(err)
+    }
 }
 
-impl From<types::TypeError> for Error {
-    fn from(err: types::TypeError) -> Error {
-        Error::TypeError(err)
+#[cfg(feature = "sign")]
+impl From<secp256k1::Error> for Error {
+    fn from(err: secp256k1::Error) -> Error {
+        Error::Secp256k1Error(err)
     }
 }
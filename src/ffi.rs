@@ -0,0 +1,241 @@
+//! C ABI for mobile wallets
+//!
+//! Build static or dynamic library with
+//! `cargo rustc --release --features ffi --crate-type staticlib` (or `cdylib`),
+//! and include `include/cash_tx_builder.h` (generated by `cbindgen`).
+//!
+//! Functions returning `i32` return `0` on success and negative value on error.
+//! Addresses are not parsed, use `scriptPubKey` instead.
+
+use std::slice;
+use super::tx_builder::TxBuilder;
+use super::tx_builder::sig_hash::SigHashType;
+use super::types::{Txid, Amount};
+
+/// Succeeded
+pub const CASH_TX_BUILDER_OK: i32 = 0;
+/// Null pointer or invalid argument
+pub const CASH_TX_BUILDER_INVALID_ARGUMENT: i32 = -1;
+/// Operation failed
+pub const CASH_TX_BUILDER_FAILED: i32 = -2;
+
+type AddressParser = fn(&str) -> Option<(Vec<u8>, bool)>;
+
+fn no_address(_: &str) -> Option<(Vec<u8>, bool)> {
+    None
+}
+
+/// Opaque transaction builder handle
+#[derive(Debug)]
+pub struct CashTxBuilder {
+    inner: TxBuilder<AddressParser>,
+}
+
+/// Borrow `len` bytes at `ptr` (empty if `len` is `0`)
+unsafe fn bytes<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (ptr.is_null(), len) {
+        (_, 0) => Some(&[]),
+        (true, _) => None,
+        (false, _) => Some(slice::from_raw_parts(ptr, len)),
+    }
+}
+
+fn status<T, E>(r: Result<T, E>) -> i32 {
+    match r {
+        Ok(_) => CASH_TX_BUILDER_OK,
+        Err(_) => CASH_TX_BUILDER_FAILED,
+    }
+}
+
+/// Construct new transaction builder
+/// # Returns
+/// * handle, must be released by `cash_tx_builder_free`
+#[no_mangle]
+pub extern "C" fn cash_tx_builder_new() -> *mut CashTxBuilder {
+    Box::into_raw(Box::new(CashTxBuilder {
+        inner: TxBuilder::new(no_address as AddressParser),
+    }))
+}
+
+/// Release transaction builder
+/// # Safety
+/// `builder` must be returned by `cash_tx_builder_new` (or null) and not used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn cash_tx_builder_free(builder: *mut CashTxBuilder) {
+    if !builder.is_null() {
+        drop(Box::from_raw(builder));
+    }
+}
+
+/// Add input
+/// # Arguments
+/// * `txid` - 32 bytes previous transaction hash in serialized (not reversed) order
+/// * `index` - previous txout-index
+/// * `value` - previous value
+/// * `script`, `script_len` - previous `scriptPubKey`
+/// * `sequence_no` - sequence number
+/// # Safety
+/// `builder` must be valid handle, `txid` must point to 32 bytes and `script` to `script_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cash_tx_builder_add_input(
+    builder: *mut CashTxBuilder,
+    txid: *const u8,
+    index: u32,
+    value: u64,
+    script: *const u8,
+    script_len: usize,
+    sequence_no: u32,
+) -> i32 {
+    let (builder, txid, script) = match (builder.as_mut(), bytes(txid, 32), bytes(script, script_len)) {
+        (Some(b), Some(t), Some(s)) if !t.is_empty() => (b, t, s),
+        _ => return CASH_TX_BUILDER_INVALID_ARGUMENT,
+    };
+    let mut hash = [0; 32];
+    hash.copy_from_slice(txid);
+
+    status(builder.inner.add_input(Txid::from(hash), index, Some(value), Some(script), Some(sequence_no)))
+}
+
+/// Set `scriptSig` of input
+/// # Safety
+/// `builder` must be valid handle and `script` must point to `script_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cash_tx_builder_set_script_sig(
+    builder: *mut CashTxBuilder,
+    index: usize,
+    script: *const u8,
+    script_len: usize,
+) -> i32 {
+    let (builder, script) = match (builder.as_mut(), bytes(script, script_len)) {
+        (Some(b), Some(s)) => (b, s),
+        _ => return CASH_TX_BUILDER_INVALID_ARGUMENT,
+    };
+
+    status(builder.inner.set_script_sig(index, script))
+}
+
+/// Add output
+/// # Safety
+/// `builder` must be valid handle and `script` must point to `script_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cash_tx_builder_add_output(
+    builder: *mut CashTxBuilder,
+    value: u64,
+    script: *const u8,
+    script_len: usize,
+) -> i32 {
+    let (builder, script) = match (builder.as_mut(), bytes(script, script_len)) {
+        (Some(b), Some(s)) => (b, s),
+        _ => return CASH_TX_BUILDER_INVALID_ARGUMENT,
+    };
+    builder.inner.add_output(Amount::from_sat(value), script);
+
+    CASH_TX_BUILDER_OK
+}
+
+/// Get BIP143 sighash of input with previous output
+/// # Arguments
+/// * `hash_type` - sighash type (`FORKID` is required)
+/// * `out` - buffer of 32 bytes to receive sighash
+/// # Safety
+/// `builder` must be valid handle and `out` must point to writable 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cash_tx_builder_sighash(
+    builder: *const CashTxBuilder,
+    hash_type: u32,
+    index: u32,
+    out: *mut u8,
+) -> i32 {
+    let builder = match builder.as_ref() {
+        Some(b) if !out.is_null() => b,
+        _ => return CASH_TX_BUILDER_INVALID_ARGUMENT,
+    };
+    let hash_type = match SigHashType::from_u32(hash_type) {
+        Ok(hash_type) => hash_type,
+        Err(_) => return CASH_TX_BUILDER_INVALID_ARGUMENT,
+    };
+
+    match builder.inner.witness_v0_hash(hash_type, index, None, None) {
+        Ok(hash) => {
+            slice::from_raw_parts_mut(out, 32).copy_from_slice(&hash);
+            CASH_TX_BUILDER_OK
+        },
+        Err(_) => CASH_TX_BUILDER_FAILED,
+    }
+}
+
+/// Serialize transaction
+///
+/// Call with null `out` to get required size.
+/// # Arguments
+/// * `out`, `out_len` - buffer to receive serialized transaction
+/// # Returns
+/// * serialized size (nothing is written if it exceeds `out_len`), `0` if `builder` is null
+/// # Safety
+/// `builder` must be valid handle and `out` must point to writable `out_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cash_tx_builder_serialize(
+    builder: *const CashTxBuilder,
+    out: *mut u8,
+    out_len: usize,
+) -> usize {
+    let builder = match builder.as_ref() {
+        Some(b) => b,
+        None => return 0,
+    };
+
+    let v = builder.inner.to_vec();
+    if !out.is_null() && v.len() <= out_len {
+        slice::from_raw_parts_mut(out, v.len()).copy_from_slice(&v);
+    }
+
+    v.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::ptr;
+    use super::*;
+    use super::super::types::transaction::Transaction;
+    use super::super::tx_builder::sig_hash;
+    use super::super::script::p2pkh;
+
+    #[test]
+    fn builder() {
+        let txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse::<Txid>().unwrap();
+        let prev_script = hex!("76a9143424f163208a3b676fa0ec17034f0f290322a2a688ac");
+        let script_sig = p2pkh::script_sig(
+            &hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036"),
+            &hex!("304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b3041"),
+        ).unwrap();
+        let outputs = [
+            (11000, hex!("76a91426b61031a0fdf3cb9f17da8b9c1fc54a638c369188ac")),
+            (88757, hex!("76a9142647f73de90d8e9db5b5f77d1cc1ad5afe81d68c88ac")),
+        ];
+
+        unsafe {
+            let b = cash_tx_builder_new();
+            assert_eq!(cash_tx_builder_add_input(b, txid.as_ref().as_ptr(), 1, 100_000, prev_script.as_ptr(), prev_script.len(), 0xffff_ffff), CASH_TX_BUILDER_OK);
+            assert_eq!(cash_tx_builder_add_input(b, ptr::null(), 1, 100_000, prev_script.as_ptr(), prev_script.len(), 0xffff_ffff), CASH_TX_BUILDER_INVALID_ARGUMENT);
+            for (value, script) in &outputs {
+                assert_eq!(cash_tx_builder_add_output(b, *value, script.as_ptr(), script.len()), CASH_TX_BUILDER_OK);
+            }
+
+            let mut hash = [0; 32];
+            let hash_type = (sig_hash::ALL | sig_hash::FORKID).to_u32();
+            assert_eq!(cash_tx_builder_sighash(b, hash_type, 0, hash.as_mut_ptr()), CASH_TX_BUILDER_OK);
+            assert_eq!(hash, hex!("2b492e7c4c8a3d670fd7fe324a87e3c55df1802c9a100f4006f8fff7c0913dd4"));
+            assert_eq!(cash_tx_builder_sighash(b, hash_type, 1, hash.as_mut_ptr()), CASH_TX_BUILDER_FAILED);
+            assert_eq!(cash_tx_builder_sighash(b, sig_hash::ALL.to_u32(), 0, hash.as_mut_ptr()), CASH_TX_BUILDER_INVALID_ARGUMENT);
+
+            assert_eq!(cash_tx_builder_set_script_sig(b, 0, script_sig.as_ptr(), script_sig.len()), CASH_TX_BUILDER_OK);
+            let len = cash_tx_builder_serialize(b, ptr::null_mut(), 0);
+            let mut raw = vec![0; len];
+            assert_eq!(cash_tx_builder_serialize(b, raw.as_mut_ptr(), raw.len()), len);
+            assert_eq!(Transaction::try_from(&raw[..]).unwrap().txid().to_string(), "ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda");
+
+            cash_tx_builder_free(b);
+        }
+    }
+}
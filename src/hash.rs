@@ -1,5 +1,286 @@
+//! hash functions used by bitcoin
+
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Deref;
 use sha2::{Sha256, Digest};
+use sha2::digest::generic_array::GenericArray;
+use ripemd::Ripemd160;
+
+macro_rules! hash_newtype {
+    ($name:ident, $doc:expr) => {
+        #[doc = $doc]
+        ///
+        /// Dereferences to its 32 bytes and is displayed in hex (in byte order, not reversed).
+        #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+        pub struct $name([u8; 32]);
+
+        impl From<[u8; 32]> for $name {
+            fn from(v: [u8; 32]) -> $name {
+                $name(v)
+            }
+        }
+
+        impl From<$name> for [u8; 32] {
+            fn from(v: $name) -> [u8; 32] {
+                v.0
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = [u8];
+
+            fn deref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl PartialEq<[u8; 32]> for $name {
+            fn eq(&self, other: &[u8; 32]) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.iter().try_for_each(|v| write!(f, "{:02x}", v))
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+    };
+}
+
+hash_newtype!(Sha256d, "Double SHA-256 digest");
+hash_newtype!(SigHash, "Signature hash (digest signed by input signature)");
+
+impl From<Sha256d> for SigHash {
+    fn from(v: Sha256d) -> SigHash {
+        SigHash(v.0)
+    }
+}
+
+/// SHA-256
+/// # Arguments
+/// * `v` - data
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::hash::sha256;
+/// assert_eq!(sha256(b"abc"), hex!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"));
+/// ```
+pub fn sha256(v: &[u8]) -> Vec<u8> {
+    Sha256::digest(v).to_vec()
+}
+
+/// Double SHA-256 (used for txid and sighash)
+/// # Arguments
+/// * `v` - data
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::hash::hash256;
+/// let hash = hash256(b"abc");
+/// assert_eq!(hash, hex!("4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358"));
+/// assert_eq!(hash.to_string(), "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358");
+/// ```
+pub fn hash256(v: &[u8]) -> Sha256d {
+    finalize_hash256(Sha256::new().chain_update(v))
+}
+
+/// RIPEMD-160 of SHA-256 (used for addresses)
+/// # Arguments
+/// * `v` - data
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::hash::hash160;
+/// let pubkey = hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036");
+/// assert_eq!(hash160(&pubkey), hex!("3424f163208a3b676fa0ec17034f0f290322a2a6"));
+/// ```
+pub fn hash160(v: &[u8]) -> Vec<u8> {
+    Ripemd160::digest(Sha256::digest(v)).to_vec()
+}
+
+/// Double SHA-256 of data fed to `hasher`
+pub(crate) fn finalize_hash256(hasher: Sha256) -> Sha256d {
+    let mut v = [0; 32];
+    v.copy_from_slice(&Sha256::digest(hasher.finalize()));
+    Sha256d(v)
+}
+
+/// Writer computing double SHA-256 of written data incrementally
+/// # Example
+/// ```
+/// # use std::io::Write;
+/// # use cash_tx_builder::hash::{hash256, Hash256Writer};
+/// let mut w = Hash256Writer::new();
+/// w.write_all(b"a")?;
+/// w.write_all(b"bc")?;
+/// assert_eq!(w.finalize(), hash256(b"abc"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Clone, Default)]
+pub struct Hash256Writer {
+    hasher: Sha256,
+}
+
+impl Hash256Writer {
+    /// Construct writer without data
+    pub fn new() -> Hash256Writer {
+        Hash256Writer::default()
+    }
+
+    /// Double SHA-256 of written data
+    pub fn finalize(self) -> Sha256d {
+        finalize_hash256(self.hasher)
+    }
+}
+
+impl Write for Hash256Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.hasher.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Hash256Writer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hash256Writer").finish()
+    }
+}
+
+/// Initial state of SHA-256
+const SHA256_INIT: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Compress 64-byte blocks into SHA-256 `state`
+fn sha256_compress(state: &mut [u32; 8], blocks: &[u8]) {
+    let blocks = blocks.chunks(64).map(GenericArray::clone_from_slice).collect::<Vec<_>>();
+    sha2::compress256(state, &blocks);
+}
+
+/// SHA-256 state after hashing the whole 64-byte blocks of a prefix
+///
+/// Data following the prefix can be hashed repeatedly without rehashing the prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Midstate {
+    state: [u32; 8],
+    len: u64,
+    remainder: Vec<u8>,
+}
+
+impl Midstate {
+    /// Internal state (8 big-endian words), e.g. for stratum
+    pub fn state(&self) -> [u8; 32] {
+        let mut v = [0; 32];
+        for (chunk, word) in v.chunks_mut(4).zip(&self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        v
+    }
+
+    /// Number of bytes compressed into the state (multiple of 64)
+    pub fn compressed_len(&self) -> u64 {
+        self.len
+    }
+
+    /// Bytes of the prefix not compressed yet (shorter than 64)
+    pub fn remainder(&self) -> &[u8] {
+        &self.remainder
+    }
+
+    /// SHA-256 of the prefix followed by `tail`
+    /// # Arguments
+    /// * `tail` - data following the prefix
+    pub fn sha256(&self, tail: &[u8]) -> Vec<u8> {
+        let mut state = self.state;
+        let mut data = [&self.remainder[..], tail].concat();
+        let bit_len = (self.len + data.len() as u64) * 8;
+        data.push(0x80);
+        while data.len() % 64 != 56 {
+            data.push(0);
+        }
+        data.extend(&bit_len.to_be_bytes());
+        sha256_compress(&mut state, &data);
+
+        Midstate { state, len: 0, remainder: vec![] }.state().to_vec()
+    }
+
+    /// Double SHA-256 of the prefix followed by `tail`
+    /// # Arguments
+    /// * `tail` - data following the prefix
+    pub fn hash256(&self, tail: &[u8]) -> Sha256d {
+        let mut v = [0; 32];
+        v.copy_from_slice(&sha256(&self.sha256(tail)));
+        Sha256d(v)
+    }
+}
+
+/// Hash whole 64-byte blocks of `prefix`
+/// # Arguments
+/// * `prefix` - static part of data
+/// # Example
+/// ```
+/// # use cash_tx_builder::hash::{sha256, hash256, sha256_midstate};
+/// let prefix = [0x01; 100];
+/// let midstate = sha256_midstate(&prefix);
+/// assert_eq!(midstate.compressed_len(), 64);
+/// assert_eq!(midstate.remainder().len(), 36);
+/// for nonce in 0u32..3 {
+///     let data = [&prefix[..], &nonce.to_le_bytes()].concat();
+///     assert_eq!(midstate.sha256(&nonce.to_le_bytes()), sha256(&data));
+///     assert_eq!(midstate.hash256(&nonce.to_le_bytes()), hash256(&data));
+/// }
+/// ```
+pub fn sha256_midstate(prefix: &[u8]) -> Midstate {
+    let mut state = SHA256_INIT;
+    let compressed = prefix.len() - prefix.len() % 64;
+    sha256_compress(&mut state, &prefix[..compressed]);
+
+    Midstate {
+        state,
+        len: compressed as u64,
+        remainder: prefix[compressed..].to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn midstate() {
+        let data = (0..200).map(|i| i as u8).collect::<Vec<_>>();
+        for len in 0..data.len() {
+            let midstate = sha256_midstate(&data[..len / 2]);
+            assert_eq!(midstate.sha256(&data[len / 2..len]), sha256(&data[..len]), "length {}", len);
+        }
+        assert_eq!(sha256_midstate(&[]).state()[..4], SHA256_INIT[0].to_be_bytes());
 
-pub fn hash256(hasher: Sha256) -> Vec<u8> {
-    Sha256::digest(&hasher.result()).to_vec()
+        // state after the padded block of "abc" is its digest (FIPS 180-2 example)
+        let mut block = [0; 64];
+        block[..4].copy_from_slice(b"abc\x80");
+        block[63] = 24;
+        let midstate = sha256_midstate(&block);
+        assert_eq!(midstate.compressed_len(), 64);
+        assert!(midstate.remainder().is_empty());
+        assert_eq!(hex::encode(midstate.state()), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
 }
@@ -0,0 +1,416 @@
+//! BIP32 hierarchical deterministic keys
+//! [spec](https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki)
+
+mod wallet;
+
+use std::fmt;
+use std::str::FromStr;
+use hmac::{Hmac, Mac};
+use secp256k1::{PublicKey, SecretKey, Scalar, SECP256K1};
+use sha2::Sha512;
+use super::hash;
+use super::base58;
+use super::signer::Signer;
+use super::error::{Error, Result};
+
+pub use wallet::Wallet;
+
+/// Offset of hardened child index
+pub const HARDENED: u32 = 0x8000_0000;
+
+/// Version bytes of serialized mainnet extended private key (`xprv`)
+const XPRV_VERSION: [u8; 4] = [0x04, 0x88, 0xad, 0xe4];
+
+/// Version bytes of serialized mainnet extended public key (`xpub`)
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+
+/// Length of serialized extended key
+const EXTENDED_KEY_LEN: usize = 78;
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts any key length");
+    for d in data {
+        mac.update(d);
+    }
+
+    let code = mac.finalize().into_bytes();
+    let mut left = [0; 32];
+    let mut right = [0; 32];
+    left.copy_from_slice(&code[..32]);
+    right.copy_from_slice(&code[32..]);
+    (left, right)
+}
+
+fn tweak(il: [u8; 32]) -> Result<Scalar> {
+    Scalar::from_be_bytes(il).map_err(|_| Error::InvalidHdKey("derived key is out of range".to_string()))
+}
+
+/// Derivation path like `m/44'/145'/0'/0/0`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DerivationPath(pub Vec<u32>);
+
+impl From<Vec<u32>> for DerivationPath {
+    fn from(v: Vec<u32>) -> DerivationPath {
+        DerivationPath(v)
+    }
+}
+
+impl AsRef<[u32]> for DerivationPath {
+    fn as_ref(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        self.0.iter().try_for_each(|&i| {
+            if i >= HARDENED {
+                write!(f, "/{}'", i - HARDENED)
+            } else {
+                write!(f, "/{}", i)
+            }
+        })
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = Error;
+
+    /// Parse derivation path (`'` or `h` marks hardened index)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::hd::{DerivationPath, HARDENED};
+    /// let path: DerivationPath = "m/44'/145h/0'/0/1".parse()?;
+    /// assert_eq!(path.0, [44 + HARDENED, 145 + HARDENED, HARDENED, 0, 1]);
+    /// assert_eq!(path.to_string(), "m/44'/145'/0'/0/1");
+    /// assert!("m/2147483648".parse::<DerivationPath>().is_err());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidDerivationPath(s.to_string());
+        let mut elements = s.split('/');
+        if elements.next() != Some("m") {
+            return Err(invalid());
+        }
+
+        elements.map(|e| {
+            let (index, hardened) = match e.strip_suffix('\'').or_else(|| e.strip_suffix('h')) {
+                Some(index) => (index, true),
+                None => (e, false),
+            };
+            let index = index.parse::<u32>().map_err(|_| invalid())?;
+            if index >= HARDENED {
+                return Err(invalid());
+            }
+
+            Ok(if hardened { index + HARDENED } else { index })
+        }).collect::<Result<Vec<_>>>().map(DerivationPath)
+    }
+}
+
+/// Extended private key
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Xpriv {
+    /// depth from master key
+    pub depth: u8,
+    /// first 4 bytes of parent's `public key hash`
+    pub parent_fingerprint: [u8; 4],
+    /// child index
+    pub child_number: u32,
+    /// chain code
+    pub chain_code: [u8; 32],
+    /// private key
+    pub secret_key: SecretKey,
+}
+
+impl Xpriv {
+    /// Generate master key
+    /// # Arguments
+    /// * `seed` - seed bytes (16 - 64 bytes, else `Error::InvalidHdKey`)
+    pub fn new_master(seed: &[u8]) -> Result<Xpriv> {
+        if !(16..=64).contains(&seed.len()) {
+            return Err(Error::InvalidHdKey(format!("seed length: {}", seed.len())));
+        }
+        let (il, ir) = hmac_sha512(b"Bitcoin seed", &[seed]);
+
+        Ok(Xpriv {
+            depth: 0,
+            parent_fingerprint: [0; 4],
+            child_number: 0,
+            chain_code: ir,
+            secret_key: SecretKey::from_slice(&il)?,
+        })
+    }
+
+    /// Get extended public key
+    pub fn xpub(&self) -> Xpub {
+        Xpub {
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            chain_code: self.chain_code,
+            public_key: PublicKey::from_secret_key(SECP256K1, &self.secret_key),
+        }
+    }
+
+    /// First 4 bytes of `public key hash`
+    pub fn fingerprint(&self) -> [u8; 4] {
+        self.xpub().fingerprint()
+    }
+
+    /// Derive child key
+    /// # Arguments
+    /// * `index` - child index (`HARDENED` or larger for hardened derivation)
+    pub fn derive_child(&self, index: u32) -> Result<Xpriv> {
+        let (il, ir) = if index >= HARDENED {
+            hmac_sha512(&self.chain_code, &[&[0x00], &self.secret_key.secret_bytes(), &index.to_be_bytes()])
+        } else {
+            let pubkey = PublicKey::from_secret_key(SECP256K1, &self.secret_key).serialize();
+            hmac_sha512(&self.chain_code, &[&pubkey, &index.to_be_bytes()])
+        };
+
+        Ok(Xpriv {
+            depth: self.depth.checked_add(1).ok_or_else(|| Error::InvalidHdKey("depth overflow".to_string()))?,
+            parent_fingerprint: self.fingerprint(),
+            child_number: index,
+            chain_code: ir,
+            secret_key: self.secret_key.add_tweak(&tweak(il)?)?,
+        })
+    }
+
+    /// Derive descendant key
+    /// # Arguments
+    /// * `path` - derivation path from this key
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::hd::Xpriv;
+    /// let master = Xpriv::new_master(&hex::decode("000102030405060708090a0b0c0d0e0f")?)?;
+    /// let child = master.derive_path(&"m/0'/1".parse()?)?;
+    /// assert_eq!(child.to_string(), "xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Xpriv> {
+        path.0.iter().try_fold(*self, |key, &index| key.derive_child(index))
+    }
+}
+
+impl Signer for Xpriv {
+    fn sign(&self, digest: &[u8]) -> Result<Vec<u8>> {
+        self.secret_key.sign(digest)
+    }
+
+    fn public_key(&self) -> Vec<u8> {
+        Signer::public_key(&self.secret_key)
+    }
+}
+
+/// Extended public key
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Xpub {
+    /// depth from master key
+    pub depth: u8,
+    /// first 4 bytes of parent's `public key hash`
+    pub parent_fingerprint: [u8; 4],
+    /// child index
+    pub child_number: u32,
+    /// chain code
+    pub chain_code: [u8; 32],
+    /// public key
+    pub public_key: PublicKey,
+}
+
+impl Xpub {
+    /// First 4 bytes of `public key hash`
+    pub fn fingerprint(&self) -> [u8; 4] {
+        let mut fingerprint = [0; 4];
+        fingerprint.copy_from_slice(&hash::hash160(&self.public_key.serialize())[..4]);
+        fingerprint
+    }
+
+    /// Derive non-hardened child key
+    /// # Arguments
+    /// * `index` - child index (less than `HARDENED`)
+    pub fn derive_child(&self, index: u32) -> Result<Xpub> {
+        if index >= HARDENED {
+            return Err(Error::InvalidHdKey(format!("hardened derivation from public key: {}", index)));
+        }
+        let (il, ir) = hmac_sha512(&self.chain_code, &[&self.public_key.serialize(), &index.to_be_bytes()]);
+
+        Ok(Xpub {
+            depth: self.depth.checked_add(1).ok_or_else(|| Error::InvalidHdKey("depth overflow".to_string()))?,
+            parent_fingerprint: self.fingerprint(),
+            child_number: index,
+            chain_code: ir,
+            public_key: self.public_key.add_exp_tweak(SECP256K1, &tweak(il)?)?,
+        })
+    }
+
+    /// Derive descendant key
+    /// # Arguments
+    /// * `path` - derivation path from this key (non-hardened only)
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Xpub> {
+        path.0.iter().try_fold(*self, |key, &index| key.derive_child(index))
+    }
+}
+
+/// Fields of serialized extended key
+struct ExtendedKey {
+    version: [u8; 4],
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: u32,
+    chain_code: [u8; 32],
+    /// 33 bytes key data
+    key: Vec<u8>,
+}
+
+impl ExtendedKey {
+    fn encode(&self) -> String {
+        let v = [
+            &self.version[..],
+            &[self.depth],
+            &self.parent_fingerprint,
+            &self.child_number.to_be_bytes(),
+            &self.chain_code,
+            &self.key,
+        ].concat();
+
+        base58::encode_check(&v)
+    }
+
+    fn decode(s: &str) -> Result<ExtendedKey> {
+        let v = base58::decode_check(s).ok_or_else(|| Error::InvalidHdKey(s.to_string()))?;
+        if v.len() != EXTENDED_KEY_LEN {
+            return Err(Error::InvalidHdKey(s.to_string()));
+        }
+
+        let mut version = [0; 4];
+        let mut parent_fingerprint = [0; 4];
+        let mut child_number = [0; 4];
+        let mut chain_code = [0; 32];
+        version.copy_from_slice(&v[0..4]);
+        parent_fingerprint.copy_from_slice(&v[5..9]);
+        child_number.copy_from_slice(&v[9..13]);
+        chain_code.copy_from_slice(&v[13..45]);
+
+        Ok(ExtendedKey {
+            version,
+            depth: v[4],
+            parent_fingerprint,
+            child_number: u32::from_be_bytes(child_number),
+            chain_code,
+            key: v[45..].to_vec(),
+        })
+    }
+}
+
+impl fmt::Display for Xpriv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let extended_key = ExtendedKey {
+            version: XPRV_VERSION,
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            chain_code: self.chain_code,
+            key: [&[0x00][..], &self.secret_key.secret_bytes()].concat(),
+        };
+        f.write_str(&extended_key.encode())
+    }
+}
+
+impl FromStr for Xpriv {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let v = ExtendedKey::decode(s)?;
+        if v.version != XPRV_VERSION || v.key[0] != 0x00 {
+            return Err(Error::InvalidHdKey(s.to_string()));
+        }
+
+        Ok(Xpriv {
+            depth: v.depth,
+            parent_fingerprint: v.parent_fingerprint,
+            child_number: v.child_number,
+            chain_code: v.chain_code,
+            secret_key: SecretKey::from_slice(&v.key[1..])?,
+        })
+    }
+}
+
+impl fmt::Display for Xpub {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let extended_key = ExtendedKey {
+            version: XPUB_VERSION,
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            chain_code: self.chain_code,
+            key: self.public_key.serialize().to_vec(),
+        };
+        f.write_str(&extended_key.encode())
+    }
+}
+
+impl FromStr for Xpub {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let v = ExtendedKey::decode(s)?;
+        if v.version != XPUB_VERSION {
+            return Err(Error::InvalidHdKey(s.to_string()));
+        }
+
+        Ok(Xpub {
+            depth: v.depth,
+            parent_fingerprint: v.parent_fingerprint,
+            child_number: v.child_number,
+            chain_code: v.chain_code,
+            public_key: PublicKey::from_slice(&v.key)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bip32_vector1() -> Result<()> {
+        let master = Xpriv::new_master(&hex!("000102030405060708090a0b0c0d0e0f"))?;
+        assert_eq!(master.to_string(), "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi");
+        assert_eq!(master.xpub().to_string(), "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8");
+
+        let child = master.derive_path(&DerivationPath::from_str("m/0'")?)?;
+        assert_eq!(child.to_string(), "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7");
+        assert_eq!(child.xpub().to_string(), "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw");
+
+        // public derivation matches private derivation
+        let path = DerivationPath::from_str("m/1/2")?;
+        assert_eq!(child.xpub().derive_path(&path)?, child.derive_path(&path)?.xpub());
+        assert!(child.xpub().derive_child(HARDENED).is_err());
+
+        let leaf = master.derive_path(&DerivationPath::from_str("m/0'/1/2'/2/1000000000")?)?;
+        assert_eq!(leaf.to_string(), "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76");
+        assert_eq!(Xpriv::from_str(&leaf.to_string())?, leaf);
+        assert_eq!(Xpub::from_str(&leaf.xpub().to_string())?, leaf.xpub());
+        assert!(Xpub::from_str(&leaf.to_string()).is_err());
+        assert!(Xpriv::from_str("xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHj").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn seed_length() -> Result<()> {
+        Xpriv::new_master(&[0x01; 16])?;
+        Xpriv::new_master(&[0x01; 64])?;
+        for len in &[0, 15, 65] {
+            match Xpriv::new_master(&vec![0x01; *len]) {
+                Err(Error::InvalidHdKey(_)) => {},
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        Ok(())
+    }
+}
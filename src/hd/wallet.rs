@@ -0,0 +1,134 @@
+//! BIP39 mnemonic wallet
+//! [spec](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki)
+
+use std::collections::HashMap;
+use bip39::Mnemonic;
+use super::{Xpriv, DerivationPath, HARDENED};
+use super::super::signer::Signer;
+use super::super::script::p2pkh;
+use super::super::hash;
+use super::super::error::{Error, Result};
+
+/// `signature` and `public key` returned by signer
+type Signature = (Vec<u8>, Vec<u8>);
+
+/// Wallet of BIP44 style account (`<coin_path>/<change>/<index>`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wallet {
+    account: Xpriv,
+}
+
+impl Wallet {
+    /// Restore wallet from mnemonic
+    /// # Arguments
+    /// * `phrase` - BIP39 mnemonic (English)
+    /// * `passphrase` - BIP39 passphrase (empty if none)
+    /// * `coin_path` - derivation path of account, e.g. `m/44'/145'/0'`
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, sig_hash, Amount};
+    /// # use cash_tx_builder::hd::Wallet;
+    /// # use cash_tx_builder::script::address_to_script;
+    /// let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    /// let wallet = Wallet::from_mnemonic(phrase, "", &"m/44'/145'/0'".parse()?)?;
+    ///
+    /// // any address encoder, e.g. cashaddr
+    /// let formatter = |hash: &[u8], _is_pkh: bool| Some(hex::encode(hash));
+    /// let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+    /// let address = wallet.addresses(false, formatter).next().unwrap();
+    ///
+    /// let mut txb = TxBuilder::new(parser);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// let prev_script = address_to_script(&address, &parser)?;
+    /// txb.add_input(prev_txid, 0, Some(50_000), Some(&prev_script), None)?;
+    /// txb.add_address_output(Amount::from_sat(49_000), &address)?;
+    /// txb.sign_all(sig_hash::ALL | sig_hash::FORKID, wallet.signer(20))?;
+    /// txb.validate_input(0)?;
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn from_mnemonic(phrase: &str, passphrase: &str, coin_path: &DerivationPath) -> Result<Wallet> {
+        let mnemonic = Mnemonic::parse(phrase).map_err(|e| Error::InvalidMnemonic(e.to_string()))?;
+        let master = Xpriv::new_master(&mnemonic.to_seed(passphrase))?;
+
+        Ok(Wallet {
+            account: master.derive_path(coin_path)?,
+        })
+    }
+
+    /// Extended private key of account
+    pub fn account(&self) -> &Xpriv {
+        &self.account
+    }
+
+    /// Derive key of address
+    /// # Arguments
+    /// * `change` - `true` for internal (change) chain
+    /// * `index` - address index
+    pub fn key(&self, change: bool, index: u32) -> Result<Xpriv> {
+        self.account.derive_child(change as u32)?.derive_child(index)
+    }
+
+    /// Iterate P2PKH addresses from index 0
+    ///
+    /// Indices whose key is invalid are skipped, iteration ends where `formatter` returns `None`.
+    /// # Arguments
+    /// * `change` - `true` for internal (change) chain
+    /// * `formatter` - address formatter, receives `public key hash` and `true`
+    pub fn addresses<'a, F>(&'a self, change: bool, formatter: F) -> impl Iterator<Item = String> + 'a
+        where F: Fn(&[u8], bool) -> Option<String> + 'a {
+        (0..HARDENED)
+            .filter_map(move |index| self.key(change, index).ok())
+            .map_while(move |key| formatter(&hash::hash160(&Signer::public_key(&key)), true))
+    }
+
+    /// Build signer for `TxBuilder::sign_all`
+    ///
+    /// Keys are looked up by previous P2PKH `scriptPubKey`
+    /// in the first `lookahead` addresses of both chains (invalid keys are skipped).
+    /// # Arguments
+    /// * `lookahead` - number of addresses per chain
+    pub fn signer(&self, lookahead: u32) -> impl Fn(usize, &[u8], &[u8]) -> Result<Signature> {
+        let keys = [false, true].iter()
+            .flat_map(|&change| (0..lookahead).map(move |index| (change, index)))
+            .filter_map(|(change, index)| {
+                let key = self.key(change, index).ok()?;
+                let script = p2pkh::script_pub_key(&hash::hash160(&Signer::public_key(&key))).ok()?;
+                Some((script, key))
+            })
+            .collect::<HashMap<_, _>>();
+
+        move |index: usize, sighash: &[u8], prev_script: &[u8]| {
+            let key = keys.get(prev_script)
+                .ok_or_else(|| Error::InvalidHdKey(format!("no key for input {}", index)))?;
+            Ok((key.sign(sighash)?, Signer::public_key(key)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wallet() -> Result<()> {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let wallet = Wallet::from_mnemonic(phrase, "TREZOR", &"m".parse()?)?;
+        assert_eq!(wallet.account().to_string(), "xprv9s21ZrQH143K3h3fDYiay8mocZ3afhfULfb5GX8kCBdno77K4HiA15Tg23wpbeF1pLfs1c5SPmYHrEpTuuRhxMwvKDwqdKiGJS9XFKzUsAF");
+        assert!(Wallet::from_mnemonic("abandon abandon", "", &"m".parse()?).is_err());
+
+        let wallet = Wallet::from_mnemonic(phrase, "", &"m/44'/145'/0'".parse()?)?;
+        let formatter = |hash: &[u8], _: bool| Some(hex::encode(hash));
+        let addresses = wallet.addresses(true, formatter).take(3).collect::<Vec<_>>();
+        let key = wallet.key(true, 2)?;
+        assert_eq!(addresses[2], hex::encode(hash::hash160(&Signer::public_key(&key))));
+        assert_eq!(wallet.addresses(true, |_, _| None).next(), None);
+
+        let signer = wallet.signer(3);
+        let prev_script = p2pkh::script_pub_key(&hash::hash160(&Signer::public_key(&key)))?;
+        let (_, pubkey) = signer(0, &[0x01; 32], &prev_script)?;
+        assert_eq!(pubkey, Signer::public_key(&key));
+        assert!(signer(0, &[0x01; 32], &[0x51]).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+//! Minimal HTTP/1.1 client for JSON APIs
+//!
+//! Only plain HTTP connections are supported.
+
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// POST JSON `body` and parse JSON response body
+///
+/// Response body may be sent with `Transfer-Encoding: chunked`.
+/// # Arguments
+/// * `addr` - server address, e.g. `"127.0.0.1:8332"`
+/// * `path` - request path
+/// * `headers` - additional headers
+/// * `body` - request body
+/// # Returns
+/// * response body (error message on failure or non-2xx status, with response body if any)
+pub(crate) async fn post_json(addr: &str, path: &str, headers: &[(&str, &str)], body: &Value) -> Result<Value, String> {
+    let body = body.to_string();
+    let headers = headers.iter()
+        .map(|(name, value)| format!("{}: {}\r\n", name, value))
+        .collect::<String>();
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path, addr, headers, body.len(), body,
+    );
+
+    let mut stream = TcpStream::connect(addr).await.map_err(|e| e.to_string())?;
+    stream.write_all(request.as_bytes()).await.map_err(|e| e.to_string())?;
+    let mut response = vec![];
+    stream.read_to_end(&mut response).await.map_err(|e| e.to_string())?;
+
+    let invalid = || format!("invalid response: {}", String::from_utf8_lossy(&response));
+    let i = response.windows(4).position(|w| w == b"\r\n\r\n").ok_or_else(invalid)?;
+    let head = String::from_utf8_lossy(&response[..i]);
+    let mut lines = head.split("\r\n");
+    let status = lines.next()
+        .and_then(|l| l.split(' ').nth(1))
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(invalid)?;
+    let chunked = lines
+        .filter_map(|l| l.split_once(':'))
+        .any(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding") && value.to_ascii_lowercase().contains("chunked")
+        });
+
+    let body = if chunked {
+        decode_chunked(&response[i + 4..]).ok_or_else(invalid)?
+    } else {
+        response[i + 4..].to_vec()
+    };
+    if !(200..300).contains(&status) {
+        return Err(format!("HTTP status {}: {}", status, String::from_utf8_lossy(&body)));
+    }
+
+    serde_json::from_slice(&body).map_err(|_| String::from_utf8_lossy(&body).to_string())
+}
+
+/// Decode body sent with `Transfer-Encoding: chunked`
+/// # Arguments
+/// * `body` - encoded body
+/// # Returns
+/// * decoded body (`None` if malformed)
+fn decode_chunked(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = vec![];
+    loop {
+        let i = body.windows(2).position(|w| w == b"\r\n")?;
+        let size = std::str::from_utf8(&body[..i]).ok()?;
+        let size = size.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        body = &body[i + 2..];
+        if size == 0 {
+            // trailers are ignored
+            return Some(decoded);
+        }
+
+        let end = size.checked_add(2)?;
+        if body.get(size..end)? != b"\r\n" {
+            return None;
+        }
+        decoded.extend_from_slice(&body[..size]);
+        body = &body[end..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::net::TcpListener;
+    use super::*;
+
+    async fn serve(listener: TcpListener, response: &'static str) {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request: Vec<u8> = vec![];
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+            let mut buf = [0; 1024];
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend(&buf[..n]);
+        }
+        stream.write_all(response.as_bytes()).await.unwrap();
+    }
+
+    async fn post(response: &'static str) -> Result<Value, String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.map_err(|e| e.to_string())?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?.to_string();
+        tokio::spawn(serve(listener, response));
+
+        post_json(&addr, "/", &[], &json!({})).await
+    }
+
+    #[test]
+    fn chunked() {
+        assert_eq!(decode_chunked(b"4\r\nWiki\r\n5;ext=1\r\npedia\r\n0\r\nTrailer: x\r\n\r\n"), Some(b"Wikipedia".to_vec()));
+        assert_eq!(decode_chunked(b"0\r\n\r\n"), Some(vec![]));
+        assert_eq!(decode_chunked(b"4\r\nWikipedia\r\n0\r\n\r\n"), None);
+        assert_eq!(decode_chunked(b"4\r\nWiki\r\n"), None);
+        assert_eq!(decode_chunked(b"x\r\n\r\n"), None);
+    }
+
+    #[tokio::test]
+    async fn post_chunked() -> Result<(), String> {
+        let response = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n\
+                        7\r\n{\"resul\r\n9\r\nt\": true}\r\n0\r\n\r\n";
+        assert_eq!(post(response).await?, json!({ "result": true }));
+
+        let response = "HTTP/1.1 200 OK\r\nContent-Length: 16\r\n\r\n{\"result\": true}";
+        assert_eq!(post(response).await?, json!({ "result": true }));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn post_error_status() {
+        let response = "HTTP/1.1 500 Internal Server Error\r\nTransfer-Encoding: chunked\r\n\r\n\
+                        e\r\n{\"error\": \"e\"}\r\n0\r\n\r\n";
+        assert_eq!(post(response).await, Err("HTTP status 500: {\"error\": \"e\"}".to_string()));
+
+        let response = "HTTP/1.1 404 Not Found\r\n\r\n";
+        assert_eq!(post(response).await, Err("HTTP status 404: ".to_string()));
+
+        let response = "garbage";
+        assert_eq!(post(response).await, Err("invalid response: garbage".to_string()));
+    }
+}
@@ -0,0 +1,984 @@
+//! script interpreter
+//! [reference](https://github.com/Bitcoin-ABC/bitcoin-abc/blob/master/src/script/interpreter.cpp)
+
+use std::ops::BitOr;
+use std::result;
+use thiserror::Error;
+use ripemd::Ripemd160;
+use sha1::Sha1;
+use sha2::Digest;
+use super::opcode::OpCode::{self, *};
+use super::script::{Script, encode, get_opcode, is_push_only, num, p2sh};
+use super::script::num::{MAX_NUM_SIZE, LEGACY_MAX_NUM_SIZE};
+use super::error::Result;
+use super::hash;
+
+/// Maximum size of script
+pub const MAX_SCRIPT_SIZE: usize = 10_000;
+
+/// Maximum size of stack element
+pub const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
+
+/// Maximum number of non-push operations per script
+pub const MAX_OPS_PER_SCRIPT: usize = 201;
+
+/// Maximum number of elements in stack and altstack
+pub const MAX_STACK_SIZE: usize = 1000;
+
+/// Maximum number of public keys per multisig
+pub const MAX_PUBKEYS_PER_MULTISIG: usize = 20;
+
+/// Maximum size of script number used by `OP_CHECKLOCKTIMEVERIFY` and `OP_CHECKSEQUENCEVERIFY`
+const MAX_LOCK_TIME_NUM_SIZE: usize = 5;
+
+/// Disable flag of relative lock-time
+const SEQUENCE_LOCKTIME_DISABLE_FLAG: i64 = 1 << 31;
+
+/// Length of Schnorr signature with sighash type
+const SCHNORR_TX_SIG_LEN: usize = 65;
+
+/// Stack of script
+pub type Stack = Vec<Vec<u8>>;
+
+type EvalResult<T> = result::Result<T, ScriptError>;
+
+/// Error of script evaluation
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ScriptError {
+    /// Script finished with false on top of stack.
+    #[error("Script evaluated without error but finished with a false/empty top stack element")]
+    EvalFalse,
+
+    /// `OP_RETURN` was executed.
+    #[error("OP_RETURN was encountered")]
+    OpReturn,
+
+    /// Script is too large.
+    /// # Arguments
+    /// * size
+    #[error("Script is too big: {0}")]
+    ScriptSize(usize),
+
+    /// Pushed element is too large.
+    /// # Arguments
+    /// * size
+    #[error("Push value size limit exceeded: {0}")]
+    PushSize(usize),
+
+    /// Too many operations.
+    #[error("Operation limit exceeded")]
+    OpCount,
+
+    /// Too many stack elements.
+    #[error("Stack size limit exceeded")]
+    StackSize,
+
+    /// Invalid number of signatures of multisig.
+    #[error("Signature count negative or greater than pubkey count")]
+    SigCount,
+
+    /// Invalid number of public keys of multisig.
+    #[error("Pubkey count negative or limit exceeded")]
+    PubKeyCount,
+
+    /// `*VERIFY` operation failed.
+    /// # Arguments
+    /// * op code
+    #[error("Script failed an {0:?} operation")]
+    Verify(OpCode),
+
+    /// Unknown or invalid op code.
+    /// # Arguments
+    /// * op code
+    #[error("Opcode missing or not understood: {0}")]
+    BadOpCode(u8),
+
+    /// Disabled op code.
+    /// # Arguments
+    /// * op code
+    #[error("Attempted to use a disabled opcode: {0:?}")]
+    DisabledOpCode(OpCode),
+
+    /// Not enough elements in stack.
+    #[error("Operation not valid with the current stack size")]
+    InvalidStackOperation,
+
+    /// Not enough elements in altstack.
+    #[error("Operation not valid with the current altstack size")]
+    InvalidAltstackOperation,
+
+    /// Unbalanced `OP_IF`/`OP_ELSE`/`OP_ENDIF`.
+    #[error("Invalid OP_IF construction")]
+    UnbalancedConditional,
+
+    /// Data is not pushed by minimal operation.
+    #[error("Data push larger than necessary")]
+    MinimalData,
+
+    /// Stack element is not a valid script number.
+    #[error("Invalid number encoding")]
+    InvalidNumber,
+
+    /// Result of arithmetic operation is out of range.
+    #[error("Integer overflow")]
+    NumberOverflow,
+
+    /// Division by zero.
+    #[error("Division by zero error")]
+    DivByZero,
+
+    /// Modulo by zero.
+    #[error("Modulo by zero error")]
+    ModByZero,
+
+    /// Invalid range of `OP_SPLIT`.
+    #[error("Invalid OP_SPLIT range")]
+    InvalidSplitRange,
+
+    /// Operands of bitwise operation have different sizes.
+    #[error("Invalid operand size")]
+    InvalidOperandSize,
+
+    /// Number can not be encoded in requested size.
+    #[error("The requested encoding is impossible to satisfy")]
+    ImpossibleEncoding,
+
+    /// `scriptSig` contains non-push operation.
+    #[error("Only push operators allowed in signatures")]
+    SigPushOnly,
+
+    /// Stack has extra elements after evaluation.
+    #[error("Extra items left on stack after execution")]
+    CleanStack,
+
+    /// Signature check failed with non-empty signature.
+    #[error("Signature must be zero for failed CHECK(MULTI)SIG operation")]
+    NullFail,
+
+    /// Invalid bitfield of Schnorr multisig.
+    #[error("Bitfield of unexpected size or popcount")]
+    InvalidBitfield,
+
+    /// Lock-time is negative.
+    #[error("Negative locktime")]
+    NegativeLockTime,
+
+    /// Lock-time requirement is not satisfied.
+    #[error("Locktime requirement not satisfied")]
+    UnsatisfiedLockTime,
+}
+
+/// Flags selecting script rules of network upgrades, combined by `|`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::interpreter::{eval_script_with_flags, ScriptFlags, SignatureChecker};
+/// # struct NoSig;
+/// # impl SignatureChecker for NoSig {
+/// #     fn check_sig(&self, _: &[u8], _: &[u8], _: &[u8]) -> bool { false }
+/// #     fn check_data_sig(&self, _: &[u8], _: &[u8], _: &[u8]) -> bool { false }
+/// # }
+/// // 2 3 OP_MUL
+/// let script = hex!("525395");
+/// let mut stack = vec![];
+/// eval_script_with_flags(&mut stack, &script, &NoSig, ScriptFlags::MAY_2022)?;
+/// assert_eq!(stack, vec![vec![6]]);
+/// assert!(eval_script_with_flags(&mut vec![], &script, &NoSig, ScriptFlags::PRE_MAY_2022).is_err());
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScriptFlags(u32);
+
+impl ScriptFlags {
+    /// `OP_MUL` is enabled
+    pub const ENABLE_MUL: ScriptFlags = ScriptFlags(1 << 0);
+    /// script numbers are 64-bit, otherwise 32-bit
+    pub const ENABLE_64_BIT_INTEGERS: ScriptFlags = ScriptFlags(1 << 1);
+
+    /// rules before May 2022 upgrade
+    pub const PRE_MAY_2022: ScriptFlags = ScriptFlags(0);
+    /// rules since May 2022 upgrade (default)
+    pub const MAY_2022: ScriptFlags = ScriptFlags(Self::ENABLE_MUL.0 | Self::ENABLE_64_BIT_INTEGERS.0);
+
+    /// Check if all of `flags` are set
+    pub fn contains(self, flags: ScriptFlags) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+
+    /// Maximum size of script number operand
+    pub fn max_num_size(self) -> usize {
+        if self.contains(ScriptFlags::ENABLE_64_BIT_INTEGERS) { MAX_NUM_SIZE } else { LEGACY_MAX_NUM_SIZE }
+    }
+}
+
+impl Default for ScriptFlags {
+    fn default() -> ScriptFlags {
+        ScriptFlags::MAY_2022
+    }
+}
+
+impl BitOr for ScriptFlags {
+    type Output = ScriptFlags;
+
+    fn bitor(self, rhs: ScriptFlags) -> ScriptFlags {
+        ScriptFlags(self.0 | rhs.0)
+    }
+}
+
+/// Checker of signatures and lock-time used by interpreter
+pub trait SignatureChecker {
+    /// Check transaction signature
+    /// # Arguments
+    /// * `sig` - signature with sighash type
+    /// * `pubkey` - `public key`
+    /// * `script_code` - script after the last executed `OP_CODESEPARATOR`
+    fn check_sig(&self, sig: &[u8], pubkey: &[u8], script_code: &[u8]) -> bool;
+
+    /// Check signature of `OP_CHECKDATASIG`
+    /// # Arguments
+    /// * `sig` - signature
+    /// * `msg` - message (not hashed)
+    /// * `pubkey` - `public key`
+    fn check_data_sig(&self, sig: &[u8], msg: &[u8], pubkey: &[u8]) -> bool;
+
+    /// Check absolute lock-time of `OP_CHECKLOCKTIMEVERIFY`
+    fn check_lock_time(&self, _lock_time: i64) -> bool {
+        false
+    }
+
+    /// Check relative lock-time of `OP_CHECKSEQUENCEVERIFY`
+    fn check_sequence(&self, _sequence: i64) -> bool {
+        false
+    }
+}
+
+fn decode_num(v: &[u8], max_size: usize) -> EvalResult<i64> {
+    num::decode(v, max_size).map_err(|_| ScriptError::InvalidNumber)
+}
+
+fn check_num(n: Option<i64>) -> EvalResult<i64> {
+    match n {
+        Some(n) if n != i64::MIN => Ok(n),
+        _ => Err(ScriptError::NumberOverflow),
+    }
+}
+
+/// Strip redundant padding of little endian sign-magnitude number
+fn minimally_encode(mut v: Vec<u8>) -> Vec<u8> {
+    let last = match v.last() {
+        Some(last) => *last,
+        None => return v,
+    };
+    if last & 0x7f != 0 || (v.len() > 1 && v[v.len() - 2] & 0x80 != 0) {
+        return v;
+    }
+
+    match v[..v.len() - 1].iter().rposition(|b| *b != 0) {
+        Some(i) if v[i] & 0x80 != 0 => {
+            v[i + 1] = last;
+            v.truncate(i + 2);
+        },
+        Some(i) => {
+            v[i] |= last;
+            v.truncate(i + 1);
+        },
+        None => v.clear(),
+    }
+
+    v
+}
+
+fn cast_to_bool(v: &[u8]) -> bool {
+    v.iter().enumerate().any(|(i, b)| *b != 0 && !(i == v.len() - 1 && *b == 0x80))
+}
+
+fn pop(stack: &mut Stack) -> EvalResult<Vec<u8>> {
+    stack.pop().ok_or(ScriptError::InvalidStackOperation)
+}
+
+fn pop_num(stack: &mut Stack, max_size: usize) -> EvalResult<i64> {
+    decode_num(&pop(stack)?, max_size)
+}
+
+fn pop_bool(stack: &mut Stack) -> EvalResult<bool> {
+    Ok(cast_to_bool(&pop(stack)?))
+}
+
+/// Get element at `depth` from the top (`1` is the top)
+fn peek(stack: &Stack, depth: usize) -> EvalResult<&Vec<u8>> {
+    if depth == 0 || depth > stack.len() {
+        return Err(ScriptError::InvalidStackOperation);
+    }
+    Ok(&stack[stack.len() - depth])
+}
+
+fn require(stack: &Stack, n: usize) -> EvalResult<()> {
+    peek(stack, n).map(|_| ())
+}
+
+fn push_bool(stack: &mut Stack, b: bool) {
+    stack.push(if b { vec![1] } else { vec![] });
+}
+
+fn is_disabled(op: OpCode, flags: ScriptFlags) -> bool {
+    op.is_disabled() || (op == OP_MUL && !flags.contains(ScriptFlags::ENABLE_MUL))
+}
+
+fn check_multisig<C: SignatureChecker>(stack: &mut Stack, script_code: &[u8], checker: &C, op_count: &mut usize, num_size: usize) -> EvalResult<bool> {
+    let n = pop_num(stack, num_size)?;
+    if n < 0 || n as usize > MAX_PUBKEYS_PER_MULTISIG {
+        return Err(ScriptError::PubKeyCount);
+    }
+    *op_count += n as usize;
+    if *op_count > MAX_OPS_PER_SCRIPT {
+        return Err(ScriptError::OpCount);
+    }
+    let mut pubkeys = (0..n).map(|_| pop(stack)).collect::<EvalResult<Vec<_>>>()?;
+    pubkeys.reverse();
+
+    let m = pop_num(stack, num_size)?;
+    if m < 0 || m > n {
+        return Err(ScriptError::SigCount);
+    }
+    let mut sigs = (0..m).map(|_| pop(stack)).collect::<EvalResult<Vec<_>>>()?;
+    sigs.reverse();
+
+    let dummy = pop(stack)?;
+    if dummy.is_empty() {
+        // legacy mode, ECDSA signatures are checked against remaining public keys in order
+        let mut keys = pubkeys.iter();
+        let success = sigs.iter().all(|sig| {
+            sig.len() != SCHNORR_TX_SIG_LEN &&
+            keys.any(|key| checker.check_sig(sig, key, script_code))
+        });
+        if !success && sigs.iter().any(|sig| !sig.is_empty()) {
+            return Err(ScriptError::NullFail);
+        }
+        return Ok(success);
+    }
+
+    // Schnorr mode, bitfield selects public keys of signatures
+    if dummy.len() != pubkeys.len().div_ceil(8) {
+        return Err(ScriptError::InvalidBitfield);
+    }
+    let bitfield = dummy.iter().enumerate().fold(0u32, |b, (i, v)| b | u32::from(*v) << (8 * i));
+    if bitfield >> pubkeys.len() != 0 || bitfield.count_ones() as usize != sigs.len() {
+        return Err(ScriptError::InvalidBitfield);
+    }
+    let keys = pubkeys.iter().enumerate().filter(|(i, _)| bitfield >> i & 1 == 1).map(|(_, key)| key);
+    for (sig, key) in sigs.iter().zip(keys) {
+        if sig.len() != SCHNORR_TX_SIG_LEN || !checker.check_sig(sig, key, script_code) {
+            return Err(ScriptError::NullFail);
+        }
+    }
+
+    Ok(true)
+}
+
+fn eval<C: SignatureChecker>(stack: &mut Stack, script: &[u8], checker: &C, flags: ScriptFlags) -> EvalResult<()> {
+    if script.len() > MAX_SCRIPT_SIZE {
+        return Err(ScriptError::ScriptSize(script.len()));
+    }
+    let num_size = flags.max_num_size();
+
+    let mut alt_stack = Stack::new();
+    let mut exec = Vec::<bool>::new();
+    let mut op_count = 0;
+    let mut script_code = script;
+    let mut cur = script;
+
+    while !cur.is_empty() {
+        let executing = exec.iter().all(|e| *e);
+        let (op, next) = match get_opcode(cur) {
+            Some(op) => op,
+            None if cur[0] > OP_PUSHDATA4 as u8 && !executing => {
+                cur = &cur[1..];
+                continue;
+            },
+            None => return Err(ScriptError::BadOpCode(cur[0])),
+        };
+        let raw = &cur[..cur.len() - next.len()];
+        cur = next;
+
+        let op = match op {
+            Script::OpCode(op) => op,
+            script => {
+                let data = script.data().unwrap_or_default();
+                if data.len() > MAX_SCRIPT_ELEMENT_SIZE {
+                    return Err(ScriptError::PushSize(data.len()));
+                }
+                if executing {
+                    if encode(&[Script::Data(data)]).ok().as_deref() != Some(raw) {
+                        return Err(ScriptError::MinimalData);
+                    }
+                    stack.push(data.to_vec());
+                    if stack.len() + alt_stack.len() > MAX_STACK_SIZE {
+                        return Err(ScriptError::StackSize);
+                    }
+                }
+                continue;
+            },
+        };
+
+        if op > OP_16 {
+            op_count += 1;
+            if op_count > MAX_OPS_PER_SCRIPT {
+                return Err(ScriptError::OpCount);
+            }
+        }
+        if is_disabled(op, flags) {
+            return Err(ScriptError::DisabledOpCode(op));
+        }
+        if !executing && !op.is_conditional() {
+            continue;
+        }
+
+        match op {
+            OP_1NEGATE | OP_1 | OP_2 | OP_3 | OP_4 | OP_5 | OP_6 | OP_7 | OP_8 |
+            OP_9 | OP_10 | OP_11 | OP_12 | OP_13 | OP_14 | OP_15 | OP_16 => {
+                stack.push(num::encode(op as i64 - OP_RESERVED as i64));
+            },
+
+            // control
+            OP_NOP | OP_NOP1 | OP_NOP4 | OP_NOP5 | OP_NOP6 | OP_NOP7 | OP_NOP8 | OP_NOP9 | OP_NOP10 => {},
+            OP_CHECKLOCKTIMEVERIFY => {
+                let lock_time = decode_num(peek(stack, 1)?, MAX_LOCK_TIME_NUM_SIZE)?;
+                if lock_time < 0 {
+                    return Err(ScriptError::NegativeLockTime);
+                }
+                if !checker.check_lock_time(lock_time) {
+                    return Err(ScriptError::UnsatisfiedLockTime);
+                }
+            },
+            OP_CHECKSEQUENCEVERIFY => {
+                let sequence = decode_num(peek(stack, 1)?, MAX_LOCK_TIME_NUM_SIZE)?;
+                if sequence < 0 {
+                    return Err(ScriptError::NegativeLockTime);
+                }
+                if sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG == 0 && !checker.check_sequence(sequence) {
+                    return Err(ScriptError::UnsatisfiedLockTime);
+                }
+            },
+            OP_IF | OP_NOTIF => {
+                let value = if executing {
+                    let value = pop(stack).map_err(|_| ScriptError::UnbalancedConditional)?;
+                    cast_to_bool(&value) == (op == OP_IF)
+                } else {
+                    false
+                };
+                exec.push(value);
+            },
+            OP_ELSE => {
+                let last = exec.last_mut().ok_or(ScriptError::UnbalancedConditional)?;
+                *last = !*last;
+            },
+            OP_ENDIF => {
+                exec.pop().ok_or(ScriptError::UnbalancedConditional)?;
+            },
+            OP_VERIFY => {
+                if !pop_bool(stack)? {
+                    return Err(ScriptError::Verify(op));
+                }
+            },
+            OP_RETURN => return Err(ScriptError::OpReturn),
+
+            // stack ops
+            OP_TOALTSTACK => {
+                let v = pop(stack)?;
+                alt_stack.push(v);
+            },
+            OP_FROMALTSTACK => {
+                let v = alt_stack.pop().ok_or(ScriptError::InvalidAltstackOperation)?;
+                stack.push(v);
+            },
+            OP_2DROP => {
+                require(stack, 2)?;
+                stack.truncate(stack.len() - 2);
+            },
+            OP_2DUP | OP_3DUP | OP_2OVER => {
+                let (depth, n) = match op {
+                    OP_2DUP => (2, 2),
+                    OP_3DUP => (3, 3),
+                    _ => (4, 2),
+                };
+                require(stack, depth)?;
+                let start = stack.len() - depth;
+                let items = stack[start..start + n].to_vec();
+                stack.extend(items);
+            },
+            OP_2ROT => {
+                require(stack, 6)?;
+                let start = stack.len() - 6;
+                let items = stack.drain(start..start + 2).collect::<Vec<_>>();
+                stack.extend(items);
+            },
+            OP_2SWAP => {
+                require(stack, 4)?;
+                let len = stack.len();
+                stack.swap(len - 4, len - 2);
+                stack.swap(len - 3, len - 1);
+            },
+            OP_IFDUP => {
+                let v = peek(stack, 1)?.clone();
+                if cast_to_bool(&v) {
+                    stack.push(v);
+                }
+            },
+            OP_DEPTH => {
+                stack.push(num::encode(stack.len() as i64));
+            },
+            OP_DROP => {
+                pop(stack)?;
+            },
+            OP_DUP => {
+                let v = peek(stack, 1)?.clone();
+                stack.push(v);
+            },
+            OP_NIP => {
+                require(stack, 2)?;
+                stack.remove(stack.len() - 2);
+            },
+            OP_OVER => {
+                let v = peek(stack, 2)?.clone();
+                stack.push(v);
+            },
+            OP_PICK | OP_ROLL => {
+                let n = pop_num(stack, num_size)?;
+                if n < 0 || n as usize >= stack.len() {
+                    return Err(ScriptError::InvalidStackOperation);
+                }
+                let i = stack.len() - 1 - n as usize;
+                let v = if op == OP_ROLL { stack.remove(i) } else { stack[i].clone() };
+                stack.push(v);
+            },
+            OP_ROT => {
+                require(stack, 3)?;
+                let v = stack.remove(stack.len() - 3);
+                stack.push(v);
+            },
+            OP_SWAP => {
+                require(stack, 2)?;
+                let len = stack.len();
+                stack.swap(len - 2, len - 1);
+            },
+            OP_TUCK => {
+                let v = peek(stack, 1)?.clone();
+                require(stack, 2)?;
+                stack.insert(stack.len() - 2, v);
+            },
+
+            // splice ops
+            OP_CAT => {
+                let b = pop(stack)?;
+                let mut a = pop(stack)?;
+                if a.len() + b.len() > MAX_SCRIPT_ELEMENT_SIZE {
+                    return Err(ScriptError::PushSize(a.len() + b.len()));
+                }
+                a.extend(b);
+                stack.push(a);
+            },
+            OP_SPLIT => {
+                let n = pop_num(stack, num_size)?;
+                let mut a = pop(stack)?;
+                if n < 0 || n as usize > a.len() {
+                    return Err(ScriptError::InvalidSplitRange);
+                }
+                let b = a.split_off(n as usize);
+                stack.push(a);
+                stack.push(b);
+            },
+            OP_NUM2BIN => {
+                let size = pop_num(stack, num_size)?;
+                if size < 0 || size as usize > MAX_SCRIPT_ELEMENT_SIZE {
+                    return Err(ScriptError::PushSize(size as usize));
+                }
+                let mut v = minimally_encode(pop(stack)?);
+                if v.len() > size as usize {
+                    return Err(ScriptError::ImpossibleEncoding);
+                }
+                if let Some(last) = v.last_mut() {
+                    let sign = *last & 0x80;
+                    *last &= 0x7f;
+                    v.resize(size as usize, 0);
+                    *v.last_mut().expect("size is not less than length") |= sign;
+                } else {
+                    v.resize(size as usize, 0);
+                }
+                stack.push(v);
+            },
+            OP_BIN2NUM => {
+                let v = minimally_encode(pop(stack)?);
+                if v.len() > num_size {
+                    return Err(ScriptError::InvalidNumber);
+                }
+                stack.push(v);
+            },
+            OP_SIZE => {
+                let len = peek(stack, 1)?.len();
+                stack.push(num::encode(len as i64));
+            },
+
+            // bit logic
+            OP_AND | OP_OR | OP_XOR => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
+                if a.len() != b.len() {
+                    return Err(ScriptError::InvalidOperandSize);
+                }
+                let v = a.iter().zip(b.iter()).map(|(a, b)| match op {
+                    OP_AND => a & b,
+                    OP_OR => a | b,
+                    _ => a ^ b,
+                }).collect();
+                stack.push(v);
+            },
+            OP_EQUAL | OP_EQUALVERIFY => {
+                let b = pop(stack)?;
+                let a = pop(stack)?;
+                if op == OP_EQUAL {
+                    push_bool(stack, a == b);
+                } else if a != b {
+                    return Err(ScriptError::Verify(op));
+                }
+            },
+
+            // numeric
+            OP_1ADD | OP_1SUB | OP_NEGATE | OP_ABS | OP_NOT | OP_0NOTEQUAL => {
+                let a = pop_num(stack, num_size)?;
+                let n = match op {
+                    OP_1ADD => check_num(a.checked_add(1))?,
+                    OP_1SUB => check_num(a.checked_sub(1))?,
+                    OP_NEGATE => -a,
+                    OP_ABS => a.abs(),
+                    OP_NOT => (a == 0) as i64,
+                    _ => (a != 0) as i64,
+                };
+                stack.push(num::encode(n));
+            },
+            OP_ADD | OP_SUB | OP_MUL | OP_DIV | OP_MOD | OP_BOOLAND | OP_BOOLOR |
+            OP_NUMEQUAL | OP_NUMEQUALVERIFY | OP_NUMNOTEQUAL | OP_LESSTHAN | OP_GREATERTHAN |
+            OP_LESSTHANOREQUAL | OP_GREATERTHANOREQUAL | OP_MIN | OP_MAX => {
+                let b = pop_num(stack, num_size)?;
+                let a = pop_num(stack, num_size)?;
+                let n = match op {
+                    OP_ADD => check_num(a.checked_add(b))?,
+                    OP_SUB => check_num(a.checked_sub(b))?,
+                    OP_MUL => check_num(a.checked_mul(b))?,
+                    OP_DIV if b == 0 => return Err(ScriptError::DivByZero),
+                    OP_DIV => a / b,
+                    OP_MOD if b == 0 => return Err(ScriptError::ModByZero),
+                    OP_MOD => a % b,
+                    OP_BOOLAND => (a != 0 && b != 0) as i64,
+                    OP_BOOLOR => (a != 0 || b != 0) as i64,
+                    OP_NUMEQUAL | OP_NUMEQUALVERIFY => (a == b) as i64,
+                    OP_NUMNOTEQUAL => (a != b) as i64,
+                    OP_LESSTHAN => (a < b) as i64,
+                    OP_GREATERTHAN => (a > b) as i64,
+                    OP_LESSTHANOREQUAL => (a <= b) as i64,
+                    OP_GREATERTHANOREQUAL => (a >= b) as i64,
+                    OP_MIN => a.min(b),
+                    _ => a.max(b),
+                };
+                if op == OP_NUMEQUALVERIFY {
+                    if n == 0 {
+                        return Err(ScriptError::Verify(op));
+                    }
+                } else {
+                    stack.push(num::encode(n));
+                }
+            },
+            OP_WITHIN => {
+                let max = pop_num(stack, num_size)?;
+                let min = pop_num(stack, num_size)?;
+                let x = pop_num(stack, num_size)?;
+                push_bool(stack, min <= x && x < max);
+            },
+
+            // crypto
+            OP_RIPEMD160 | OP_SHA1 | OP_SHA256 | OP_HASH160 | OP_HASH256 => {
+                let v = pop(stack)?;
+                let h = match op {
+                    OP_RIPEMD160 => Ripemd160::digest(&v).to_vec(),
+                    OP_SHA1 => Sha1::digest(&v).to_vec(),
+                    OP_SHA256 => hash::sha256(&v),
+                    OP_HASH160 => hash::hash160(&v),
+                    _ => hash::hash256(&v).to_vec(),
+                };
+                stack.push(h);
+            },
+            OP_CODESEPARATOR => {
+                script_code = cur;
+            },
+            OP_CHECKSIG | OP_CHECKSIGVERIFY => {
+                let pubkey = pop(stack)?;
+                let sig = pop(stack)?;
+                let success = !sig.is_empty() && checker.check_sig(&sig, &pubkey, script_code);
+                if !success && !sig.is_empty() {
+                    return Err(ScriptError::NullFail);
+                }
+                if op == OP_CHECKSIG {
+                    push_bool(stack, success);
+                } else if !success {
+                    return Err(ScriptError::Verify(op));
+                }
+            },
+            OP_CHECKDATASIG | OP_CHECKDATASIGVERIFY => {
+                let pubkey = pop(stack)?;
+                let msg = pop(stack)?;
+                let sig = pop(stack)?;
+                let success = !sig.is_empty() && checker.check_data_sig(&sig, &msg, &pubkey);
+                if !success && !sig.is_empty() {
+                    return Err(ScriptError::NullFail);
+                }
+                if op == OP_CHECKDATASIG {
+                    push_bool(stack, success);
+                } else if !success {
+                    return Err(ScriptError::Verify(op));
+                }
+            },
+            OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY => {
+                let success = check_multisig(stack, script_code, checker, &mut op_count, num_size)?;
+                if op == OP_CHECKMULTISIG {
+                    push_bool(stack, success);
+                } else if !success {
+                    return Err(ScriptError::Verify(op));
+                }
+            },
+
+            _ => return Err(ScriptError::BadOpCode(op as u8)),
+        }
+
+        if stack.len() + alt_stack.len() > MAX_STACK_SIZE {
+            return Err(ScriptError::StackSize);
+        }
+    }
+
+    if !exec.is_empty() {
+        return Err(ScriptError::UnbalancedConditional);
+    }
+
+    Ok(())
+}
+
+/// Evaluate script on stack
+/// # Arguments
+/// * `stack` - stack
+/// * `script` - raw script
+/// * `checker` - signature checker
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::interpreter::{eval_script, SignatureChecker};
+/// struct NoSig;
+/// impl SignatureChecker for NoSig {
+///     fn check_sig(&self, _: &[u8], _: &[u8], _: &[u8]) -> bool { false }
+///     fn check_data_sig(&self, _: &[u8], _: &[u8], _: &[u8]) -> bool { false }
+/// }
+///
+/// let mut stack = vec![];
+/// // 2 3 OP_ADD
+/// eval_script(&mut stack, &hex!("525393"), &NoSig)?;
+/// assert_eq!(stack, vec![vec![5]]);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn eval_script<C: SignatureChecker>(stack: &mut Stack, script: &[u8], checker: &C) -> Result<()> {
+    eval_script_with_flags(stack, script, checker, ScriptFlags::default())
+}
+
+/// Evaluate script on stack under rules selected by `flags`
+/// # Arguments
+/// * `stack` - stack
+/// * `script` - raw script
+/// * `checker` - signature checker
+/// * `flags` - script rules
+pub fn eval_script_with_flags<C: SignatureChecker>(stack: &mut Stack, script: &[u8], checker: &C, flags: ScriptFlags) -> Result<()> {
+    Ok(eval(stack, script, checker, flags)?)
+}
+
+/// Verify `scriptSig` against `scriptPubKey` (including P2SH evaluation)
+/// # Arguments
+/// * `script_sig` - `scriptSig`
+/// * `script_pub_key` - `scriptPubKey`
+/// * `checker` - signature checker
+pub fn verify_script<C: SignatureChecker>(script_sig: &[u8], script_pub_key: &[u8], checker: &C) -> Result<()> {
+    verify_script_with_flags(script_sig, script_pub_key, checker, ScriptFlags::default())
+}
+
+/// Verify `scriptSig` against `scriptPubKey` under rules selected by `flags`
+/// # Arguments
+/// * `script_sig` - `scriptSig`
+/// * `script_pub_key` - `scriptPubKey`
+/// * `checker` - signature checker
+/// * `flags` - script rules
+pub fn verify_script_with_flags<C: SignatureChecker>(script_sig: &[u8], script_pub_key: &[u8], checker: &C, flags: ScriptFlags) -> Result<()> {
+    if !is_push_only(script_sig) {
+        return Err(ScriptError::SigPushOnly.into());
+    }
+
+    let mut stack = Stack::new();
+    eval(&mut stack, script_sig, checker, flags)?;
+    let mut p2sh_stack = stack.clone();
+
+    eval(&mut stack, script_pub_key, checker, flags)?;
+    if !matches!(stack.last(), Some(v) if cast_to_bool(v)) {
+        return Err(ScriptError::EvalFalse.into());
+    }
+
+    if p2sh::is_p2sh(script_pub_key) {
+        let redeem_script = pop(&mut p2sh_stack)?;
+        eval(&mut p2sh_stack, &redeem_script, checker, flags)?;
+        if !matches!(p2sh_stack.last(), Some(v) if cast_to_bool(v)) {
+            return Err(ScriptError::EvalFalse.into());
+        }
+        stack = p2sh_stack;
+    }
+
+    if stack.len() != 1 {
+        return Err(ScriptError::CleanStack.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Error;
+    use super::super::script::{from_asm, multisig};
+
+    /// accepts signature `[0x01, pubkey[0], ..]` and data signature `[msg[0], pubkey[0]]`
+    struct DummyChecker;
+
+    impl SignatureChecker for DummyChecker {
+        fn check_sig(&self, sig: &[u8], pubkey: &[u8], _script_code: &[u8]) -> bool {
+            sig.len() >= 2 && sig[0] == 0x01 && sig[1] == pubkey[0]
+        }
+
+        fn check_data_sig(&self, sig: &[u8], msg: &[u8], pubkey: &[u8]) -> bool {
+            sig == [msg[0], pubkey[0]]
+        }
+
+        fn check_lock_time(&self, lock_time: i64) -> bool {
+            lock_time <= 100
+        }
+    }
+
+    fn run(asm: &str) -> EvalResult<Stack> {
+        run_with_flags(asm, ScriptFlags::default())
+    }
+
+    fn run_with_flags(asm: &str, flags: ScriptFlags) -> EvalResult<Stack> {
+        let mut stack = Stack::new();
+        eval(&mut stack, &from_asm(asm).unwrap(), &DummyChecker, flags)?;
+        Ok(stack)
+    }
+
+    fn script_error(r: Result<()>) -> ScriptError {
+        match r {
+            Err(Error::ScriptError(e)) => e,
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn minimal_encoding() {
+        assert_eq!(minimally_encode(vec![0x01, 0x00, 0x80]), vec![0x81]);
+        assert_eq!(minimally_encode(vec![0x80, 0x00, 0x00]), vec![0x80, 0x00]);
+        assert!(minimally_encode(vec![0x00, 0x80]).is_empty());
+    }
+
+    #[test]
+    fn eval_ops() -> EvalResult<()> {
+        assert_eq!(run("OP_2 OP_3 OP_ADD OP_5 OP_NUMEQUAL")?, vec![vec![1]]);
+        assert_eq!(run("OP_1NEGATE OP_ABS OP_16 OP_MUL OP_3 OP_MOD")?, vec![vec![1]]);
+        assert_eq!(run("OP_1 OP_IF OP_2 OP_ELSE OP_3 OP_ENDIF")?, vec![vec![2]]);
+        assert_eq!(run("OP_0 OP_NOTIF OP_0 OP_IF OP_RETURN OP_ENDIF OP_4 OP_ENDIF")?, vec![vec![4]]);
+        assert_eq!(run("OP_1 OP_2 OP_3 OP_ROT OP_2 OP_ROLL OP_DEPTH")?, vec![vec![3], vec![1], vec![2], vec![3]]);
+        assert_eq!(run("0102 0304 OP_CAT OP_1 OP_SPLIT OP_SWAP OP_SIZE")?, vec![vec![2, 3, 4], vec![1], vec![1]]);
+        assert_eq!(run("OP_1NEGATE OP_4 OP_NUM2BIN OP_DUP OP_BIN2NUM")?, vec![vec![1, 0, 0, 0x80], vec![0x81]]);
+        assert_eq!(run("0f0f f0f0 OP_XOR")?, vec![vec![0xff, 0xff]]);
+        assert_eq!(run("OP_0 OP_SHA256 OP_SIZE OP_NIP")?, vec![vec![32]]);
+        assert_eq!(run("OP_3 OP_2 OP_5 OP_WITHIN OP_TOALTSTACK OP_FROMALTSTACK")?, vec![vec![1]]);
+
+        assert_eq!(run("OP_RETURN"), Err(ScriptError::OpReturn));
+        assert_eq!(run("OP_0 OP_IF OP_2MUL OP_ENDIF"), Err(ScriptError::DisabledOpCode(OP_2MUL)));
+        assert_eq!(run("OP_1 OP_IF"), Err(ScriptError::UnbalancedConditional));
+        assert_eq!(run("OP_ENDIF"), Err(ScriptError::UnbalancedConditional));
+        assert_eq!(run("01"), Err(ScriptError::MinimalData));
+        assert_eq!(run("OP_PUSHDATA1 02"), Err(ScriptError::MinimalData));
+        assert_eq!(run("OP_DROP"), Err(ScriptError::InvalidStackOperation));
+        assert_eq!(run("OP_1 OP_0 OP_DIV"), Err(ScriptError::DivByZero));
+        assert_eq!(run("ffffffffffffff7f OP_1ADD"), Err(ScriptError::NumberOverflow));
+        assert_eq!(run("OP_1 OP_2 OP_EQUALVERIFY"), Err(ScriptError::Verify(OP_EQUALVERIFY)));
+        assert_eq!(run("OP_1 OP_2 OP_SPLIT"), Err(ScriptError::InvalidSplitRange));
+        assert_eq!(run("OP_16 OP_CHECKLOCKTIMEVERIFY")?, vec![vec![16]]);
+        assert_eq!(run("0165 OP_CHECKLOCKTIMEVERIFY"), Err(ScriptError::UnsatisfiedLockTime));
+        assert_eq!(run("OP_1 OP_HASH160 OP_NOP OP_RESERVED"), Err(ScriptError::BadOpCode(OP_RESERVED as u8)));
+
+        // data pushes count towards stack size
+        assert_eq!(run(&vec!["11"; MAX_STACK_SIZE].join(" "))?.len(), MAX_STACK_SIZE);
+        assert_eq!(run(&vec!["11"; MAX_STACK_SIZE + 1].join(" ")), Err(ScriptError::StackSize));
+        assert_eq!(run(&vec!["OP_1"; MAX_STACK_SIZE + 1].join(" ")), Err(ScriptError::StackSize));
+
+        Ok(())
+    }
+
+    #[test]
+    fn flags() -> EvalResult<()> {
+        let legacy = ScriptFlags::PRE_MAY_2022;
+        assert_eq!(run_with_flags("OP_2 OP_3 OP_MUL", legacy), Err(ScriptError::DisabledOpCode(OP_MUL)));
+        assert_eq!(run_with_flags("OP_0 OP_IF OP_MUL OP_ENDIF", legacy), Err(ScriptError::DisabledOpCode(OP_MUL)));
+        assert_eq!(run_with_flags("OP_2 OP_3 OP_MUL", legacy | ScriptFlags::ENABLE_MUL)?, vec![vec![6]]);
+
+        // 2^31 needs 5 bytes
+        assert_eq!(run_with_flags("0000008000 OP_1ADD", legacy), Err(ScriptError::InvalidNumber));
+        assert_eq!(run("0000008000 OP_1ADD")?, vec![vec![0x01, 0x00, 0x00, 0x80, 0x00]]);
+        // results may exceed 4 bytes but can not be used as operands
+        assert_eq!(run_with_flags("ffffff7f OP_1ADD", legacy)?, vec![vec![0x00, 0x00, 0x00, 0x80, 0x00]]);
+        assert_eq!(run_with_flags("0000008000 OP_BIN2NUM", legacy), Err(ScriptError::InvalidNumber));
+
+        assert_eq!(ScriptFlags::default(), ScriptFlags::MAY_2022);
+        assert!(ScriptFlags::MAY_2022.contains(ScriptFlags::ENABLE_64_BIT_INTEGERS));
+        assert_eq!(legacy.max_num_size(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_sig() -> Result<()> {
+        assert_eq!(run("0102 0200 OP_CHECKSIG")?, vec![vec![1]]);
+        assert_eq!(run("OP_0 0200 OP_CHECKSIG")?, vec![Vec::<u8>::new()]);
+        assert_eq!(run("0103 0200 OP_CHECKSIG"), Err(ScriptError::NullFail));
+        assert!(run("0304 0300 0400 OP_CHECKDATASIGVERIFY")?.is_empty());
+
+        // 2-of-3 legacy multisig
+        let pubkeys = [&[0x02, 0x00][..], &[0x03, 0x00][..], &[0x04, 0x00][..]];
+        let redeem_script = multisig::redeem_script(2, &pubkeys)?;
+        let script_pub_key = multisig::script_pub_key(&redeem_script)?;
+        let script_sig = multisig::script_sig(&[&[0x01, 0x02], &[0x01, 0x04]], &redeem_script)?;
+        verify_script(&script_sig, &script_pub_key, &DummyChecker)?;
+
+        let script_sig = multisig::script_sig(&[&[0x01, 0x04], &[0x01, 0x02]], &redeem_script)?;
+        assert_eq!(script_error(verify_script(&script_sig, &script_pub_key, &DummyChecker)), ScriptError::NullFail);
+
+        // Schnorr multisig selects 1st and 3rd public keys by bitfield
+        let schnorr_sig = |key: u8| [&[0x01, key][..], &[0; 63]].concat();
+        let script_sig = encode(&[
+            Script::Data(&[0x05]),
+            Script::Data(&schnorr_sig(0x02)),
+            Script::Data(&schnorr_sig(0x04)),
+            Script::Data(&redeem_script),
+        ])?;
+        verify_script(&script_sig, &script_pub_key, &DummyChecker)?;
+
+        let script_sig = encode(&[Script::Data(&[0x03]), Script::Data(&schnorr_sig(0x02)), Script::Data(&schnorr_sig(0x04)), Script::Data(&redeem_script)])?;
+        assert_eq!(script_error(verify_script(&script_sig, &script_pub_key, &DummyChecker)), ScriptError::NullFail);
+
+        // push only and clean stack
+        assert_eq!(script_error(verify_script(&from_asm("OP_1 OP_DUP")?, &[0x51], &DummyChecker)), ScriptError::SigPushOnly);
+        assert_eq!(script_error(verify_script(&[0x51, 0x51], &[0x51], &DummyChecker)), ScriptError::CleanStack);
+        assert_eq!(script_error(verify_script(&[], &[0x00], &DummyChecker)), ScriptError::EvalFalse);
+
+        Ok(())
+    }
+}
@@ -9,7 +9,7 @@
 //! ```
 //! #[macro_use] extern crate hex_literal;
 //! use bch_addr::{AddressType, Converter};
-//! use cash_tx_builder::{TxBuilder, sig_hash};
+//! use cash_tx_builder::{TxBuilder, sig_hash, Amount};
 //! use cash_tx_builder::script::{address_to_script, p2pkh};
 //! 
 //! let converter = Converter::new();
@@ -23,15 +23,15 @@
 //!     }
 //! };
 //! let mut txb = TxBuilder::new(&parser);
-//! let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c";
+//! let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
 //! let prev_index = 1;
 //! let prev_script = address_to_script("qq6zfutryz9rkem05rkpwq60pu5sxg4z5c330k4w75", &parser)?;
 //! let prev_value = 100_000;
 //! 
 //! txb.add_input(prev_txid, prev_index, Some(prev_value), Some(&prev_script), None)?;
 //! 
-//! txb.add_address_output(11000, "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
-//! txb.add_address_output(88757, "qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70")?;
+//! txb.add_address_output(Amount::from_sat(11000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+//! txb.add_address_output(Amount::from_sat(88757), "qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70")?;
 //! 
 //! let script_sig = p2pkh::script_sig(
 //!     &hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036"),
@@ -44,7 +44,7 @@
 //! let txid = txb.txid();
 //! 
 //! assert_eq!(sighash, hex!("2b492e7c4c8a3d670fd7fe324a87e3c55df1802c9a100f4006f8fff7c0913dd4"));
-//! assert_eq!(txid, "ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda");
+//! assert_eq!(txid.to_string(), "ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda");
 //! # Ok::<(), cash_tx_builder::Error>(())
 //! ```
 
@@ -52,8 +52,46 @@ mod tx_builder;
 mod error;
 mod opcode;
 pub mod script;
-mod hash;
+pub mod slp;
+pub mod protocols;
+pub mod templates;
+pub mod pst;
+pub mod assembly;
+pub mod draft;
+pub mod verify;
+pub mod policy;
+pub mod metrics;
+pub mod mempool;
+pub mod interpreter;
+pub mod hash;
+pub mod provider;
+pub mod network;
+pub mod uri;
 mod bit_util;
+#[cfg(feature = "sign")]
+pub mod signer;
+#[cfg(feature = "hd")]
+pub mod hd;
+#[cfg(feature = "address")]
+pub mod address;
+#[cfg(any(feature = "hd", feature = "address"))]
+mod base58;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "electrum")]
+pub mod electrum;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(any(feature = "rpc", feature = "chaingraph"))]
+mod http;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "template")]
+pub mod template;
+#[cfg(feature = "payment-protocol")]
+pub mod payment_protocol;
 /// Types for transaction
 pub mod types;
 
@@ -68,5 +106,12 @@ extern crate hex_literal;
 
 pub use error::{Error, Result};
 pub use opcode::OpCode;
-pub use tx_builder::{TxBuilder, sig_hash};
+pub use tx_builder::{TxBuilder, BalanceReport, InputState, InputBuilder, OutputBuilder, sig_hash};
+pub use tx_builder::sig_hash::SigHashType;
 pub use types::*;
+pub use network::Network;
+#[cfg(feature = "sign")]
+pub use signer::Signer;
+#[cfg(feature = "sign")]
+pub use secp256k1;
+pub use rand_core;
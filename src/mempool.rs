@@ -0,0 +1,129 @@
+//! Mempool-lite tracking of spent outpoints
+//!
+//! Outpoints of transactions under construction are reserved,
+//! so that transactions built concurrently do not double-spend each other before broadcast.
+
+use std::collections::HashSet;
+use super::types::transaction::OutPoint;
+use super::error::{Error, Result};
+
+/// Set of outpoints spent by transactions not mined yet
+/// # Example
+/// ```
+/// # use cash_tx_builder::TxBuilder;
+/// # use cash_tx_builder::mempool::OutPointSet;
+/// let mut spent = OutPointSet::new();
+/// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+///
+/// let mut txb = TxBuilder::new(|_: &str| None);
+/// txb.add_input(prev_txid, 0, None, None, None)?;
+/// spent.insert(&txb.outpoints())?;
+///
+/// let mut other = TxBuilder::new(|_: &str| None);
+/// other.add_input(prev_txid, 1, None, None, None)?;
+/// other.add_input(prev_txid, 0, None, None, None)?;
+/// assert_eq!(spent.conflicts(&other.outpoints()), [1]);
+/// assert!(spent.insert(&other.outpoints()).is_err());
+///
+/// spent.remove(&txb.outpoints());
+/// spent.insert(&other.outpoints())?;
+/// assert_eq!(spent.len(), 2);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OutPointSet {
+    outpoints: HashSet<OutPoint>,
+}
+
+impl OutPointSet {
+    /// Construct empty set
+    pub fn new() -> OutPointSet {
+        OutPointSet::default()
+    }
+
+    /// Check if `outpoint` is spent
+    /// # Arguments
+    /// * `outpoint` - outpoint
+    pub fn contains(&self, outpoint: &OutPoint) -> bool {
+        self.outpoints.contains(outpoint)
+    }
+
+    /// Find outpoints which are already spent
+    /// # Arguments
+    /// * `outpoints` - outpoints of transaction inputs
+    /// # Returns
+    /// * indexes of conflicting `outpoints`
+    pub fn conflicts(&self, outpoints: &[OutPoint]) -> Vec<usize> {
+        outpoints.iter().enumerate()
+            .filter(|(_, o)| self.contains(o))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Mark outpoints spent
+    ///
+    /// Nothing is inserted if any outpoint is already spent or appears twice.
+    /// # Arguments
+    /// * `outpoints` - outpoints of transaction inputs
+    pub fn insert(&mut self, outpoints: &[OutPoint]) -> Result<()> {
+        let mut new = HashSet::with_capacity(outpoints.len());
+        for outpoint in outpoints {
+            if self.contains(outpoint) || !new.insert(outpoint.clone()) {
+                return Err(Error::DuplicateOutPoint(outpoint.txid, outpoint.n));
+            }
+        }
+        self.outpoints.extend(new);
+
+        Ok(())
+    }
+
+    /// Release outpoints, e.g. after the transaction is mined or dropped
+    /// # Arguments
+    /// * `outpoints` - outpoints of transaction inputs
+    pub fn remove(&mut self, outpoints: &[OutPoint]) {
+        for outpoint in outpoints {
+            self.outpoints.remove(outpoint);
+        }
+    }
+
+    /// Number of spent outpoints
+    pub fn len(&self) -> usize {
+        self.outpoints.len()
+    }
+
+    /// Check if no outpoint is spent
+    pub fn is_empty(&self) -> bool {
+        self.outpoints.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outpoint(i: u8) -> OutPoint {
+        OutPoint { txid: [i; 32].into(), n: u32::from(i) }
+    }
+
+    #[test]
+    fn insert() -> Result<()> {
+        let mut set = OutPointSet::new();
+        set.insert(&[outpoint(1), outpoint(2)])?;
+
+        match set.insert(&[outpoint(3), outpoint(3)]) {
+            Err(Error::DuplicateOutPoint(_, 3)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match set.insert(&[outpoint(3), outpoint(2)]) {
+            Err(Error::DuplicateOutPoint(_, 2)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(!set.contains(&outpoint(3)));
+        assert_eq!(set.conflicts(&[outpoint(3), outpoint(2), outpoint(1)]), [1, 2]);
+
+        set.remove(&[outpoint(1), outpoint(2)]);
+        assert!(set.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+//! Transaction metrics for logging and pre-broadcast policy checks
+
+use std::fmt;
+use super::policy::{self, DUST_RELAY_FEE_RATE};
+use super::types::Amount;
+use super::types::transaction::Transaction;
+use super::types::transaction::output::Output;
+
+/// Size, counts, fee and policy relevant figures of transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metrics {
+    /// size in bytes (estimated size after signing for `TxBuilder`)
+    pub size: usize,
+    /// number of inputs
+    pub input_count: usize,
+    /// number of outputs
+    pub output_count: usize,
+    /// fee (`None` if previous outputs are unknown or outputs exceed inputs)
+    pub fee: Option<Amount>,
+    /// fee rate in satoshi per byte (`None` if fee is unknown)
+    pub fee_rate: Option<f64>,
+    /// signature operations (`None` if previous outputs are unknown)
+    pub sigop_count: Option<usize>,
+    /// signature operations per byte (`None` if unknown)
+    pub sigop_density: Option<f64>,
+    /// indexes of dust outputs at `DUST_RELAY_FEE_RATE`
+    pub dust_outputs: Vec<usize>,
+}
+
+impl Metrics {
+    /// Measure transaction
+    /// # Arguments
+    /// * `tx` - transaction
+    /// * `prev_outputs` - (option) previous outputs in input order, required for fee and sigops
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::Amount;
+    /// # use cash_tx_builder::metrics::Metrics;
+    /// # use cash_tx_builder::types::transaction::Transaction;
+    /// # use cash_tx_builder::types::transaction::output::Output;
+    /// let tx = Transaction::from_hex("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000")?;
+    /// let metrics = Metrics::from_transaction(&tx, None);
+    /// assert_eq!((metrics.size, metrics.input_count, metrics.output_count), (225, 1, 2));
+    /// assert_eq!(metrics.fee, None);
+    ///
+    /// let prev_output = Output::new(Amount::from_sat(19_801_296), &tx.outputs[0].script);
+    /// let metrics = Metrics::from_transaction(&tx, Some(&[prev_output]));
+    /// assert_eq!(metrics.fee, Some(Amount::from_sat(2_025)));
+    /// assert_eq!(metrics.fee_rate, Some(9.0));
+    /// assert_eq!(metrics.sigop_count, Some(2));
+    /// assert!(metrics.dust_outputs.is_empty());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn from_transaction(tx: &Transaction, prev_outputs: Option<&[Output]>) -> Metrics {
+        Metrics::with_size(tx, prev_outputs, Vec::from(tx).len())
+    }
+
+    /// Measure transaction with `size` other than its serialized size
+    pub(crate) fn with_size(tx: &Transaction, prev_outputs: Option<&[Output]>, size: usize) -> Metrics {
+        let prev_outputs = prev_outputs.filter(|prev| prev.len() == tx.inputs.len());
+        let fee = prev_outputs.and_then(|prev| {
+            let input_value = prev.iter().try_fold(Amount::ZERO, |sum, o| sum.checked_add(o.value))?;
+            let output_value = tx.outputs.iter().try_fold(Amount::ZERO, |sum, o| sum.checked_add(o.value))?;
+            input_value.checked_sub(output_value)
+        });
+        let sigop_count = prev_outputs.and_then(|prev| {
+            let prev_scripts = prev.iter().map(|o| o.script.as_slice()).collect::<Vec<_>>();
+            tx.sigop_count(&prev_scripts).ok()
+        });
+        let per_byte = |n: u64| if size == 0 { 0.0 } else { n as f64 / size as f64 };
+
+        Metrics {
+            size,
+            input_count: tx.inputs.len(),
+            output_count: tx.outputs.len(),
+            fee,
+            fee_rate: fee.map(|fee| per_byte(fee.to_sat())),
+            sigop_count,
+            sigop_density: sigop_count.map(|n| per_byte(n as u64)),
+            dust_outputs: tx.outputs.iter().enumerate()
+                .filter(|(_, o)| policy::is_dust(o, DUST_RELAY_FEE_RATE))
+                .map(|(i, _)| i)
+                .collect(),
+        }
+    }
+}
+
+/// Single line of `key: value` pairs
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn format<T: fmt::Display>(v: &Option<T>) -> String {
+            v.as_ref().map(|v| v.to_string()).unwrap_or_else(|| "unknown".to_string())
+        }
+
+        write!(f, "size: {}, inputs: {}, outputs: {}, fee: {}, fee rate: {}, sigops: {}, sigop density: {}, dust outputs: {:?}",
+            self.size, self.input_count, self.output_count, format(&self.fee), format(&self.fee_rate),
+            format(&self.sigop_count), format(&self.sigop_density), self.dust_outputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::TxBuilder;
+    use super::super::script::p2pkh;
+    use super::super::error::Result;
+
+    #[test]
+    fn builder() -> Result<()> {
+        let prev_script = p2pkh::script_pub_key(&[0x01; 20])?;
+        let mut txb = TxBuilder::new(|_: &str| None);
+        txb.add_input([0x01; 32].into(), 0, Some(10_000), Some(&prev_script), None)?;
+        txb.add_output(Amount::from_sat(9_000), &prev_script);
+        txb.add_output(Amount::from_sat(100), &prev_script);
+
+        let metrics = txb.metrics()?;
+        assert_eq!(metrics.size, txb.estimated_size_with_signatures()?);
+        assert_eq!(metrics.fee, Some(Amount::from_sat(900)));
+        assert_eq!(metrics.fee_rate, Some(900.0 / metrics.size as f64));
+        assert_eq!(metrics.sigop_count, Some(2));
+        assert_eq!(metrics.dust_outputs, vec![1]);
+        assert!(metrics.to_string().starts_with("size: 227, inputs: 1, outputs: 2, fee: "));
+
+        txb.add_input([0x01; 32].into(), 1, None, None, None)?;
+        let metrics = txb.metrics()?;
+        assert_eq!((metrics.input_count, metrics.fee, metrics.sigop_count), (2, None, None));
+        assert!(metrics.to_string().contains("fee rate: unknown"));
+
+        Ok(())
+    }
+}
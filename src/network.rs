@@ -0,0 +1,125 @@
+//! Networks (chains) of Bitcoin Cash
+
+use std::fmt;
+use std::str::FromStr;
+use super::policy;
+use super::types::FeeRate;
+use super::error::{Error, Result};
+#[cfg(feature = "address")]
+use super::address::AddressNetwork;
+
+/// Network which transaction is built for
+///
+/// Names follow `-chain` option of BCHN (`main`, `test`, `test4`, `scale`, `chip`, `regtest`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Network {
+    /// main network
+    #[default]
+    Mainnet,
+    /// testnet3
+    Testnet3,
+    /// testnet4
+    Testnet4,
+    /// scalenet
+    Scalenet,
+    /// chipnet (upgrades activate in advance of mainnet)
+    Chipnet,
+    /// local regression test network
+    Regtest,
+}
+
+impl Network {
+    /// All networks
+    pub const ALL: [Network; 6] = [
+        Network::Mainnet, Network::Testnet3, Network::Testnet4,
+        Network::Scalenet, Network::Chipnet, Network::Regtest,
+    ];
+
+    /// Name used by `-chain` option of node
+    pub fn name(self) -> &'static str {
+        match self {
+            Network::Mainnet => "main",
+            Network::Testnet3 => "test",
+            Network::Testnet4 => "test4",
+            Network::Scalenet => "scale",
+            Network::Chipnet => "chip",
+            Network::Regtest => "regtest",
+        }
+    }
+
+    /// Prefix of cashaddr
+    pub fn cashaddr_prefix(self) -> &'static str {
+        match self {
+            Network::Mainnet => "bitcoincash",
+            Network::Regtest => "bchreg",
+            _ => "bchtest",
+        }
+    }
+
+    /// Default minimum relay fee rate of node
+    ///
+    /// Fee rates given to `TxBuilder` are charged as is, transactions paying less are not relayed.
+    pub fn min_fee_rate(self) -> FeeRate {
+        policy::MIN_RELAY_FEE_RATE
+    }
+
+    /// `true` if node rejects non-standard transactions by default
+    ///
+    /// Only mainnet does, other networks accept them (`-acceptnonstdtxn`).
+    pub fn require_standard(self) -> bool {
+        self == Network::Mainnet
+    }
+
+    /// Network encoded in addresses
+    #[cfg(feature = "address")]
+    pub fn address_network(self) -> AddressNetwork {
+        match self {
+            Network::Mainnet => AddressNetwork::Mainnet,
+            Network::Regtest => AddressNetwork::Regtest,
+            _ => AddressNetwork::Testnet,
+        }
+    }
+
+    /// Check that address of `address_network` can be paid on this network
+    ///
+    /// Regtest also accepts testnet addresses as legacy addresses are shared.
+    #[cfg(feature = "address")]
+    pub(crate) fn accepts(self, address_network: AddressNetwork) -> bool {
+        address_network == self.address_network() ||
+            (self == Network::Regtest && address_network == AddressNetwork::Testnet)
+    }
+}
+
+impl FromStr for Network {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Network> {
+        Network::ALL.iter().cloned()
+            .find(|n| n.name() == s)
+            .ok_or_else(|| Error::InvalidNetwork(s.to_string()))
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() -> Result<()> {
+        for &network in &Network::ALL {
+            assert_eq!(network.to_string().parse::<Network>()?, network);
+        }
+        assert!("testnet".parse::<Network>().is_err());
+        assert_eq!(Network::Testnet4.cashaddr_prefix(), Network::Chipnet.cashaddr_prefix());
+        assert!(Network::Mainnet.require_standard());
+        assert!(!Network::Regtest.require_standard());
+
+        Ok(())
+    }
+}
@@ -1,8 +1,12 @@
 #![allow(non_camel_case_types, missing_docs)]
 use std::fmt;
+use std::str::FromStr;
+use num_traits::FromPrimitive;
+use super::error::{Error, Result};
 
 /// [Script opcodes](https://github.com/Bitcoin-ABC/bitcoin-abc/blob/master/src/script/script.h#L42)
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, FromPrimitive, ToPrimitive)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum OpCode {
     // push value
     OP_0 = 0x00,
@@ -181,7 +185,34 @@ impl fmt::Display for OpCode {
         if let Some(s) = s {
             write!(f, "{}", s)
         } else {
-            write!(f, "{:?}", self)
+            write!(f, "{}", self.name())
+        }
+    }
+}
+
+impl FromStr for OpCode {
+    type Err = Error;
+
+    /// Parse name like `"OP_DUP"` (including aliases such as `OP_TRUE`)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::OpCode;
+    /// let op: OpCode = "OP_CHECKSIG".parse()?;
+    /// assert_eq!(op, OpCode::OP_CHECKSIG);
+    /// assert_eq!(op.name(), "OP_CHECKSIG");
+    /// assert_eq!("OP_TRUE".parse::<OpCode>()?, OpCode::OP_1);
+    /// assert!("OP_UNKNOWN".parse::<OpCode>().is_err());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    fn from_str(s: &str) -> Result<OpCode> {
+        match s {
+            "OP_FALSE" => Ok(OpCode::OP_FALSE),
+            "OP_TRUE" => Ok(OpCode::OP_TRUE),
+            "OP_NOP2" => Ok(OpCode::OP_NOP2),
+            "OP_NOP3" => Ok(OpCode::OP_NOP3),
+            _ => (0..=u8::MAX).filter_map(OpCode::from_u8)
+                .find(|op| op.name() == s)
+                .ok_or_else(|| Error::UnknownOpCode(s.to_string())),
         }
     }
 }
@@ -191,6 +222,148 @@ impl OpCode {
     pub const OP_TRUE: OpCode = OpCode::OP_1;
     pub const OP_NOP2: OpCode = OpCode::OP_CHECKLOCKTIMEVERIFY;
     pub const OP_NOP3: OpCode = OpCode::OP_CHECKSEQUENCEVERIFY;
+
+    /// Name like `"OP_DUP"` (aliases such as `OP_TRUE` are not returned)
+    pub fn name(self) -> &'static str {
+        match self {
+            OpCode::OP_0                     => "OP_0",
+            OpCode::OP_PUSHDATA1             => "OP_PUSHDATA1",
+            OpCode::OP_PUSHDATA2             => "OP_PUSHDATA2",
+            OpCode::OP_PUSHDATA4             => "OP_PUSHDATA4",
+            OpCode::OP_1NEGATE               => "OP_1NEGATE",
+            OpCode::OP_RESERVED              => "OP_RESERVED",
+            OpCode::OP_1                     => "OP_1",
+            OpCode::OP_2                     => "OP_2",
+            OpCode::OP_3                     => "OP_3",
+            OpCode::OP_4                     => "OP_4",
+            OpCode::OP_5                     => "OP_5",
+            OpCode::OP_6                     => "OP_6",
+            OpCode::OP_7                     => "OP_7",
+            OpCode::OP_8                     => "OP_8",
+            OpCode::OP_9                     => "OP_9",
+            OpCode::OP_10                    => "OP_10",
+            OpCode::OP_11                    => "OP_11",
+            OpCode::OP_12                    => "OP_12",
+            OpCode::OP_13                    => "OP_13",
+            OpCode::OP_14                    => "OP_14",
+            OpCode::OP_15                    => "OP_15",
+            OpCode::OP_16                    => "OP_16",
+            OpCode::OP_NOP                   => "OP_NOP",
+            OpCode::OP_VER                   => "OP_VER",
+            OpCode::OP_IF                    => "OP_IF",
+            OpCode::OP_NOTIF                 => "OP_NOTIF",
+            OpCode::OP_VERIF                 => "OP_VERIF",
+            OpCode::OP_VERNOTIF              => "OP_VERNOTIF",
+            OpCode::OP_ELSE                  => "OP_ELSE",
+            OpCode::OP_ENDIF                 => "OP_ENDIF",
+            OpCode::OP_VERIFY                => "OP_VERIFY",
+            OpCode::OP_RETURN                => "OP_RETURN",
+            OpCode::OP_TOALTSTACK            => "OP_TOALTSTACK",
+            OpCode::OP_FROMALTSTACK          => "OP_FROMALTSTACK",
+            OpCode::OP_2DROP                 => "OP_2DROP",
+            OpCode::OP_2DUP                  => "OP_2DUP",
+            OpCode::OP_3DUP                  => "OP_3DUP",
+            OpCode::OP_2OVER                 => "OP_2OVER",
+            OpCode::OP_2ROT                  => "OP_2ROT",
+            OpCode::OP_2SWAP                 => "OP_2SWAP",
+            OpCode::OP_IFDUP                 => "OP_IFDUP",
+            OpCode::OP_DEPTH                 => "OP_DEPTH",
+            OpCode::OP_DROP                  => "OP_DROP",
+            OpCode::OP_DUP                   => "OP_DUP",
+            OpCode::OP_NIP                   => "OP_NIP",
+            OpCode::OP_OVER                  => "OP_OVER",
+            OpCode::OP_PICK                  => "OP_PICK",
+            OpCode::OP_ROLL                  => "OP_ROLL",
+            OpCode::OP_ROT                   => "OP_ROT",
+            OpCode::OP_SWAP                  => "OP_SWAP",
+            OpCode::OP_TUCK                  => "OP_TUCK",
+            OpCode::OP_CAT                   => "OP_CAT",
+            OpCode::OP_SPLIT                 => "OP_SPLIT",
+            OpCode::OP_NUM2BIN               => "OP_NUM2BIN",
+            OpCode::OP_BIN2NUM               => "OP_BIN2NUM",
+            OpCode::OP_SIZE                  => "OP_SIZE",
+            OpCode::OP_INVERT                => "OP_INVERT",
+            OpCode::OP_AND                   => "OP_AND",
+            OpCode::OP_OR                    => "OP_OR",
+            OpCode::OP_XOR                   => "OP_XOR",
+            OpCode::OP_EQUAL                 => "OP_EQUAL",
+            OpCode::OP_EQUALVERIFY           => "OP_EQUALVERIFY",
+            OpCode::OP_RESERVED1             => "OP_RESERVED1",
+            OpCode::OP_RESERVED2             => "OP_RESERVED2",
+            OpCode::OP_1ADD                  => "OP_1ADD",
+            OpCode::OP_1SUB                  => "OP_1SUB",
+            OpCode::OP_2MUL                  => "OP_2MUL",
+            OpCode::OP_2DIV                  => "OP_2DIV",
+            OpCode::OP_NEGATE                => "OP_NEGATE",
+            OpCode::OP_ABS                   => "OP_ABS",
+            OpCode::OP_NOT                   => "OP_NOT",
+            OpCode::OP_0NOTEQUAL             => "OP_0NOTEQUAL",
+            OpCode::OP_ADD                   => "OP_ADD",
+            OpCode::OP_SUB                   => "OP_SUB",
+            OpCode::OP_MUL                   => "OP_MUL",
+            OpCode::OP_DIV                   => "OP_DIV",
+            OpCode::OP_MOD                   => "OP_MOD",
+            OpCode::OP_LSHIFT                => "OP_LSHIFT",
+            OpCode::OP_RSHIFT                => "OP_RSHIFT",
+            OpCode::OP_BOOLAND               => "OP_BOOLAND",
+            OpCode::OP_BOOLOR                => "OP_BOOLOR",
+            OpCode::OP_NUMEQUAL              => "OP_NUMEQUAL",
+            OpCode::OP_NUMEQUALVERIFY        => "OP_NUMEQUALVERIFY",
+            OpCode::OP_NUMNOTEQUAL           => "OP_NUMNOTEQUAL",
+            OpCode::OP_LESSTHAN              => "OP_LESSTHAN",
+            OpCode::OP_GREATERTHAN           => "OP_GREATERTHAN",
+            OpCode::OP_LESSTHANOREQUAL       => "OP_LESSTHANOREQUAL",
+            OpCode::OP_GREATERTHANOREQUAL    => "OP_GREATERTHANOREQUAL",
+            OpCode::OP_MIN                   => "OP_MIN",
+            OpCode::OP_MAX                   => "OP_MAX",
+            OpCode::OP_WITHIN                => "OP_WITHIN",
+            OpCode::OP_RIPEMD160             => "OP_RIPEMD160",
+            OpCode::OP_SHA1                  => "OP_SHA1",
+            OpCode::OP_SHA256                => "OP_SHA256",
+            OpCode::OP_HASH160               => "OP_HASH160",
+            OpCode::OP_HASH256               => "OP_HASH256",
+            OpCode::OP_CODESEPARATOR         => "OP_CODESEPARATOR",
+            OpCode::OP_CHECKSIG              => "OP_CHECKSIG",
+            OpCode::OP_CHECKSIGVERIFY        => "OP_CHECKSIGVERIFY",
+            OpCode::OP_CHECKMULTISIG         => "OP_CHECKMULTISIG",
+            OpCode::OP_CHECKMULTISIGVERIFY   => "OP_CHECKMULTISIGVERIFY",
+            OpCode::OP_NOP1                  => "OP_NOP1",
+            OpCode::OP_CHECKLOCKTIMEVERIFY   => "OP_CHECKLOCKTIMEVERIFY",
+            OpCode::OP_CHECKSEQUENCEVERIFY   => "OP_CHECKSEQUENCEVERIFY",
+            OpCode::OP_NOP4                  => "OP_NOP4",
+            OpCode::OP_NOP5                  => "OP_NOP5",
+            OpCode::OP_NOP6                  => "OP_NOP6",
+            OpCode::OP_NOP7                  => "OP_NOP7",
+            OpCode::OP_NOP8                  => "OP_NOP8",
+            OpCode::OP_NOP9                  => "OP_NOP9",
+            OpCode::OP_NOP10                 => "OP_NOP10",
+            OpCode::OP_CHECKDATASIG          => "OP_CHECKDATASIG",
+            OpCode::OP_CHECKDATASIGVERIFY    => "OP_CHECKDATASIGVERIFY",
+            OpCode::FIRST_UNDEFINED_OP_VALUE => "FIRST_UNDEFINED_OP_VALUE",
+            OpCode::OP_PREFIX_BEGIN          => "OP_PREFIX_BEGIN",
+            OpCode::OP_PREFIX_END            => "OP_PREFIX_END",
+            OpCode::OP_SMALLINTEGER          => "OP_SMALLINTEGER",
+            OpCode::OP_PUBKEYS               => "OP_PUBKEYS",
+            OpCode::OP_PUBKEYHASH            => "OP_PUBKEYHASH",
+            OpCode::OP_PUBKEY                => "OP_PUBKEY",
+            OpCode::OP_INVALIDOPCODE         => "OP_INVALIDOPCODE",
+        }
+    }
+
+    /// Check if opcode pushes value (`OP_0`..`OP_16`, including `OP_RESERVED` as consensus does)
+    pub fn is_push(self) -> bool {
+        self <= OpCode::OP_16
+    }
+
+    /// Check if opcode is disabled regardless of script flags (`OP_MUL` depends on them)
+    pub fn is_disabled(self) -> bool {
+        matches!(self, OpCode::OP_INVERT | OpCode::OP_2MUL | OpCode::OP_2DIV | OpCode::OP_LSHIFT | OpCode::OP_RSHIFT)
+    }
+
+    /// Check if opcode is conditional (`OP_IF`..`OP_ENDIF`), which is evaluated even in unexecuted branch
+    pub fn is_conditional(self) -> bool {
+        OpCode::OP_IF <= self && self <= OpCode::OP_ENDIF
+    }
 }
 
 #[cfg(test)]
@@ -202,4 +375,25 @@ mod tests {
         assert_eq!(OpCode::OP_0.to_string(), "0");
         assert_eq!(OpCode::OP_PUSHDATA1.to_string(), "OP_PUSHDATA1");
     }
+
+    #[test]
+    fn metadata() -> Result<()> {
+        for op in (0..=u8::MAX).filter_map(OpCode::from_u8) {
+            assert_eq!(op.name(), format!("{:?}", op));
+            assert_eq!(op.name().parse::<OpCode>()?, op);
+        }
+        assert_eq!("OP_NOP3".parse::<OpCode>()?, OpCode::OP_CHECKSEQUENCEVERIFY);
+        match "OP_dup".parse::<OpCode>() {
+            Err(Error::UnknownOpCode(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        assert!(OpCode::OP_16.is_push() && OpCode::OP_PUSHDATA4.is_push());
+        assert!(!OpCode::OP_NOP.is_push());
+        assert!(OpCode::OP_2MUL.is_disabled() && !OpCode::OP_MUL.is_disabled());
+        assert!(OpCode::OP_VERIF.is_conditional() && OpCode::OP_ENDIF.is_conditional());
+        assert!(!OpCode::OP_VERIFY.is_conditional());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,211 @@
+//! JSON Payment Protocol (BitPay style)
+//!
+//! Wallet fetches a payment request (`application/payment-request`), builds and signs
+//! a transaction paying its outputs, and posts it as payment (`application/payment`).
+//! Merchant answers with a payment ACK carrying a memo.
+
+use serde_json::{json, Value};
+use super::{TxBuilder, Amount, FeeRate};
+use super::network::Network;
+use super::error::{Error, Result};
+
+/// Output requested by merchant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentOutput {
+    /// value
+    pub amount: Amount,
+    /// address
+    pub address: String,
+}
+
+/// Payment request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentRequest {
+    /// network
+    pub network: Network,
+    /// currency code, e.g. `"BCH"`
+    pub currency: String,
+    /// minimum fee rate (rounded up to satoshi per 1000 bytes)
+    pub required_fee_rate: FeeRate,
+    /// outputs to be paid
+    pub outputs: Vec<PaymentOutput>,
+    /// memo for the payer
+    pub memo: String,
+    /// URL to post payment
+    pub payment_url: String,
+    /// merchant's payment id
+    pub payment_id: String,
+    /// expiration time (ISO 8601) if given
+    pub expires: Option<String>,
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidPaymentRequest(reason.to_string())
+}
+
+fn parse_json(json: &str) -> Result<Value> {
+    serde_json::from_str(json).map_err(|e| Error::InvalidPaymentRequest(e.to_string()))
+}
+
+fn outputs(v: &Value) -> Option<Vec<PaymentOutput>> {
+    v.as_array()?.iter().map(|o| Some(PaymentOutput {
+        amount: Some(Amount::from_sat(o["amount"].as_u64()?)).filter(|a| a.is_valid())?,
+        address: o["address"].as_str()?.to_string(),
+    })).collect()
+}
+
+impl PaymentRequest {
+    /// Parse payment request
+    /// # Arguments
+    /// * `json` - body of payment request response
+    pub fn from_json(json: &str) -> Result<PaymentRequest> {
+        let v = parse_json(json)?;
+        let string = |name: &str| v[name].as_str().map(str::to_string)
+            .ok_or_else(|| Error::InvalidPaymentRequest(format!("missing or invalid {}", name)));
+
+        let fee_rate = v["requiredFeePerByte"].as_f64()
+            .filter(|rate| rate.is_finite() && *rate >= 0.0)
+            .ok_or_else(|| invalid("missing or invalid requiredFeePerByte"))?;
+        let outputs = outputs(&v["outputs"])
+            .filter(|outputs| !outputs.is_empty())
+            .ok_or_else(|| invalid("missing or invalid outputs"))?;
+
+        Ok(PaymentRequest {
+            network: string("network")?.parse()?,
+            currency: string("currency")?,
+            required_fee_rate: FeeRate::from_sat_per_kb((fee_rate * 1000.0).ceil() as u64),
+            outputs,
+            memo: v["memo"].as_str().unwrap_or_default().to_string(),
+            payment_url: string("paymentUrl")?,
+            payment_id: string("paymentId")?,
+            expires: v["expires"].as_str().map(str::to_string),
+        })
+    }
+
+    /// Total value of outputs
+    pub fn total(&self) -> Option<Amount> {
+        self.outputs.iter().try_fold(Amount::ZERO, |sum, o| sum.checked_add(o.amount))
+    }
+
+    /// Transaction builder paying requested outputs on requested network
+    ///
+    /// Fund it and add change with `required_fee_rate` before signing.
+    /// # Arguments
+    /// * `address_parser` - address parser closure, see `TxBuilder::new`
+    pub fn to_builder<F>(&self, address_parser: F) -> Result<TxBuilder<F>>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        let mut txb = TxBuilder::new(address_parser);
+        txb.set_network(self.network);
+        for output in &self.outputs {
+            txb.add_address_output(output.amount, &output.address)?;
+        }
+
+        Ok(txb)
+    }
+
+    /// Body of payment to post to `payment_url`
+    /// # Arguments
+    /// * `txb` - signed transaction paying this request
+    /// # Returns
+    /// * JSON (`Error::IncompleteInput` if not signed)
+    pub fn payment<F>(&self, txb: &TxBuilder<F>) -> Result<String>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        let tx = txb.to_complete_vec()?;
+
+        Ok(json!({
+            "currency": self.currency,
+            "transactions": [hex::encode(tx)],
+        }).to_string())
+    }
+}
+
+/// Parse payment ACK
+/// # Arguments
+/// * `json` - body of payment response
+/// # Returns
+/// * memo from merchant
+pub fn parse_payment_ack(json: &str) -> Result<String> {
+    let v = parse_json(json)?;
+    if !v["payment"].is_object() {
+        return Err(invalid("missing or invalid payment"));
+    }
+
+    Ok(v["memo"].as_str().unwrap_or_default().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::types::Txid;
+
+    const REQUEST: &str = r#"{
+        "network": "main",
+        "currency": "BCH",
+        "requiredFeePerByte": 1.2,
+        "outputs": [
+            { "amount": 39300, "address": "bitcoincash:qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3" },
+            { "amount": 700, "address": "bitcoincash:qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70" }
+        ],
+        "time": "2019-09-05T00:00:00.000Z",
+        "expires": "2019-09-05T00:15:00.000Z",
+        "memo": "Payment request for invoice 1",
+        "paymentUrl": "https://example.com/i/1",
+        "paymentId": "1"
+    }"#;
+
+    #[test]
+    fn request() -> Result<()> {
+        let request = PaymentRequest::from_json(REQUEST)?;
+        assert_eq!(request.network, Network::Mainnet);
+        assert_eq!(request.required_fee_rate, FeeRate::from_sat_per_kb(1200));
+        assert_eq!(request.total(), Some(Amount::from_sat(40_000)));
+        assert_eq!(request.expires.as_deref(), Some("2019-09-05T00:15:00.000Z"));
+
+        let parser = |address: &str| Some((address.as_bytes()[12..32].to_vec(), true));
+        let mut txb = request.to_builder(&parser)?;
+        assert_eq!(txb.output_count(), 2);
+        assert_eq!(txb.output_value(), request.total());
+
+        let txid: Txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        txb.add_input(txid, 0, Some(50_000), Some(&[0x51]), None)?;
+        match request.payment(&txb) {
+            Err(Error::IncompleteInput(0)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        txb.set_script_sig(0, &[0x51])?;
+        let payment: Value = serde_json::from_str(&request.payment(&txb)?).unwrap();
+        assert_eq!(payment["currency"], "BCH");
+        assert_eq!(payment["transactions"][0], txb.to_hex());
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        for json in &[
+            "",
+            "{}",
+            &REQUEST.replace("\"main\"", "\"mainnet\""),
+            &REQUEST.replace("1.2", "-1"),
+            &REQUEST.replace("39300", "-1"),
+            &REQUEST.replace("39300", "2100000000000001"),
+        ] {
+            assert!(PaymentRequest::from_json(json).is_err(), "{}", json);
+        }
+
+        let request = PaymentRequest::from_json(&REQUEST.replace(r#""memo": "Payment request for invoice 1","#, "")).unwrap();
+        assert_eq!(request.memo, "");
+    }
+
+    #[test]
+    fn ack() -> Result<()> {
+        let memo = parse_payment_ack(r#"{ "payment": { "transactions": ["00"] }, "memo": "Thanks" }"#)?;
+        assert_eq!(memo, "Thanks");
+        match parse_payment_ack(r#"{ "memo": "Thanks" }"#) {
+            Err(Error::InvalidPaymentRequest(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+}
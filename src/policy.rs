@@ -0,0 +1,203 @@
+//! standardness policy of default node
+
+use thiserror::Error;
+use super::opcode::OpCode::*;
+use super::script::{Script, bare_multisig, decode, is_push_only};
+use super::types::FeeRate;
+use super::types::transaction::Transaction;
+use super::types::transaction::output::Output;
+
+/// Fee rate used to decide dust by default node
+pub const DUST_RELAY_FEE_RATE: FeeRate = FeeRate::from_sat_per_byte(1);
+
+/// Minimum fee rate relayed by default node
+pub const MIN_RELAY_FEE_RATE: FeeRate = FeeRate::MIN_RELAY;
+
+/// Maximum size of standard transaction
+pub const MAX_STANDARD_TX_SIZE: usize = 100_000;
+
+/// Maximum signature operations of transaction (counted by `Transaction::sigop_count`)
+pub const MAX_TX_SIGOPS_COUNT: usize = 20_000;
+
+/// Maximum size of standard `scriptSig`
+pub const MAX_STANDARD_SCRIPT_SIG_SIZE: usize = 1650;
+
+/// Maximum size of data carried by single push null data output
+pub const MAX_NULL_DATA_SIZE: usize = 220;
+
+/// Maximum size of null data `scriptPubKey` (220 bytes of data with `OP_RETURN` and push opcodes)
+pub const MAX_NULL_DATA_SCRIPT_SIZE: usize = 223;
+
+/// Estimated size of input spending an output
+const SPEND_INPUT_SIZE: usize = 148;
+
+/// Violation of standardness policy
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum Violation {
+    /// `scriptPubKey` is not a standard template.
+    /// # Arguments
+    /// * output index
+    #[error("Non-standard scriptPubKey: {0}")]
+    NonStandardScript(usize),
+
+    /// `scriptSig` contains non-push operation.
+    /// # Arguments
+    /// * input index
+    #[error("Non push-only scriptSig: {0}")]
+    NonPushOnlyScriptSig(usize),
+
+    /// `scriptSig` exceeds `MAX_STANDARD_SCRIPT_SIG_SIZE`.
+    /// # Arguments
+    /// * input index
+    #[error("Oversized scriptSig: {0}")]
+    OversizedScriptSig(usize),
+
+    /// Output value is dust.
+    /// # Arguments
+    /// * output index
+    #[error("Dust output: {0}")]
+    DustOutput(usize),
+
+    /// Serialized transaction exceeds `MAX_STANDARD_TX_SIZE`.
+    /// # Arguments
+    /// * size in bytes
+    #[error("Oversized transaction: {0}")]
+    OversizedTransaction(usize),
+}
+
+/// Check whether `scriptPubKey` is null data (`OP_RETURN` followed by pushes)
+fn is_null_data(script: &[u8]) -> bool {
+    script.first() == Some(&(OP_RETURN as u8)) && is_push_only(&script[1..])
+}
+
+/// Check whether `scriptPubKey` is a standard template (P2PK, P2PKH, P2SH, bare multisig or null data)
+/// # Arguments
+/// * `script` - `scriptPubKey`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::policy::is_standard_script;
+/// assert!(is_standard_script(&hex!("76a91432b57f34861bcbe33a701be9ac3a50288fbc0a3d88ac")));
+/// assert!(is_standard_script(&hex!("6a04686f6765")));
+/// assert!(!is_standard_script(&hex!("51")));
+/// ```
+pub fn is_standard_script(script: &[u8]) -> bool {
+    if is_null_data(script) {
+        return script.len() <= MAX_NULL_DATA_SCRIPT_SIZE;
+    }
+    if bare_multisig::is_standard(script) {
+        return true;
+    }
+
+    match decode(script).as_ref().map(Vec::as_slice) {
+        Ok([Script::OpCode(OP_DUP), Script::OpCode(OP_HASH160), Script::Data(hash),
+            Script::OpCode(OP_EQUALVERIFY), Script::OpCode(OP_CHECKSIG)]) => hash.len() == 20,
+        Ok([Script::OpCode(OP_HASH160), Script::Data(hash), Script::OpCode(OP_EQUAL)]) => hash.len() == 20,
+        Ok([Script::Data(pubkey), Script::OpCode(OP_CHECKSIG)]) => pubkey.len() == 33 || pubkey.len() == 65,
+        _ => false,
+    }
+}
+
+/// Get minimum non-dust value of output
+/// # Arguments
+/// * `output` - output
+/// * `fee_rate` - fee rate
+/// # Returns
+/// * dust threshold (`0` for null data)
+pub fn dust_threshold(output: &Output, fee_rate: FeeRate) -> u64 {
+    if is_null_data(&output.script) {
+        return 0;
+    }
+
+    fee_rate.fee(output.to_vec().len() + SPEND_INPUT_SIZE).to_sat().saturating_mul(3)
+}
+
+/// Check whether output is dust
+/// # Arguments
+/// * `output` - output
+/// * `fee_rate` - fee rate
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::policy::{is_dust, DUST_RELAY_FEE_RATE};
+/// # use cash_tx_builder::types::transaction::Output;
+/// # use cash_tx_builder::Amount;
+/// let script = hex!("76a91432b57f34861bcbe33a701be9ac3a50288fbc0a3d88ac");
+/// assert!(is_dust(&Output::new(Amount::from_sat(545), &script), DUST_RELAY_FEE_RATE));
+/// assert!(!is_dust(&Output::new(Amount::from_sat(546), &script), DUST_RELAY_FEE_RATE));
+/// ```
+pub fn is_dust(output: &Output, fee_rate: FeeRate) -> bool {
+    output.value.to_sat() < dust_threshold(output, fee_rate)
+}
+
+/// Check standardness of transaction
+/// # Arguments
+/// * `tx` - transaction
+/// # Returns
+/// * list of violations (empty if none)
+pub fn check_standard(tx: &Transaction) -> Vec<Violation> {
+    let mut violations = vec![];
+
+    for (i, input) in tx.inputs.iter().enumerate() {
+        if !is_push_only(&input.script) {
+            violations.push(Violation::NonPushOnlyScriptSig(i));
+        }
+        if input.script.len() > MAX_STANDARD_SCRIPT_SIG_SIZE {
+            violations.push(Violation::OversizedScriptSig(i));
+        }
+    }
+
+    for (i, output) in tx.outputs.iter().enumerate() {
+        if !is_standard_script(&output.script) {
+            violations.push(Violation::NonStandardScript(i));
+        } else if is_dust(output, DUST_RELAY_FEE_RATE) {
+            violations.push(Violation::DustOutput(i));
+        }
+    }
+
+    let size = Vec::from(tx).len();
+    if size > MAX_STANDARD_TX_SIZE {
+        violations.push(Violation::OversizedTransaction(size));
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::types::transaction::input::Input;
+    use super::super::types::Amount;
+
+    #[test]
+    fn standard() {
+        let p2pkh = hex!("76a91432b57f34861bcbe33a701be9ac3a50288fbc0a3d88ac");
+        let p2sh = hex!("a914023a723c9e8b8297d84f6ab7dc08784c36b0729a87");
+        assert!(is_standard_script(&p2sh));
+        assert!(is_standard_script(&hex!("210366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036ac")));
+        assert!(is_standard_script(&[&[0x6a, 0x4c, 220][..], &[0; 220]].concat()));
+        assert!(!is_standard_script(&[&[0x6a, 0x4c, 221][..], &[0; 221]].concat()));
+        assert!(!is_standard_script(&hex!("6a76")));
+        let pubkey = hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036");
+        assert!(is_standard_script(&bare_multisig::script_pub_key(1, &[&pubkey[..]; 3]).unwrap()));
+        assert!(!is_standard_script(&bare_multisig::script_pub_key(1, &[&pubkey[..]; 4]).unwrap()));
+        assert_eq!(dust_threshold(&Output::new(Amount::from_sat(0), &p2sh), FeeRate::MIN_RELAY), 540);
+        assert_eq!(dust_threshold(&Output::new(Amount::from_sat(0), &p2sh), FeeRate::from_sat_per_kb(500)), 270);
+        assert!(!is_dust(&Output::new(Amount::from_sat(0), &hex!("6a")), FeeRate::MIN_RELAY));
+
+        let mut tx = Transaction::default();
+        tx.inputs.push(Input::new(&[0x01; 32], 0, None));
+        tx.inputs[0].script = vec![0x76];
+        tx.outputs.push(Output::new(Amount::from_sat(1000), &p2pkh));
+        tx.outputs.push(Output::new(Amount::from_sat(1000), &hex!("51")));
+        tx.outputs.push(Output::new(Amount::from_sat(100), &p2pkh));
+        tx.inputs.push(Input::new(&[0x01; 32], 1, None));
+        tx.inputs[1].script = [&[0x4d, 0x70, 0x06][..], &[0; MAX_STANDARD_SCRIPT_SIG_SIZE - 2]].concat();
+        assert_eq!(check_standard(&tx), vec![
+            Violation::NonPushOnlyScriptSig(0),
+            Violation::OversizedScriptSig(1),
+            Violation::NonStandardScript(1),
+            Violation::DustOutput(2),
+        ]);
+    }
+}
@@ -0,0 +1,3 @@
+//! Application protocols carried by null data outputs
+
+pub mod memo;
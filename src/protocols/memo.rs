@@ -0,0 +1,146 @@
+//! memo.cash actions
+//! [spec](https://memo.cash/protocol)
+
+use super::super::script::null_data_script_multi;
+use super::super::types::Txid;
+use super::super::error::{Error, Result};
+
+/// Prefix of `set name` action
+pub const PREFIX_SET_NAME: &[u8; 2] = b"\x6d\x01";
+
+/// Prefix of `post` action
+pub const PREFIX_POST: &[u8; 2] = b"\x6d\x02";
+
+/// Prefix of `reply` action
+pub const PREFIX_REPLY: &[u8; 2] = b"\x6d\x03";
+
+/// Prefix of `like` action
+pub const PREFIX_LIKE: &[u8; 2] = b"\x6d\x04";
+
+/// Prefix of `follow` action
+pub const PREFIX_FOLLOW: &[u8; 2] = b"\x6d\x06";
+
+/// Maximum length of name
+pub const MAX_NAME_LEN: usize = 217;
+
+/// Maximum length of post message
+pub const MAX_POST_LEN: usize = 217;
+
+/// Maximum length of reply message
+pub const MAX_REPLY_LEN: usize = 184;
+
+fn check_len(field: &str, data: &[u8], max: usize) -> Result<()> {
+    if data.len() > max {
+        return Err(Error::InvalidMemo(format!("{} exceeds {} bytes", field, max)));
+    }
+
+    Ok(())
+}
+
+/// Build `set name` message
+/// # Arguments
+/// * `name` - profile name (up to `MAX_NAME_LEN` bytes)
+/// # Returns
+/// * `scriptPubKey`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::protocols::memo::set_name_script;
+/// let script = set_name_script("hoge")?;
+/// assert_eq!(script, hex!("6a026d0104686f6765"));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn set_name_script(name: &str) -> Result<Vec<u8>> {
+    check_len("name", name.as_bytes(), MAX_NAME_LEN)?;
+
+    null_data_script_multi(&[PREFIX_SET_NAME, name.as_bytes()])
+}
+
+/// Build `post` message
+/// # Arguments
+/// * `message` - message (up to `MAX_POST_LEN` bytes)
+/// # Returns
+/// * `scriptPubKey`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::protocols::memo::post_script;
+/// let script = post_script("hoge")?;
+/// assert_eq!(script, hex!("6a026d0204686f6765"));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn post_script(message: &str) -> Result<Vec<u8>> {
+    check_len("message", message.as_bytes(), MAX_POST_LEN)?;
+
+    null_data_script_multi(&[PREFIX_POST, message.as_bytes()])
+}
+
+/// Build `reply` message
+/// # Arguments
+/// * `txid` - txid of replied post
+/// * `message` - message (up to `MAX_REPLY_LEN` bytes)
+/// # Returns
+/// * `scriptPubKey`
+pub fn reply_script(txid: Txid, message: &str) -> Result<Vec<u8>> {
+    check_len("message", message.as_bytes(), MAX_REPLY_LEN)?;
+
+    null_data_script_multi(&[PREFIX_REPLY, txid.as_ref(), message.as_bytes()])
+}
+
+/// Build `like` message
+/// # Arguments
+/// * `txid` - txid of liked post
+/// # Returns
+/// * `scriptPubKey`
+pub fn like_script(txid: Txid) -> Result<Vec<u8>> {
+    null_data_script_multi(&[PREFIX_LIKE, txid.as_ref()])
+}
+
+/// Build `follow` message
+/// # Arguments
+/// * `pubkey_hash` - 20 bytes `public key hash` of followed user
+/// # Returns
+/// * `scriptPubKey`
+pub fn follow_script(pubkey_hash: &[u8]) -> Result<Vec<u8>> {
+    if pubkey_hash.len() != 20 {
+        return Err(Error::InvalidMemo(format!("public key hash: {}", hex::encode(pubkey_hash))));
+    }
+
+    null_data_script_multi(&[PREFIX_FOLLOW, pubkey_hash])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use super::*;
+    use super::super::super::script::{decode, Script};
+    use super::super::super::policy::is_standard_script;
+    use super::super::super::opcode::OpCode::*;
+
+    #[test]
+    fn memo() -> Result<()> {
+        let txid = Txid::from_str("ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda")?;
+
+        let script = reply_script(txid, "hoge")?;
+        assert_eq!(decode(&script)?, [
+            Script::OpCode(OP_RETURN),
+            Script::Data(PREFIX_REPLY),
+            Script::Data(txid.as_ref()),
+            Script::Data(b"hoge"),
+        ]);
+        assert!(is_standard_script(&reply_script(txid, &"a".repeat(MAX_REPLY_LEN))?));
+        assert!(reply_script(txid, &"a".repeat(MAX_REPLY_LEN + 1)).is_err());
+
+        assert_eq!(like_script(txid)?, [&hex!("6a026d0420")[..], txid.as_ref()].concat());
+
+        let pubkey_hash = hex!("32b57f34861bcbe33a701be9ac3a50288fbc0a3d");
+        assert_eq!(follow_script(&pubkey_hash)?, [&hex!("6a026d0614")[..], &pubkey_hash].concat());
+        assert!(follow_script(&pubkey_hash[1..]).is_err());
+
+        assert!(is_standard_script(&post_script(&"a".repeat(MAX_POST_LEN))?));
+        assert!(post_script(&"a".repeat(MAX_POST_LEN + 1)).is_err());
+        assert!(set_name_script(&"a".repeat(MAX_NAME_LEN + 1)).is_err());
+
+        Ok(())
+    }
+}
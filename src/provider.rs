@@ -0,0 +1,61 @@
+//! Backend-agnostic source of unspent outputs
+
+#[cfg(feature = "chaingraph")]
+pub mod chaingraph;
+
+use std::future::Future;
+use super::tx_builder::TxBuilder;
+use super::types::Txid;
+use super::types::transaction::Transaction;
+use super::error::{Error, Result};
+
+/// Unspent output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Utxo {
+    /// txid
+    pub txid: Txid,
+    /// output index
+    pub vout: u32,
+    /// satoshi
+    pub value: u64,
+}
+
+/// Source of unspent outputs and previous transactions, e.g. indexer API
+pub trait UtxoProvider {
+    /// List unspent outputs of `scriptPubKey`
+    /// # Arguments
+    /// * `script` - `scriptPubKey`
+    fn utxos(&self, script: &[u8]) -> impl Future<Output = Result<Vec<Utxo>>>;
+
+    /// Get transaction
+    /// # Arguments
+    /// * `txid` - txid
+    fn raw_tx(&self, txid: Txid) -> impl Future<Output = Result<Transaction>>;
+}
+
+/// Add all unspent outputs of `scriptPubKey` as inputs
+///
+/// Previous transactions are fetched so that previous outputs
+/// (including token data) are known to `txb`.
+/// # Arguments
+/// * `provider` - UTXO source
+/// * `txb` - transaction builder
+/// * `script` - `scriptPubKey`
+/// # Returns
+/// * total value of added inputs
+pub async fn add_inputs<P, F>(provider: &P, txb: &mut TxBuilder<F>, script: &[u8]) -> Result<u64>
+    where P: UtxoProvider, F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+    let mut total = 0;
+    for utxo in provider.utxos(script).await? {
+        let prev_tx = provider.raw_tx(utxo.txid).await?;
+        let prev_output = prev_tx.outputs.get(utxo.vout as usize)
+            .filter(|o| o.script == script && o.value.to_sat() == utxo.value)
+            .ok_or_else(|| Error::ProviderError(format!("unexpected output: {}:{}", utxo.txid, utxo.vout)))?;
+
+        txb.add_input(utxo.txid, utxo.vout, None, None, None)?;
+        txb.set_prev_output(txb.input_count() - 1, prev_output.clone())?;
+        total += utxo.value;
+    }
+
+    Ok(total)
+}
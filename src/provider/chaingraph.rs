@@ -0,0 +1,163 @@
+//! [Chaingraph](https://chaingraph.cash) GraphQL client
+//!
+//! Only plain HTTP connections are supported, e.g. self-hosted instance.
+
+use std::fmt::Display;
+use serde_json::{json, Value};
+use super::{Utxo, UtxoProvider};
+use super::super::types::Txid;
+use super::super::types::transaction::Transaction;
+use super::super::http;
+use super::super::error::{Error, Result};
+
+const UTXOS_QUERY: &str = "query ($script: bytea!) { \
+    output(where: { locking_bytecode: { _eq: $script }, _not: { spent_by: {} } }) { \
+    transaction_hash output_index value_satoshis } }";
+const RAW_TX_QUERY: &str = "query ($hash: bytea!) { \
+    transaction(where: { hash: { _eq: $hash } }) { encoded_hex } }";
+
+fn provider_error<E: Display>(err: E) -> Error {
+    Error::ProviderError(err.to_string())
+}
+
+/// `bytea` literal of Hasura
+fn bytea(v: &[u8]) -> String {
+    format!("\\x{}", hex::encode(v))
+}
+
+/// Parse number which may be serialized as string
+fn as_u64(v: &Value) -> Option<u64> {
+    v.as_u64().or_else(|| v.as_str()?.parse().ok())
+}
+
+fn utxo_from_json(v: &Value) -> Option<Utxo> {
+    Some(Utxo {
+        txid: v["transaction_hash"].as_str()?.trim_start_matches("\\x").parse().ok()?,
+        vout: as_u64(&v["output_index"])? as u32,
+        value: as_u64(&v["value_satoshis"])?,
+    })
+}
+
+/// Chaingraph GraphQL client
+#[derive(Debug, Clone)]
+pub struct Client {
+    addr: String,
+    path: String,
+}
+
+impl Client {
+    /// Construct client
+    /// # Arguments
+    /// * `addr` - server address, e.g. `"127.0.0.1:8080"`
+    /// * `path` - GraphQL endpoint, e.g. `"/v1/graphql"`
+    pub fn new(addr: &str, path: &str) -> Client {
+        Client {
+            addr: addr.to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    /// Send GraphQL query
+    /// # Arguments
+    /// * `query` - query
+    /// * `variables` - variables
+    /// # Returns
+    /// * `data` of response
+    pub async fn query(&self, query: &str, variables: Value) -> Result<Value> {
+        let body = json!({ "query": query, "variables": variables });
+        let mut response = http::post_json(&self.addr, &self.path, &[], &body).await
+            .map_err(Error::ProviderError)?;
+        if !response["errors"].is_null() {
+            return Err(Error::ProviderError(response["errors"].to_string()));
+        }
+
+        Ok(response["data"].take())
+    }
+}
+
+impl UtxoProvider for Client {
+    async fn utxos(&self, script: &[u8]) -> Result<Vec<Utxo>> {
+        let data = self.query(UTXOS_QUERY, json!({ "script": bytea(script) })).await?;
+        let outputs = data["output"].as_array().ok_or_else(|| provider_error(&data))?;
+
+        outputs.iter()
+            .map(|v| utxo_from_json(v).ok_or_else(|| provider_error(v)))
+            .collect()
+    }
+
+    async fn raw_tx(&self, txid: Txid) -> Result<Transaction> {
+        let hash = format!("\\x{}", txid);
+        let data = self.query(RAW_TX_QUERY, json!({ "hash": hash })).await?;
+        let hex = data["transaction"][0]["encoded_hex"].as_str()
+            .ok_or_else(|| provider_error(&data))?;
+
+        Transaction::from_hex(hex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use super::*;
+    use super::super::super::types::Amount;
+    use super::super::add_inputs;
+    use super::super::super::tx_builder::TxBuilder;
+
+    const RAW_TX: &str = "0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000";
+
+    async fn serve(listener: TcpListener) {
+        let txid = Transaction::from_hex(RAW_TX).unwrap().txid();
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![];
+            let body = loop {
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(i) = text.find("\r\n\r\n") {
+                    let len = text.lines()
+                        .find_map(|l| l.strip_prefix("Content-Length: "))
+                        .map(|l| l.parse::<usize>().unwrap())
+                        .unwrap();
+                    if request.len() >= i + 4 + len {
+                        break serde_json::from_slice::<Value>(&request[i + 4..]).unwrap();
+                    }
+                }
+            };
+            assert!(String::from_utf8_lossy(&request).starts_with("POST /v1/graphql "));
+
+            let data = if body["query"] == UTXOS_QUERY {
+                json!({ "output": [{ "transaction_hash": format!("\\x{}", txid), "output_index": 1, "value_satoshis": "10000" }] })
+            } else {
+                assert_eq!(body["variables"]["hash"], format!("\\x{}", txid));
+                json!({ "transaction": [{ "encoded_hex": RAW_TX }] })
+            };
+            let response = json!({ "data": data }).to_string();
+            let v = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", response.len(), response);
+            stream.write_all(v.as_bytes()).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn client() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.map_err(provider_error)?;
+        let addr = listener.local_addr().map_err(provider_error)?.to_string();
+        tokio::spawn(serve(listener));
+
+        let tx = Transaction::from_hex(RAW_TX)?;
+        let script = tx.outputs[1].script.clone();
+
+        let client = Client::new(&addr, "/v1/graphql");
+        let utxos = client.utxos(&script).await?;
+        assert_eq!(utxos, vec![Utxo { txid: tx.txid(), vout: 1, value: 10000 }]);
+
+        let mut txb = TxBuilder::new(|_: &str| None);
+        assert_eq!(add_inputs(&client, &mut txb, &script).await?, 10000);
+        txb.add_output(Amount::from_sat(9000), &script);
+        assert_eq!(txb.fee()?, Amount::from_sat(1000));
+
+        Ok(())
+    }
+}
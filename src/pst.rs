@@ -0,0 +1,385 @@
+//! Partially signed transaction
+//!
+//! BIP174 like container which carries the unsigned transaction and partial signing state between signers.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use super::error::{Error, Result};
+use super::script::{p2pkh, multisig};
+use super::types::VarInt;
+use super::types::transaction::{Transaction, Output};
+
+/// Magic bytes
+pub const MAGIC: &[u8; 4] = b"pst\xff";
+
+const GLOBAL_UNSIGNED_TX: u8 = 0x00;
+
+const IN_PREV_OUTPUT: u8 = 0x00;
+const IN_PARTIAL_SIG: u8 = 0x02;
+const IN_SIGHASH_TYPE: u8 = 0x03;
+const IN_REDEEM_SCRIPT: u8 = 0x04;
+const IN_FINAL_SCRIPT_SIG: u8 = 0x07;
+
+const OUT_REDEEM_SCRIPT: u8 = 0x00;
+
+type KeyValues = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// Input of partially signed transaction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PstInput {
+    /// previous output
+    pub prev_output: Option<Output>,
+    /// `public key` to `signature` (with sighash type)
+    pub partial_sigs: BTreeMap<Vec<u8>, Vec<u8>>,
+    /// sighash type signers should use
+    pub hash_type: Option<u32>,
+    /// `redeem script` of P2SH input
+    pub redeem_script: Option<Vec<u8>>,
+    /// finalized `scriptSig`
+    pub final_script_sig: Option<Vec<u8>>,
+    /// unknown key-value pairs
+    pub unknown: KeyValues,
+}
+
+/// Output of partially signed transaction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PstOutput {
+    /// `redeem script` of P2SH output
+    pub redeem_script: Option<Vec<u8>>,
+    /// unknown key-value pairs
+    pub unknown: KeyValues,
+}
+
+/// Partially signed transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pst {
+    /// unsigned transaction
+    pub tx: Transaction,
+    /// per input data
+    pub inputs: Vec<PstInput>,
+    /// per output data
+    pub outputs: Vec<PstOutput>,
+    /// unknown global key-value pairs
+    pub unknown: KeyValues,
+}
+
+fn write_pair(v: &mut Vec<u8>, key: &[u8], value: &[u8]) {
+    v.extend(Vec::from(VarInt::from(key.len() as u64)));
+    v.extend(key);
+    v.extend(Vec::from(VarInt::from(value.len() as u64)));
+    v.extend(value);
+}
+
+fn write_unknown(v: &mut Vec<u8>, unknown: &KeyValues) {
+    for (key, value) in unknown {
+        write_pair(v, key, value);
+    }
+    v.push(0x00);
+}
+
+impl From<&Pst> for Vec<u8> {
+    fn from(pst: &Pst) -> Vec<u8> {
+        let mut v = MAGIC.to_vec();
+        write_pair(&mut v, &[GLOBAL_UNSIGNED_TX], &Vec::from(&pst.tx));
+        write_unknown(&mut v, &pst.unknown);
+
+        for input in &pst.inputs {
+            if let Some(o) = &input.prev_output {
+                write_pair(&mut v, &[IN_PREV_OUTPUT], &o.to_vec());
+            }
+            for (pubkey, sig) in &input.partial_sigs {
+                write_pair(&mut v, &[&[IN_PARTIAL_SIG], &pubkey[..]].concat(), sig);
+            }
+            if let Some(hash_type) = input.hash_type {
+                write_pair(&mut v, &[IN_SIGHASH_TYPE], &hash_type.to_le_bytes());
+            }
+            if let Some(script) = &input.redeem_script {
+                write_pair(&mut v, &[IN_REDEEM_SCRIPT], script);
+            }
+            if let Some(script) = &input.final_script_sig {
+                write_pair(&mut v, &[IN_FINAL_SCRIPT_SIG], script);
+            }
+            write_unknown(&mut v, &input.unknown);
+        }
+
+        for output in &pst.outputs {
+            if let Some(script) = &output.redeem_script {
+                write_pair(&mut v, &[OUT_REDEEM_SCRIPT], script);
+            }
+            write_unknown(&mut v, &output.unknown);
+        }
+
+        v
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self) -> Error {
+        Error::InvalidPst(format!("unexpected data at {}", self.pos))
+    }
+
+    fn read(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len).ok_or_else(|| self.error())?;
+        let data = self.bytes.get(self.pos..end).ok_or_else(|| self.error())?;
+        self.pos += len;
+        Ok(data)
+    }
+
+    fn read_var_bytes(&mut self) -> Result<&'a [u8]> {
+        let len = VarInt::try_from(&self.bytes[self.pos..]).map_err(|_| self.error())?;
+        self.pos += len.len();
+        self.read(u64::from(len) as usize)
+    }
+
+    /// Read key-value pairs until separator
+    fn read_map(&mut self) -> Result<KeyValues> {
+        let mut map = KeyValues::new();
+        loop {
+            let key = self.read_var_bytes()?;
+            if key.is_empty() {
+                return Ok(map);
+            }
+            let value = self.read_var_bytes()?;
+            if map.insert(key.to_vec(), value.to_vec()).is_some() {
+                return Err(Error::InvalidPst(format!("duplicated key: {}", hex::encode(key))));
+            }
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for Pst {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Pst> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.read(MAGIC.len())? != MAGIC {
+            return Err(Error::InvalidPst("magic".to_string()));
+        }
+
+        let mut unknown = reader.read_map()?;
+        let tx = unknown.remove(&vec![GLOBAL_UNSIGNED_TX])
+            .ok_or_else(|| Error::InvalidPst("missing unsigned transaction".to_string()))?;
+        let tx = Transaction::try_from(&tx[..])?;
+
+        let mut inputs = Vec::with_capacity(tx.inputs.len());
+        for _ in 0..tx.inputs.len() {
+            let mut input = PstInput::default();
+            for (key, value) in reader.read_map()? {
+                match (key[0], key.len()) {
+                    (IN_PREV_OUTPUT, 1) => input.prev_output = Some(Output::try_from(&value[..])?),
+                    (IN_PARTIAL_SIG, _) => {
+                        input.partial_sigs.insert(key[1..].to_vec(), value);
+                    },
+                    (IN_SIGHASH_TYPE, 1) if value.len() == 4 => {
+                        let mut hash_type = [0; 4];
+                        hash_type.copy_from_slice(&value);
+                        input.hash_type = Some(u32::from_le_bytes(hash_type));
+                    },
+                    (IN_REDEEM_SCRIPT, 1) => input.redeem_script = Some(value),
+                    (IN_FINAL_SCRIPT_SIG, 1) => input.final_script_sig = Some(value),
+                    _ => {
+                        input.unknown.insert(key, value);
+                    },
+                }
+            }
+            inputs.push(input);
+        }
+
+        let mut outputs = Vec::with_capacity(tx.outputs.len());
+        for _ in 0..tx.outputs.len() {
+            let mut output = PstOutput::default();
+            for (key, value) in reader.read_map()? {
+                match (key[0], key.len()) {
+                    (OUT_REDEEM_SCRIPT, 1) => output.redeem_script = Some(value),
+                    _ => {
+                        output.unknown.insert(key, value);
+                    },
+                }
+            }
+            outputs.push(output);
+        }
+
+        if reader.pos != bytes.len() {
+            return Err(reader.error());
+        }
+
+        Ok(Pst { tx, inputs, outputs, unknown })
+    }
+}
+
+impl Pst {
+    /// Construct `Pst` from transaction (`scriptSig`s are removed)
+    /// # Arguments
+    /// * `tx` - transaction
+    pub fn new(tx: &Transaction) -> Pst {
+        let mut tx = tx.clone();
+        for input in &mut tx.inputs {
+            input.script.clear();
+        }
+
+        Pst {
+            inputs: vec![PstInput::default(); tx.inputs.len()],
+            outputs: vec![PstOutput::default(); tx.outputs.len()],
+            unknown: KeyValues::new(),
+            tx,
+        }
+    }
+
+    /// Add partial signature
+    /// # Arguments
+    /// * `index` - input index
+    /// * `pubkey` - `public key`
+    /// * `sig` - `signature` (with sighash type)
+    pub fn add_signature(&mut self, index: usize, pubkey: &[u8], sig: &[u8]) -> Result<()> {
+        let input = self.inputs.get_mut(index).ok_or(Error::InvalidIndex(index))?;
+        input.partial_sigs.insert(pubkey.to_vec(), sig.to_vec());
+        Ok(())
+    }
+
+    /// Merge partial signing state of another `Pst` of the same transaction
+    /// # Arguments
+    /// * `other` - `Pst` to be merged
+    pub fn merge(&mut self, other: &Pst) -> Result<()> {
+        if self.tx != other.tx {
+            return Err(Error::InvalidPst("transaction mismatch".to_string()));
+        }
+
+        for (input, other) in self.inputs.iter_mut().zip(&other.inputs) {
+            if input.prev_output.is_none() {
+                input.prev_output = other.prev_output.clone();
+            }
+            input.partial_sigs.extend(other.partial_sigs.clone());
+            input.hash_type = input.hash_type.or(other.hash_type);
+            if input.redeem_script.is_none() {
+                input.redeem_script = other.redeem_script.clone();
+            }
+            if input.final_script_sig.is_none() {
+                input.final_script_sig = other.final_script_sig.clone();
+            }
+            input.unknown.extend(other.unknown.clone());
+        }
+        for (output, other) in self.outputs.iter_mut().zip(&other.outputs) {
+            if output.redeem_script.is_none() {
+                output.redeem_script = other.redeem_script.clone();
+            }
+            output.unknown.extend(other.unknown.clone());
+        }
+        self.unknown.extend(other.unknown.clone());
+
+        Ok(())
+    }
+
+    /// Build `scriptSig`s from partial signatures
+    /// # Returns
+    /// * signed transaction
+    /// # Example
+    /// ```
+    /// # #[macro_use] extern crate hex_literal;
+    /// # use std::convert::TryFrom;
+    /// # use cash_tx_builder::types::transaction::Transaction;
+    /// # use cash_tx_builder::pst::Pst;
+    /// let hex = hex!("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000");
+    /// let tx = Transaction::try_from(&hex[..])?;
+    /// let mut pst = Pst::new(&tx);
+    /// assert!(pst.finalize().is_err());
+    ///
+    /// pst.add_signature(
+    ///     0,
+    ///     &hex!("030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1de"),
+    ///     &hex!("304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d441"),
+    /// )?;
+    /// assert_eq!(pst.finalize()?, tx);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn finalize(&self) -> Result<Transaction> {
+        let mut tx = self.tx.clone();
+        for (i, (tx_input, input)) in tx.inputs.iter_mut().zip(&self.inputs).enumerate() {
+            tx_input.script = if let Some(script) = &input.final_script_sig {
+                script.clone()
+            } else if let Some(redeem_script) = &input.redeem_script {
+                let (m, pubkeys) = multisig::parse_redeem_script(redeem_script)?;
+                let sigs = pubkeys.iter()
+                    .filter_map(|pubkey| input.partial_sigs.get(*pubkey))
+                    .map(|sig| &sig[..])
+                    .take(m)
+                    .collect::<Vec<_>>();
+                if sigs.len() < m {
+                    return Err(Error::IncompleteInput(i));
+                }
+                multisig::script_sig(&sigs, redeem_script)?
+            } else if input.partial_sigs.len() == 1 {
+                let (pubkey, sig) = input.partial_sigs.iter().next().ok_or(Error::IncompleteInput(i))?;
+                p2pkh::script_sig(pubkey, sig)?
+            } else {
+                return Err(Error::IncompleteInput(i));
+            };
+        }
+
+        Ok(tx)
+    }
+
+    /// Convert to `Vec<u8>`
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::types::Amount;
+
+    #[test]
+    fn multisig_flow() -> Result<()> {
+        let hex = hex!("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000");
+        let tx = Transaction::try_from(&hex[..])?;
+
+        let pubkeys = [
+            &hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036")[..],
+            &hex!("030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1de")[..],
+        ];
+        let redeem_script = multisig::redeem_script(2, &pubkeys)?;
+
+        let mut pst = Pst::new(&tx);
+        pst.inputs[0].prev_output = Some(Output::new(Amount::from_sat(20_000_000), &multisig::script_pub_key(&redeem_script)?));
+        pst.inputs[0].redeem_script = Some(redeem_script.clone());
+        pst.inputs[0].hash_type = Some(0x41);
+
+        let mut pst_a = Pst::try_from(&pst.to_vec()[..])?;
+        assert_eq!(pst_a, pst);
+        let mut pst_b = pst_a.clone();
+
+        pst_b.add_signature(0, pubkeys[1], &[0x02; 71])?;
+        assert!(pst_b.finalize().is_err());
+        pst_a.add_signature(0, pubkeys[0], &[0x01; 71])?;
+
+        pst_a.merge(&Pst::try_from(&pst_b.to_vec()[..])?)?;
+        let signed = pst_a.finalize()?;
+        assert_eq!(signed.inputs[0].script, multisig::script_sig(&[&[0x01; 71], &[0x02; 71]], &redeem_script)?);
+
+        let mut other = Pst::new(&tx);
+        other.tx.lock_time = 1;
+        assert!(pst_a.merge(&other).is_err());
+
+        let mut v = pst.to_vec();
+        v[0] = 0;
+        assert!(Pst::try_from(&v[..]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn oversized_length() {
+        let v = [&MAGIC[..], &hex!("ffffffffffffffffff")].concat();
+        match Pst::try_from(&v[..]) {
+            Err(Error::InvalidPst(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
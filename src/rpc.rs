@@ -0,0 +1,261 @@
+//! bitcoind (BCHN) JSON-RPC client
+//!
+//! Only plain HTTP connections are supported.
+
+use std::fmt::Display;
+use serde_json::{json, Value};
+use super::tx_builder::TxBuilder;
+use super::types::{Txid, Amount, FeeRate};
+use super::script::{script_type, ScriptType};
+use super::http;
+use super::error::{Error, Result};
+
+fn rpc_error<E: Display>(err: E) -> Error {
+    Error::RpcError(err.to_string())
+}
+
+fn base64(v: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    v.chunks(3).flat_map(|chunk| {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        (0..4).map(move |i| {
+            if i > chunk.len() {
+                '='
+            } else {
+                char::from(TABLE[(n >> (18 - 6 * i) & 0x3f) as usize])
+            }
+        })
+    }).collect()
+}
+
+/// Unspent output returned by `listunspent`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unspent {
+    /// txid
+    pub txid: Txid,
+    /// output index
+    pub vout: u32,
+    /// `scriptPubKey`
+    pub script: Vec<u8>,
+    /// satoshi
+    pub value: u64,
+    /// number of confirmations
+    pub confirmations: u64,
+    /// `true` if output carries CashTokens
+    pub has_token: bool,
+}
+
+impl Unspent {
+    fn from_json(v: &Value) -> Option<Unspent> {
+        Some(Unspent {
+            txid: v["txid"].as_str()?.parse().ok()?,
+            vout: v["vout"].as_u64()? as u32,
+            script: hex::decode(v["scriptPubKey"].as_str()?).ok()?,
+            value: (v["amount"].as_f64()? * 100_000_000.0).round() as u64,
+            confirmations: v["confirmations"].as_u64()?,
+            has_token: !v["tokenData"].is_null(),
+        })
+    }
+}
+
+/// JSON-RPC client of a node
+#[derive(Debug, Clone)]
+pub struct Client {
+    addr: String,
+    auth: String,
+    next_id: u64,
+}
+
+impl Client {
+    /// Construct client
+    /// # Arguments
+    /// * `addr` - RPC address, e.g. `"127.0.0.1:8332"`
+    /// * `user` - `rpcuser`
+    /// * `password` - `rpcpassword`
+    pub fn new(addr: &str, user: &str, password: &str) -> Client {
+        Client {
+            addr: addr.to_string(),
+            auth: base64(format!("{}:{}", user, password).as_bytes()),
+            next_id: 0,
+        }
+    }
+
+    /// Call RPC method
+    /// # Arguments
+    /// * `method` - method name
+    /// * `params` - parameters
+    /// # Returns
+    /// * `result` of response
+    pub async fn call(&mut self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let body = json!({ "jsonrpc": "1.0", "id": id, "method": method, "params": params });
+        let auth = format!("Basic {}", self.auth);
+        // node replies with error status and JSON error, which `post_json` keeps in message
+        let mut response = http::post_json(&self.addr, "/", &[("Authorization", &auth)], &body).await
+            .map_err(Error::RpcError)?;
+        if !response["error"].is_null() {
+            return Err(Error::RpcError(response["error"].to_string()));
+        }
+
+        Ok(response["result"].take())
+    }
+
+    /// List unspent outputs of node's wallet
+    /// # Arguments
+    /// * `min_conf` - minimum number of confirmations
+    pub async fn list_unspent(&mut self, min_conf: u64) -> Result<Vec<Unspent>> {
+        let result = self.call("listunspent", json!([min_conf])).await?;
+        let unspents = result.as_array().ok_or_else(|| rpc_error(&result))?;
+
+        unspents.iter()
+            .map(|v| Unspent::from_json(v).ok_or_else(|| rpc_error(v)))
+            .collect()
+    }
+
+    /// Broadcast transaction
+    /// # Arguments
+    /// * `txb` - transaction builder with signed inputs
+    /// # Returns
+    /// * txid accepted by node
+    pub async fn send_raw_transaction<F>(&mut self, txb: &TxBuilder<F>) -> Result<Txid>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        let result = self.call("sendrawtransaction", json!([txb.to_hex()])).await?;
+        let txid = result.as_str().ok_or_else(|| rpc_error(&result))?;
+
+        txid.parse()
+    }
+
+    /// Add inputs from node's wallet and change output, like `fundrawtransaction`
+    ///
+    /// Confirmed P2PKH and P2PK outputs without tokens are selected largest first.
+    /// Dust change is left to fee. Added inputs are removed on error.
+    /// # Arguments
+    /// * `txb` - transaction builder with outputs
+    /// * `change_address` - address to receive change
+    /// * `fee_rate` - fee rate
+    /// # Returns
+    /// * change value (`0` if change output is not added)
+    pub async fn fund<F>(&mut self, txb: &mut TxBuilder<F>, change_address: &str, fee_rate: FeeRate) -> Result<Amount>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        let mut unspents = self.list_unspent(1).await?.into_iter()
+            .filter(|u| !u.has_token)
+            .filter(|u| matches!(script_type(&u.script), ScriptType::PubKeyHash | ScriptType::PubKey))
+            .collect::<Vec<_>>();
+        unspents.sort_by_key(|u| std::cmp::Reverse(u.value));
+
+        let input_count = txb.input_count();
+        let mut result = Err(Error::InsufficientFunds(0, 0));
+        for unspent in unspents {
+            txb.add_input(unspent.txid, unspent.vout, Some(unspent.value), Some(&unspent.script), None)?;
+            result = txb.add_change_output(change_address, fee_rate);
+            match result {
+                Ok(_) => break,
+                Err(Error::InsufficientFunds(..)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        if result.is_err() {
+            while txb.input_count() > input_count {
+                txb.remove_input(txb.input_count() - 1)?;
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use super::*;
+    use super::super::script::p2pkh;
+
+    async fn serve(listener: TcpListener, unspents: Value) {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![];
+            let body = loop {
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(i) = text.find("\r\n\r\n") {
+                    let len = text.lines()
+                        .find_map(|l| l.strip_prefix("Content-Length: "))
+                        .map(|l| l.parse::<usize>().unwrap())
+                        .unwrap();
+                    if request.len() >= i + 4 + len {
+                        break serde_json::from_slice::<Value>(&request[i + 4..]).unwrap();
+                    }
+                }
+            };
+            assert!(String::from_utf8_lossy(&request).contains("Authorization: Basic dXNlcjpwYXNz"));
+
+            let result = match body["method"].as_str().unwrap() {
+                "listunspent" => unspents.clone(),
+                "sendrawtransaction" => json!("ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda"),
+                _ => Value::Null,
+            };
+            let response = json!({ "id": body["id"], "result": result, "error": null }).to_string();
+            let v = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", response.len(), response);
+            stream.write_all(v.as_bytes()).await.unwrap();
+        }
+    }
+
+    #[test]
+    fn base64_encode() {
+        assert_eq!(base64(b"user:pass"), "dXNlcjpwYXNz");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"a"), "YQ==");
+    }
+
+    #[tokio::test]
+    async fn fund() -> Result<()> {
+        let script = p2pkh::script_pub_key(&[0x01; 20])?;
+        let unspent = |txid: &str, amount: f64, token: bool| json!({
+            "txid": txid,
+            "vout": 0,
+            "scriptPubKey": hex::encode(&script),
+            "amount": amount,
+            "confirmations": 6,
+            "tokenData": if token { json!({ "category": txid, "amount": "1" }) } else { Value::Null },
+        });
+        let unspents = json!([
+            unspent("427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c", 0.0003, false),
+            unspent("ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda", 1.0, true),
+            unspent("ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcdb", 0.0004, false),
+        ]);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.map_err(rpc_error)?;
+        let addr = listener.local_addr().map_err(rpc_error)?.to_string();
+        tokio::spawn(serve(listener, unspents));
+
+        let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+        let mut client = Client::new(&addr, "user", "pass");
+        let mut txb = TxBuilder::new(parser);
+        txb.add_output(Amount::from_sat(50_000), &script);
+
+        let change = client.fund(&mut txb, &hex::encode([0x02; 20]), FeeRate::MIN_RELAY).await?;
+        assert_eq!(txb.input_count(), 2);
+        assert_eq!(txb.output_count(), 2);
+        assert_eq!(txb.fee()?.to_sat(), 70_000 - 50_000 - change.to_sat());
+        assert_eq!(txb.fee()?.to_sat(), txb.estimated_size_with_signatures()? as u64);
+
+        let mut txb = TxBuilder::new(parser);
+        txb.add_output(Amount::from_sat(100_000), &script);
+        match client.fund(&mut txb, &hex::encode([0x02; 20]), FeeRate::MIN_RELAY).await {
+            Err(Error::InsufficientFunds(..)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(txb.input_count(), 0);
+
+        let txid = client.send_raw_transaction(&txb).await?;
+        assert_eq!(txid.to_string(), "ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda");
+
+        Ok(())
+    }
+}
@@ -1,21 +1,83 @@
 //! utility of bitcoin script
 
+pub mod p2pk;
 pub mod p2pkh;
 pub mod p2sh;
+pub mod multisig;
+pub mod bare_multisig;
+pub mod num;
+pub mod timelock;
+pub mod checkdatasig;
+pub mod signature;
+pub mod pubkey;
+mod asm;
+mod builder;
 
 use num_traits::FromPrimitive;
 use std::convert::TryInto;
 use super::opcode::OpCode;
 use OpCode::*;
+use super::network::Network;
+use super::interpreter::{MAX_SCRIPT_SIZE, MAX_PUBKEYS_PER_MULTISIG};
 use super::error::{Error, Result};
 
+pub use asm::{from_asm, to_asm};
+pub use builder::ScriptBuilder;
+
 /// Element to build bitcoin script
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Script<'a> {
     /// op code
     OpCode(OpCode),
     /// data
     Data(&'a [u8]),
+    /// owned data
+    OwnedData(Vec<u8>),
+    /// raw bytes of push cut off by the end of script (decoded in lenient mode only)
+    Truncated(Vec<u8>),
+}
+
+impl Script<'_> {
+    /// Get pushed data
+    /// # Returns
+    /// * data, `None` if op code
+    pub fn data(&self) -> Option<&[u8]> {
+        match self {
+            Script::OpCode(_) | Script::Truncated(_) => None,
+            Script::Data(data) => Some(data),
+            Script::OwnedData(data) => Some(data),
+        }
+    }
+
+    /// Convert to `Script` which does not borrow data
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::script::{Script, encode};
+    /// # use cash_tx_builder::OpCode::*;
+    /// let scripts = (1..=3u8).map(|i| Script::Data(&[i; 20]).into_owned()).collect::<Vec<_>>();
+    /// assert_eq!(scripts[0], Script::OwnedData(vec![1; 20]));
+    /// assert_eq!(encode(&scripts)?.len(), 63);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn into_owned(self) -> Script<'static> {
+        match self {
+            Script::OpCode(op) => Script::OpCode(op),
+            Script::Data(data) => Script::OwnedData(data.to_vec()),
+            Script::OwnedData(data) => Script::OwnedData(data),
+            Script::Truncated(raw) => Script::Truncated(raw),
+        }
+    }
+}
+
+/// Generate op code or borrowed data (`OP_PUSHDATA*` are generated as data)
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Script<'a> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Script<'a>> {
+        match u.arbitrary()? {
+            OP_PUSHDATA1 | OP_PUSHDATA2 | OP_PUSHDATA4 => Ok(Script::Data(u.arbitrary()?)),
+            op => Ok(Script::OpCode(op)),
+        }
+    }
 }
 
 const DATA_OPCODE: [OpCode; 17] = [
@@ -62,7 +124,7 @@ fn push_data(data: &[u8], v: &mut Vec<u8>) -> Result<()> {
 /// # Arguments
 /// * `scripts` - array of `Script`
 /// # Returns
-/// * raw script
+/// * raw script (`Error::ScriptTooLarge` if it exceeds `interpreter::MAX_SCRIPT_SIZE`)
 /// # Example
 /// ```
 /// # #[macro_use] extern crate hex_literal;
@@ -80,7 +142,26 @@ fn push_data(data: &[u8], v: &mut Vec<u8>) -> Result<()> {
 /// # Ok::<(), cash_tx_builder::Error>(())
 /// ```
 pub fn encode(scripts: &[Script<'_>]) -> Result<Vec<u8>> {
-    scripts.iter().try_fold(Vec::new(), |mut v, script| {
+    encode_with_limit(scripts, Some(MAX_SCRIPT_SIZE))
+}
+
+/// Build raw script with size limit
+/// # Arguments
+/// * `scripts` - array of `Script`
+/// * `max_size` - maximum size of raw script (`None` for no limit)
+/// # Returns
+/// * raw script (`Error::ScriptTooLarge` if it exceeds `max_size`)
+/// # Example
+/// ```
+/// # use cash_tx_builder::script::{Script, encode, encode_with_limit};
+/// let data = [0x01; 520];
+/// let scripts = vec![Script::Data(&data); 20];
+/// assert!(encode(&scripts).is_err());
+/// assert_eq!(encode_with_limit(&scripts, None)?.len(), 20 * 523);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn encode_with_limit(scripts: &[Script<'_>], max_size: Option<usize>) -> Result<Vec<u8>> {
+    let v = scripts.iter().try_fold(Vec::new(), |mut v, script| -> Result<Vec<u8>> {
         match script {
             Script::OpCode(op) => {
                 v.push(*op as u8);
@@ -88,13 +169,29 @@ pub fn encode(scripts: &[Script<'_>]) -> Result<Vec<u8>> {
             Script::Data(data) => {
                 push_data(data, &mut v)?;
             },
+            Script::OwnedData(data) => {
+                push_data(data, &mut v)?;
+            },
+            Script::Truncated(raw) => {
+                v.extend(raw);
+            },
         };
         Ok(v)
-    })
+    })?;
+
+    check_size(&v, max_size)?;
+    Ok(v)
 }
 
-fn get_opcode(v: &[u8]) -> Option<(Script<'_>, &[u8])> {
-    let op = v.get(0)?;
+fn check_size(script: &[u8], max_size: Option<usize>) -> Result<()> {
+    match max_size {
+        Some(max_size) if script.len() > max_size => Err(Error::ScriptTooLarge(script.len(), max_size)),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn get_opcode(v: &[u8]) -> Option<(Script<'_>, &[u8])> {
+    let op = v.first()?;
     let v = v.get(1..)?;
 
     if *op <= 0x4b {
@@ -104,7 +201,7 @@ fn get_opcode(v: &[u8]) -> Option<(Script<'_>, &[u8])> {
 
     match OpCode::from_u8(*op) {
         Some(OP_PUSHDATA1) => {
-            let len = *v.get(0)? as usize;
+            let len = *v.first()? as usize;
             let v = v.get(1..)?;
             Some((Script::Data(v.get(..len)?), v.get(len..)?))
         },
@@ -123,6 +220,48 @@ fn get_opcode(v: &[u8]) -> Option<(Script<'_>, &[u8])> {
     }
 }
 
+/// Bytes needed by push at the beginning of `v` (`None` if not push)
+fn push_size(v: &[u8]) -> Option<usize> {
+    let op = *v.first()?;
+    let len_size = match op {
+        0x00..=0x4b => return Some(1 + op as usize),
+        0x4c => 1,
+        0x4d => 2,
+        0x4e => 4,
+        _ => return None,
+    };
+    let len = match v.get(1..=len_size) {
+        Some(len) => len,
+        None => return Some(1 + len_size),
+    };
+    let mut buf = [0; 4];
+    buf[..len_size].copy_from_slice(len);
+
+    Some(1 + len_size + u32::from_le_bytes(buf) as usize)
+}
+
+/// Remove `OP_CODESEPARATOR`s from script, leaving other bytes as they are
+pub(crate) fn remove_code_separators(v: &[u8]) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(v.len());
+    let mut cur = v;
+    while !cur.is_empty() {
+        let next = match get_opcode(cur) {
+            Some((Script::OpCode(OP_CODESEPARATOR), next)) => {
+                cur = next;
+                continue;
+            },
+            Some((_, next)) => next,
+            None if cur[0] > OP_PUSHDATA4 as u8 => &cur[1..],
+            None => break,
+        };
+        ret.extend(&cur[..cur.len() - next.len()]);
+        cur = next;
+    }
+    ret.extend(cur);
+
+    ret
+}
+
 /// Decode raw script to array of `Script`
 /// # Arguments
 /// * v - raw script
@@ -146,23 +285,343 @@ fn get_opcode(v: &[u8]) -> Option<(Script<'_>, &[u8])> {
 /// # Ok::<(), cash_tx_builder::Error>(())
 /// ```
 pub fn decode(v: &[u8]) -> Result<Vec<Script<'_>>> {
-    let mut scripts = Vec::new();
-    let mut cur = v;
+    iter(v).map(|r| r.map(|(_, script)| script)).collect()
+}
+
+/// Decode raw script to array of `Script`, keeping push cut off by the end of script
+/// # Arguments
+/// * v - raw script
+/// # Returns
+/// * array of `Script`, the last one is `Script::Truncated` if the script ends in the middle of push
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::{Script, decode, decode_lenient, encode};
+/// # use cash_tx_builder::OpCode::*;
+/// let hex = hex!("6a4c050102");
+/// assert!(decode(&hex).is_err());
+///
+/// let decoded = decode_lenient(&hex)?;
+/// assert_eq!(decoded, [Script::OpCode(OP_RETURN), Script::Truncated(hex!("4c050102").to_vec())]);
+/// assert_eq!(encode(&decoded)?, hex);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn decode_lenient(v: &[u8]) -> Result<Vec<Script<'_>>> {
+    iter(v).lenient().map(|r| r.map(|(_, script)| script)).collect()
+}
+
+/// Iterator over instructions of raw script with their byte offsets
+///
+/// Stops after the first error.
+#[derive(Debug, Clone)]
+pub struct Instructions<'a> {
+    script: &'a [u8],
+    cur: &'a [u8],
+    lenient: bool,
+}
+
+impl<'a> Instructions<'a> {
+    /// Yield `Script::Truncated` instead of `Error::UnexpectedEndOfScript`
+    pub fn lenient(mut self) -> Instructions<'a> {
+        self.lenient = true;
+        self
+    }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Result<(usize, Script<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cur.is_empty() {
+            return None;
+        }
+        let offset = self.script.len() - self.cur.len();
+        match get_opcode(self.cur) {
+            Some((script, next)) => {
+                self.cur = next;
+                Some(Ok((offset, script)))
+            },
+            None => {
+                let cur = std::mem::take(&mut self.cur);
+                match push_size(cur) {
+                    Some(_) if self.lenient => Some(Ok((offset, Script::Truncated(cur.to_vec())))),
+                    Some(needed) => Some(Err(Error::UnexpectedEndOfScript { needed, available: cur.len(), offset })),
+                    None => Some(Err(Error::InvalidOpCode(cur[0]))),
+                }
+            },
+        }
+    }
+}
+
+/// Iterate instructions of raw script lazily
+/// # Arguments
+/// * v - raw script
+/// # Returns
+/// * iterator of byte offset and `Script`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::{Script, iter};
+/// # use cash_tx_builder::OpCode::*;
+/// let script = hex!("a914023a723c9e8b8297d84f6ab7dc08784c36b0729a87");
+/// let mut instructions = iter(&script);
+/// assert_eq!(instructions.next().transpose()?, Some((0, Script::OpCode(OP_HASH160))));
+/// assert_eq!(instructions.next().transpose()?.map(|(offset, _)| offset), Some(1));
+/// assert_eq!(instructions.next().transpose()?, Some((22, Script::OpCode(OP_EQUAL))));
+/// assert!(instructions.next().is_none());
+///
+/// // offset of malformed push
+/// let offset = iter(&hex!("51024c")).take_while(Result::is_ok).count();
+/// assert_eq!(offset, 1);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn iter(v: &[u8]) -> Instructions<'_> {
+    Instructions { script: v, cur: v, lenient: false }
+}
+
+/// Decode raw script to iterator of owned `Script`
+/// # Arguments
+/// * v - raw script
+/// # Returns
+/// * iterator of `Script`, which stops after the first error
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::{Script, decode_owned};
+/// # use cash_tx_builder::OpCode::*;
+/// let scripts = decode_owned(&hex!("6a0401020304"))
+///     .collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(scripts, [Script::OpCode(OP_RETURN), Script::OwnedData(vec![1, 2, 3, 4])]);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn decode_owned(v: &[u8]) -> impl Iterator<Item = Result<Script<'static>>> + '_ {
+    iter(v).map(|r| r.map(|(_, script)| script.into_owned()))
+}
+
+/// Check whether script consists of push operations only
+/// # Arguments
+/// * v - raw script
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::is_push_only;
+/// assert!(is_push_only(&hex!("00514c0101")));
+/// assert!(!is_push_only(&hex!("0076")));
+/// ```
+pub fn is_push_only(v: &[u8]) -> bool {
+    match decode(v) {
+        Ok(scripts) => scripts.iter().all(|script| match script {
+            Script::OpCode(op) => *op <= OP_16,
+            Script::Data(_) | Script::OwnedData(_) => true,
+            Script::Truncated(_) => false,
+        }),
+        Err(_) => false,
+    }
+}
+
+/// Count signature operations
+///
+/// `OP_CHECKSIG`, `OP_CHECKDATASIG` and their `VERIFY` variants count 1.
+/// `OP_CHECKMULTISIG` and `OP_CHECKMULTISIGVERIFY` count the number of `public keys`
+/// if `accurate` and preceded by `OP_1`..`OP_16`, otherwise `MAX_PUBKEYS_PER_MULTISIG`.
+/// Counting stops at malformed push.
+/// # Arguments
+/// * `script` - raw script
+/// * `accurate` - count `public keys` of multisig (used for `redeem script`)
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::sigop_count;
+/// // OP_2 <pubkey> <pubkey> <pubkey> OP_3 OP_CHECKMULTISIG
+/// let mut multisig = vec![0x52];
+/// for _ in 0..3 {
+///     multisig.push(0x21);
+///     multisig.extend(&[0x02; 33]);
+/// }
+/// multisig.extend(&[0x53, 0xae]);
+/// assert_eq!(sigop_count(&multisig, true), 3);
+/// assert_eq!(sigop_count(&multisig, false), 20);
+/// assert_eq!(sigop_count(&hex!("76a914023a723c9e8b8297d84f6ab7dc08784c36b0729a88ac"), false), 1);
+/// ```
+pub fn sigop_count(script: &[u8], accurate: bool) -> usize {
+    let mut count = 0;
+    let mut last = None;
+    let mut cur = script;
     while !cur.is_empty() {
-        if let Some((script, n)) = get_opcode(cur) {
-            scripts.push(script);
-            cur = n;
+        let (op, next) = match get_opcode(cur) {
+            Some((Script::OpCode(op), next)) => (Some(op), next),
+            Some((_, next)) => (None, next),
+            None if cur[0] > OP_PUSHDATA4 as u8 => (None, &cur[1..]),
+            None => break,
+        };
+        match op {
+            Some(OP_CHECKSIG) | Some(OP_CHECKSIGVERIFY) | Some(OP_CHECKDATASIG) | Some(OP_CHECKDATASIGVERIFY) => count += 1,
+            Some(OP_CHECKMULTISIG) | Some(OP_CHECKMULTISIGVERIFY) => count += match last {
+                Some(n) if accurate && n >= OP_1 && n <= OP_16 => (n as usize) - (OP_1 as usize) + 1,
+                _ => MAX_PUBKEYS_PER_MULTISIG,
+            },
+            _ => {},
+        }
+        last = op;
+        cur = next;
+    }
+
+    count
+}
+
+/// Standard `scriptPubKey` type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptType {
+    /// pay to public key
+    PubKey,
+    /// pay to public key hash
+    PubKeyHash,
+    /// pay to script hash
+    ScriptHash,
+    /// bare multisig
+    Multisig,
+    /// `OP_RETURN` data carrier
+    NullData,
+    /// other scripts
+    NonStandard,
+}
+
+impl ScriptType {
+    /// Name used by bitcoind
+    pub fn name(self) -> &'static str {
+        match self {
+            ScriptType::PubKey => "pubkey",
+            ScriptType::PubKeyHash => "pubkeyhash",
+            ScriptType::ScriptHash => "scripthash",
+            ScriptType::Multisig => "multisig",
+            ScriptType::NullData => "nulldata",
+            ScriptType::NonStandard => "nonstandard",
+        }
+    }
+}
+
+/// Get type of `scriptPubKey`
+/// # Arguments
+/// * `script` - `scriptPubKey`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::{script_type, ScriptType};
+/// let script = hex!("76a91432b57f34861bcbe33a701be9ac3a50288fbc0a3d88ac");
+/// assert_eq!(script_type(&script), ScriptType::PubKeyHash);
+/// assert_eq!(script_type(&hex!("6a04686f6765")), ScriptType::NullData);
+/// ```
+pub fn script_type(script: &[u8]) -> ScriptType {
+    if script.first() == Some(&(OP_RETURN as u8)) && is_push_only(&script[1..]) {
+        return ScriptType::NullData;
+    }
+    if multisig::parse_redeem_script(script).is_ok() {
+        return ScriptType::Multisig;
+    }
+
+    match decode(script).as_ref().map(Vec::as_slice) {
+        Ok([Script::Data(pubkey), Script::OpCode(OP_CHECKSIG)])
+            if pubkey.len() == 33 || pubkey.len() == 65 => ScriptType::PubKey,
+        Ok([Script::OpCode(OP_DUP), Script::OpCode(OP_HASH160), Script::Data(hash),
+            Script::OpCode(OP_EQUALVERIFY), Script::OpCode(OP_CHECKSIG)]) if hash.len() == 20 => ScriptType::PubKeyHash,
+        Ok([Script::OpCode(OP_HASH160), Script::Data(hash), Script::OpCode(OP_EQUAL)])
+            if hash.len() == 20 => ScriptType::ScriptHash,
+        _ => ScriptType::NonStandard,
+    }
+}
+
+/// Convert `scriptPubKey` to address
+/// # Arguments
+/// * `script` - `scriptPubKey`
+/// * `formatter` - address formatter, receives hash and `true` if P2PKH
+/// # Returns
+/// * address (`None` if `script` has no address)
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::script_to_address;
+/// let formatter = |hash: &[u8], is_pkh: bool| {
+///     Some(format!("{}:{}", if is_pkh { "pkh" } else { "sh" }, hex::encode(hash)))
+/// };
+///
+/// let script = hex!("76a9146f4b705e3e0407bf3159e9c4050df1b791d2c3f688ac");
+/// let address = script_to_address(&script, &formatter);
+/// assert_eq!(address.as_deref(), Some("pkh:6f4b705e3e0407bf3159e9c4050df1b791d2c3f6"));
+/// assert_eq!(script_to_address(&hex!("6a04686f6765"), &formatter), None);
+/// ```
+pub fn script_to_address<F>(script: &[u8], formatter: &F) -> Option<String>
+    where F: Fn(&[u8], bool) -> Option<String> {
+    match script_type(script) {
+        ScriptType::PubKeyHash => formatter(&script[3..23], true),
+        ScriptType::ScriptHash => formatter(&script[2..22], false),
+        ScriptType::PubKey => formatter(&super::hash::hash160(&script[1..script.len() - 1]), true),
+        _ => None,
+    }
+}
+
+/// Destination which can be converted to `scriptPubKey`
+///
+/// Implemented for address strings (parsed by address parser)
+/// and for `address::Address` with `address` feature.
+pub trait ToAddressScript {
+    /// Build `scriptPubKey`
+    /// # Arguments
+    /// * `parser` - address parser, receives address and returns `hash` and `true` if P2PKH
+    fn to_address_script<F>(&self, parser: &F) -> Result<Vec<u8>>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)>;
+
+    /// `false` if address is known to belong to other network
+    fn is_for_network(&self, _network: Network) -> bool {
+        true
+    }
+}
+
+impl ToAddressScript for str {
+    fn to_address_script<F>(&self, parser: &F) -> Result<Vec<u8>>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        let (hash, is_pkh) = parser(self).ok_or_else(|| Error::InvalidAddress(self.to_string()))?;
+
+        if is_pkh {
+            p2pkh::script_pub_key(&hash)
         } else {
-            return Err(Error::InvalidOpCode(cur[0]));
+            p2sh::script_pub_key(&hash)
+        }
+    }
+
+    /// Cashaddr prefix is checked,
+    /// and addresses without prefix are also checked with `address` feature.
+    fn is_for_network(&self, network: Network) -> bool {
+        if let Some(i) = self.find(':') {
+            let prefix = &self[..i];
+            let is_known = Network::ALL.iter().any(|n| n.cashaddr_prefix().eq_ignore_ascii_case(prefix));
+            return !is_known || network.cashaddr_prefix().eq_ignore_ascii_case(prefix);
+        }
+
+        #[cfg(feature = "address")]
+        {
+            if let Ok(address) = self.parse::<super::address::Address>() {
+                return address.is_for_network(network);
+            }
         }
+
+        true
+    }
+}
+
+impl ToAddressScript for String {
+    fn to_address_script<F>(&self, parser: &F) -> Result<Vec<u8>>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        self.as_str().to_address_script(parser)
     }
 
-    Ok(scripts)
+    fn is_for_network(&self, network: Network) -> bool {
+        self.as_str().is_for_network(network)
+    }
 }
 
 /// Convert address to `scriptPubKey`
 /// # Arguments
-/// * `address` - bitcoin address
+/// * `address` - bitcoin address (string or `address::Address`)
 /// * `parser` - address parser
 /// # Returns
 /// * `scriptPubKey`
@@ -192,15 +651,10 @@ pub fn decode(v: &[u8]) -> Result<Vec<Script<'_>>> {
 /// assert_eq!(p2sh_script, hex!("a9146f4b705e3e0407bf3159e9c4050df1b791d2c3f687"));
 /// # Ok::<(), cash_tx_builder::Error>(())
 /// ```
-pub fn address_to_script<F>(address: &str, parser: &F) -> Result<Vec<u8>>
-    where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
-    let (hash, is_pkh) = parser(address).ok_or_else(|| Error::InvalidAddress(address.to_string()))?;
-
-    if is_pkh {
-        p2pkh::script_pub_key(&hash)
-    } else {
-        p2sh::script_pub_key(&hash)
-    }
+pub fn address_to_script<A, F>(address: &A, parser: &F) -> Result<Vec<u8>>
+    where A: ToAddressScript + ?Sized,
+          F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+    address.to_address_script(parser)
 }
 
 /// Build `scriptPubKey` from `null data`
@@ -224,6 +678,26 @@ pub fn null_data_script(data: &[u8]) -> Result<Vec<u8>> {
     ])
 }
 
+/// Build `scriptPubKey` from multiple `null data` pushes
+/// # Arguments
+/// * `data` - null data, each pushed separately
+/// # Returns
+/// * `scriptPubKey`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::null_data_script_multi;
+/// let script_pub_key = null_data_script_multi(&[&hex!("6d02"), b"hoge"])?;
+/// assert_eq!(script_pub_key, hex!("6a026d0204686f6765"));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn null_data_script_multi(data: &[&[u8]]) -> Result<Vec<u8>> {
+    let mut scripts = vec![Script::OpCode(OP_RETURN)];
+    scripts.extend(data.iter().map(|d| Script::Data(d)));
+
+    encode(&scripts)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -234,12 +708,7 @@ mod tests {
         let converter = Converter::new();
         let parser = |address: &str| {
             let parsed = converter.parse(address).ok();
-            match parsed {
-                Some((_, _, address_type, hash)) => {
-                    Some((hash, address_type == AddressType::P2PKH))
-                }
-                None => None
-            }
+            parsed.map(|(_, _, address_type, hash)| (hash, address_type == AddressType::P2PKH))
         };
         let script = address_to_script("qq6zfutryz9rkem05rkpwq60pu5sxg4z5c330k4w75", &parser)?;
         assert_eq!(script, hex!("76a9143424f163208a3b676fa0ec17034f0f290322a2a688ac"));
@@ -263,4 +732,85 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn iter_offsets() -> Result<()> {
+        let script = [&[0x00, 0x4c, 0x02, 0x01, 0x02, 0x4d, 0x01, 0x00, 0x03][..], &[0x6a]].concat();
+        let offsets = iter(&script).map(|r| r.map(|(offset, _)| offset)).collect::<Result<Vec<_>>>()?;
+        assert_eq!(offsets, [0, 1, 5, 9]);
+
+        let mut instructions = iter(&[0x51, 0xbd]);
+        assert!(instructions.next().unwrap().is_ok());
+        match instructions.next() {
+            Some(Err(Error::InvalidOpCode(0xbd))) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(instructions.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn truncated() -> Result<()> {
+        for (script, needed, available) in &[
+            (&[0x51, 0x02, 0x01][..], 3, 2),
+            (&[0x51, 0x4c][..], 2, 1),
+            (&[0x51, 0x4d, 0x00, 0x01, 0x01][..], 259, 4),
+            (&[0x51, 0x4e, 0x01, 0x00][..], 5, 3),
+        ] {
+            match decode(script) {
+                Err(Error::UnexpectedEndOfScript { needed: n, available: a, offset: 1 }) if n == *needed && a == *available => {},
+                r => panic!("unexpected result: {:?}", r),
+            }
+            let decoded = decode_lenient(script)?;
+            assert_eq!(decoded, [Script::OpCode(OP_1), Script::Truncated(script[1..].to_vec())]);
+            assert_eq!(encode(&decoded)?, *script);
+        }
+        match decode_lenient(&[0x51, 0xbd]) {
+            Err(Error::InvalidOpCode(0xbd)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn owned_script() -> Result<()> {
+        fn build(keys: &[[u8; 33]]) -> Vec<Script<'static>> {
+            let mut scripts = vec![Script::OpCode(OP_1)];
+            for key in keys {
+                scripts.push(Script::OwnedData(key.to_vec()));
+            }
+            scripts.push(Script::OpCode(OP_CHECKMULTISIG));
+            scripts
+        }
+
+        let encoded = encode(&build(&[[0x02; 33], [0x03; 33]]))?;
+        let decoded = decode_owned(&encoded).collect::<Result<Vec<_>>>()?;
+        assert_eq!(decoded[1], Script::OwnedData(vec![0x02; 33]));
+        assert_eq!(decoded.iter().map(Script::data).collect::<Vec<_>>(), decode(&encoded)?.iter().map(Script::data).collect::<Vec<_>>());
+
+        let mut iter = decode_owned(&hex!("7602ff"));
+        assert_eq!(iter.next().map(Result::ok), Some(Some(Script::OpCode(OP_DUP))));
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_script() -> Result<()> {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let data = (0..=255u8).cycle().take(4096).collect::<Vec<_>>();
+        let mut u = Unstructured::new(&data);
+        while !u.is_empty() {
+            let scripts = Vec::<Script<'_>>::arbitrary(&mut u).unwrap();
+            let encoded = encode(&scripts)?;
+            assert_eq!(decode(&encoded)?.len(), scripts.len());
+        }
+
+        Ok(())
+    }
 }
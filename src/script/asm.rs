@@ -0,0 +1,135 @@
+//! script disassembly
+
+use super::super::opcode::OpCode::{self, *};
+use super::super::error::{Error, Result};
+use super::{Script, get_opcode, push_data};
+
+/// Convert raw script to ASM
+///
+/// Pushed data is rendered as hex, prefixed by `OP_PUSHDATA1`/`2`/`4` when pushed by it.
+/// Undecodable remainder is rendered as `[error]`.
+/// # Arguments
+/// * v - raw script
+/// # Returns
+/// * ASM
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::to_asm;
+/// let asm = to_asm(&hex!("76a914023a723c9e8b8297d84f6ab7dc08784c36b0729a88ac"));
+/// assert_eq!(asm, "OP_DUP OP_HASH160 023a723c9e8b8297d84f6ab7dc08784c36b0729a OP_EQUALVERIFY OP_CHECKSIG");
+/// ```
+pub fn to_asm(v: &[u8]) -> String {
+    let mut tokens = vec![];
+    let mut cur = v;
+    while !cur.is_empty() {
+        let next = match get_opcode(cur) {
+            Some((Script::OpCode(op), next)) => {
+                tokens.push(op.name().to_string());
+                next
+            },
+            Some((script, next)) => {
+                let data = hex::encode(script.data().unwrap_or_default());
+                let token = match cur[0] {
+                    0x00 => "OP_0".to_string(),
+                    0x01..=0x4b => data,
+                    0x4c => format!("OP_PUSHDATA1 {}", data),
+                    0x4d => format!("OP_PUSHDATA2 {}", data),
+                    _ => format!("OP_PUSHDATA4 {}", data),
+                };
+                tokens.push(token);
+                next
+            },
+            None => {
+                tokens.push("[error]".to_string());
+                break;
+            },
+        };
+        cur = next;
+    }
+
+    tokens.join(" ")
+}
+
+/// Convert ASM to raw script
+///
+/// Hex data up to 75 bytes is pushed directly, longer data by the smallest `OP_PUSHDATA`.
+/// # Arguments
+/// * asm - ASM
+/// # Returns
+/// * raw script
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::from_asm;
+/// let script = from_asm("OP_HASH160 023a723c9e8b8297d84f6ab7dc08784c36b0729a OP_EQUAL")?;
+/// assert_eq!(script, hex!("a914023a723c9e8b8297d84f6ab7dc08784c36b0729a87"));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn from_asm(asm: &str) -> Result<Vec<u8>> {
+    let decode_hex = |token: &str| hex::decode(token).map_err(|_| Error::InvalidAsm(token.to_string()));
+
+    let mut v = vec![];
+    let mut tokens = asm.split_whitespace();
+    while let Some(token) = tokens.next() {
+        let op = match token.parse::<OpCode>() {
+            Ok(op) => op,
+            Err(_) => {
+                let data = decode_hex(token)?;
+                if data.len() <= 0x4b {
+                    v.push(data.len() as u8);
+                    v.extend(data);
+                } else {
+                    push_data(&data, &mut v)?;
+                }
+                continue;
+            },
+        };
+
+        v.push(op as u8);
+        let len_size = match op {
+            OP_PUSHDATA1 => 1,
+            OP_PUSHDATA2 => 2,
+            OP_PUSHDATA4 => 4,
+            _ => continue,
+        };
+        let data = decode_hex(tokens.next().ok_or_else(|| Error::InvalidAsm(token.to_string()))?)?;
+        if (data.len() as u64) >> (len_size * 8) != 0 {
+            return Err(Error::InvalidLengthData(data.len()));
+        }
+        v.extend(&(data.len() as u32).to_le_bytes()[..len_size]);
+        v.extend(data);
+    }
+
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let set = [
+            ("OP_0 OP_1 OP_1NEGATE 01 OP_CHECKLOCKTIMEVERIFY", "00514f0101b1"),
+            ("OP_PUSHDATA1 05 OP_PUSHDATA2 0506 OP_PUSHDATA4 07", "4c01054d020005064e0100000007"),
+            ("OP_RETURN 1234567890", "6a051234567890"),
+        ];
+        for (asm, hex) in set.iter() {
+            assert_eq!(hex::encode(from_asm(asm)?), *hex);
+            assert_eq!(to_asm(&hex::decode(hex).unwrap()), *asm);
+        }
+
+        let long = format!("OP_RETURN {}", "00".repeat(80));
+        assert_eq!(from_asm(&long)?[1..3], [0x4c, 80]);
+        assert_eq!(to_asm(&from_asm(&long)?), format!("OP_RETURN OP_PUSHDATA1 {}", "00".repeat(80)));
+        assert_eq!(from_asm("OP_NOP2 OP_TRUE")?, [0xb1, 0x51]);
+
+        assert_eq!(to_asm(&hex!("7602ff")), "OP_DUP [error]");
+        assert!(from_asm("OP_HOGE").is_err());
+        assert!(from_asm("OP_PUSHDATA1").is_err());
+        assert!(from_asm(&format!("OP_PUSHDATA1 {}", "00".repeat(256))).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+//! Bare (non-P2SH) multisig utility
+
+use super::super::script::{encode, Script, multisig};
+use super::super::opcode::OpCode::*;
+use super::super::error::{Result};
+
+/// Maximum number of `public key`s in standard bare multisig `scriptPubKey`
+pub const MAX_STANDARD_PUBKEYS: usize = 3;
+
+/// Build M-of-N bare multisig `scriptPubKey`
+/// # Arguments
+/// * `m` - number of required signatures
+/// * `pubkeys` - `public key`s
+/// # Returns
+/// * `scriptPubKey`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::bare_multisig::script_pub_key;
+/// let pubkeys = [
+///     &hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036")[..],
+///     &hex!("030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1de")[..],
+/// ];
+/// let script_pub_key = script_pub_key(1, &pubkeys)?;
+/// assert_eq!(script_pub_key, hex!("51210366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb03621030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1de52ae").to_vec());
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn script_pub_key(m: usize, pubkeys: &[&[u8]]) -> Result<Vec<u8>> {
+    multisig::redeem_script(m, pubkeys)
+}
+
+/// Build `scriptSig` from `signature`s
+/// # Arguments
+/// * `sigs` - transaction's `signature`s in the order of `public key`s
+/// # Returns
+/// * `scriptSig`
+pub fn script_sig(sigs: &[&[u8]]) -> Result<Vec<u8>> {
+    let mut scripts = vec![Script::OpCode(OP_0)];
+    scripts.extend(sigs.iter().map(|sig| Script::Data(sig)));
+
+    encode(&scripts)
+}
+
+/// Check whether `scriptPubKey` is standard bare multisig
+/// (at most `MAX_STANDARD_PUBKEYS` compressed or uncompressed `public key`s)
+/// # Arguments
+/// * `script` - `scriptPubKey`
+pub fn is_standard(script: &[u8]) -> bool {
+    match multisig::parse_redeem_script(script) {
+        Ok((m, pubkeys)) => {
+            m >= 1 && pubkeys.len() <= MAX_STANDARD_PUBKEYS
+                && pubkeys.iter().all(|pubkey| pubkey.len() == 33 || pubkey.len() == 65)
+        },
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::decode;
+
+    #[test]
+    fn bare_multisig() -> Result<()> {
+        let pubkey = hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036");
+        let script = script_pub_key(2, &[&pubkey[..]; 3])?;
+        assert!(is_standard(&script));
+        assert!(!is_standard(&script_pub_key(2, &[&pubkey[..]; 4])?));
+        assert!(!is_standard(&script_pub_key(1, &[&pubkey[..32]])?));
+        assert!(!is_standard(&hex!("51")));
+
+        let sig = hex!("304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b3041");
+        let script_sig = script_sig(&[&sig, &sig])?;
+        assert_eq!(decode(&script_sig)?, [
+            Script::Data(&[]),
+            Script::Data(&sig),
+            Script::Data(&sig),
+        ]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! fluent script builder
+
+use super::super::opcode::OpCode;
+use super::super::error::{Error, Result};
+use super::super::interpreter::MAX_SCRIPT_SIZE;
+use super::{num, push_data, check_size};
+
+/// Builder of raw script
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::ScriptBuilder;
+/// # use cash_tx_builder::OpCode::*;
+/// let hash = hex!("023a723c9e8b8297d84f6ab7dc08784c36b0729a");
+/// let script = ScriptBuilder::new()
+///     .int(500_000)
+///     .op(OP_CHECKLOCKTIMEVERIFY)
+///     .op(OP_DROP)
+///     .op(OP_DUP)
+///     .op(OP_HASH160)
+///     .data(&hash)
+///     .op(OP_EQUALVERIFY)
+///     .op(OP_CHECKSIG)
+///     .build()?;
+/// assert_eq!(script, hex!("0320a107b17576a914023a723c9e8b8297d84f6ab7dc08784c36b0729a88ac"));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptBuilder {
+    script: Vec<u8>,
+    invalid_len: Option<usize>,
+    max_size: Option<usize>,
+}
+
+impl Default for ScriptBuilder {
+    fn default() -> ScriptBuilder {
+        ScriptBuilder {
+            script: vec![],
+            invalid_len: None,
+            max_size: Some(MAX_SCRIPT_SIZE),
+        }
+    }
+}
+
+impl ScriptBuilder {
+    /// Construct empty `ScriptBuilder`
+    pub fn new() -> ScriptBuilder {
+        ScriptBuilder::default()
+    }
+
+    /// Set maximum size of built script (default: `interpreter::MAX_SCRIPT_SIZE`)
+    /// # Arguments
+    /// * `max_size` - maximum size (`None` for no limit)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::script::ScriptBuilder;
+    /// # use cash_tx_builder::policy::MAX_STANDARD_SCRIPT_SIG_SIZE;
+    /// let mut builder = ScriptBuilder::new();
+    /// builder.max_size(Some(MAX_STANDARD_SCRIPT_SIG_SIZE));
+    /// for _ in 0..4 {
+    ///     builder.data(&[0x01; 520]);
+    /// }
+    /// assert!(builder.build().is_err());
+    /// assert!(builder.max_size(None).build().is_ok());
+    /// ```
+    pub fn max_size(&mut self, max_size: Option<usize>) -> &mut ScriptBuilder {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Append op code
+    /// # Arguments
+    /// * `op` - op code
+    pub fn op(&mut self, op: OpCode) -> &mut ScriptBuilder {
+        self.script.push(op as u8);
+        self
+    }
+
+    /// Append data by minimal push operation
+    /// # Arguments
+    /// * `data` - data
+    pub fn data(&mut self, data: &[u8]) -> &mut ScriptBuilder {
+        if push_data(data, &mut self.script).is_err() && self.invalid_len.is_none() {
+            self.invalid_len = Some(data.len());
+        }
+        self
+    }
+
+    /// Append script number by minimal push operation
+    /// # Arguments
+    /// * `n` - number
+    pub fn int(&mut self, n: i64) -> &mut ScriptBuilder {
+        self.data(&num::encode(n))
+    }
+
+    /// Build raw script
+    /// # Returns
+    /// * raw script
+    pub fn build(&self) -> Result<Vec<u8>> {
+        match self.invalid_len {
+            Some(len) => Err(Error::InvalidLengthData(len)),
+            None => check_size(&self.script, self.max_size).map(|_| self.script.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::opcode::OpCode::*;
+
+    #[test]
+    fn build() -> Result<()> {
+        let script = ScriptBuilder::new().int(0).int(-1).int(16).int(17).int(-255).int(128).build()?;
+        assert_eq!(script, hex!("004f60011102ff80028000"));
+
+        let mut builder = ScriptBuilder::new();
+        builder.op(OP_2);
+        for key in &[[0x02; 33], [0x03; 33]] {
+            builder.data(key);
+        }
+        builder.op(OP_2).op(OP_CHECKMULTISIG);
+        assert_eq!(builder.build()?.len(), 1 + 34 * 2 + 2);
+        assert_eq!(ScriptBuilder::new().data(&[0x4c; 76]).build()?[..2], [0x4c, 76]);
+
+        let mut builder = ScriptBuilder::new();
+        for _ in 0..20 {
+            builder.data(&[0x01; 520]);
+        }
+        match builder.build() {
+            Err(Error::ScriptTooLarge(10_460, 10_000)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(builder.max_size(None).build()?.len(), 10_460);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+//! Oracle-verified covenant script utility (`OP_CHECKDATASIG`)
+//!
+//! `OP_CHECKDATASIG` checks a signature of an arbitrary message (single SHA-256 of it),
+//! which lets scripts depend on data signed by an oracle.
+
+use super::super::script::{encode, Script, ScriptBuilder, p2sh};
+use super::super::opcode::OpCode::*;
+use super::super::hash;
+use super::super::error::Result;
+#[cfg(feature = "sign")]
+use super::super::signer::Signer;
+
+/// Digest signed for `OP_CHECKDATASIG`
+/// # Arguments
+/// * `message` - message (not hashed)
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::checkdatasig::message_digest;
+/// assert_eq!(message_digest(b"abc"), hex!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"));
+/// ```
+pub fn message_digest(message: &[u8]) -> [u8; 32] {
+    let mut v = [0; 32];
+    v.copy_from_slice(&hash::sha256(message));
+    v
+}
+
+/// Sign message for `OP_CHECKDATASIG`
+/// # Arguments
+/// * `signer` - signer of oracle
+/// * `message` - message (not hashed)
+/// # Returns
+/// * `signature` (without sighash type)
+#[cfg(feature = "sign")]
+pub fn sign_message<S: Signer>(signer: &S, message: &[u8]) -> Result<Vec<u8>> {
+    signer.sign(&message_digest(message))
+}
+
+/// Append check of oracle signature
+///
+/// `[<message>] <oracle pubkey> OP_CHECKDATASIGVERIFY`
+/// # Arguments
+/// * `builder` - script builder
+/// * `message` - (option) fixed message, otherwise the message is pushed by `scriptSig`
+/// * `oracle_pubkey` - `public key` of oracle
+pub fn append_verify<'a>(builder: &'a mut ScriptBuilder, message: Option<&[u8]>, oracle_pubkey: &[u8]) -> &'a mut ScriptBuilder {
+    if let Some(message) = message {
+        builder.data(message);
+    }
+    builder.data(oracle_pubkey).op(OP_CHECKDATASIGVERIFY)
+}
+
+/// Build `redeem script` spendable by `public key` with oracle signature of message
+///
+/// `[<message>] <oracle pubkey> OP_CHECKDATASIGVERIFY <pubkey> OP_CHECKSIG`
+/// # Arguments
+/// * `oracle_pubkey` - `public key` of oracle
+/// * `message` - (option) fixed message, otherwise any message signed by oracle is accepted
+/// * `pubkey` - `public key`
+/// # Returns
+/// * `redeem script`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::checkdatasig::redeem_script;
+/// let oracle = hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036");
+/// let pubkey = hex!("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+/// let redeem_script = redeem_script(&oracle, Some(b"yes"), &pubkey)?;
+/// assert_eq!(redeem_script[..5], hex!("0379657321"));
+/// assert_eq!(redeem_script[38], 0xbb);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn redeem_script(oracle_pubkey: &[u8], message: Option<&[u8]>, pubkey: &[u8]) -> Result<Vec<u8>> {
+    let mut builder = ScriptBuilder::new();
+    append_verify(&mut builder, message, oracle_pubkey)
+        .data(pubkey)
+        .op(OP_CHECKSIG)
+        .build()
+}
+
+/// Build `scriptPubKey` from `redeem script`
+/// # Arguments
+/// * `redeem_script` - `redeem script`
+/// # Returns
+/// * `scriptPubKey`
+pub fn script_pub_key(redeem_script: &[u8]) -> Result<Vec<u8>> {
+    p2sh::script_pub_key(&hash::hash160(redeem_script))
+}
+
+/// Build `scriptSig` for `redeem_script`
+/// # Arguments
+/// * `sig` - transaction's `signature`
+/// * `oracle_sig` - oracle's `signature` of message
+/// * `message` - message if not fixed in `redeem_script`
+/// * `redeem_script` - `redeem script`
+/// # Returns
+/// * `scriptSig`
+pub fn script_sig(sig: &[u8], oracle_sig: &[u8], message: Option<&[u8]>, redeem_script: &[u8]) -> Result<Vec<u8>> {
+    let mut scripts = vec![Script::Data(sig), Script::Data(oracle_sig)];
+    if let Some(message) = message {
+        scripts.push(Script::Data(message));
+    }
+    scripts.push(Script::Data(redeem_script));
+
+    encode(&scripts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::interpreter::{verify_script, SignatureChecker};
+
+    /// accepts transaction signature `[0x01, pubkey[0]]` and message signature `[0x02, msg..]`
+    struct Checker;
+
+    impl SignatureChecker for Checker {
+        fn check_sig(&self, sig: &[u8], pubkey: &[u8], _script_code: &[u8]) -> bool {
+            sig.len() >= 2 && sig[0] == 0x01 && sig[1] == pubkey[0]
+        }
+
+        fn check_data_sig(&self, sig: &[u8], msg: &[u8], _pubkey: &[u8]) -> bool {
+            sig.first() == Some(&0x02) && sig[1..] == *msg
+        }
+    }
+
+    #[test]
+    fn oracle() -> Result<()> {
+        let (oracle, key) = ([0x03; 33], [0x02; 33]);
+        let sig = [0x01, 0x02];
+
+        let redeem = redeem_script(&oracle, Some(b"yes"), &key)?;
+        let spk = script_pub_key(&redeem)?;
+        verify_script(&script_sig(&sig, &[0x02, b'y', b'e', b's'], None, &redeem)?, &spk, &Checker)?;
+        assert!(verify_script(&script_sig(&sig, &[0x02, b'n', b'o'], None, &redeem)?, &spk, &Checker).is_err());
+
+        let redeem = redeem_script(&oracle, None, &key)?;
+        let spk = script_pub_key(&redeem)?;
+        verify_script(&script_sig(&sig, &[0x02, b'n', b'o'], Some(b"no"), &redeem)?, &spk, &Checker)?;
+        assert!(verify_script(&script_sig(&sig, &[0x02, b'n', b'o'], Some(b"yes"), &redeem)?, &spk, &Checker).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn sign() -> Result<()> {
+        use secp256k1::SecretKey;
+        use super::super::super::signer;
+
+        let key = SecretKey::from_slice(&[0x01; 32])?;
+        let sig = sign_message(&key, b"price:42")?;
+        assert!(signer::verify(&Signer::public_key(&key), &message_digest(b"price:42"), &sig));
+        assert!(!signer::verify(&Signer::public_key(&key), &message_digest(b"price:43"), &sig));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+//! P2SH multisig utility
+
+use super::super::script::{encode, decode, Script, p2sh};
+use super::super::opcode::OpCode::*;
+use super::super::hash;
+use super::super::error::{Error, Result};
+
+/// Build M-of-N multisig `redeem script`
+/// # Arguments
+/// * `m` - number of required signatures
+/// * `pubkeys` - `public key`s
+/// # Returns
+/// * `redeem script`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::multisig::redeem_script;
+/// let pubkeys = [
+///     &hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036")[..],
+///     &hex!("030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1de")[..],
+/// ];
+/// let redeem_script = redeem_script(1, &pubkeys)?;
+/// assert_eq!(redeem_script, hex!("51210366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb03621030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1de52ae").to_vec());
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn redeem_script(m: usize, pubkeys: &[&[u8]]) -> Result<Vec<u8>> {
+    let n = pubkeys.len();
+    if m == 0 || m > n || n > 16 {
+        return Err(Error::InvalidMultisig(m, n));
+    }
+
+    let m = [m as u8];
+    let n = [n as u8];
+    let mut scripts = vec![Script::Data(&m)];
+    scripts.extend(pubkeys.iter().map(|pubkey| Script::Data(pubkey)));
+    scripts.push(Script::Data(&n));
+    scripts.push(Script::OpCode(OP_CHECKMULTISIG));
+
+    encode(&scripts)
+}
+
+/// Parse M-of-N multisig `redeem script`
+/// # Arguments
+/// * `redeem_script` - `redeem script`
+/// # Returns
+/// * number of required signatures
+/// * `public key`s
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::multisig::{redeem_script, parse_redeem_script};
+/// let pubkey = hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036");
+/// let script = redeem_script(1, &[&pubkey])?;
+/// let (m, pubkeys) = parse_redeem_script(&script)?;
+/// assert_eq!(m, 1);
+/// assert_eq!(pubkeys, [&pubkey[..]]);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn parse_redeem_script(redeem_script: &[u8]) -> Result<(usize, Vec<&[u8]>)> {
+    let scripts = decode(redeem_script)?;
+    let small_int = |s: &Script<'_>| match s {
+        Script::OpCode(op) if *op >= OP_1 && *op <= OP_16 => Some(*op as usize - OP_1 as usize + 1),
+        _ => None,
+    };
+
+    match &scripts[..] {
+        [first, keys @ .., last, Script::OpCode(OP_CHECKMULTISIG)] => {
+            let m = small_int(first).ok_or(Error::InvalidMultisig(0, keys.len()))?;
+            let n = small_int(last).ok_or(Error::InvalidMultisig(m, keys.len()))?;
+            let pubkeys = keys.iter().filter_map(|k| match k {
+                Script::Data(data) => Some(*data),
+                _ => None,
+            }).collect::<Vec<_>>();
+            if m > n || n != keys.len() || pubkeys.len() != n {
+                return Err(Error::InvalidMultisig(m, n));
+            }
+
+            Ok((m, pubkeys))
+        },
+        _ => Err(Error::InvalidMultisig(0, 0)),
+    }
+}
+
+/// Build `scriptPubKey` from `redeem script`
+/// # Arguments
+/// * `redeem_script` - `redeem script`
+/// # Returns
+/// * `scriptPubKey`
+pub fn script_pub_key(redeem_script: &[u8]) -> Result<Vec<u8>> {
+    p2sh::script_pub_key(&hash::hash160(redeem_script))
+}
+
+/// Build `scriptSig` from `signature`s and `redeem script`
+/// # Arguments
+/// * `sigs` - transaction's `signature`s in the order of `public key`s
+/// * `redeem_script` - `redeem script`
+/// # Returns
+/// * `scriptSig`
+pub fn script_sig(sigs: &[&[u8]], redeem_script: &[u8]) -> Result<Vec<u8>> {
+    let mut scripts = vec![Script::OpCode(OP_0)];
+    scripts.extend(sigs.iter().map(|sig| Script::Data(sig)));
+    scripts.push(Script::Data(redeem_script));
+
+    encode(&scripts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::decode;
+
+    #[test]
+    fn multisig() -> Result<()> {
+        let pubkeys = [
+            &hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036")[..],
+            &hex!("030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1de")[..],
+        ];
+        let redeem_script = redeem_script(2, &pubkeys)?;
+        assert_eq!(redeem_script[0], OP_2 as u8);
+        assert_eq!(redeem_script[redeem_script.len() - 2], OP_2 as u8);
+
+        let script_pub_key = script_pub_key(&redeem_script)?;
+        assert_eq!(script_pub_key, p2sh::script_pub_key(&hash::hash160(&redeem_script))?);
+
+        let sig = hex!("304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b3041");
+        let script_sig = script_sig(&[&sig, &sig], &redeem_script)?;
+        assert_eq!(decode(&script_sig)?, [
+            Script::Data(&[]),
+            Script::Data(&sig),
+            Script::Data(&sig),
+            Script::Data(&redeem_script),
+        ]);
+
+        assert!(super::redeem_script(3, &pubkeys).is_err());
+        assert!(super::redeem_script(0, &pubkeys).is_err());
+
+        Ok(())
+    }
+}
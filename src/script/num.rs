@@ -0,0 +1,96 @@
+//! script number (little endian sign-magnitude) encoding
+
+use super::super::error::{Error, Result};
+
+/// Maximum size of script number (64-bit integers since May 2022 upgrade)
+pub const MAX_NUM_SIZE: usize = 8;
+
+/// Maximum size of script number before May 2022 upgrade (32-bit integers)
+pub const LEGACY_MAX_NUM_SIZE: usize = 4;
+
+/// Encode script number in minimal format
+/// # Arguments
+/// * `n` - number
+/// # Returns
+/// * encoded number
+/// # Example
+/// ```
+/// # use cash_tx_builder::script::num::encode;
+/// assert!(encode(0).is_empty());
+/// assert_eq!(encode(-1), [0x81]);
+/// assert_eq!(encode(128), [0x80, 0x00]);
+/// assert_eq!(encode(-32768), [0x00, 0x80, 0x80]);
+/// ```
+pub fn encode(n: i64) -> Vec<u8> {
+    let mut v = vec![];
+    let mut abs = n.unsigned_abs();
+    while abs > 0 {
+        v.push(abs as u8);
+        abs >>= 8;
+    }
+    match v.last_mut() {
+        Some(last) if *last & 0x80 != 0 => v.push(if n < 0 { 0x80 } else { 0x00 }),
+        Some(last) if n < 0 => *last |= 0x80,
+        _ => {},
+    }
+
+    v
+}
+
+/// Decode minimally encoded script number
+/// # Arguments
+/// * `v` - encoded number
+/// * `max_size` - maximum size in bytes (up to `MAX_NUM_SIZE`)
+/// # Returns
+/// * number
+/// # Example
+/// ```
+/// # use cash_tx_builder::script::num::{decode, MAX_NUM_SIZE};
+/// assert_eq!(decode(&[0xff, 0x80], MAX_NUM_SIZE)?, -255);
+/// assert!(decode(&[0x01, 0x00], MAX_NUM_SIZE).is_err());
+/// assert!(decode(&[0x01; 5], 4).is_err());
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn decode(v: &[u8], max_size: usize) -> Result<i64> {
+    if v.len() > max_size.min(MAX_NUM_SIZE) {
+        return Err(Error::InvalidScriptNum(v.to_vec()));
+    }
+    let last = match v.last() {
+        Some(last) => *last,
+        None => return Ok(0),
+    };
+    if last & 0x7f == 0 && (v.len() == 1 || v[v.len() - 2] & 0x80 == 0) {
+        return Err(Error::InvalidScriptNum(v.to_vec()));
+    }
+
+    let magnitude = v.iter().enumerate().fold(0u64, |n, (i, b)| n | u64::from(*b) << (8 * i));
+    let sign_bit = 0x80u64 << (8 * (v.len() - 1));
+    if magnitude & sign_bit != 0 {
+        Ok(-((magnitude & !sign_bit) as i64))
+    } else {
+        Ok(magnitude as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        for n in &[0, 1, -1, 127, 128, -128, 255, 256, -32768, 0x7fff_ffff, -0x8000_0000, i64::MAX, -i64::MAX] {
+            assert_eq!(decode(&encode(*n), MAX_NUM_SIZE)?, *n);
+        }
+        assert_eq!(encode(-128), [0x80, 0x80]);
+        assert_eq!(encode(i64::MAX), [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]);
+        assert_eq!(encode(-0x8000_0000).len(), 5);
+
+        assert!(decode(&[0x00], MAX_NUM_SIZE).is_err());
+        assert!(decode(&[0x80], MAX_NUM_SIZE).is_err());
+        assert!(decode(&[0x01, 0x80], MAX_NUM_SIZE).is_err());
+        assert!(decode(&encode(-0x8000_0000), 4).is_err());
+        assert!(decode(&[0x01; 9], 9).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+//! P2PK utility
+
+use super::super::script::{encode, Script};
+use super::super::opcode::OpCode::*;
+use super::super::error::{Result};
+
+/// Build `scriptPubKey` from `public key`
+/// # Arguments
+/// * `pubkey` - `public key`
+/// # Returns
+/// * `scriptPubKey`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::p2pk::script_pub_key;
+/// let pubkey = hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036");
+/// let script_pub_key = script_pub_key(&pubkey)?;
+/// assert_eq!(script_pub_key, hex!("210366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036ac"));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn script_pub_key(pubkey: &[u8]) -> Result<Vec<u8>> {
+    encode(&[
+        Script::Data(pubkey),
+        Script::OpCode(OP_CHECKSIG),
+    ])
+}
+
+/// Build `scriptSig` from `signature`
+/// # Arguments
+/// * `sig` - transaction's `signature`
+/// # Returns
+/// * `scriptSig`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::p2pk::script_sig;
+/// let sig = hex!("304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b3041");
+/// let script_sig = script_sig(&sig)?;
+/// assert_eq!(script_sig[0], 0x47);
+/// assert_eq!(script_sig[1..], sig);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn script_sig(sig: &[u8]) -> Result<Vec<u8>> {
+    encode(&[
+        Script::Data(sig),
+    ])
+}
@@ -1,6 +1,6 @@
 //! P2PKH utility
 
-use super::super::script::{encode, Script};
+use super::super::script::{encode, pubkey, Script};
 use super::super::opcode::OpCode::*;
 use super::super::error::{Result};
 
@@ -50,3 +50,21 @@ pub fn script_sig(pubkey: &[u8], sig: &[u8]) -> Result<Vec<u8>> {
         Script::Data(pubkey),
     ])
 }
+
+/// Build `scriptSig` rejecting malformed `public key`
+/// # Arguments
+/// * `pubkey` - `public key` (SEC1 compressed or uncompressed)
+/// * `sig` - transaction's `signature`
+/// # Returns
+/// * `scriptSig` (`Error::InvalidPublicKey` if `pubkey` is malformed)
+/// # Example
+/// ```
+/// # use cash_tx_builder::script::p2pkh::script_sig_checked;
+/// assert!(script_sig_checked(&[0x02; 33], &[0x30; 72]).is_ok());
+/// assert!(script_sig_checked(&[0x05; 33], &[0x30; 72]).is_err());
+/// ```
+pub fn script_sig_checked(pubkey: &[u8], sig: &[u8]) -> Result<Vec<u8>> {
+    pubkey::validate(pubkey)?;
+
+    script_sig(pubkey, sig)
+}
@@ -1,8 +1,11 @@
 //! P2SH utility
 
-use super::super::script::{encode, Script};
+use super::super::script::{encode, decode, Script, multisig};
 use super::super::opcode::OpCode::*;
-use super::super::error::{Result};
+use super::super::error::{Error, Result};
+
+/// Length of Schnorr signature with sighash type
+const SCHNORR_TX_SIG_LEN: usize = 65;
 
 /// Build `scriptPubKey` from hashed `redeem script`
 /// # Arguments
@@ -25,3 +28,148 @@ pub fn script_pub_key(hash: &[u8]) -> Result<Vec<u8>> {
         Script::OpCode(OP_EQUAL),
     ])
 }
+
+/// Build `scriptSig` from pushes and `redeem script`
+/// # Arguments
+/// * `pushes` - data satisfying `redeem script` (e.g. `signature`s)
+/// * `redeem_script` - `redeem script`
+/// # Returns
+/// * `scriptSig`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::p2sh::script_sig;
+/// let script_sig = script_sig(&[b"hoge"], &hex!("51"))?;
+/// assert_eq!(script_sig, hex!("04686f67650151"));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn script_sig(pushes: &[&[u8]], redeem_script: &[u8]) -> Result<Vec<u8>> {
+    let mut scripts = pushes.iter().map(|data| Script::Data(data)).collect::<Vec<_>>();
+    scripts.push(Script::Data(redeem_script));
+
+    encode(&scripts)
+}
+
+/// Build `scriptSig` of multisig `redeem script` from signatures collected in any order
+///
+/// Signatures are ordered by their `public key`s in `redeem script` and extra ones are dropped.
+/// The dummy is `OP_0` for ECDSA signatures, or the bitfield of signing keys for Schnorr signatures.
+/// # Arguments
+/// * `redeem_script` - M-of-N multisig `redeem script`
+/// * `partial_sigs` - `public key` and `signature` (with sighash type) pairs
+/// # Returns
+/// * `scriptSig` (`Error::MissingSignatures` if less than M keys signed)
+/// # Example
+/// ```
+/// # use cash_tx_builder::OpCode;
+/// # use cash_tx_builder::script::{decode, Script, multisig, p2sh::multisig_finalize};
+/// let pubkeys = [&[0x02; 33][..], &[0x03; 33], &[0x04; 33]];
+/// let redeem_script = multisig::redeem_script(2, &pubkeys)?;
+///
+/// let script_sig = multisig_finalize(&redeem_script, &[(pubkeys[2], &[0x30; 72]), (pubkeys[0], &[0x31; 72])])?;
+/// assert_eq!(script_sig, multisig::script_sig(&[&[0x31; 72], &[0x30; 72]], &redeem_script)?);
+///
+/// let script_sig = multisig_finalize(&redeem_script, &[(pubkeys[2], &[0x30; 65]), (pubkeys[1], &[0x31; 65])])?;
+/// // bitfield 0b110 of keys 1 and 2
+/// assert_eq!(decode(&script_sig)?[0], Script::OpCode(OpCode::OP_6));
+///
+/// assert!(multisig_finalize(&redeem_script, &[(pubkeys[0], &[0x30; 72])]).is_err());
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn multisig_finalize(redeem_script: &[u8], partial_sigs: &[(&[u8], &[u8])]) -> Result<Vec<u8>> {
+    let (m, pubkeys) = multisig::parse_redeem_script(redeem_script)?;
+    let pushes = multisig_pushes(m, &pubkeys, partial_sigs)?;
+
+    let mut scripts = pushes.iter().map(|data| Script::Data(data)).collect::<Vec<_>>();
+    scripts.push(Script::Data(redeem_script));
+
+    encode(&scripts)
+}
+
+/// Dummy and signatures of `OP_CHECKMULTISIG` ordered by `pubkeys`, see `multisig_finalize`
+pub(crate) fn multisig_pushes(m: usize, pubkeys: &[&[u8]], partial_sigs: &[(&[u8], &[u8])]) -> Result<Vec<Vec<u8>>> {
+    if let Some((pubkey, _)) = partial_sigs.iter().find(|(pubkey, _)| !pubkeys.contains(pubkey)) {
+        return Err(Error::InvalidPublicKey(pubkey.to_vec()));
+    }
+
+    let sigs = pubkeys.iter().enumerate()
+        .filter_map(|(i, pubkey)| partial_sigs.iter().find(|(p, _)| p == pubkey).map(|(_, sig)| (i, *sig)))
+        .take(m)
+        .collect::<Vec<_>>();
+    if sigs.len() < m {
+        return Err(Error::MissingSignatures(sigs.len(), m));
+    }
+
+    let mut dummy = vec![];
+    if sigs.iter().any(|(_, sig)| sig.len() == SCHNORR_TX_SIG_LEN) {
+        if let Some((_, sig)) = sigs.iter().find(|(_, sig)| sig.len() != SCHNORR_TX_SIG_LEN) {
+            return Err(Error::InvalidSignature(sig.to_vec()));
+        }
+        let bitfield = sigs.iter().fold(0u32, |b, (i, _)| b | 1 << i);
+        dummy = bitfield.to_le_bytes()[..pubkeys.len().div_ceil(8)].to_vec();
+    }
+
+    let mut pushes = vec![dummy];
+    pushes.extend(sigs.iter().map(|(_, sig)| sig.to_vec()));
+
+    Ok(pushes)
+}
+
+/// Check whether `scriptPubKey` is P2SH
+pub(crate) fn is_p2sh(script: &[u8]) -> bool {
+    match decode(script).as_ref().map(Vec::as_slice) {
+        Ok([Script::OpCode(OP_HASH160), Script::Data(hash), Script::OpCode(OP_EQUAL)]) => hash.len() == 20,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::hash;
+    use super::super::super::interpreter::{verify_script, SignatureChecker};
+
+    /// accepts signature of `pubkey` if its first byte matches
+    struct Checker;
+
+    impl SignatureChecker for Checker {
+        fn check_sig(&self, sig: &[u8], pubkey: &[u8], _script_code: &[u8]) -> bool {
+            sig[0] == pubkey[0]
+        }
+
+        fn check_data_sig(&self, _sig: &[u8], _msg: &[u8], _pubkey: &[u8]) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn multisig_finalize() -> Result<()> {
+        let pubkeys = (0..9u8).map(|i| [i + 2; 33]).collect::<Vec<_>>();
+        let pubkeys = pubkeys.iter().map(|k| &k[..]).collect::<Vec<_>>();
+        let redeem_script = multisig::redeem_script(3, &pubkeys)?;
+        let script_pub_key = script_pub_key(&hash::hash160(&redeem_script))?;
+
+        for len in &[72, SCHNORR_TX_SIG_LEN] {
+            let sigs = [8, 1, 5, 3].iter().map(|&i| (pubkeys[i], vec![pubkeys[i][0]; *len])).collect::<Vec<_>>();
+            let sigs = sigs.iter().map(|(k, s)| (*k, &s[..])).collect::<Vec<_>>();
+            let script_sig = super::multisig_finalize(&redeem_script, &sigs)?;
+            verify_script(&script_sig, &script_pub_key, &Checker)?;
+        }
+
+        let mixed = [(pubkeys[0], &[0x02; 72][..]), (pubkeys[1], &[0x03; 65]), (pubkeys[2], &[0x04; 65])];
+        match super::multisig_finalize(&redeem_script, &mixed) {
+            Err(Error::InvalidSignature(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match super::multisig_finalize(&redeem_script, &[(&[0x01; 33], &[0x01; 72])]) {
+            Err(Error::InvalidPublicKey(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match super::multisig_finalize(&redeem_script, &mixed[..2]) {
+            Err(Error::MissingSignatures(2, 3)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+}
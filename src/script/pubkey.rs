@@ -0,0 +1,129 @@
+//! Public key encoding
+//! [SEC1](https://www.secg.org/sec1-v2.pdf) section 2.3.3
+//!
+//! Only the encoding is checked, not that the point is on the curve.
+
+use super::super::hash;
+use super::super::error::{Error, Result};
+
+/// Length of compressed `public key`
+pub const COMPRESSED_LEN: usize = 33;
+
+/// Length of uncompressed `public key`
+pub const UNCOMPRESSED_LEN: usize = 65;
+
+/// Check if `pubkey` is compressed or uncompressed SEC1 encoding (hybrid keys are rejected)
+/// # Arguments
+/// * `pubkey` - serialized `public key`
+pub fn is_valid_encoding(pubkey: &[u8]) -> bool {
+    match pubkey.first() {
+        Some(0x02) | Some(0x03) => pubkey.len() == COMPRESSED_LEN,
+        Some(0x04) => pubkey.len() == UNCOMPRESSED_LEN,
+        _ => false,
+    }
+}
+
+/// Validate SEC1 encoding
+/// # Arguments
+/// * `pubkey` - serialized `public key`
+pub fn validate(pubkey: &[u8]) -> Result<()> {
+    if is_valid_encoding(pubkey) {
+        Ok(())
+    } else {
+        Err(Error::InvalidPublicKey(pubkey.to_vec()))
+    }
+}
+
+/// Check if `pubkey` is compressed
+/// # Arguments
+/// * `pubkey` - serialized `public key`
+/// # Returns
+/// * `true` if compressed, `false` if uncompressed (error if malformed)
+pub fn is_compressed(pubkey: &[u8]) -> Result<bool> {
+    validate(pubkey)?;
+
+    Ok(pubkey.len() == COMPRESSED_LEN)
+}
+
+/// Compress `public key`
+/// # Arguments
+/// * `pubkey` - serialized `public key` (returned as is if already compressed)
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::pubkey;
+/// let uncompressed = hex!("0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8");
+/// let compressed = pubkey::compress(&uncompressed)?;
+/// assert_eq!(compressed, hex!("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"));
+/// assert!(pubkey::is_compressed(&compressed)?);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn compress(pubkey: &[u8]) -> Result<Vec<u8>> {
+    if is_compressed(pubkey)? {
+        return Ok(pubkey.to_vec());
+    }
+    let prefix = 0x02 | (pubkey[UNCOMPRESSED_LEN - 1] & 0x01);
+
+    Ok([&[prefix][..], &pubkey[1..COMPRESSED_LEN]].concat())
+}
+
+/// Decompress `public key`
+/// # Arguments
+/// * `pubkey` - serialized `public key` (returned as is if already uncompressed)
+#[cfg(feature = "sign")]
+pub fn decompress(pubkey: &[u8]) -> Result<Vec<u8>> {
+    if !is_compressed(pubkey)? {
+        return Ok(pubkey.to_vec());
+    }
+    let key = secp256k1::PublicKey::from_slice(pubkey).map_err(|_| Error::InvalidPublicKey(pubkey.to_vec()))?;
+
+    Ok(key.serialize_uncompressed().to_vec())
+}
+
+/// `public key hash` of validated `public key`
+/// # Arguments
+/// * `pubkey` - serialized `public key`
+pub fn hash160(pubkey: &[u8]) -> Result<[u8; 20]> {
+    validate(pubkey)?;
+    let mut v = [0; 20];
+    v.copy_from_slice(&hash::hash160(pubkey));
+
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPRESSED: [u8; 33] = hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036");
+
+    #[test]
+    fn encoding() -> Result<()> {
+        assert!(is_compressed(&COMPRESSED)?);
+        assert!(!is_compressed(&[&[0x04][..], &[0x01; 64]].concat())?);
+        assert_eq!(hash160(&COMPRESSED)?, hex!("3424f163208a3b676fa0ec17034f0f290322a2a6"));
+        assert_eq!(compress(&COMPRESSED)?, COMPRESSED.to_vec());
+
+        for invalid in &[&[][..], &COMPRESSED[..32], &[0x04; 33], &[0x06; 65], &[0x02; 65]] {
+            match validate(invalid) {
+                Err(Error::InvalidPublicKey(_)) => {},
+                r => panic!("unexpected result: {:?}", r),
+            }
+            assert!(hash160(invalid).is_err());
+            assert!(compress(invalid).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn decompression() -> Result<()> {
+        let uncompressed = decompress(&COMPRESSED)?;
+        assert_eq!(uncompressed.len(), UNCOMPRESSED_LEN);
+        assert_eq!(compress(&uncompressed)?, COMPRESSED.to_vec());
+        assert!(decompress(&[&[0x02][..], &[0xff; 32]].concat()).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+//! Transaction signature encoding
+//! [strict DER](https://github.com/bitcoin/bips/blob/master/bip-0066.mediawiki)
+//!
+//! ECDSA signatures must be strict DER with low S. 64 bytes signatures are Schnorr.
+
+use super::super::tx_builder::sig_hash::SigHashType;
+use super::super::error::{Error, Result};
+
+/// Length of Schnorr signature (without sighash type)
+pub const SCHNORR_SIG_LEN: usize = 64;
+
+/// Maximum length of DER signature (without sighash type)
+pub const MAX_DER_SIG_LEN: usize = 72;
+
+/// Half of secp256k1 curve order, the maximum low S
+const HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Split pushed signature into signature and sighash type byte
+/// # Arguments
+/// * `push` - signature pushed in `scriptSig`
+/// # Returns
+/// * signature and sighash type byte
+pub fn split(push: &[u8]) -> Result<(&[u8], u8)> {
+    match push.split_last() {
+        Some((&hash_type, sig)) => Ok((sig, hash_type)),
+        None => Err(Error::InvalidSignature(push.to_vec())),
+    }
+}
+
+/// Check if `sig` is strict DER (`IsValidSignatureEncoding` without sighash type)
+pub fn is_strict_der(sig: &[u8]) -> bool {
+    // 0x30 [total-len] 0x02 [r-len] [r] 0x02 [s-len] [s]
+    if sig.len() < 8 || sig.len() > MAX_DER_SIG_LEN || sig[0] != 0x30 || sig[1] as usize != sig.len() - 2 {
+        return false;
+    }
+    let r_len = sig[3] as usize;
+    if 5 + r_len >= sig.len() {
+        return false;
+    }
+    let s_len = sig[5 + r_len] as usize;
+    if r_len + s_len + 6 != sig.len() {
+        return false;
+    }
+
+    let is_valid_integer = |int: &[u8]| {
+        !int.is_empty()
+            && int[0] & 0x80 == 0
+            && !(int.len() > 1 && int[0] == 0x00 && int[1] & 0x80 == 0)
+    };
+    sig[2] == 0x02 && is_valid_integer(&sig[4..4 + r_len])
+        && sig[4 + r_len] == 0x02 && is_valid_integer(&sig[6 + r_len..])
+}
+
+/// Decode strict DER signature
+/// # Arguments
+/// * `sig` - signature (without sighash type)
+/// # Returns
+/// * `r` and `s` as 32 bytes big endian
+pub fn decode_der(sig: &[u8]) -> Result<([u8; 32], [u8; 32])> {
+    let invalid = || Error::InvalidSignature(sig.to_vec());
+    if !is_strict_der(sig) {
+        return Err(invalid());
+    }
+    let r_len = sig[3] as usize;
+    let to_array = |int: &[u8]| {
+        let int = if int[0] == 0x00 { &int[1..] } else { int };
+        if int.len() > 32 {
+            return None;
+        }
+        let mut v = [0; 32];
+        v[32 - int.len()..].copy_from_slice(int);
+        Some(v)
+    };
+
+    let r = to_array(&sig[4..4 + r_len]).ok_or_else(invalid)?;
+    let s = to_array(&sig[6 + r_len..]).ok_or_else(invalid)?;
+    Ok((r, s))
+}
+
+/// Encode `r` and `s` as strict DER signature
+/// # Arguments
+/// * `r` - 32 bytes big endian
+/// * `s` - 32 bytes big endian
+/// # Example
+/// ```
+/// # use cash_tx_builder::script::signature::{encode_der, decode_der, is_strict_der};
+/// let sig = encode_der(&[0x01; 32], &[0x00; 32]);
+/// assert!(is_strict_der(&sig));
+/// assert_eq!(decode_der(&sig)?, ([0x01; 32], [0x00; 32]));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn encode_der(r: &[u8; 32], s: &[u8; 32]) -> Vec<u8> {
+    let integer = |int: &[u8; 32]| {
+        let start = int.iter().position(|&b| b != 0).unwrap_or(31);
+        let int = &int[start..];
+        let pad: &[u8] = if int[0] & 0x80 != 0 { &[0x00] } else { &[] };
+        [&[0x02, (pad.len() + int.len()) as u8][..], pad, int].concat()
+    };
+    let body = [integer(r), integer(s)].concat();
+
+    [&[0x30, body.len() as u8][..], &body].concat()
+}
+
+/// Check if S of strict DER signature is not greater than half of curve order
+pub fn is_low_s(sig: &[u8]) -> bool {
+    decode_der(sig).map(|(_, s)| s <= HALF_ORDER).unwrap_or(false)
+}
+
+/// Check encoding of pushed signature as BCH consensus does
+///
+/// Empty push is accepted as it is used to fail `OP_CHECKSIG` deliberately.
+/// # Arguments
+/// * `push` - signature with sighash type pushed in `scriptSig`
+/// # Returns
+/// * signature and sighash type
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::signature;
+/// # use cash_tx_builder::sig_hash;
+/// let push = hex!("304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b3041");
+/// let (sig, hash_type) = signature::check(&push)?.unwrap();
+/// assert_eq!(hash_type, sig_hash::ALL | sig_hash::FORKID);
+/// assert_eq!(signature::encode(sig, hash_type), push);
+/// assert!(signature::check(&push[1..]).is_err());
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn check(push: &[u8]) -> Result<Option<(&[u8], SigHashType)>> {
+    if push.is_empty() {
+        return Ok(None);
+    }
+    let (sig, hash_type) = split(push)?;
+    let hash_type = SigHashType::from_u32(u32::from(hash_type))?;
+    if sig.len() != SCHNORR_SIG_LEN && !(is_strict_der(sig) && is_low_s(sig)) {
+        return Err(Error::InvalidSignature(push.to_vec()));
+    }
+
+    Ok(Some((sig, hash_type)))
+}
+
+/// Append sighash type to signature
+/// # Arguments
+/// * `sig` - signature (without sighash type)
+/// * `hash_type` - sighash type
+/// # Returns
+/// * signature to push in `scriptSig`
+pub fn encode(sig: &[u8], hash_type: SigHashType) -> Vec<u8> {
+    [sig, &[hash_type.to_u8()]].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::tx_builder::sig_hash;
+
+    #[test]
+    fn der() -> Result<()> {
+        let sig = hex!("304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b30");
+        assert!(is_strict_der(&sig));
+        assert!(is_low_s(&sig));
+        let (r, s) = decode_der(&sig)?;
+        assert_eq!(encode_der(&r, &s), sig.to_vec());
+
+        // r with high bit needs 0x00 padding
+        let padded = encode_der(&[0x80; 32], &[0x01; 32]);
+        assert_eq!(padded[..5], [0x30, 0x45, 0x02, 0x21, 0x00]);
+        assert_eq!(decode_der(&padded)?, ([0x80; 32], [0x01; 32]));
+
+        let mut high_s = HALF_ORDER;
+        high_s[31] += 1;
+        assert!(is_low_s(&encode_der(&r, &HALF_ORDER)));
+        assert!(!is_low_s(&encode_der(&r, &high_s)));
+
+        for invalid in &[
+            &hex!("30050200020101")[..],
+            &hex!("300602010102010100")[..],
+            &hex!("3006020180020101")[..],
+            &hex!("300702020001020101")[..],
+            &hex!("3106020101020101")[..],
+            &hex!("30060201010201")[..],
+        ] {
+            assert!(!is_strict_der(invalid), "{}", hex::encode(invalid));
+            assert!(decode_der(invalid).is_err());
+        }
+        assert!(is_strict_der(&hex!("3006020101020101")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn check_push() -> Result<()> {
+        let der = encode_der(&[0x01; 32], &[0x01; 32]);
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        let push = encode(&der, hash_type);
+        assert_eq!(check(&push)?, Some((&der[..], hash_type)));
+        assert_eq!(check(&[])?, None);
+        assert_eq!(check(&encode(&[0x01; 64], hash_type))?.map(|(sig, _)| sig.len()), Some(SCHNORR_SIG_LEN));
+
+        match check(&[&der[..], &[0x01]].concat()) {
+            Err(Error::InvalidSigHashType(0x01)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match check(&encode(&der[1..], hash_type)) {
+            Err(Error::InvalidSignature(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match split(&[]) {
+            Err(Error::InvalidSignature(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+//! P2SH time-locked script utility
+
+use super::super::script::{encode, Script, ScriptBuilder, p2sh};
+use super::super::opcode::OpCode::*;
+use super::super::hash;
+use super::super::error::Result;
+
+/// Time lock checked by script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeLock {
+    /// absolute lock-time checked by `OP_CHECKLOCKTIMEVERIFY` (block height or unix time)
+    Absolute(u32),
+    /// relative lock-time checked by `OP_CHECKSEQUENCEVERIFY` (BIP68 sequence)
+    Relative(u32),
+}
+
+impl TimeLock {
+    fn append_to(self, builder: &mut ScriptBuilder) {
+        let (n, op) = match self {
+            TimeLock::Absolute(n) => (n, OP_CHECKLOCKTIMEVERIFY),
+            TimeLock::Relative(n) => (n, OP_CHECKSEQUENCEVERIFY),
+        };
+        builder.int(i64::from(n)).op(op).op(OP_DROP);
+    }
+}
+
+/// Build `redeem script` spendable by `public key` after time lock
+///
+/// `<lock> OP_CHECKLOCKTIMEVERIFY|OP_CHECKSEQUENCEVERIFY OP_DROP <pubkey> OP_CHECKSIG`
+/// # Arguments
+/// * `lock` - time lock
+/// * `pubkey` - `public key`
+/// # Returns
+/// * `redeem script`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::script::timelock::{redeem_script, TimeLock};
+/// let pubkey = hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036");
+/// let redeem_script = redeem_script(TimeLock::Absolute(600_000), &pubkey)?;
+/// assert_eq!(redeem_script[..6], hex!("03c02709b175"));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn redeem_script(lock: TimeLock, pubkey: &[u8]) -> Result<Vec<u8>> {
+    let mut builder = ScriptBuilder::new();
+    lock.append_to(&mut builder);
+    builder.data(pubkey).op(OP_CHECKSIG).build()
+}
+
+/// Build `redeem script` spendable by a `public key` after time lock, or by another `public key` anytime
+///
+/// `OP_IF <lock> OP_CHECKLOCKTIMEVERIFY|OP_CHECKSEQUENCEVERIFY OP_DROP <locked pubkey> OP_ELSE <pubkey> OP_ENDIF OP_CHECKSIG`
+/// # Arguments
+/// * `lock` - time lock
+/// * `locked_pubkey` - `public key` which can spend after time lock
+/// * `pubkey` - `public key` which can spend anytime
+/// # Returns
+/// * `redeem script`
+pub fn redeem_script_with_fallback(lock: TimeLock, locked_pubkey: &[u8], pubkey: &[u8]) -> Result<Vec<u8>> {
+    let mut builder = ScriptBuilder::new();
+    builder.op(OP_IF);
+    lock.append_to(&mut builder);
+    builder.data(locked_pubkey)
+        .op(OP_ELSE)
+        .data(pubkey)
+        .op(OP_ENDIF)
+        .op(OP_CHECKSIG)
+        .build()
+}
+
+/// Build `scriptPubKey` from `redeem script`
+/// # Arguments
+/// * `redeem_script` - `redeem script`
+/// # Returns
+/// * `scriptPubKey`
+pub fn script_pub_key(redeem_script: &[u8]) -> Result<Vec<u8>> {
+    p2sh::script_pub_key(&hash::hash160(redeem_script))
+}
+
+/// Build `scriptSig` for `redeem_script`
+/// # Arguments
+/// * `sig` - transaction's `signature`
+/// * `redeem_script` - `redeem script`
+/// # Returns
+/// * `scriptSig`
+pub fn script_sig(sig: &[u8], redeem_script: &[u8]) -> Result<Vec<u8>> {
+    encode(&[
+        Script::Data(sig),
+        Script::Data(redeem_script),
+    ])
+}
+
+/// Build `scriptSig` for `redeem_script_with_fallback`
+/// # Arguments
+/// * `sig` - transaction's `signature`
+/// * `locked` - `true` if signed by the time-locked `public key`
+/// * `redeem_script` - `redeem script`
+/// # Returns
+/// * `scriptSig`
+pub fn script_sig_with_fallback(sig: &[u8], locked: bool, redeem_script: &[u8]) -> Result<Vec<u8>> {
+    encode(&[
+        Script::Data(sig),
+        Script::OpCode(if locked { OP_1 } else { OP_0 }),
+        Script::Data(redeem_script),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::interpreter::{verify_script, SignatureChecker};
+
+    /// accepts signature `[0x01, pubkey[0], ..]` and lock-time up to 700,000
+    struct Checker;
+
+    impl SignatureChecker for Checker {
+        fn check_sig(&self, sig: &[u8], pubkey: &[u8], _script_code: &[u8]) -> bool {
+            sig.len() >= 2 && sig[0] == 0x01 && sig[1] == pubkey[0]
+        }
+
+        fn check_data_sig(&self, _sig: &[u8], _msg: &[u8], _pubkey: &[u8]) -> bool {
+            false
+        }
+
+        fn check_lock_time(&self, lock_time: i64) -> bool {
+            lock_time <= 700_000
+        }
+    }
+
+    #[test]
+    fn timelock() -> Result<()> {
+        let (key_a, key_b) = ([0x02; 33], [0x03; 33]);
+        let (sig_a, sig_b) = ([0x01, 0x02], [0x01, 0x03]);
+
+        let redeem = redeem_script(TimeLock::Relative(10), &key_a)?;
+        assert_eq!(redeem[..3], [OP_10 as u8, OP_CHECKSEQUENCEVERIFY as u8, OP_DROP as u8]);
+
+        let redeem = redeem_script(TimeLock::Absolute(600_000), &key_a)?;
+        verify_script(&script_sig(&sig_a, &redeem)?, &script_pub_key(&redeem)?, &Checker)?;
+        let redeem = redeem_script(TimeLock::Absolute(800_000), &key_a)?;
+        assert!(verify_script(&script_sig(&sig_a, &redeem)?, &script_pub_key(&redeem)?, &Checker).is_err());
+
+        let redeem = redeem_script_with_fallback(TimeLock::Absolute(800_000), &key_a, &key_b)?;
+        let spk = script_pub_key(&redeem)?;
+        verify_script(&script_sig_with_fallback(&sig_b, false, &redeem)?, &spk, &Checker)?;
+        assert!(verify_script(&script_sig_with_fallback(&sig_a, true, &redeem)?, &spk, &Checker).is_err());
+        assert!(verify_script(&script_sig_with_fallback(&sig_a, false, &redeem)?, &spk, &Checker).is_err());
+
+        let redeem = redeem_script_with_fallback(TimeLock::Absolute(600_000), &key_a, &key_b)?;
+        let spk = script_pub_key(&redeem)?;
+        verify_script(&script_sig_with_fallback(&sig_a, true, &redeem)?, &spk, &Checker)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+//! transaction signer
+
+pub mod schnorr;
+
+use secp256k1::{ecdsa, Message, PublicKey, SecretKey, SECP256K1};
+use super::error::Result;
+
+/// Signer of transaction digest
+/// # Example
+/// ```
+/// # use cash_tx_builder::Signer;
+/// # use cash_tx_builder::secp256k1::SecretKey;
+/// let key = SecretKey::from_slice(&[0x01; 32])?;
+/// let sig = key.sign(&[0x02; 32])?;
+/// assert_eq!(sig[0], 0x30);
+/// assert_eq!(Signer::public_key(&key).len(), 33);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub trait Signer {
+    /// Sign digest
+    /// # Arguments
+    /// * `digest` - 32 bytes sighash
+    /// # Returns
+    /// * signature (without sighash type)
+    fn sign(&self, digest: &[u8]) -> Result<Vec<u8>>;
+
+    /// Get serialized `public key`
+    fn public_key(&self) -> Vec<u8>;
+}
+
+/// ECDSA signer
+impl Signer for SecretKey {
+    fn sign(&self, digest: &[u8]) -> Result<Vec<u8>> {
+        let msg = Message::from_digest_slice(digest)?;
+        let sig = SECP256K1.sign_ecdsa(&msg, self);
+
+        Ok(sig.serialize_der().to_vec())
+    }
+
+    fn public_key(&self) -> Vec<u8> {
+        PublicKey::from_secret_key(SECP256K1, self).serialize().to_vec()
+    }
+}
+
+/// Verify ECDSA (strict DER, low S) or Schnorr (64 bytes) signature
+/// # Arguments
+/// * `pubkey` - serialized `public key`
+/// * `digest` - 32 bytes sighash
+/// * `sig` - signature (without sighash type)
+/// # Returns
+/// * `true` if signature is valid, `false` if invalid or malformed
+/// # Example
+/// ```
+/// # use cash_tx_builder::Signer;
+/// # use cash_tx_builder::signer::verify;
+/// # use cash_tx_builder::signer::schnorr::SchnorrSigner;
+/// # use cash_tx_builder::secp256k1::SecretKey;
+/// let key = SecretKey::from_slice(&[0x01; 32])?;
+/// let sig = key.sign(&[0x02; 32])?;
+/// assert!(verify(&Signer::public_key(&key), &[0x02; 32], &sig));
+/// let sig = SchnorrSigner(key).sign(&[0x02; 32])?;
+/// assert!(verify(&Signer::public_key(&key), &[0x02; 32], &sig));
+/// assert!(!verify(&Signer::public_key(&key), &[0x03; 32], &sig));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn verify(pubkey: &[u8], digest: &[u8], sig: &[u8]) -> bool {
+    let pubkey = match PublicKey::from_slice(pubkey) {
+        Ok(pubkey) => pubkey,
+        Err(_) => return false,
+    };
+    if sig.len() == 64 {
+        return schnorr::verify(&pubkey, digest, sig).unwrap_or(false);
+    }
+
+    let (msg, sig) = match (Message::from_digest_slice(digest), ecdsa::Signature::from_der(sig)) {
+        (Ok(msg), Ok(sig)) => (msg, sig),
+        _ => return false,
+    };
+    let mut normalized = sig;
+    normalized.normalize_s();
+
+    normalized == sig && SECP256K1.verify_ecdsa(&msg, &sig, &pubkey).is_ok()
+}
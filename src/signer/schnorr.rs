@@ -0,0 +1,235 @@
+//! Schnorr signature
+//! [spec](https://github.com/bitcoincashorg/bitcoincash.org/blob/master/spec/2019-05-15-schnorr.md)
+
+use hmac::{Hmac, Mac};
+use num_bigint::BigUint;
+use secp256k1::{PublicKey, SecretKey, Scalar, SECP256K1};
+use sha2::{Sha256, Digest};
+use super::Signer;
+use super::super::tx_builder::sig_hash::SigHashType;
+use super::super::error::{Error, Result};
+
+/// Field size of secp256k1
+const FIELD_SIZE: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xff, 0xff, 0xfc, 0x2f,
+];
+
+/// Group order of secp256k1
+const GROUP_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// Additional data of RFC6979 nonce
+const NONCE_ALGO: &[u8; 16] = b"Schnorr+SHA256  ";
+
+/// Schnorr signer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchnorrSigner(pub SecretKey);
+
+impl Signer for SchnorrSigner {
+    fn sign(&self, digest: &[u8]) -> Result<Vec<u8>> {
+        Ok(sign(&self.0, digest)?.to_vec())
+    }
+
+    fn public_key(&self) -> Vec<u8> {
+        PublicKey::from_secret_key(SECP256K1, &self.0).serialize().to_vec()
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[&[u8]]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    for d in data {
+        mac.update(d);
+    }
+
+    let mut ret = [0; 32];
+    ret.copy_from_slice(&mac.finalize().into_bytes());
+    ret
+}
+
+/// RFC6979 nonce, same as `nonce_function_rfc6979` of libsecp256k1
+fn nonce(key: &SecretKey, digest: &[u8]) -> SecretKey {
+    let seed = [&key.secret_bytes()[..], digest, NONCE_ALGO].concat();
+
+    let mut k = hmac_sha256(&[0x00; 32], &[&[0x01; 32], &[0x00], &seed]);
+    let mut v = hmac_sha256(&k, &[&[0x01; 32]]);
+    k = hmac_sha256(&k, &[&v, &[0x01], &seed]);
+    v = hmac_sha256(&k, &[&v]);
+
+    loop {
+        v = hmac_sha256(&k, &[&v]);
+        if let Ok(nonce) = SecretKey::from_slice(&v) {
+            return nonce;
+        }
+        k = hmac_sha256(&k, &[&v, &[0x00]]);
+        v = hmac_sha256(&k, &[&v]);
+    }
+}
+
+fn is_jacobi_one(y: &[u8]) -> bool {
+    let p = BigUint::from_bytes_be(&FIELD_SIZE);
+    let exp = (&p - 1u32) >> 1;
+
+    BigUint::from_bytes_be(y).modpow(&exp, &p) == BigUint::from(1u32)
+}
+
+fn challenge(r: &[u8], pubkey: &PublicKey, digest: &[u8]) -> Result<Scalar> {
+    let e = Sha256::new()
+        .chain_update(r)
+        .chain_update(&pubkey.serialize()[..])
+        .chain_update(digest)
+        .finalize();
+    let e = BigUint::from_bytes_be(&e) % BigUint::from_bytes_be(&GROUP_ORDER);
+
+    let bytes = e.to_bytes_be();
+    let mut ret = [0; 32];
+    ret[32 - bytes.len()..].copy_from_slice(&bytes);
+
+    Scalar::from_be_bytes(ret).map_err(|_| Error::from(secp256k1::Error::InvalidTweak))
+}
+
+/// Sign digest by Schnorr signature
+/// # Arguments
+/// * `key` - `private key`
+/// * `digest` - 32 bytes sighash
+/// # Returns
+/// * 64 bytes signature
+pub fn sign(key: &SecretKey, digest: &[u8]) -> Result<[u8; 64]> {
+    if digest.len() != 32 {
+        return Err(Error::InvalidLengthData(digest.len()));
+    }
+
+    let pubkey = PublicKey::from_secret_key(SECP256K1, key);
+    let mut k = nonce(key, digest);
+    let r = PublicKey::from_secret_key(SECP256K1, &k).serialize_uncompressed();
+    if !is_jacobi_one(&r[33..]) {
+        k = k.negate();
+    }
+
+    let e = challenge(&r[1..33], &pubkey, digest)?;
+    let s = key.mul_tweak(&e)?.add_tweak(&Scalar::from(k))?;
+
+    let mut sig = [0; 64];
+    sig[..32].copy_from_slice(&r[1..33]);
+    sig[32..].copy_from_slice(&s.secret_bytes());
+    Ok(sig)
+}
+
+/// Verify Schnorr signature
+/// # Arguments
+/// * `pubkey` - `public key`
+/// * `digest` - 32 bytes sighash
+/// * `sig` - 64 bytes signature
+/// # Returns
+/// * `true` if signature is valid
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::secp256k1::PublicKey;
+/// # use cash_tx_builder::signer::schnorr::verify;
+/// let pubkey = PublicKey::from_slice(&hex!("0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798"))?;
+/// let digest = [0; 32];
+/// let sig = hex!("787A848E71043D280C50470E8E1532B2DD5D20EE912A45DBDD2BD1DFBF187EF67031A98831859DC34DFFEEDDA86831842CCD0079E1F92AF177F7F22CC1DCED05");
+/// assert!(verify(&pubkey, &digest, &sig)?);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn verify(pubkey: &PublicKey, digest: &[u8], sig: &[u8]) -> Result<bool> {
+    if digest.len() != 32 {
+        return Err(Error::InvalidLengthData(digest.len()));
+    }
+    if sig.len() != 64 {
+        return Err(Error::InvalidLengthData(sig.len()));
+    }
+
+    let (r, s) = sig.split_at(32);
+    if BigUint::from_bytes_be(r) >= BigUint::from_bytes_be(&FIELD_SIZE) {
+        return Ok(false);
+    }
+    let s = match SecretKey::from_slice(s) {
+        Ok(s) => s,
+        Err(_) => return Ok(false),
+    };
+
+    let e = challenge(r, pubkey, digest)?;
+
+    verify_challenge(pubkey, r, &s, &e)
+}
+
+/// Check `s * G - e * P` against `r`
+fn verify_challenge(pubkey: &PublicKey, r: &[u8], s: &SecretKey, e: &Scalar) -> Result<bool> {
+    if *e == Scalar::ZERO {
+        return Ok(false);
+    }
+    let sg = PublicKey::from_secret_key(SECP256K1, s);
+    let ep = pubkey.mul_tweak(SECP256K1, e)?.negate(SECP256K1);
+    let point = match sg.combine(&ep) {
+        Ok(point) => point.serialize_uncompressed(),
+        Err(_) => return Ok(false),
+    };
+
+    Ok(is_jacobi_one(&point[33..]) && &point[1..33] == r)
+}
+
+/// Serialize Schnorr signature with sighash type for `scriptSig`
+/// # Arguments
+/// * `sig` - 64 bytes signature
+/// * `hash_type` - sighash type
+/// # Returns
+/// * 65 bytes signature
+/// # Example
+/// ```
+/// # use cash_tx_builder::sig_hash;
+/// # use cash_tx_builder::signer::schnorr::tx_signature;
+/// let sig = tx_signature(&[0x01; 64], sig_hash::ALL | sig_hash::FORKID)?;
+/// assert_eq!(sig.len(), 65);
+/// assert_eq!(sig[64], 0x41);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn tx_signature(sig: &[u8], hash_type: SigHashType) -> Result<Vec<u8>> {
+    if sig.len() != 64 {
+        return Err(Error::InvalidLengthData(sig.len()));
+    }
+    hash_type.validate()?;
+
+    Ok([sig, &[hash_type.to_u8()]].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_and_verify() -> Result<()> {
+        let key = SecretKey::from_slice(&hex!("B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF"))?;
+        let pubkey = PublicKey::from_secret_key(SECP256K1, &key);
+        assert_eq!(pubkey.serialize()[..], hex!("02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659")[..]);
+
+        let digest = hex!("243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89");
+        let sig = hex!("2A298DACAE57395A15D0795DDBFD1DCB564DA82B0F269BC70A74F8220429BA1D1E51A22CCEC35599B8F266912281F8365FFC2D035A230434A1A64DC59F7013FD");
+        assert!(verify(&pubkey, &digest, &sig)?);
+
+        let sig = sign(&key, &digest)?;
+        assert!(verify(&pubkey, &digest, &sig)?);
+        assert_eq!(sign(&key, &digest)?[..], sig[..]);
+
+        let mut invalid = sig;
+        invalid[63] ^= 0x01;
+        assert!(!verify(&pubkey, &digest, &invalid)?);
+        assert!(!verify(&pubkey, &[0; 32], &sig)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn zero_challenge() -> Result<()> {
+        let key = SecretKey::from_slice(&[0x01; 32])?;
+        let pubkey = PublicKey::from_secret_key(SECP256K1, &key);
+        let sig = sign(&key, &[0x01; 32])?;
+        let s = SecretKey::from_slice(&sig[32..])?;
+        assert!(!verify_challenge(&pubkey, &sig[..32], &s, &Scalar::ZERO)?);
+
+        Ok(())
+    }
+}
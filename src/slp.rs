@@ -0,0 +1,180 @@
+//! SLP (Simple Ledger Protocol) token type 1 messages
+//! [spec](https://github.com/simpleledger/slp-specifications/blob/master/slp-token-type-1.md)
+
+use super::opcode::OpCode::*;
+use super::error::{Error, Result};
+
+/// Lokad id of SLP
+pub const LOKAD_ID: &[u8; 4] = b"SLP\x00";
+
+/// Token type 1
+pub const TOKEN_TYPE: u8 = 0x01;
+
+/// Maximum number of token outputs in `SEND`
+pub const MAX_SEND_OUTPUTS: usize = 19;
+
+/// SLP forbids `OP_0`..`OP_16` and `OP_1NEGATE`, so every data must be pushed explicitly.
+fn push(data: &[u8], v: &mut Vec<u8>) -> Result<()> {
+    match data.len() {
+        0 => {
+            v.push(OP_PUSHDATA1 as u8);
+            v.push(0x00);
+        },
+        l @ 0x01..=0x4b => {
+            v.push(l as u8);
+            v.extend(data);
+        },
+        l @ 0x4c..=0xff => {
+            v.push(OP_PUSHDATA1 as u8);
+            v.push(l as u8);
+            v.extend(data);
+        },
+        l @ 0x100..=0xffff => {
+            v.push(OP_PUSHDATA2 as u8);
+            v.extend(&(l as u16).to_le_bytes());
+            v.extend(data);
+        },
+        e => return Err(Error::InvalidLengthData(e)),
+    };
+    Ok(())
+}
+
+fn encode(pushes: &[&[u8]]) -> Result<Vec<u8>> {
+    pushes.iter().try_fold(vec![OP_RETURN as u8], |mut v, data| {
+        push(data, &mut v)?;
+        Ok(v)
+    })
+}
+
+fn token_id_bytes(token_id: &str) -> Result<Vec<u8>> {
+    let id = hex::decode(token_id).map_err(|_| Error::InvalidSlp(format!("token id: {}", token_id)))?;
+    if id.len() != 32 {
+        return Err(Error::InvalidSlp(format!("token id: {}", token_id)));
+    }
+
+    Ok(id)
+}
+
+fn mint_baton_bytes(mint_baton_vout: Option<u8>) -> Result<Vec<u8>> {
+    match mint_baton_vout {
+        Some(vout) if vout < 2 => Err(Error::InvalidSlp(format!("mint baton vout: {}", vout))),
+        Some(vout) => Ok(vec![vout]),
+        None => Ok(vec![]),
+    }
+}
+
+/// Build `GENESIS` message
+/// # Arguments
+/// * `ticker` - token ticker
+/// * `name` - token name
+/// * `document_url` - token document url
+/// * `document_hash` - (option) 32 bytes hash of token document
+/// * `decimals` - decimal places (0 - 9)
+/// * `mint_baton_vout` - (option) output index of mint baton (2 - 255)
+/// * `quantity` - initial mint quantity
+/// # Returns
+/// * `scriptPubKey`
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::slp::genesis_script;
+/// let script = genesis_script("TEST", "", "", None, 8, Some(2), 100)?;
+/// assert_eq!(script, hex!("6a04534c500001010747454e4553495304544553544c004c004c0001080102080000000000000064").to_vec());
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn genesis_script(
+    ticker: &str,
+    name: &str,
+    document_url: &str,
+    document_hash: Option<&[u8]>,
+    decimals: u8,
+    mint_baton_vout: Option<u8>,
+    quantity: u64,
+) -> Result<Vec<u8>> {
+    let document_hash = document_hash.unwrap_or(&[]);
+    if !document_hash.is_empty() && document_hash.len() != 32 {
+        return Err(Error::InvalidSlp(format!("document hash: {:?}", document_hash)));
+    }
+    if decimals > 9 {
+        return Err(Error::InvalidSlp(format!("decimals: {}", decimals)));
+    }
+
+    encode(&[
+        LOKAD_ID,
+        &[TOKEN_TYPE],
+        b"GENESIS",
+        ticker.as_bytes(),
+        name.as_bytes(),
+        document_url.as_bytes(),
+        document_hash,
+        &[decimals],
+        &mint_baton_bytes(mint_baton_vout)?,
+        &quantity.to_be_bytes(),
+    ])
+}
+
+/// Build `MINT` message
+/// # Arguments
+/// * `token_id` - token id
+/// * `mint_baton_vout` - (option) output index of new mint baton (2 - 255)
+/// * `quantity` - additional mint quantity
+/// # Returns
+/// * `scriptPubKey`
+pub fn mint_script(token_id: &str, mint_baton_vout: Option<u8>, quantity: u64) -> Result<Vec<u8>> {
+    encode(&[
+        LOKAD_ID,
+        &[TOKEN_TYPE],
+        b"MINT",
+        &token_id_bytes(token_id)?,
+        &mint_baton_bytes(mint_baton_vout)?,
+        &quantity.to_be_bytes(),
+    ])
+}
+
+/// Build `SEND` message
+/// # Arguments
+/// * `token_id` - token id
+/// * `amounts` - token amounts sent to output 1, 2, ...
+/// # Returns
+/// * `scriptPubKey`
+pub fn send_script(token_id: &str, amounts: &[u64]) -> Result<Vec<u8>> {
+    if amounts.is_empty() || amounts.len() > MAX_SEND_OUTPUTS {
+        return Err(Error::InvalidSlp(format!("number of outputs: {}", amounts.len())));
+    }
+
+    let token_id = token_id_bytes(token_id)?;
+    let amounts = amounts.iter().map(|a| a.to_be_bytes()).collect::<Vec<_>>();
+    let mut pushes: Vec<&[u8]> = vec![LOKAD_ID, &[TOKEN_TYPE], b"SEND", &token_id];
+    pushes.extend(amounts.iter().map(|a| &a[..]));
+
+    encode(&pushes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send() -> Result<()> {
+        let token_id = "959a6818cba5af8aba391d3f7649f5f6a5ceb6cdcd2c2a3dcb5d2fbfc4b08e98";
+        let script = send_script(token_id, &[0x42, 1])?;
+        assert_eq!(script, hex!("6a04534c500001010453454e4420959a6818cba5af8aba391d3f7649f5f6a5ceb6cdcd2c2a3dcb5d2fbfc4b08e98080000000000000042080000000000000001").to_vec());
+
+        assert!(send_script(token_id, &[]).is_err());
+        assert!(send_script(token_id, &[1; 20]).is_err());
+        assert!(send_script("959a", &[1]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn mint() -> Result<()> {
+        let token_id = "959a6818cba5af8aba391d3f7649f5f6a5ceb6cdcd2c2a3dcb5d2fbfc4b08e98";
+        let script = mint_script(token_id, None, 1000)?;
+        assert_eq!(script, hex!("6a04534c50000101044d494e5420959a6818cba5af8aba391d3f7649f5f6a5ceb6cdcd2c2a3dcb5d2fbfc4b08e984c000800000000000003e8").to_vec());
+
+        assert!(mint_script(token_id, Some(1), 1000).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+//! Contract templates from [CashScript](https://cashscript.org) artifacts
+//!
+//! Locking bytecode is the artifact's bytecode preceded by constructor arguments in reverse order.
+//! Unlocking bytecode pushes function arguments in reverse order, the function index
+//! if the contract has more than one function, and the `redeem script`.
+
+use serde_json::Value;
+use super::script::{self, Script, from_asm, num, p2sh};
+use super::hash;
+use super::error::{Error, Result};
+
+/// Argument of constructor or function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Argument {
+    /// `int`
+    Int(i64),
+    /// `bool`
+    Bool(bool),
+    /// `string`
+    String(String),
+    /// `bytes`, `bytesN`, `pubkey`, `sig` and `datasig`
+    Bytes(Vec<u8>),
+}
+
+/// Named and typed parameter of constructor or function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    /// name
+    pub name: String,
+    /// CashScript type, e.g. `"bytes20"`
+    pub type_name: String,
+}
+
+impl Parameter {
+    /// Encode `arg` as data push
+    fn encode(&self, arg: &Argument) -> Result<Vec<u8>> {
+        let invalid = || Error::InvalidTemplate(format!("invalid argument for {}: {:?}", self.name, arg));
+        let data = match (self.type_name.as_str(), arg) {
+            ("int", Argument::Int(n)) => num::encode(*n),
+            ("bool", Argument::Bool(b)) => num::encode(i64::from(*b)),
+            ("string", Argument::String(s)) => s.as_bytes().to_vec(),
+            ("pubkey", Argument::Bytes(v)) if v.len() == 33 => v.clone(),
+            ("bytes", Argument::Bytes(v)) | ("sig", Argument::Bytes(v)) | ("datasig", Argument::Bytes(v)) => v.clone(),
+            (t, Argument::Bytes(v)) if t.starts_with("bytes") => {
+                let len = t["bytes".len()..].parse::<usize>().map_err(|_| invalid())?;
+                if v.len() != len {
+                    return Err(invalid());
+                }
+                v.clone()
+            },
+            _ => return Err(invalid()),
+        };
+
+        Ok(data)
+    }
+}
+
+/// Function of contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    /// name
+    pub name: String,
+    /// parameters
+    pub inputs: Vec<Parameter>,
+}
+
+/// Encode `args` of `params` in reverse order
+fn encode_args(params: &[Parameter], args: &[Argument]) -> Result<Vec<Vec<u8>>> {
+    if params.len() != args.len() {
+        return Err(Error::InvalidTemplate(format!("expected {} arguments, got {}", params.len(), args.len())));
+    }
+
+    params.iter().zip(args).rev().map(|(param, arg)| param.encode(arg)).collect()
+}
+
+/// Contract template, instantiated with constructor arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    /// contract name
+    pub name: String,
+    /// constructor parameters
+    pub constructor_inputs: Vec<Parameter>,
+    /// functions
+    pub functions: Vec<Function>,
+    /// bytecode without constructor arguments
+    pub bytecode: Vec<u8>,
+}
+
+impl Template {
+    /// Instantiate contract
+    /// # Arguments
+    /// * `args` - constructor arguments
+    pub fn instantiate(&self, args: &[Argument]) -> Result<Contract> {
+        let pushes = encode_args(&self.constructor_inputs, args)?;
+        let scripts = pushes.iter().map(|v| Script::Data(v)).collect::<Vec<_>>();
+        let redeem_script = [script::encode(&scripts)?, self.bytecode.clone()].concat();
+
+        Ok(Contract {
+            redeem_script,
+            functions: self.functions.clone(),
+        })
+    }
+}
+
+/// Contract instance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contract {
+    redeem_script: Vec<u8>,
+    functions: Vec<Function>,
+}
+
+impl Contract {
+    /// `redeem script` (locking bytecode)
+    pub fn redeem_script(&self) -> &[u8] {
+        &self.redeem_script
+    }
+
+    /// P2SH `scriptPubKey`
+    pub fn script_pub_key(&self) -> Result<Vec<u8>> {
+        p2sh::script_pub_key(&hash::hash160(&self.redeem_script))
+    }
+
+    /// Builder of unlocking scripts of function
+    /// # Arguments
+    /// * `name` - function name
+    pub fn unlocker(&self, name: &str) -> Result<Unlocker<'_>> {
+        let index = self.functions.iter().position(|f| f.name == name)
+            .ok_or_else(|| Error::InvalidTemplate(format!("unknown function: {}", name)))?;
+
+        Ok(Unlocker {
+            function: &self.functions[index],
+            selector: if self.functions.len() > 1 { Some(index as i64) } else { None },
+            redeem_script: &self.redeem_script,
+        })
+    }
+}
+
+/// Builder of unlocking scripts of a function
+#[derive(Debug, Clone, Copy)]
+pub struct Unlocker<'a> {
+    function: &'a Function,
+    selector: Option<i64>,
+    redeem_script: &'a [u8],
+}
+
+impl Unlocker<'_> {
+    /// Function parameters
+    pub fn inputs(&self) -> &[Parameter] {
+        &self.function.inputs
+    }
+
+    /// Build `scriptSig`
+    /// # Arguments
+    /// * `args` - function arguments, `sig` with sighash type
+    pub fn script_sig(&self, args: &[Argument]) -> Result<Vec<u8>> {
+        let mut pushes = encode_args(&self.function.inputs, args)?;
+        if let Some(selector) = self.selector {
+            pushes.push(num::encode(selector));
+        }
+        pushes.push(self.redeem_script.to_vec());
+
+        let scripts = pushes.iter().map(|v| Script::Data(v)).collect::<Vec<_>>();
+        script::encode(&scripts)
+    }
+}
+
+fn parameters(v: &Value) -> Option<Vec<Parameter>> {
+    v.as_array()?.iter().map(|p| Some(Parameter {
+        name: p["name"].as_str()?.to_string(),
+        type_name: p["type"].as_str()?.to_string(),
+    })).collect()
+}
+
+fn functions(v: &Value) -> Option<Vec<Function>> {
+    v.as_array()?.iter().map(|f| Some(Function {
+        name: f["name"].as_str()?.to_string(),
+        inputs: parameters(&f["inputs"])?,
+    })).collect()
+}
+
+/// Read artifact JSON of CashScript compiler
+/// # Arguments
+/// * `json` - artifact JSON
+/// # Example
+/// ```
+/// # #[macro_use] extern crate hex_literal;
+/// # use cash_tx_builder::template::{from_cashscript_artifact, Argument};
+/// let artifact = r#"{
+///     "contractName": "P2PKH",
+///     "constructorInputs": [{ "name": "pkh", "type": "bytes20" }],
+///     "abi": [{ "name": "spend", "inputs": [{ "name": "pk", "type": "pubkey" }, { "name": "s", "type": "sig" }] }],
+///     "bytecode": "OP_OVER OP_HASH160 OP_EQUALVERIFY OP_CHECKSIG"
+/// }"#;
+/// let template = from_cashscript_artifact(artifact)?;
+/// let contract = template.instantiate(&[Argument::Bytes(vec![0x01; 20])])?;
+/// assert_eq!(contract.redeem_script()[..2], hex!("1401"));
+///
+/// let script_sig = contract.unlocker("spend")?.script_sig(&[
+///     Argument::Bytes(vec![0x02; 33]),
+///     Argument::Bytes(vec![0x30; 72]),
+/// ])?;
+/// assert_eq!(script_sig[..2], hex!("4830"));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn from_cashscript_artifact(json: &str) -> Result<Template> {
+    let v: Value = serde_json::from_str(json).map_err(|e| Error::InvalidTemplate(e.to_string()))?;
+    let field = |name: &str| Error::InvalidTemplate(format!("missing or invalid {}", name));
+
+    Ok(Template {
+        name: v["contractName"].as_str().ok_or_else(|| field("contractName"))?.to_string(),
+        constructor_inputs: parameters(&v["constructorInputs"]).ok_or_else(|| field("constructorInputs"))?,
+        functions: functions(&v["abi"]).ok_or_else(|| field("abi"))?,
+        bytecode: from_asm(v["bytecode"].as_str().ok_or_else(|| field("bytecode"))?)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::interpreter::{verify_script, SignatureChecker};
+
+    /// accepts any signature
+    struct Checker;
+
+    impl SignatureChecker for Checker {
+        fn check_sig(&self, _sig: &[u8], _pubkey: &[u8], _script_code: &[u8]) -> bool {
+            true
+        }
+
+        fn check_data_sig(&self, _sig: &[u8], _msg: &[u8], _pubkey: &[u8]) -> bool {
+            true
+        }
+    }
+
+    // contract Bet(int target, pubkey alice) {
+    //     function win(int guess) { require(guess == target); }
+    //     function refund(sig s) { require(checkSig(s, alice)); }
+    // }
+    const ARTIFACT: &str = r#"{
+        "contractName": "Bet",
+        "constructorInputs": [{ "name": "target", "type": "int" }, { "name": "alice", "type": "pubkey" }],
+        "abi": [
+            { "name": "win", "inputs": [{ "name": "guess", "type": "int" }] },
+            { "name": "refund", "inputs": [{ "name": "s", "type": "sig" }] }
+        ],
+        "bytecode": "OP_2 OP_PICK OP_0 OP_NUMEQUAL OP_IF OP_3 OP_ROLL OP_NUMEQUALVERIFY OP_2DROP OP_1 OP_ELSE OP_2 OP_ROLL OP_1 OP_NUMEQUALVERIFY OP_DROP OP_CHECKSIG OP_ENDIF"
+    }"#;
+
+    #[test]
+    fn artifact() -> Result<()> {
+        let template = from_cashscript_artifact(ARTIFACT)?;
+        assert_eq!(template.name, "Bet");
+        assert_eq!(template.functions[1].inputs[0].type_name, "sig");
+
+        let contract = template.instantiate(&[Argument::Int(1000), Argument::Bytes(vec![0x02; 33])])?;
+        let script_pub_key = contract.script_pub_key()?;
+        // constructor arguments are pushed in reverse order
+        assert_eq!(contract.redeem_script()[..2], [0x21, 0x02]);
+
+        let win = contract.unlocker("win")?;
+        verify_script(&win.script_sig(&[Argument::Int(1000)])?, &script_pub_key, &Checker)?;
+        assert!(verify_script(&win.script_sig(&[Argument::Int(999)])?, &script_pub_key, &Checker).is_err());
+
+        let refund = contract.unlocker("refund")?;
+        verify_script(&refund.script_sig(&[Argument::Bytes(vec![0x30; 65])])?, &script_pub_key, &Checker)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        let template = from_cashscript_artifact(ARTIFACT).unwrap();
+        for args in &[
+            vec![Argument::Int(1)],
+            vec![Argument::Bool(true), Argument::Bytes(vec![0x02; 33])],
+            vec![Argument::Int(1), Argument::Bytes(vec![0x02; 32])],
+        ] {
+            match template.instantiate(args) {
+                Err(Error::InvalidTemplate(_)) => {},
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+        let contract = template.instantiate(&[Argument::Int(1), Argument::Bytes(vec![0x02; 33])]).unwrap();
+        assert!(contract.unlocker("unknown").is_err());
+
+        assert!(from_cashscript_artifact("{}").is_err());
+        assert!(from_cashscript_artifact(&ARTIFACT.replace("OP_DROP", "OP_UNKNOWN")).is_err());
+    }
+}
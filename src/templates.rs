@@ -0,0 +1,129 @@
+//! Transaction templates of common contracts
+//!
+//! Each P2SH template builds its `redeem script` and the transactions spending it,
+//! which are returned as `Spend` exposing the digest to be signed by each party.
+
+pub mod escrow;
+pub mod htlc;
+pub mod token;
+
+use super::TxBuilder;
+use super::hash::SigHash;
+use super::script::{encode, Script, ToAddressScript, p2sh};
+use super::tx_builder::MAX_SIG_LEN;
+use super::tx_builder::sig_hash::SigHashType;
+use super::types::{Amount, FeeRate, VarInt};
+use super::error::{Error, Result};
+
+/// Signatures unlocking a spending path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Unlock {
+    /// one signature of `public key`
+    Single(Vec<u8>),
+    /// `OP_CHECKMULTISIG` with required number and `public key`s
+    Multisig(usize, Vec<Vec<u8>>),
+}
+
+impl Unlock {
+    /// Estimated length of signature pushes
+    fn len(&self) -> usize {
+        match self {
+            Unlock::Single(_) => 1 + MAX_SIG_LEN,
+            Unlock::Multisig(m, _) => 1 + m * (1 + MAX_SIG_LEN),
+        }
+    }
+}
+
+/// Transaction spending a template output at input 0 to a single output
+#[derive(Debug)]
+pub struct Spend<F>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+    txb: TxBuilder<F>,
+    redeem_script: Vec<u8>,
+    unlock: Unlock,
+    suffix: Vec<Vec<u8>>,
+}
+
+impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> Spend<F> {
+    /// Add output to `destination` paying all but fee of signed transaction
+    /// # Arguments
+    /// * `txb` - builder with the template output at input 0
+    /// * `suffix` - pushes between signatures and `redeem script`, e.g. branch selector
+    pub(crate) fn new<A>(mut txb: TxBuilder<F>, redeem_script: &[u8], unlock: Unlock, suffix: Vec<Vec<u8>>, destination: &A, fee_rate: FeeRate) -> Result<Spend<F>>
+        where A: ToAddressScript + ?Sized {
+        let value = txb.input_value().ok_or(Error::MissingPrevOutput(0))?;
+        txb.set_redeem_script(0, redeem_script)?;
+        txb.add_address_output(Amount::ZERO, destination)?;
+
+        let pushes = suffix.iter().map(|data| Script::Data(data))
+            .chain(std::iter::once(Script::Data(redeem_script)))
+            .collect::<Vec<_>>();
+        let script_sig_len = unlock.len() + encode(&pushes)?.len();
+        let size = txb.size() + script_sig_len + VarInt::from(script_sig_len as u64).len() - 1;
+        let fee = fee_rate.fee(size);
+        let output_value = value.checked_sub(fee)
+            .ok_or(Error::InsufficientFunds(value.to_sat(), fee.to_sat()))?;
+        txb.remove_output(0)?;
+        txb.add_address_output(output_value, destination)?;
+
+        Ok(Spend {
+            txb,
+            redeem_script: redeem_script.to_vec(),
+            unlock,
+            suffix,
+        })
+    }
+
+    /// Unsigned transaction
+    pub fn builder(&self) -> &TxBuilder<F> {
+        &self.txb
+    }
+
+    /// `public key`s which can sign
+    pub fn signers(&self) -> Vec<&[u8]> {
+        match &self.unlock {
+            Unlock::Single(pubkey) => vec![pubkey],
+            Unlock::Multisig(_, pubkeys) => pubkeys.iter().map(Vec::as_slice).collect(),
+        }
+    }
+
+    /// Number of signatures required
+    pub fn required_signatures(&self) -> usize {
+        match &self.unlock {
+            Unlock::Single(_) => 1,
+            Unlock::Multisig(m, _) => *m,
+        }
+    }
+
+    /// Digest to be signed by each signer
+    /// # Arguments
+    /// * `hash_type` - sighash type
+    pub fn sighash(&self, hash_type: SigHashType) -> Result<SigHash> {
+        self.txb.p2sh_sighash(0, hash_type, &self.redeem_script)
+    }
+
+    /// Set `scriptSig` from signatures
+    /// # Arguments
+    /// * `sigs` - `public key` and `signature` (with sighash type) pairs in any order
+    /// # Returns
+    /// * signed transaction (`Error::MissingSignatures` if not enough signers signed)
+    pub fn finalize(mut self, sigs: &[(&[u8], &[u8])]) -> Result<TxBuilder<F>> {
+        let mut pushes = match &self.unlock {
+            Unlock::Single(pubkey) => {
+                let (_, sig) = sigs.iter().find(|(p, _)| p == pubkey).ok_or(Error::MissingSignatures(0, 1))?;
+                vec![sig.to_vec()]
+            },
+            Unlock::Multisig(m, pubkeys) => {
+                let pubkeys = pubkeys.iter().map(Vec::as_slice).collect::<Vec<_>>();
+                p2sh::multisig_pushes(*m, &pubkeys, sigs)?
+            },
+        };
+        pushes.extend(self.suffix.iter().cloned());
+        let pushes = pushes.iter().map(|data| &data[..]).collect::<Vec<_>>();
+
+        let script_sig = p2sh::script_sig(&pushes, &self.redeem_script)?;
+        self.txb.set_script_sig(0, &script_sig)?;
+
+        Ok(self.txb)
+    }
+}
@@ -0,0 +1,197 @@
+//! 2-of-3 escrow with time-locked refund
+//!
+//! Any two of buyer, seller and arbiter release the funds,
+//! and the buyer alone can take them back after the timeout:
+//!
+//! `OP_IF OP_2 <buyer> <seller> <arbiter> OP_3 OP_CHECKMULTISIG
+//! OP_ELSE <timeout> OP_CHECKLOCKTIMEVERIFY OP_DROP <buyer> OP_CHECKSIG OP_ENDIF`
+//!
+//! As the refund only needs the buyer's signature, it can be signed right after funding
+//! and kept until the timeout.
+
+use super::{Spend, Unlock};
+use super::super::TxBuilder;
+use super::super::script::{ScriptBuilder, ToAddressScript, p2sh};
+use super::super::opcode::OpCode::*;
+use super::super::hash;
+use super::super::types::{Amount, FeeRate, LockTime, Sequence};
+use super::super::types::transaction::OutPoint;
+use super::super::error::Result;
+
+/// Parties and timeout of escrow
+/// # Example
+/// ```
+/// # use cash_tx_builder::{TxBuilder, Amount, FeeRate, LockTime, sig_hash};
+/// # use cash_tx_builder::templates::escrow::Escrow;
+/// # use cash_tx_builder::types::transaction::OutPoint;
+/// let (buyer, seller, arbiter) = ([0x02; 33], [0x03; 33], [0x04; 33]);
+/// let escrow = Escrow::new(&buyer, &seller, &arbiter, LockTime::Height(800_000));
+/// let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+///
+/// let mut funding = TxBuilder::new(&parser);
+/// escrow.add_funding_output(&mut funding, Amount::from_sat(100_000))?;
+/// // ... add inputs and change, sign and broadcast
+/// let outpoint = OutPoint { txid: funding.txid(), n: 0 };
+///
+/// let hash_type = sig_hash::ALL | sig_hash::FORKID;
+/// let release = escrow.release(outpoint.clone(), Amount::from_sat(100_000), &hex::encode([0x05; 20]), FeeRate::MIN_RELAY, &parser)?;
+/// assert_eq!(release.required_signatures(), 2);
+/// let sighash = release.sighash(hash_type)?;
+/// // seller and arbiter sign `sighash`
+/// let tx = release.finalize(&[(&arbiter, &[0x30; 72]), (&seller, &[0x30; 72])])?;
+/// assert!(tx.is_complete());
+///
+/// let refund = escrow.refund(outpoint, Amount::from_sat(100_000), &hex::encode([0x06; 20]), FeeRate::MIN_RELAY, &parser)?;
+/// assert_eq!(refund.signers(), [&buyer[..]]);
+/// let v = refund.builder().to_vec();
+/// assert_eq!(v[v.len() - 4..], 800_000u32.to_le_bytes());
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Escrow {
+    buyer: Vec<u8>,
+    seller: Vec<u8>,
+    arbiter: Vec<u8>,
+    timeout: LockTime,
+}
+
+impl Escrow {
+    /// Construct escrow
+    /// # Arguments
+    /// * `buyer` - `public key` of buyer (funds escrow, refunded after timeout)
+    /// * `seller` - `public key` of seller
+    /// * `arbiter` - `public key` of arbiter
+    /// * `timeout` - block height or unix time after which buyer can take refund
+    pub fn new(buyer: &[u8], seller: &[u8], arbiter: &[u8], timeout: LockTime) -> Escrow {
+        Escrow {
+            buyer: buyer.to_vec(),
+            seller: seller.to_vec(),
+            arbiter: arbiter.to_vec(),
+            timeout,
+        }
+    }
+
+    /// Build `redeem script`
+    pub fn redeem_script(&self) -> Result<Vec<u8>> {
+        ScriptBuilder::new()
+            .op(OP_IF)
+            .int(2).data(&self.buyer).data(&self.seller).data(&self.arbiter).int(3).op(OP_CHECKMULTISIG)
+            .op(OP_ELSE)
+            .int(i64::from(u32::from(self.timeout))).op(OP_CHECKLOCKTIMEVERIFY).op(OP_DROP)
+            .data(&self.buyer).op(OP_CHECKSIG)
+            .op(OP_ENDIF)
+            .build()
+    }
+
+    /// Build P2SH `scriptPubKey`
+    pub fn script_pub_key(&self) -> Result<Vec<u8>> {
+        p2sh::script_pub_key(&hash::hash160(&self.redeem_script()?))
+    }
+
+    /// Add output funding escrow
+    /// # Arguments
+    /// * `txb` - funding transaction
+    /// * `value` - escrowed value
+    pub fn add_funding_output<F>(&self, txb: &mut TxBuilder<F>, value: Amount) -> Result<()>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        txb.add_output(value, &self.script_pub_key()?);
+        Ok(())
+    }
+
+    /// Build transaction releasing escrow, signed by two of the parties
+    /// # Arguments
+    /// * `outpoint` - escrow output
+    /// * `value` - value of escrow output
+    /// * `destination` - address receiving value after fee
+    /// * `fee_rate` - fee rate, e.g. `Network::min_fee_rate`
+    /// * `address_parser` - address parser closure, see `TxBuilder::new`
+    pub fn release<F, A>(&self, outpoint: OutPoint, value: Amount, destination: &A, fee_rate: FeeRate, address_parser: F) -> Result<Spend<F>>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)>,
+              A: ToAddressScript + ?Sized {
+        let mut txb = TxBuilder::new(address_parser);
+        txb.add_input(outpoint.txid, outpoint.n, Some(value.to_sat()), Some(&self.script_pub_key()?), None)?;
+        let parties = vec![self.buyer.clone(), self.seller.clone(), self.arbiter.clone()];
+
+        Spend::new(txb, &self.redeem_script()?, Unlock::Multisig(2, parties), vec![vec![1]], destination, fee_rate)
+    }
+
+    /// Build transaction refunding escrow to buyer, valid after timeout
+    ///
+    /// Lock time is set to timeout and the input has non-final sequence.
+    /// # Arguments
+    /// * `outpoint` - escrow output
+    /// * `value` - value of escrow output
+    /// * `destination` - address receiving value after fee
+    /// * `fee_rate` - fee rate, e.g. `Network::min_fee_rate`
+    /// * `address_parser` - address parser closure, see `TxBuilder::new`
+    pub fn refund<F, A>(&self, outpoint: OutPoint, value: Amount, destination: &A, fee_rate: FeeRate, address_parser: F) -> Result<Spend<F>>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)>,
+              A: ToAddressScript + ?Sized {
+        let mut txb = TxBuilder::new(address_parser);
+        txb.set_lock_time(self.timeout);
+        let sequence = Some(Sequence::MAX_NON_FINAL.into());
+        txb.add_input(outpoint.txid, outpoint.n, Some(value.to_sat()), Some(&self.script_pub_key()?), sequence)?;
+
+        Spend::new(txb, &self.redeem_script()?, Unlock::Single(self.buyer.clone()), vec![vec![]], destination, fee_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::error::Error;
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn spend() -> Result<()> {
+        use secp256k1::SecretKey;
+        use super::super::super::signer::Signer;
+        use super::super::super::tx_builder::sig_hash;
+
+        let keys = (1..=3u8).map(|i| SecretKey::from_slice(&[i; 32])).collect::<std::result::Result<Vec<_>, _>>()?;
+        let pubkeys = keys.iter().map(Signer::public_key).collect::<Vec<_>>();
+        let escrow = Escrow::new(&pubkeys[0], &pubkeys[1], &pubkeys[2], LockTime::Height(800_000));
+        let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+        let outpoint = OutPoint { txid: [0x01; 32].into(), n: 1 };
+        let value = Amount::from_sat(100_000);
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        let sign = |key: &SecretKey, digest: &[u8]| -> Result<Vec<u8>> {
+            Ok([key.sign(digest)?, vec![hash_type.to_u8()]].concat())
+        };
+
+        for (a, b) in &[(0, 1), (2, 0), (1, 2)] {
+            let release = escrow.release(outpoint.clone(), value, &hex::encode([0x05; 20]), FeeRate::MIN_RELAY, &parser)?;
+            let sighash = release.sighash(hash_type)?;
+            let (sig_a, sig_b) = (sign(&keys[*a], &sighash)?, sign(&keys[*b], &sighash)?);
+            let tx = release.finalize(&[(&pubkeys[*a], &sig_a), (&pubkeys[*b], &sig_b)])?;
+            tx.validate_input(0)?;
+            assert!(tx.fee()?.to_sat() >= tx.size() as u64);
+        }
+
+        let refund = escrow.refund(outpoint.clone(), value, &hex::encode([0x06; 20]), FeeRate::MIN_RELAY, &parser)?;
+        let sig = sign(&keys[0], &refund.sighash(hash_type)?)?;
+        let tx = refund.finalize(&[(&pubkeys[0], &sig)])?;
+        tx.validate_input(0)?;
+        assert!(tx.fee()?.to_sat() >= tx.size() as u64);
+
+        let refund = escrow.refund(outpoint, value, &hex::encode([0x06; 20]), FeeRate::MIN_RELAY, &parser)?;
+        let sig = sign(&keys[1], &refund.sighash(hash_type)?)?;
+        match refund.finalize(&[(&pubkeys[1], &sig)]) {
+            Err(Error::MissingSignatures(0, 1)) => {},
+            r => panic!("unexpected result: {:?}", r.map(|txb| txb.to_hex())),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn insufficient_value() {
+        let escrow = Escrow::new(&[0x02; 33], &[0x03; 33], &[0x04; 33], LockTime::Height(800_000));
+        let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+        let outpoint = OutPoint { txid: [0x01; 32].into(), n: 1 };
+        match escrow.release(outpoint, Amount::from_sat(300), &hex::encode([0x05; 20]), FeeRate::MIN_RELAY, &parser) {
+            Err(Error::InsufficientFunds(300, _)) => {},
+            r => panic!("unexpected result: {:?}", r.map(|spend| spend.builder().to_hex())),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Hash time locked contract (HTLC) for atomic swaps
+//!
+//! Recipient takes the funds by revealing the secret whose SHA-256 is locked,
+//! and sender can take them back after the timeout:
+//!
+//! `OP_IF OP_SHA256 <secret hash> OP_EQUALVERIFY <recipient> OP_CHECKSIG
+//! OP_ELSE <timeout> OP_CHECKLOCKTIMEVERIFY OP_DROP <sender> OP_CHECKSIG OP_ENDIF`
+//!
+//! SHA-256 is used as hash lock, so the same secret hash can be used on other chains.
+
+use super::{Spend, Unlock};
+use super::super::TxBuilder;
+use super::super::script::{self, Script, ScriptBuilder, ToAddressScript, p2sh};
+use super::super::opcode::OpCode::*;
+use super::super::hash;
+use super::super::types::{Amount, FeeRate, LockTime, Sequence};
+use super::super::types::transaction::OutPoint;
+use super::super::error::{Error, Result};
+
+/// Parties, hash lock and timeout of HTLC
+/// # Example
+/// ```
+/// # use cash_tx_builder::{TxBuilder, Amount, FeeRate, LockTime, sig_hash};
+/// # use cash_tx_builder::templates::htlc::Htlc;
+/// # use cash_tx_builder::types::transaction::OutPoint;
+/// # use cash_tx_builder::hash::sha256;
+/// let (sender, recipient) = ([0x02; 33], [0x03; 33]);
+/// let secret = b"swap secret";
+/// let htlc = Htlc::new(&sender, &recipient, &sha256(secret), LockTime::Height(800_000))?;
+/// let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+///
+/// let mut funding = TxBuilder::new(&parser);
+/// htlc.add_funding_output(&mut funding, Amount::from_sat(100_000))?;
+/// // ... add inputs and change, sign and broadcast
+/// let outpoint = OutPoint { txid: funding.txid(), n: 0 };
+///
+/// let hash_type = sig_hash::ALL | sig_hash::FORKID;
+/// let redeem = htlc.redeem(secret, outpoint.clone(), Amount::from_sat(100_000), &hex::encode([0x05; 20]), FeeRate::MIN_RELAY, &parser)?;
+/// assert_eq!(redeem.signers(), [&recipient[..]]);
+/// let sighash = redeem.sighash(hash_type)?;
+/// // recipient signs `sighash`
+/// let tx = redeem.finalize(&[(&recipient, &[0x30; 72])])?;
+/// assert!(tx.is_complete());
+///
+/// let refund = htlc.refund(outpoint, Amount::from_sat(100_000), &hex::encode([0x06; 20]), FeeRate::MIN_RELAY, &parser)?;
+/// assert_eq!(refund.signers(), [&sender[..]]);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Htlc {
+    sender: Vec<u8>,
+    recipient: Vec<u8>,
+    secret_hash: Vec<u8>,
+    timeout: LockTime,
+}
+
+impl Htlc {
+    /// Construct HTLC
+    /// # Arguments
+    /// * `sender` - `public key` of sender (funds HTLC, refunded after timeout)
+    /// * `recipient` - `public key` of recipient (redeems with secret)
+    /// * `secret_hash` - SHA-256 of secret
+    /// * `timeout` - block height or unix time after which sender can take refund
+    pub fn new(sender: &[u8], recipient: &[u8], secret_hash: &[u8], timeout: LockTime) -> Result<Htlc> {
+        if secret_hash.len() != 32 {
+            return Err(Error::InvalidLengthData(secret_hash.len()));
+        }
+
+        Ok(Htlc {
+            sender: sender.to_vec(),
+            recipient: recipient.to_vec(),
+            secret_hash: secret_hash.to_vec(),
+            timeout,
+        })
+    }
+
+    /// SHA-256 of secret
+    pub fn secret_hash(&self) -> &[u8] {
+        &self.secret_hash
+    }
+
+    /// Build `redeem script`
+    pub fn redeem_script(&self) -> Result<Vec<u8>> {
+        ScriptBuilder::new()
+            .op(OP_IF)
+            .op(OP_SHA256).data(&self.secret_hash).op(OP_EQUALVERIFY)
+            .data(&self.recipient).op(OP_CHECKSIG)
+            .op(OP_ELSE)
+            .int(i64::from(u32::from(self.timeout))).op(OP_CHECKLOCKTIMEVERIFY).op(OP_DROP)
+            .data(&self.sender).op(OP_CHECKSIG)
+            .op(OP_ENDIF)
+            .build()
+    }
+
+    /// Build P2SH `scriptPubKey`
+    pub fn script_pub_key(&self) -> Result<Vec<u8>> {
+        p2sh::script_pub_key(&hash::hash160(&self.redeem_script()?))
+    }
+
+    /// Add output funding HTLC
+    /// # Arguments
+    /// * `txb` - funding transaction
+    /// * `value` - locked value
+    pub fn add_funding_output<F>(&self, txb: &mut TxBuilder<F>, value: Amount) -> Result<()>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        txb.add_output(value, &self.script_pub_key()?);
+        Ok(())
+    }
+
+    /// Build transaction redeeming HTLC by recipient
+    ///
+    /// The secret is revealed in `scriptSig`.
+    /// # Arguments
+    /// * `secret` - preimage of secret hash
+    /// * `outpoint` - HTLC output
+    /// * `value` - value of HTLC output
+    /// * `destination` - address receiving value after fee
+    /// * `fee_rate` - fee rate, e.g. `Network::min_fee_rate`
+    /// * `address_parser` - address parser closure, see `TxBuilder::new`
+    pub fn redeem<F, A>(&self, secret: &[u8], outpoint: OutPoint, value: Amount, destination: &A, fee_rate: FeeRate, address_parser: F) -> Result<Spend<F>>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)>,
+              A: ToAddressScript + ?Sized {
+        if hash::sha256(secret) != self.secret_hash {
+            return Err(Error::InvalidSecret(secret.to_vec()));
+        }
+
+        let mut txb = TxBuilder::new(address_parser);
+        txb.add_input(outpoint.txid, outpoint.n, Some(value.to_sat()), Some(&self.script_pub_key()?), None)?;
+
+        let suffix = vec![secret.to_vec(), vec![1]];
+        Spend::new(txb, &self.redeem_script()?, Unlock::Single(self.recipient.clone()), suffix, destination, fee_rate)
+    }
+
+    /// Build transaction refunding HTLC to sender, valid after timeout
+    ///
+    /// Lock time is set to timeout and the input has non-final sequence.
+    /// # Arguments
+    /// * `outpoint` - HTLC output
+    /// * `value` - value of HTLC output
+    /// * `destination` - address receiving value after fee
+    /// * `fee_rate` - fee rate, e.g. `Network::min_fee_rate`
+    /// * `address_parser` - address parser closure, see `TxBuilder::new`
+    pub fn refund<F, A>(&self, outpoint: OutPoint, value: Amount, destination: &A, fee_rate: FeeRate, address_parser: F) -> Result<Spend<F>>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)>,
+              A: ToAddressScript + ?Sized {
+        let mut txb = TxBuilder::new(address_parser);
+        txb.set_lock_time(self.timeout);
+        let sequence = Some(Sequence::MAX_NON_FINAL.into());
+        txb.add_input(outpoint.txid, outpoint.n, Some(value.to_sat()), Some(&self.script_pub_key()?), sequence)?;
+
+        Spend::new(txb, &self.redeem_script()?, Unlock::Single(self.sender.clone()), vec![vec![]], destination, fee_rate)
+    }
+}
+
+/// Extract secret revealed by redeem transaction
+///
+/// Useful for the other side of swap, which watches the HTLC output being spent.
+/// # Arguments
+/// * `script_sig` - `scriptSig` of input spending HTLC
+/// * `secret_hash` - SHA-256 of secret
+/// # Returns
+/// * secret if `scriptSig` redeems with it
+pub fn extract_secret(script_sig: &[u8], secret_hash: &[u8]) -> Option<Vec<u8>> {
+    script::decode(script_sig).ok()?
+        .into_iter()
+        .find_map(|push| match push {
+            Script::Data(data) if hash::sha256(data) == secret_hash => Some(data.to_vec()),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"swap secret";
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn spend() -> Result<()> {
+        use secp256k1::SecretKey;
+        use super::super::super::signer::Signer;
+        use super::super::super::tx_builder::sig_hash;
+
+        let keys = (1..=2u8).map(|i| SecretKey::from_slice(&[i; 32])).collect::<std::result::Result<Vec<_>, _>>()?;
+        let pubkeys = keys.iter().map(Signer::public_key).collect::<Vec<_>>();
+        let htlc = Htlc::new(&pubkeys[0], &pubkeys[1], &hash::sha256(SECRET), LockTime::Height(800_000))?;
+        let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+        let outpoint = OutPoint { txid: [0x01; 32].into(), n: 1 };
+        let value = Amount::from_sat(100_000);
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        let sign = |key: &SecretKey, digest: &[u8]| -> Result<Vec<u8>> {
+            Ok([key.sign(digest)?, vec![hash_type.to_u8()]].concat())
+        };
+
+        let redeem = htlc.redeem(SECRET, outpoint.clone(), value, &hex::encode([0x05; 20]), FeeRate::MIN_RELAY, &parser)?;
+        let sig = sign(&keys[1], &redeem.sighash(hash_type)?)?;
+        let tx = redeem.finalize(&[(&pubkeys[1], &sig)])?;
+        tx.validate_input(0)?;
+        assert!(tx.fee()?.to_sat() >= tx.size() as u64);
+        let script_sig = &tx.inputs().next().expect("input").script;
+        assert_eq!(extract_secret(script_sig, htlc.secret_hash()), Some(SECRET.to_vec()));
+
+        let refund = htlc.refund(outpoint, value, &hex::encode([0x06; 20]), FeeRate::MIN_RELAY, &parser)?;
+        let sig = sign(&keys[0], &refund.sighash(hash_type)?)?;
+        let tx = refund.finalize(&[(&pubkeys[0], &sig)])?;
+        tx.validate_input(0)?;
+        let script_sig = &tx.inputs().next().expect("input").script;
+        assert_eq!(extract_secret(script_sig, htlc.secret_hash()), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        match Htlc::new(&[0x02; 33], &[0x03; 33], &hash::hash160(SECRET), LockTime::Height(800_000)) {
+            Err(Error::InvalidLengthData(20)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let htlc = Htlc::new(&[0x02; 33], &[0x03; 33], &hash::sha256(SECRET), LockTime::Height(800_000)).unwrap();
+        let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+        let outpoint = OutPoint { txid: [0x01; 32].into(), n: 1 };
+        match htlc.redeem(b"wrong", outpoint, Amount::from_sat(100_000), &hex::encode([0x05; 20]), FeeRate::MIN_RELAY, &parser) {
+            Err(Error::InvalidSecret(_)) => {},
+            r => panic!("unexpected result: {:?}", r.map(|spend| spend.builder().to_hex())),
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! CashTokens genesis and minting
+//!
+//! Category of new tokens is the txid of the outpoint spent by input 0,
+//! which must be output 0 of its transaction (pre-genesis output).
+//! Later, NFTs of the category can be created only by spending its minting NFT.
+
+use super::super::TxBuilder;
+use super::super::types::{Amount, u256};
+use super::super::types::transaction::{OutPoint, Output, TokenData};
+use super::super::types::transaction::token::{Capability, Nft};
+use super::super::error::{Error, Result};
+
+/// Output carrying new tokens
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewToken {
+    /// value (satoshi) of output
+    pub value: Amount,
+    /// `scriptPubKey` of output
+    pub script: Vec<u8>,
+    /// fungible token amount (`0` if no fungible token)
+    pub amount: u64,
+    /// (option) non-fungible token
+    pub nft: Option<Nft>,
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidTokenIssuance(reason.to_string())
+}
+
+/// Add pre-genesis input and outputs of new token category
+/// # Arguments
+/// * `txb` - transaction without inputs
+/// * `pre_genesis` - outpoint spent by input 0, its index must be `0`
+/// * `prev_output` - output at `pre_genesis` (must not carry tokens)
+/// * `tokens` - outputs of new tokens
+/// # Returns
+/// * category of new tokens
+/// # Example
+/// ```
+/// # use cash_tx_builder::{TxBuilder, Amount};
+/// # use cash_tx_builder::types::transaction::{OutPoint, Output};
+/// # use cash_tx_builder::types::transaction::token::{Capability, Nft};
+/// # use cash_tx_builder::templates::token::{self, NewToken};
+/// let parser = |_: &str| None;
+/// let mut txb = TxBuilder::new(&parser);
+/// let pre_genesis: OutPoint = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c:0".parse()?;
+/// let minting = Nft { capability: Capability::Minting, commitment: vec![] };
+/// let tokens = [
+///     NewToken { value: Amount::from_sat(1000), script: vec![0x51], amount: 1_000_000, nft: None },
+///     NewToken { value: Amount::from_sat(1000), script: vec![0x51], amount: 0, nft: Some(minting) },
+/// ];
+/// let category = token::genesis(&mut txb, pre_genesis.clone(), Output::new(Amount::from_sat(10_000), &[0x51]), &tokens)?;
+/// assert_eq!(category, pre_genesis.txid.into());
+/// assert_eq!(txb.output_count(), 2);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+pub fn genesis<F>(txb: &mut TxBuilder<F>, pre_genesis: OutPoint, prev_output: Output, tokens: &[NewToken]) -> Result<u256>
+    where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+    if txb.input_count() != 0 {
+        return Err(invalid("pre-genesis output must be spent by input 0"));
+    }
+    if pre_genesis.n != 0 {
+        return Err(invalid("pre-genesis output must be output 0 of its transaction"));
+    }
+    if prev_output.token.is_some() {
+        return Err(invalid("pre-genesis output carries tokens"));
+    }
+    if tokens.is_empty() {
+        return Err(invalid("no tokens"));
+    }
+
+    let category = u256::from(pre_genesis.txid);
+    let data = tokens.iter()
+        .map(|t| TokenData::new(category, t.amount, t.nft.clone()))
+        .collect::<Result<Vec<_>>>()?;
+
+    txb.add_input(pre_genesis.txid, 0, Some(prev_output.value.to_sat()), Some(&prev_output.script), None)?;
+    for (t, token) in tokens.iter().zip(data) {
+        txb.add_token_output(t.value, &t.script, token);
+    }
+
+    Ok(category)
+}
+
+/// Add input spending minting NFT and outputs of new NFTs of its category
+///
+/// Minting NFT and fungible tokens of the input are burned
+/// unless sent to an output, e.g. an NFT with `Capability::Minting` in `nfts`.
+/// # Arguments
+/// * `txb` - transaction
+/// * `minting` - outpoint of minting NFT
+/// * `prev_output` - output at `minting`
+/// * `nfts` - outputs of new NFTs (without fungible amount)
+/// # Returns
+/// * index of input spending minting NFT
+pub fn mint<F>(txb: &mut TxBuilder<F>, minting: OutPoint, prev_output: Output, nfts: &[NewToken]) -> Result<usize>
+    where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+    let category = match &prev_output.token {
+        Some(TokenData { category, nft: Some(Nft { capability: Capability::Minting, .. }), .. }) => *category,
+        _ => return Err(invalid("input is not minting NFT")),
+    };
+    if nfts.is_empty() {
+        return Err(invalid("no tokens"));
+    }
+    if nfts.iter().any(|t| t.amount != 0 || t.nft.is_none()) {
+        return Err(invalid("fungible tokens can be created only at genesis"));
+    }
+
+    let data = nfts.iter()
+        .map(|t| TokenData::new(category, 0, t.nft.clone()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let index = txb.input_count();
+    txb.add_input(minting.txid, minting.n, Some(prev_output.value.to_sat()), Some(&prev_output.script), None)?;
+    txb.set_prev_output(index, prev_output)?;
+    for (t, token) in nfts.iter().zip(data) {
+        txb.add_token_output(t.value, &t.script, token);
+    }
+
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::types::transaction::Transaction;
+    use std::convert::TryFrom;
+
+    fn nft(capability: Capability, commitment: &[u8]) -> Option<Nft> {
+        Some(Nft { capability, commitment: commitment.to_vec() })
+    }
+
+    #[test]
+    fn genesis_and_mint() -> Result<()> {
+        let parser = |_: &str| None;
+        let pre_genesis: OutPoint = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c:0".parse()?;
+        let prev_output = Output::new(Amount::from_sat(10_000), &[0x51]);
+
+        let mut txb = TxBuilder::new(&parser);
+        let tokens = [
+            NewToken { value: Amount::from_sat(1000), script: vec![0x51], amount: 100, nft: nft(Capability::Minting, &[]) },
+            NewToken { value: Amount::from_sat(1000), script: vec![0x52], amount: 0, nft: nft(Capability::None, &[0xcc]) },
+        ];
+        let category = genesis(&mut txb, pre_genesis.clone(), prev_output.clone(), &tokens)?;
+        assert_eq!(category, u256::from(pre_genesis.txid));
+
+        let tx = Transaction::try_from(&txb.to_vec()[..])?;
+        assert_eq!(tx.outpoints().next(), Some(&pre_genesis));
+        let token = tx.outputs[0].token.clone().expect("token");
+        assert_eq!((token.category, token.amount), (category, 100));
+        assert_eq!(token.to_vec()[33], 0x32);
+        assert_eq!(tx.outputs[1].token.as_ref().map(|t| t.to_vec()[33]), Some(0x60));
+
+        let minting = OutPoint { txid: txb.txid(), n: 0 };
+        let mut txb = TxBuilder::new(&parser);
+        txb.add_input(pre_genesis.txid, 1, Some(5000), Some(&[0x51]), None)?;
+        let nfts = [
+            NewToken { value: Amount::from_sat(1000), script: vec![0x51], amount: 0, nft: nft(Capability::Minting, &[]) },
+            NewToken { value: Amount::from_sat(1000), script: vec![0x53], amount: 0, nft: nft(Capability::Mutable, &[0x01]) },
+        ];
+        assert_eq!(mint(&mut txb, minting, tx.outputs[0].clone(), &nfts)?, 1);
+        let tx = Transaction::try_from(&txb.to_vec()[..])?;
+        assert!(tx.outputs.iter().all(|o| o.token.as_ref().map(|t| t.category) == Some(category)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn mint_sighash() -> Result<()> {
+        use super::super::super::script::p2pkh;
+        use super::super::super::tx_builder::sig_hash;
+
+        let parser = |_: &str| None;
+        let minting: OutPoint = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c:0".parse()?;
+        let category = u256::from(minting.txid);
+        // P2PKH of public key of secret key 1
+        let script = p2pkh::script_pub_key(&hex::decode("751e76e8199196d454941c45d1b3a323f1433bd6")?)?;
+        let prev_output = Output::with_token(Amount::from_sat(3000), &script, TokenData::new(category, 0, nft(Capability::Minting, &[]))?);
+
+        let mut txb = TxBuilder::new(&parser);
+        let nfts = [
+            NewToken { value: Amount::from_sat(1000), script: script.clone(), amount: 0, nft: nft(Capability::Minting, &[]) },
+            NewToken { value: Amount::from_sat(800), script: vec![0x52], amount: 0, nft: nft(Capability::None, &[0xcc]) },
+        ];
+        mint(&mut txb, minting, prev_output, &nfts)?;
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        // minting NFT is committed before scriptCode
+        let sighash = txb.witness_v0_hash(hash_type, 0, None, None)?;
+        assert_eq!(sighash.to_string(), "ac3cbac8bae3aca8e9b02a2ea63caa43b0826eb30adc91f77e399fd0fba4ae5c");
+
+        #[cfg(feature = "sign")]
+        {
+            let mut key = [0; 32];
+            key[31] = 1;
+            let key = secp256k1::SecretKey::from_slice(&key)?;
+            txb.sign_input(0, &key, hash_type)?;
+            txb.validate_input(0)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_issuance() -> Result<()> {
+        let parser = |_: &str| None;
+        let pre_genesis: OutPoint = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c:0".parse()?;
+        let prev_output = Output::new(Amount::from_sat(10_000), &[0x51]);
+        let fungible = NewToken { value: Amount::from_sat(1000), script: vec![0x51], amount: 100, nft: None };
+
+        let mut txb = TxBuilder::new(&parser);
+        let not_first = OutPoint { n: 1, ..pre_genesis.clone() };
+        match genesis(&mut txb, not_first, prev_output.clone(), std::slice::from_ref(&fungible)) {
+            Err(Error::InvalidTokenIssuance(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        let long = NewToken { nft: nft(Capability::None, &[0; 41]), ..fungible.clone() };
+        match genesis(&mut txb, pre_genesis.clone(), prev_output.clone(), &[long]) {
+            Err(Error::InvalidTokenData(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(txb.input_count(), 0);
+
+        genesis(&mut txb, pre_genesis.clone(), prev_output.clone(), std::slice::from_ref(&fungible))?;
+        match genesis(&mut txb, pre_genesis.clone(), prev_output, std::slice::from_ref(&fungible)) {
+            Err(Error::InvalidTokenIssuance(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let category = u256::from(pre_genesis.txid);
+        let mutable = Output::with_token(Amount::from_sat(1000), &[0x51], TokenData::new(category, 0, nft(Capability::Mutable, &[]))?);
+        let minting = Output::with_token(Amount::from_sat(1000), &[0x51], TokenData::new(category, 0, nft(Capability::Minting, &[]))?);
+        let mut txb = TxBuilder::new(&parser);
+        let nft_token = NewToken { amount: 0, nft: nft(Capability::None, &[]), ..fungible.clone() };
+        for (prev, tokens) in &[(mutable, &nft_token), (minting, &fungible)] {
+            match mint(&mut txb, pre_genesis.clone(), prev.clone(), &[(*tokens).clone()]) {
+                Err(Error::InvalidTokenIssuance(_)) => {},
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+//! Fixtures for tests of wallets built on this crate
+//!
+//! Keys are derived from small scalars and must never hold real funds.
+
+use std::convert::TryFrom;
+use secp256k1::SecretKey;
+use super::tx_builder::TxBuilder;
+use super::tx_builder::sig_hash;
+use super::types::Amount;
+use super::types::transaction::Transaction;
+use super::types::transaction::output::Output;
+use super::address::{self, Address, AddressNetwork};
+use super::network::Network;
+use super::signer::Signer;
+use super::hash;
+use super::error::{Error, Result};
+
+/// Address parser of builders made by this module
+pub type Parser = fn(&str) -> Option<(Vec<u8>, bool)>;
+
+/// Secret key of fixture
+/// # Arguments
+/// * `index` - fixture index (the key is scalar `index + 1`)
+pub fn secret_key(index: u32) -> SecretKey {
+    let mut v = [0; 32];
+    v[24..].copy_from_slice(&(u64::from(index) + 1).to_be_bytes());
+
+    SecretKey::from_slice(&v).expect("small scalar is valid key")
+}
+
+/// Compressed `public key` of fixture
+/// # Arguments
+/// * `index` - fixture index
+pub fn public_key(index: u32) -> Vec<u8> {
+    Signer::public_key(&secret_key(index))
+}
+
+/// Regtest P2PKH address of fixture
+/// # Arguments
+/// * `index` - fixture index
+/// # Example
+/// ```
+/// # use cash_tx_builder::test_util;
+/// assert_eq!(test_util::address(0).to_cashaddr(), "bchreg:qp63uahgrxged4z5jswyt5dn5v3lzsem6c6mz8vuwd");
+/// ```
+pub fn address(index: u32) -> Address {
+    let mut pubkey_hash = [0; 20];
+    pubkey_hash.copy_from_slice(&hash::hash160(&public_key(index)));
+
+    Address::P2PKH(AddressNetwork::Regtest, pubkey_hash)
+}
+
+/// P2PKH `scriptPubKey` of fixture
+/// # Arguments
+/// * `index` - fixture index
+pub fn script_pub_key(index: u32) -> Vec<u8> {
+    address(index).script_pub_key().expect("20 bytes hash")
+}
+
+/// Factory of regtest transactions paying to and spending from fixture keys
+///
+/// Funding transactions are coinbases at increasing heights, like `generatetoaddress`,
+/// so every call yields a distinct txid. Signatures are deterministic (RFC6979).
+/// # Example
+/// ```
+/// # use cash_tx_builder::{test_util, Amount};
+/// # use cash_tx_builder::test_util::TxFactory;
+/// # use cash_tx_builder::types::transaction::output::Output;
+/// let mut factory = TxFactory::new();
+/// let funding = factory.fund(0, Amount::from_sat(100_000))?;
+/// let output = Output::new(Amount::from_sat(99_000), &test_util::script_pub_key(1));
+/// let tx = factory.spend(&funding, 0, 0, &[output])?;
+/// assert_eq!(tx.inputs[0].outpoint.txid, funding.txid());
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TxFactory {
+    height: u32,
+}
+
+impl TxFactory {
+    /// Construct factory starting at height `1`
+    pub fn new() -> TxFactory {
+        TxFactory::default()
+    }
+
+    /// Create coinbase transaction paying to fixture
+    /// # Arguments
+    /// * `index` - fixture index to receive `value`
+    /// * `value` - value of output `0`
+    pub fn fund(&mut self, index: u32, value: Amount) -> Result<Transaction> {
+        self.height += 1;
+        let output = Output::new(value, &script_pub_key(index));
+        let txb = TxBuilder::<Parser>::new_coinbase(self.height, &[], &[output], address::parse)?;
+
+        Transaction::try_from(&txb.to_vec()[..])
+    }
+
+    /// Construct regtest builder spending output of `prev` (not signed)
+    /// # Arguments
+    /// * `prev` - previous transaction
+    /// * `vout` - output index of `prev`
+    pub fn builder(&self, prev: &Transaction, vout: u32) -> Result<TxBuilder<Parser>> {
+        let prev_output = prev.outputs.get(vout as usize)
+            .ok_or(Error::InvalidIndex(vout as usize))?;
+
+        let mut txb = TxBuilder::<Parser>::new(address::parse);
+        txb.set_network(Network::Regtest);
+        txb.add_input(prev.txid(), vout, None, None, None)?;
+        txb.set_prev_output(0, prev_output.clone())?;
+        Ok(txb)
+    }
+
+    /// Create signed transaction spending output of `prev`
+    /// # Arguments
+    /// * `prev` - previous transaction
+    /// * `vout` - output index of `prev`, paying to fixture `index`
+    /// * `index` - fixture index of signing key
+    /// * `outputs` - outputs of new transaction
+    /// # Returns
+    /// * transaction signed with `ALL | FORKID` and validated by script interpreter
+    pub fn spend(&self, prev: &Transaction, vout: u32, index: u32, outputs: &[Output]) -> Result<Transaction> {
+        let mut txb = self.builder(prev, vout)?;
+        for output in outputs {
+            match &output.token {
+                Some(token) => txb.add_token_output(output.value, &output.script, token.clone()),
+                None => txb.add_output(output.value, &output.script),
+            }
+        }
+        txb.sign_input(0, &secret_key(index), sig_hash::ALL | sig_hash::FORKID)?;
+        txb.validate_input(0)?;
+
+        Transaction::try_from(&txb.to_vec()[..])
+    }
+}
+
+/// Known transaction with sighash of input `0` (`ALL | FORKID`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoldenVector {
+    /// serialized transaction in hex
+    pub raw_tx: &'static str,
+    /// txid
+    pub txid: &'static str,
+    /// previous `scriptPubKey` of input `0` in hex
+    pub prev_script: &'static str,
+    /// previous value of input `0`
+    pub prev_value: u64,
+    /// sighash of input `0` in hex
+    pub sighash: &'static str,
+}
+
+/// Mainnet P2PKH transaction of the crate level example
+pub const P2PKH_MAINNET: GoldenVector = GoldenVector {
+    raw_tx: "02000000014ceee1213404c7a96b80b86f85487220599dbefcbc192c55336a0e968afc7c42010000006a47304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b3041210366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036ffffffff02f82a0000000000001976a91426b61031a0fdf3cb9f17da8b9c1fc54a638c369188acb55a0100000000001976a9142647f73de90d8e9db5b5f77d1cc1ad5afe81d68c88ac00000000",
+    txid: "ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda",
+    prev_script: "76a9143424f163208a3b676fa0ec17034f0f290322a2a688ac",
+    prev_value: 100_000,
+    sighash: "2b492e7c4c8a3d670fd7fe324a87e3c55df1802c9a100f4006f8fff7c0913dd4",
+};
+
+impl GoldenVector {
+    /// Construct builder of `raw_tx` with previous output of input `0`
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::test_util::P2PKH_MAINNET;
+    /// let txb = P2PKH_MAINNET.builder()?;
+    /// P2PKH_MAINNET.assert_matches(&txb);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn builder(&self) -> Result<TxBuilder<Parser>> {
+        let prev_script = hex::decode(self.prev_script)?;
+        let mut txb = TxBuilder::<Parser>::from_hex(self.raw_tx, address::parse)?;
+        txb.set_prev_output(0, Output::new(Amount::from_sat(self.prev_value), &prev_script))?;
+
+        Ok(txb)
+    }
+
+    /// Assert that `txb` serializes to `raw_tx` and input `0` has `sighash`
+    /// # Panics
+    /// * on mismatch
+    pub fn assert_matches<F>(&self, txb: &TxBuilder<F>)
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        assert_tx_hex(txb, self.raw_tx);
+        assert_eq!(txb.txid().to_string(), self.txid, "txid");
+
+        let prev_script = hex::decode(self.prev_script).expect("hex of golden vector");
+        let sighash = txb.witness_v0_hash(sig_hash::ALL | sig_hash::FORKID, 0, Some(self.prev_value), Some(&prev_script))
+            .expect("sighash of input 0");
+        assert_eq!(hex::encode(sighash), self.sighash, "sighash of input 0");
+    }
+}
+
+/// Assert that `txb` serializes to `expected`
+/// # Arguments
+/// * `txb` - transaction builder
+/// * `expected` - serialized transaction in hex
+/// # Panics
+/// * on mismatch, with differing fields and both serializations
+pub fn assert_tx_hex<F>(txb: &TxBuilder<F>, expected: &str)
+    where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+    let actual = txb.to_hex();
+    if actual != expected {
+        let diff = Transaction::from_hex(&actual)
+            .and_then(|tx| Transaction::from_hex(expected).map(|expected| tx.diff(&expected).to_string()))
+            .unwrap_or_else(|e| e.to_string());
+        panic!("transaction mismatch (actual != expected)\n{}  actual: {}\nexpected: {}", diff, actual, expected);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures() {
+        assert_eq!(secret_key(0).secret_bytes()[31], 1);
+        assert_eq!(public_key(0), hex!("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"));
+        assert_eq!(address(0).network(), AddressNetwork::Regtest);
+        assert_ne!(address(0), address(1));
+    }
+
+    #[test]
+    fn factory() -> Result<()> {
+        let mut factory = TxFactory::new();
+        let funding = factory.fund(0, Amount::from_sat(100_000))?;
+        assert!(funding.is_coinbase());
+        assert_ne!(factory.fund(0, Amount::from_sat(100_000))?.txid(), funding.txid());
+
+        let outputs = [Output::new(Amount::from_sat(99_000), &script_pub_key(1))];
+        let tx = factory.spend(&funding, 0, 0, &outputs)?;
+        assert_eq!(tx, factory.spend(&funding, 0, 0, &outputs)?);
+        assert!(factory.spend(&funding, 0, 1, &outputs).is_err());
+        assert!(factory.spend(&funding, 1, 0, &outputs).is_err());
+
+        let txb = factory.builder(&funding, 0)?;
+        assert_eq!(txb.network(), Network::Regtest);
+        assert_eq!(txb.input_count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn golden() -> Result<()> {
+        P2PKH_MAINNET.assert_matches(&P2PKH_MAINNET.builder()?);
+
+        let mut txb = P2PKH_MAINNET.builder()?;
+        txb.set_lock_time(1.into());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| assert_tx_hex(&txb, P2PKH_MAINNET.raw_tx)));
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
@@ -1,24 +1,284 @@
-use std::collections::HashMap;
-use std::str::FromStr;
+use std::fmt;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
 
 use super::error::{Error, Result};
-use super::script::{Script, address_to_script, null_data_script, encode};
-use super::hash;
+use super::script::{Script, ScriptBuilder, ScriptType, ToAddressScript, multisig, p2sh, address_to_script, null_data_script, null_data_script_multi, encode, decode, remove_code_separators, script_type};
+use super::hash::{self, Hash256Writer, Midstate, SigHash};
+use super::slp;
+use super::pst::Pst;
+use super::provider::Utxo;
+use super::verify::{self, Violation};
+use super::policy;
+use super::metrics::Metrics;
+use super::network::Network;
 use sha2::{Sha256, Digest};
+use rand_core::RngCore;
 use super::bit_util::BitUtil;
-use super::types::u256;
+use super::types::{VarInt, Txid, LockTime, Sequence, Amount, FeeRate, LOCKTIME_THRESHOLD};
+use self::sig_hash::SigHashType;
 use super::types::transaction::Transaction;
+use super::types::transaction::OutPoint;
 use super::types::transaction::input::Input;
 use super::types::transaction::output::Output;
+use super::types::transaction::token::TokenData;
+use super::script::{p2pk, p2pkh};
+#[cfg(feature = "sign")]
+use super::signer::{self, Signer};
+#[cfg(feature = "sign")]
+use super::interpreter::{self, SignatureChecker};
+#[cfg(feature = "hd")]
+use super::hd::{Xpriv, DerivationPath};
+
+mod io_builder;
+pub use self::io_builder::{InputBuilder, OutputBuilder};
+use self::io_builder::Destination;
 
 /// sighash type
 pub mod sig_hash {
-    #![allow(missing_docs)]
-    pub const ALL: u32 = 0x01;
-    pub const NONE: u32 = 0x02;
-    pub const SINGLE: u32 = 0x03;
-    pub const FORKID: u32 = 0x40;
-    pub const ANYONECANPAY: u32 = 0x80;
+    use std::fmt;
+    use std::ops::BitOr;
+    use super::super::error::{Error, Result};
+
+    #[cfg(feature = "test-vectors")]
+    pub mod test_vectors;
+
+    /// Mask of base type (`ALL`, `NONE` or `SINGLE`)
+    const BASE_MASK: u32 = 0x1f;
+
+    /// Sighash type, base type combined with flags by `|`
+    ///
+    /// BCH signatures must have `FORKID`,
+    /// so digests and signatures are rejected if it is missing.
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::sig_hash::{self, SigHashType};
+    /// let hash_type = sig_hash::ALL | sig_hash::FORKID;
+    /// assert_eq!(hash_type.to_u8(), 0x41);
+    /// assert_eq!(SigHashType::from_u32(0x41)?, hash_type);
+    /// assert!(SigHashType::from_u32(0x01).is_err());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct SigHashType(u32);
+
+    /// Sign all outputs
+    pub const ALL: SigHashType = SigHashType(0x01);
+    /// Sign no outputs
+    pub const NONE: SigHashType = SigHashType(0x02);
+    /// Sign output of the same index
+    pub const SINGLE: SigHashType = SigHashType(0x03);
+    /// Sign all previous outputs (`hashUtxos`)
+    pub const UTXOS: SigHashType = SigHashType(0x20);
+    /// BCH replay protection, required
+    pub const FORKID: SigHashType = SigHashType(0x40);
+    /// Sign only own input
+    pub const ANYONECANPAY: SigHashType = SigHashType(0x80);
+
+    impl SigHashType {
+        /// Construct from raw value (e.g. last byte of signature)
+        /// # Arguments
+        /// * `value` - sighash type
+        /// # Returns
+        /// * sighash type (error if invalid, see `validate`)
+        pub fn from_u32(value: u32) -> Result<SigHashType> {
+            let hash_type = SigHashType(value);
+            hash_type.validate()?;
+
+            Ok(hash_type)
+        }
+
+        /// Check combination of base type and flags
+        ///
+        /// Base type must be `ALL`, `NONE` or `SINGLE` and `FORKID` must be set.
+        /// `UTXOS` can not be combined with `ANYONECANPAY`.
+        pub fn validate(self) -> Result<()> {
+            let defined = BASE_MASK | UTXOS.0 | FORKID.0 | ANYONECANPAY.0;
+            let base = self.base();
+            if self.0 & !defined != 0 ||
+               (base != ALL && base != NONE && base != SINGLE) ||
+               !self.is_set(FORKID) ||
+               (self.is_set(UTXOS) && self.is_set(ANYONECANPAY)) {
+                return Err(Error::InvalidSigHashType(self.0));
+            }
+
+            Ok(())
+        }
+
+        /// Base type (`ALL`, `NONE` or `SINGLE` if valid)
+        pub fn base(self) -> SigHashType {
+            SigHashType(self.0 & BASE_MASK)
+        }
+
+        /// `true` if all bits of `flag` are set
+        pub fn is_set(self, flag: SigHashType) -> bool {
+            self.0 & flag.0 == flag.0
+        }
+
+        /// Raw value
+        pub fn to_u32(self) -> u32 {
+            self.0
+        }
+
+        /// Byte appended to signature
+        pub fn to_u8(self) -> u8 {
+            self.0 as u8
+        }
+    }
+
+    impl BitOr for SigHashType {
+        type Output = SigHashType;
+
+        fn bitor(self, rhs: SigHashType) -> SigHashType {
+            SigHashType(self.0 | rhs.0)
+        }
+    }
+
+    impl From<SigHashType> for u32 {
+        fn from(hash_type: SigHashType) -> u32 {
+            hash_type.0
+        }
+    }
+
+    impl fmt::Display for SigHashType {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{:#04x}", self.0)
+        }
+    }
+}
+
+/// Length of trailing fields of `witness_v0_preimage` (`sequence`, `hashOutputs`, `lock time` and sighash type)
+const WITNESS_V0_TAIL_LEN: usize = 4 + 32 + 4 + 4;
+
+/// Maximum length of DER signature with sighash type
+pub(crate) const MAX_SIG_LEN: usize = 72 + 1;
+
+/// Estimated length of signed P2PKH `scriptSig` (signature and compressed public key)
+const P2PKH_SCRIPT_SIG_LEN: usize = 1 + MAX_SIG_LEN + 1 + 33;
+
+/// Estimated length of signed P2PK `scriptSig`
+const P2PK_SCRIPT_SIG_LEN: usize = 1 + MAX_SIG_LEN;
+
+/// Estimate worst-case length of `scriptSig`, `None` if P2SH `redeem script` is unknown
+fn estimated_script_sig_len(prev_script: &[u8], redeem_script: Option<&[u8]>) -> Option<usize> {
+    match script_type(prev_script) {
+        ScriptType::PubKeyHash => Some(P2PKH_SCRIPT_SIG_LEN),
+        ScriptType::PubKey => Some(P2PK_SCRIPT_SIG_LEN),
+        ScriptType::Multisig => {
+            let (m, _) = multisig::parse_redeem_script(prev_script).ok()?;
+            Some(1 + m * (1 + MAX_SIG_LEN))
+        },
+        ScriptType::ScriptHash => {
+            let redeem_script = redeem_script?;
+            let sigs_len = match multisig::parse_redeem_script(redeem_script) {
+                Ok((m, _)) => 1 + m * (1 + MAX_SIG_LEN),
+                Err(_) => 0,
+            };
+            Some(sigs_len + encode(&[Script::Data(redeem_script)]).ok()?.len())
+        },
+        _ => Some(0),
+    }
+}
+
+/// State of non-empty `scriptSig`, counting signatures of multisig
+fn script_sig_state(prev_script: Option<&[u8]>, script_sig: &[u8]) -> InputState {
+    let pushes = match decode(script_sig) {
+        Ok(pushes) => pushes,
+        Err(_) => return InputState::Finalized,
+    };
+    let (m, sigs) = match prev_script.map(script_type) {
+        Some(ScriptType::Multisig) => (prev_script.and_then(|s| multisig::parse_redeem_script(s).ok()), &pushes[..]),
+        Some(ScriptType::ScriptHash) | None => match pushes.split_last() {
+            Some((redeem_script, sigs)) => (redeem_script.data().and_then(|s| multisig::parse_redeem_script(s).ok()), sigs),
+            None => (None, &pushes[..]),
+        },
+        _ => (None, &pushes[..]),
+    };
+    let m = match m {
+        Some((m, _)) => m,
+        None => return InputState::Finalized,
+    };
+    // the first push is the dummy element of `OP_CHECKMULTISIG`
+    let count = sigs.iter().skip(1).filter(|s| s.data().map(|d| !d.is_empty()).unwrap_or(false)).count();
+
+    if count < m { InputState::PartiallySigned } else { InputState::Finalized }
+}
+
+/// Remove `index` from map keyed by input index and shift following keys down
+fn remove_index<V>(map: &mut HashMap<usize, V>, index: usize) {
+    *map = map.drain()
+        .filter(|(i, _)| *i != index)
+        .map(|(i, v)| if i > index { (i - 1, v) } else { (i, v) })
+        .collect();
+}
+
+/// Random permutation of `0..len` (Fisher-Yates)
+fn shuffled_indices<R: RngCore + ?Sized>(len: usize, rng: &mut R) -> Vec<usize> {
+    let mut order = (0..len).collect::<Vec<_>>();
+    for i in (1..len).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+
+    order
+}
+
+/// Checker of signatures of an input of `TxBuilder`
+#[cfg(feature = "sign")]
+struct InputChecker<'a, F: Fn(&str) -> Option<(Vec<u8>, bool)>> {
+    txb: &'a TxBuilder<F>,
+    index: usize,
+}
+
+#[cfg(feature = "sign")]
+impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> SignatureChecker for InputChecker<'_, F> {
+    fn check_sig(&self, sig: &[u8], pubkey: &[u8], script_code: &[u8]) -> bool {
+        let (hash_type, sig) = match sig.split_last() {
+            Some((hash_type, sig)) => (u32::from(*hash_type), sig),
+            None => return false,
+        };
+        let hash_type = match SigHashType::from_u32(hash_type) {
+            Ok(hash_type) => hash_type,
+            Err(_) => return false,
+        };
+        let prev_value = match self.txb.prev_outputs.get(&self.index) {
+            Some(o) => o.value,
+            None => return false,
+        };
+
+        match self.txb.witness_v0_hash(hash_type, self.index as u32, Some(prev_value.to_sat()), Some(script_code)) {
+            Ok(digest) => signer::verify(pubkey, &digest, sig),
+            Err(_) => false,
+        }
+    }
+
+    fn check_data_sig(&self, sig: &[u8], msg: &[u8], pubkey: &[u8]) -> bool {
+        signer::verify(pubkey, &Sha256::digest(msg), sig)
+    }
+
+    fn check_lock_time(&self, lock_time: i64) -> bool {
+        let threshold = i64::from(LOCKTIME_THRESHOLD);
+        let tx_lock_time = i64::from(self.txb.tx.lock_time);
+
+        (tx_lock_time < threshold) == (lock_time < threshold) &&
+        lock_time <= tx_lock_time &&
+        self.txb.tx.inputs.get(self.index).map(|i| Sequence::from(i.sequence_no)) != Some(Sequence::FINAL)
+    }
+
+    fn check_sequence(&self, sequence: i64) -> bool {
+        const MASK: u32 = Sequence::TYPE_FLAG | Sequence::MASK;
+        let tx_sequence = match self.txb.tx.inputs.get(self.index) {
+            Some(i) => i.sequence_no,
+            None => return false,
+        };
+        if self.txb.tx.version < 2 || !Sequence::from(tx_sequence).is_relative_lock_time() {
+            return false;
+        }
+        let (tx_sequence, sequence) = (tx_sequence & MASK, sequence as u32 & MASK);
+
+        (tx_sequence < Sequence::TYPE_FLAG) == (sequence < Sequence::TYPE_FLAG) && sequence <= tx_sequence
+    }
 }
 
 /// Transaction builder
@@ -27,8 +287,112 @@ pub struct TxBuilder<F>
         where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
     tx: Transaction,
     prev_outputs: HashMap<usize, Output>,
+    redeem_scripts: HashMap<usize, Vec<u8>>,
     fork_id: u32,
+    network: Network,
+    max_script_sig_size: Option<usize>,
     address_parser: F,
+    sighash_cache: RefCell<SighashCache>,
+}
+
+/// Values of inputs and outputs
+/// # Example
+/// ```
+/// # use cash_tx_builder::{TxBuilder, Amount};
+/// # use cash_tx_builder::script::p2pkh;
+/// let mut txb = TxBuilder::new(|_: &str| None);
+/// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+/// let script = p2pkh::script_pub_key(&[0x01; 20])?;
+/// txb.add_input(prev_txid, 0, Some(50_000), Some(&script), None)?;
+/// txb.add_input(prev_txid, 1, None, None, None)?;
+/// txb.add_output(Amount::from_sat(40_000), &script);
+///
+/// let report = txb.balance_report();
+/// assert_eq!(report.inputs, [Some(Amount::from_sat(50_000)), None]);
+/// assert_eq!(report.input_total, None);
+/// assert_eq!(report.output_total, Some(Amount::from_sat(40_000)));
+/// assert_eq!(report.to_string().lines().next(), Some("input 0: 0.00050000 BCH"));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceReport {
+    /// value of each input (`None` if previous output is unknown)
+    pub inputs: Vec<Option<Amount>>,
+    /// value of each output
+    pub outputs: Vec<Amount>,
+    /// sum of input values (`None` if any is unknown)
+    pub input_total: Option<Amount>,
+    /// sum of output values (`None` if it exceeds `Amount::MAX_MONEY`)
+    pub output_total: Option<Amount>,
+    /// input total minus output total (`None` if unknown or negative)
+    pub fee: Option<Amount>,
+}
+
+/// One line per input and output followed by totals
+impl fmt::Display for BalanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = |v: &Option<Amount>| v.map(|v| v.to_string()).unwrap_or_else(|| "unknown".to_string());
+
+        for (i, value) in self.inputs.iter().enumerate() {
+            writeln!(f, "input {}: {}", i, format(value))?;
+        }
+        for (i, value) in self.outputs.iter().enumerate() {
+            writeln!(f, "output {}: {}", i, value)?;
+        }
+        writeln!(f, "input total: {}", format(&self.input_total))?;
+        writeln!(f, "output total: {}", format(&self.output_total))?;
+        writeln!(f, "fee: {}", format(&self.fee))
+    }
+}
+
+/// Signing state of input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputState {
+    /// previous output is unknown and `scriptSig` is empty
+    MissingPrevOutput,
+    /// `scriptSig` is empty
+    Unsigned,
+    /// multisig `scriptSig` has less signatures than required (missing ones are `OP_0`)
+    PartiallySigned,
+    /// `scriptSig` is set
+    Finalized,
+}
+
+/// BIP143 digests shared by all inputs
+#[derive(Debug, Default)]
+struct SighashCache {
+    hash_prev_outs: Option<Vec<u8>>,
+    hash_sequence: Option<Vec<u8>>,
+    hash_outputs: Option<Vec<u8>>,
+    hash_utxos: Option<Vec<u8>>,
+}
+
+/// Serialized (unsigned or partially signed) transaction in hex
+impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> fmt::Display for TxBuilder<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.tx, f)
+    }
+}
+
+/// Serialized (unsigned or partially signed) transaction in hex
+impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> fmt::LowerHex for TxBuilder<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.tx, f)
+    }
+}
+
+/// Draft transaction
+impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> AsRef<Transaction> for TxBuilder<F> {
+    fn as_ref(&self) -> &Transaction {
+        &self.tx
+    }
+}
+
+/// Draft transaction (previous outputs and `redeem script`s are dropped)
+impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> From<TxBuilder<F>> for Transaction {
+    fn from(txb: TxBuilder<F>) -> Transaction {
+        txb.tx
+    }
 }
 
 impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> TxBuilder<F> {
@@ -46,8 +410,12 @@ impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> TxBuilder<F> {
         TxBuilder {
             tx: Transaction::new(),
             prev_outputs: HashMap::new(),
+            redeem_scripts: HashMap::new(),
             fork_id: 0,
+            network: Network::default(),
+            max_script_sig_size: Some(policy::MAX_STANDARD_SCRIPT_SIG_SIZE),
             address_parser,
+            sighash_cache: RefCell::default(),
         }
     }
 
@@ -83,18 +451,222 @@ impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> TxBuilder<F> {
     /// let txid = "7bdc016701e4c5d7ec34e99954ec3921140728d2c58b1da3cf6aa34c760d8a47";
     /// let tx = Transaction::try_from(&hex[..])?;
     /// let txb = TxBuilder::from_tx(&tx, parser)?;
-    /// assert_eq!(txb.txid(), txid);
+    /// assert_eq!(txb.txid().to_string(), txid);
     /// # Ok::<(), cash_tx_builder::Error>(())
     /// ```
     pub fn from_tx(tx: &Transaction, address_parser: F) -> Result<TxBuilder<F>> {
         Ok(TxBuilder {
             tx: tx.clone(),
             prev_outputs: HashMap::new(),
+            redeem_scripts: HashMap::new(),
             fork_id: 0,
+            network: Network::default(),
+            max_script_sig_size: Some(policy::MAX_STANDARD_SCRIPT_SIG_SIZE),
             address_parser,
+            sighash_cache: RefCell::default(),
         })
     }
 
+    /// Construct transaction builder from hex string
+    /// # Arguments
+    /// * `hex` - serialized transaction in hex
+    /// * `address_parser` - address parser (same as `TxBuilder::new`)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::TxBuilder;
+    /// let hex = "0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000";
+    /// let txb = TxBuilder::from_hex(hex, |_: &str| None)?;
+    /// assert_eq!(txb.txid().to_string(), "7bdc016701e4c5d7ec34e99954ec3921140728d2c58b1da3cf6aa34c760d8a47");
+    /// assert_eq!(txb.to_hex(), hex);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn from_hex(hex: &str, address_parser: F) -> Result<TxBuilder<F>> {
+        TxBuilder::from_tx(&Transaction::from_hex(hex)?, address_parser)
+    }
+
+    /// Construct transaction builder from `Transaction` with previous outputs
+    /// # Arguments
+    /// * `tx` - transaction
+    /// * `prevouts` - value and `scriptPubKey` of previous output of each input
+    /// * `address_parser` - address parser closure (see `from_tx`)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Amount};
+    /// # use cash_tx_builder::types::transaction::Transaction;
+    /// # use cash_tx_builder::script::p2pkh;
+    /// let tx = Transaction::from_hex("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000")?;
+    /// let prev_script = p2pkh::script_pub_key(&[0x01; 20])?;
+    /// let txb = TxBuilder::from_tx_with_prevouts(&tx, &[(19_801_296, prev_script)], |_: &str| None)?;
+    /// assert_eq!(txb.fee()?, Amount::from_sat(2025));
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn from_tx_with_prevouts(tx: &Transaction, prevouts: &[(u64, Vec<u8>)], address_parser: F) -> Result<TxBuilder<F>> {
+        if prevouts.len() < tx.inputs.len() {
+            return Err(Error::MissingPrevOutput(prevouts.len()));
+        }
+        let mut txb = TxBuilder::from_tx(tx, address_parser)?;
+        for (index, (value, script)) in prevouts.iter().enumerate() {
+            txb.hydrate_prevout(index, *value, script)?;
+        }
+
+        Ok(txb)
+    }
+
+    /// Construct transaction builder from partially signed transaction
+    /// # Arguments
+    /// * `pst` - partially signed transaction
+    /// * `address_parser` - address parser closure
+    pub fn from_pst(pst: &Pst, address_parser: F) -> Result<TxBuilder<F>> {
+        let mut txb = TxBuilder::from_tx(&pst.tx, address_parser)?;
+        for (i, input) in pst.inputs.iter().enumerate() {
+            if let Some(o) = &input.prev_output {
+                txb.prev_outputs.insert(i, o.clone());
+            }
+            if let Some(script) = &input.redeem_script {
+                txb.redeem_scripts.insert(i, script.clone());
+            }
+        }
+
+        Ok(txb)
+    }
+
+    /// Construct coinbase transaction builder
+    ///
+    /// `scriptSig` is BIP34 `height` followed by push of `extra_nonce`.
+    /// # Arguments
+    /// * `height` - block height
+    /// * `extra_nonce` - extra nonce (and any pool data)
+    /// * `reward_outputs` - outputs of block reward
+    /// * `address_parser` - address parser (same as `TxBuilder::new`)
+    /// # Example
+    /// ```
+    /// # #[macro_use] extern crate hex_literal;
+    /// # use cash_tx_builder::{TxBuilder, Amount};
+    /// # use cash_tx_builder::types::transaction::output::Output;
+    /// let reward = Output::new(Amount::from_sat(625_000_000), &hex!("76a91432b57f34861bcbe33a701be9ac3a50288fbc0a3d88ac"));
+    /// let txb = TxBuilder::new_coinbase(800_000, &[0x01, 0x02], &[reward], |_: &str| None)?;
+    /// assert_eq!(txb.to_vec()[41..50], hex!("070300350c020102ff"));
+    /// assert!(txb.verify().is_empty());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn new_coinbase(height: u32, extra_nonce: &[u8], reward_outputs: &[Output], address_parser: F) -> Result<TxBuilder<F>> {
+        let script = ScriptBuilder::new()
+            .int(i64::from(height))
+            .data(extra_nonce)
+            .build()?;
+        if script.len() > verify::MAX_COINBASE_SCRIPT_SIG_SIZE {
+            return Err(Error::InvalidLengthData(script.len()));
+        }
+
+        let mut txb = TxBuilder::new(address_parser);
+        let mut input = Input::new(&[0; 32], 0xffff_ffff, None);
+        input.script = script;
+        txb.tx.inputs.push(input);
+        txb.tx.outputs.extend_from_slice(reward_outputs);
+
+        Ok(txb)
+    }
+
+    /// Construct child transaction which pays for its parent (CPFP)
+    ///
+    /// The child spends `output_index` of `parent` to `destination` (token data is kept),
+    /// and its fee is sized so that the package of both transactions pays `fee_rate`.
+    /// The child alone pays at least `fee_rate`.
+    /// # Arguments
+    /// * `parent` - parent transaction with all previous outputs
+    /// * `output_index` - output index of parent to spend
+    /// * `fee_rate` - target fee rate of package, e.g. `Network::min_fee_rate`
+    /// * `destination` - address to receive remaining value
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Amount, FeeRate};
+    /// let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+    /// let mut parent = TxBuilder::new(&parser);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// let prev_script = cash_tx_builder::script::p2pkh::script_pub_key(&[0x01; 20])?;
+    /// parent.add_input(prev_txid, 0, Some(100_000), Some(&prev_script), None)?;
+    /// parent.add_address_output(Amount::from_sat(99_900), &hex::encode([0x02; 20]))?;
+    ///
+    /// let child = TxBuilder::cpfp_from(&parent, 0, FeeRate::from_sat_per_byte(2), &hex::encode([0x03; 20]))?;
+    /// let package_size = parent.estimated_size_with_signatures()? + child.estimated_size_with_signatures()?;
+    /// assert_eq!(parent.fee()?.checked_add(child.fee()?), Some(Amount::from_sat(package_size as u64 * 2)));
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn cpfp_from(parent: &TxBuilder<F>, output_index: u32, fee_rate: FeeRate, destination: &str) -> Result<TxBuilder<F>>
+        where F: Clone {
+        let output = parent.tx.outputs.get(output_index as usize)
+            .ok_or(Error::InvalidIndex(output_index as usize))?
+            .clone();
+        let parent_size = parent.estimated_size_with_signatures()?;
+        let parent_fee = parent.fee()?;
+
+        let mut txb = TxBuilder::new(parent.address_parser.clone());
+        txb.fork_id = parent.fork_id;
+        txb.network = parent.network;
+        txb.max_script_sig_size = parent.max_script_sig_size;
+        txb.add_input(parent.txid(), output_index, None, None, None)?;
+        txb.set_prev_output(0, output.clone())?;
+        let script = txb.address_script(destination)?;
+        match output.token {
+            Some(token) => txb.add_token_output(Amount::ZERO, &script, token),
+            None => txb.add_output(Amount::ZERO, &script),
+        }
+
+        let child_size = txb.estimated_size_with_signatures()?;
+        let package_fee = fee_rate.fee(parent_size + child_size).to_sat();
+        let fee = std::cmp::max(package_fee.saturating_sub(parent_fee.to_sat()), fee_rate.fee(child_size).to_sat());
+        txb.tx.outputs[0].value = output.value.checked_sub(Amount::from_sat(fee))
+            .ok_or(Error::InsufficientFunds(output.value.to_sat(), fee))?;
+        txb.invalidate_outputs();
+
+        Ok(txb)
+    }
+
+    /// Construct builder with the same outputs and settings but no inputs
+    ///
+    /// Useful to reuse an output set (e.g. recurring payments) with new funding inputs.
+    /// Version, lock time, fork id, network and `scriptSig` size limit are kept.
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Amount};
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// txb.add_input(prev_txid, 0, Some(50_000), Some(&[0x51]), None)?;
+    /// txb.add_output(Amount::from_sat(49_000), &[0x51]);
+    ///
+    /// let mut next = txb.clone_outputs_only();
+    /// assert_eq!((next.input_count(), next.output_count()), (0, 1));
+    /// next.add_input(prev_txid, 1, Some(60_000), Some(&[0x51]), None)?;
+    /// assert_eq!(next.fee()?, Amount::from_sat(11_000));
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn clone_outputs_only(&self) -> TxBuilder<F>
+        where F: Clone {
+        let mut txb = TxBuilder::new(self.address_parser.clone());
+        txb.tx = self.tx.strip_inputs();
+        txb.fork_id = self.fork_id;
+        txb.network = self.network;
+        txb.max_script_sig_size = self.max_script_sig_size;
+        txb
+    }
+
+    /// Convert to partially signed transaction
+    /// # Returns
+    /// * partially signed transaction with previous outputs, `scriptSig`s are moved to final `scriptSig`
+    pub fn to_pst(&self) -> Pst {
+        let mut pst = Pst::new(&self.tx);
+        for (i, input) in pst.inputs.iter_mut().enumerate() {
+            input.prev_output = self.prev_outputs.get(&i).cloned();
+            input.redeem_script = self.redeem_scripts.get(&i).cloned();
+            let script = &self.tx.inputs[i].script;
+            if !script.is_empty() {
+                input.final_script_sig = Some(script.clone());
+            }
+        }
+
+        pst
+    }
+
     /// Set transaction version (default: 2)
     /// # Arguments
     /// `v` - version
@@ -127,7 +699,7 @@ impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> TxBuilder<F> {
     /// ```
     /// # #[macro_use] extern crate hex_literal;
     /// # use bch_addr::{AddressType, Converter};
-    /// # use cash_tx_builder::{TxBuilder, sig_hash};
+    /// # use cash_tx_builder::{TxBuilder, sig_hash, Amount};
     /// # use cash_tx_builder::script::{address_to_script, p2pkh};
     /// # let converter = Converter::new();
     /// # let parser = |address: &str| {
@@ -140,13 +712,13 @@ impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> TxBuilder<F> {
     /// #     }
     /// # };
     /// # let mut txb = TxBuilder::new(&parser);
-    /// # let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c";
+    /// # let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
     /// # let prev_index = 1;
     /// # let prev_script = address_to_script("qq6zfutryz9rkem05rkpwq60pu5sxg4z5c330k4w75", &parser)?;
     /// # let prev_value = 100_000;
     /// # txb.add_input(prev_txid, prev_index, Some(prev_value), Some(&prev_script), None)?;
-    /// # txb.add_address_output(11000, "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
-    /// # txb.add_address_output(88757, "qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70")?;
+    /// # txb.add_address_output(Amount::from_sat(11000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+    /// # txb.add_address_output(Amount::from_sat(88757), "qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70")?;
     /// # let script_sig = p2pkh::script_sig(
     /// #     &hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036"),
     /// #     &hex!("304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b3041")
@@ -162,6 +734,154 @@ impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> TxBuilder<F> {
         self.fork_id = id;
     }
 
+    /// Set network (default: `Network::Mainnet`)
+    ///
+    /// Addresses of other networks are rejected
+    /// and standardness is checked only if the network requires it.
+    /// # Arguments
+    /// * `network` - network
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Network, Amount, Error};
+    /// let mut txb = TxBuilder::new(|_: &str| Some((vec![0x01; 20], true)));
+    /// txb.set_network(Network::Chipnet);
+    /// txb.add_address_output(Amount::from_sat(1000), "bchtest:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq")?;
+    /// match txb.add_address_output(Amount::from_sat(1000), "bitcoincash:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq") {
+    ///     Err(Error::NetworkMismatch(Network::Chipnet)) => {},
+    ///     r => panic!("unexpected result: {:?}", r),
+    /// }
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn set_network(&mut self, network: Network) {
+        self.network = network;
+    }
+
+    /// Network
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    /// Set maximum size of `scriptSig` set by `set_script_sig` and signing methods
+    /// (default: `policy::MAX_STANDARD_SCRIPT_SIG_SIZE`)
+    /// # Arguments
+    /// * `max_size` - maximum size (`None` for no limit)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::TxBuilder;
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// txb.add_input(prev_txid, 0, None, None, None)?;
+    /// assert!(txb.set_script_sig(0, &[0x51; 2000]).is_err());
+    /// txb.set_max_script_sig_size(Some(cash_tx_builder::interpreter::MAX_SCRIPT_SIZE));
+    /// txb.set_script_sig(0, &[0x51; 2000])?;
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn set_max_script_sig_size(&mut self, max_size: Option<usize>) {
+        self.max_script_sig_size = max_size;
+    }
+
+    /// Build `scriptPubKey` of address after checking its network
+    fn address_script<A: ToAddressScript + ?Sized>(&self, address: &A) -> Result<Vec<u8>> {
+        if !address.is_for_network(self.network) {
+            return Err(Error::NetworkMismatch(self.network));
+        }
+
+        address_to_script(address, &self.address_parser)
+    }
+
+    /// Set lock time (default: `LockTime::Height(0)`)
+    ///
+    /// `lock_time` is enforced only if any input has non-final sequence.
+    /// # Arguments
+    /// * `lock_time` - block height or unix timestamp
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, LockTime};
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// txb.set_lock_time(LockTime::Height(600_000));
+    /// let v = txb.to_vec();
+    /// assert_eq!(v[v.len() - 4..], 600_000u32.to_le_bytes());
+    /// ```
+    pub fn set_lock_time(&mut self, lock_time: LockTime) {
+        self.tx.lock_time = lock_time.into();
+    }
+
+    /// Set lock time to `current_height` to discourage fee sniping, as Bitcoin Core does
+    ///
+    /// With probability 1/10 the height is moved back by up to 99 blocks,
+    /// so that transactions broadcast with delay don't stand out.
+    /// Final sequences are changed to `Sequence::MAX_NON_FINAL` to enforce the lock time,
+    /// so call this before signing.
+    /// # Arguments
+    /// * `current_height` - height of chain tip
+    /// * `rng` - random number generator
+    /// # Returns
+    /// * lock time set
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, LockTime, Sequence};
+    /// # use cash_tx_builder::rand_core::SeedableRng;
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// txb.add_input(prev_txid, 0, None, None, None)?;
+    /// let lock_time = txb.set_anti_fee_sniping_locktime(800_000, &mut rand_pcg::Pcg32::seed_from_u64(1));
+    /// match lock_time {
+    ///     LockTime::Height(height) => assert!(height > 800_000 - 100 && height <= 800_000),
+    ///     LockTime::Time(_) => unreachable!(),
+    /// }
+    /// assert_eq!(txb.to_vec()[42..46], u32::from(Sequence::MAX_NON_FINAL).to_le_bytes());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn set_anti_fee_sniping_locktime<R: RngCore + ?Sized>(&mut self, current_height: u32, rng: &mut R) -> LockTime {
+        let mut height = current_height.min(LOCKTIME_THRESHOLD - 1);
+        if rng.next_u32().is_multiple_of(10) {
+            height = height.saturating_sub(rng.next_u32() % 100);
+        }
+        let lock_time = LockTime::Height(height);
+        self.set_lock_time(lock_time);
+
+        for input in self.tx.inputs.iter_mut().filter(|i| Sequence::from(i.sequence_no).is_final()) {
+            input.sequence_no = Sequence::MAX_NON_FINAL.into();
+        }
+        self.invalidate_inputs();
+
+        lock_time
+    }
+
+    /// Set input sequence
+    /// # Arguments
+    /// * `index` - input index
+    /// * `sequence` - sequence
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Sequence};
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// txb.add_input(prev_txid, 0, None, None, None)?;
+    /// txb.set_input_sequence(0, Sequence::from_blocks(144))?;
+    /// assert_eq!(txb.to_vec()[42..46], 144u32.to_le_bytes());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn set_input_sequence(&mut self, index: usize, sequence: Sequence) -> Result<()> {
+        let input = self.tx.inputs.get_mut(index).ok_or(Error::InvalidIndex(index))?;
+        input.sequence_no = sequence.into();
+        self.invalidate_inputs();
+        Ok(())
+    }
+
+    /// Set previous transaction hash of input, e.g. after its parent in a chain is signed
+    ///
+    /// Output index, previous output and `scriptSig` are kept.
+    /// # Arguments
+    /// * `index` - input index
+    /// * `txid` - previous transaction hash
+    pub fn set_input_txid(&mut self, index: usize, txid: Txid) -> Result<()> {
+        let input = self.tx.inputs.get_mut(index).ok_or(Error::InvalidIndex(index))?;
+        input.outpoint.txid = txid;
+        self.invalidate_inputs();
+        Ok(())
+    }
+
     /// Add input
     /// # Arguments
     /// * `txid` - previous transaction hash
@@ -169,45 +889,210 @@ impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> TxBuilder<F> {
     /// * `value` - (option) previous value
     /// * `script` - (option) previous `scriptPubKey`
     /// * `sequence_no`- (option) sequence number
-    pub fn add_input(&mut self, txid: &str, index: u32, value: Option<u64>, script: Option<&[u8]>, sequence_no: Option<u32>) -> Result<()> {
-        let txid = u256::from_str(txid)?;
-        self.tx.inputs.push(Input::new(&txid.into(), index, sequence_no));
-        if value.is_some() && script.is_some() {
-            self.prev_outputs.insert(
-                self.tx.inputs.len() - 1,
-                Output::new(value.unwrap(), script.unwrap())
-            );
+    ///
+    /// Null outpoint is rejected, use `TxBuilder::new_coinbase` instead.
+    pub fn add_input(&mut self, txid: Txid, index: u32, value: Option<u64>, script: Option<&[u8]>, sequence_no: Option<u32>) -> Result<()> {
+        let mut input = InputBuilder::new(OutPoint { txid, n: index });
+        if let Some(value) = value {
+            input.value(Amount::from_sat(value));
+        }
+        if let Some(script) = script {
+            input.script(script);
+        }
+        if let Some(sequence_no) = sequence_no {
+            input.sequence(sequence_no);
+        }
+
+        self.push_input(&input).map(|_| ())
+    }
+
+    /// Add input built by `InputBuilder`
+    ///
+    /// Previous output is recorded if both its value and `scriptPubKey` are set.
+    /// Null outpoint is rejected, use `TxBuilder::new_coinbase` instead.
+    /// # Arguments
+    /// * `input` - input
+    /// # Returns
+    /// * index of added input
+    pub fn push_input(&mut self, input: &InputBuilder) -> Result<usize> {
+        let index = self.tx.inputs.len();
+        if input.outpoint.is_null() {
+            return Err(Error::NullOutPoint(index));
+        }
+        self.tx.inputs.push(Input::new(&input.outpoint.txid.into(), input.outpoint.n, input.sequence_no));
+        self.invalidate_inputs();
+        if let Some(output) = input.prev_output() {
+            self.prev_outputs.insert(index, output);
         }
+        if let Some(script) = &input.redeem_script {
+            self.redeem_scripts.insert(index, script.clone());
+        }
+
+        Ok(index)
+    }
 
+    /// Remove input
+    /// # Arguments
+    /// * `index` - input index
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::TxBuilder;
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// txb.add_input(prev_txid, 0, None, None, None)?;
+    /// txb.add_input(prev_txid, 1, None, None, None)?;
+    /// txb.remove_input(0)?;
+    /// assert_eq!(txb.to_vec()[4], 1);
+    /// assert_eq!(txb.to_vec()[37], 1);
+    /// assert!(txb.remove_input(1).is_err());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn remove_input(&mut self, index: usize) -> Result<()> {
+        if index >= self.tx.inputs.len() {
+            return Err(Error::InvalidIndex(index));
+        }
+        self.tx.inputs.remove(index);
+        self.invalidate_inputs();
+        remove_index(&mut self.prev_outputs, index);
+        remove_index(&mut self.redeem_scripts, index);
         Ok(())
     }
 
     /// Set `scriptSig`
     /// # Arguments
     /// * `index` - previous txout-index
-    /// * `script` - `scriptSig`
+    /// * `script` - `scriptSig` (`Error::ScriptTooLarge` if it exceeds maximum size)
     pub fn set_script_sig(&mut self, index: usize, script: &[u8]) -> Result<()> {
-        let input = self.tx.inputs.get_mut(index).ok_or_else(|| Error::InvalidIndex(index))?;
+        if let Some(max_size) = self.max_script_sig_size.filter(|&max_size| script.len() > max_size) {
+            return Err(Error::ScriptTooLarge(script.len(), max_size));
+        }
+        let input = self.tx.inputs.get_mut(index).ok_or(Error::InvalidIndex(index))?;
         input.script = script.to_vec();
         Ok(())
     }
 
+    /// Set previous output spent by input
+    /// # Arguments
+    /// * `index` - input index
+    /// * `output` - previous output (with token data if any)
+    pub fn set_prev_output(&mut self, index: usize, output: Output) -> Result<()> {
+        if index >= self.tx.inputs.len() {
+            return Err(Error::InvalidIndex(index));
+        }
+        self.prev_outputs.insert(index, output);
+        self.sighash_cache.get_mut().hash_utxos = None;
+        Ok(())
+    }
+
+    /// Set previous output spent by input from its value and `scriptPubKey`
+    ///
+    /// Required to sign or bump fee of transaction constructed by `from_tx`.
+    /// # Arguments
+    /// * `index` - input index
+    /// * `value` - value of previous output
+    /// * `script` - `scriptPubKey` of previous output
+    pub fn hydrate_prevout(&mut self, index: usize, value: u64, script: &[u8]) -> Result<()> {
+        let value = Amount::from_sat(value);
+        if !value.is_valid() {
+            return Err(Error::InvalidAmount(value.to_sat().to_string()));
+        }
+
+        self.set_prev_output(index, Output::new(value, script))
+    }
+
+    /// Set `redeem script` of P2SH input
+    /// # Arguments
+    /// * `index` - input index
+    /// * `script` - `redeem script`
+    pub fn set_redeem_script(&mut self, index: usize, script: &[u8]) -> Result<()> {
+        if index >= self.tx.inputs.len() {
+            return Err(Error::InvalidIndex(index));
+        }
+        self.redeem_scripts.insert(index, script.to_vec());
+        Ok(())
+    }
+
     /// Add output by bitcoin address
     /// # Arguments
-    /// * `value` - satoshi
-    /// * `address` - bitcoin address
-    pub fn add_address_output(&mut self, value: u64, address: &str) -> Result<()> {
-        let script = address_to_script(address, &self.address_parser)?;
+    /// * `value` - value
+    /// * `address` - bitcoin address (string or `address::Address`)
+    pub fn add_address_output<A: ToAddressScript + ?Sized>(&mut self, value: Amount, address: &A) -> Result<()> {
+        let script = self.address_script(address)?;
         self.add_output(value, &script);
         Ok(())
     }
 
-    /// Add output by null data
+    /// Add outputs from rows of address and value, e.g. withdrawal requests
+    ///
+    /// No output is added if any row fails; errors of all failed rows are reported together.
     /// # Arguments
-    /// * `data` - extra data
+    /// * `rows` - address and value (satoshi)
+    /// * `merge_duplicates` - sum values of rows paying to the same `scriptPubKey` into one output
+    ///   (at the position of its first row)
+    /// # Returns
+    /// * `Error::BatchError` with row index and error of each failed row
     /// # Example
     /// ```
-    /// # use bch_addr::{AddressType, Converter};
+    /// # use cash_tx_builder::{TxBuilder, Amount, Error};
+    /// let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+    /// let mut txb = TxBuilder::new(&parser);
+    /// let rows = vec![
+    ///     (hex::encode([0x01; 20]), 1000),
+    ///     (hex::encode([0x02; 20]), 2000),
+    ///     (hex::encode([0x01; 20]), 3000),
+    /// ];
+    /// txb.add_outputs_from(rows, true)?;
+    /// assert_eq!(txb.output_value(), Some(Amount::from_sat(6000)));
+    /// assert_eq!(txb.output_count(), 2);
+    ///
+    /// match txb.add_outputs_from(vec![(hex::encode([0x03; 20]), 1000), ("zz".to_string(), 1000)], false) {
+    ///     Err(Error::BatchError(rows)) => assert_eq!(rows[0].0, 1),
+    ///     r => panic!("unexpected result: {:?}", r),
+    /// }
+    /// assert_eq!(txb.output_count(), 2);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn add_outputs_from<I>(&mut self, rows: I, merge_duplicates: bool) -> Result<()>
+        where I: IntoIterator<Item = (String, u64)> {
+        let mut outputs: Vec<(Vec<u8>, Amount)> = vec![];
+        let mut errors = vec![];
+        for (i, (address, value)) in rows.into_iter().enumerate() {
+            let value = Amount::from_sat(value);
+            let script = self.address_script(address.as_str()).and_then(|script| {
+                if value.is_valid() { Ok(script) } else { Err(Error::InvalidAmount(value.to_sat().to_string())) }
+            });
+            let script = match script {
+                Ok(script) => script,
+                Err(e) => {
+                    errors.push((i, e));
+                    continue;
+                },
+            };
+
+            match outputs.iter_mut().find(|(s, _)| merge_duplicates && *s == script) {
+                Some((_, sum)) => match sum.checked_add(value) {
+                    Some(v) => *sum = v,
+                    None => errors.push((i, Error::InvalidAmount(format!("sum of values exceeds {}", Amount::MAX_MONEY)))),
+                },
+                None => outputs.push((script, value)),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(Error::BatchError(errors));
+        }
+
+        for (script, value) in outputs {
+            self.add_output(value, &script);
+        }
+        Ok(())
+    }
+
+    /// Add output by null data
+    /// # Arguments
+    /// * `data` - extra data
+    /// # Example
+    /// ```
+    /// # use bch_addr::{AddressType, Converter};
     /// # use cash_tx_builder::TxBuilder;
     /// # let converter = Converter::new();
     /// # let parser = |address: &str| {
@@ -220,24 +1105,95 @@ impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> TxBuilder<F> {
     /// #     }
     /// # };
     /// # let mut txb = TxBuilder::new(&parser);
-    /// txb.add_null_data_output(b"hoge");
+    /// txb.add_null_data_output(b"hoge")?;
     /// assert_eq!(&txb.to_vec()[17..21], b"hoge");
+    /// assert!(txb.add_null_data_output(&[0; 221]).is_err());
+    /// # Ok::<(), cash_tx_builder::Error>(())
     /// ```
     pub fn add_null_data_output(&mut self, data: &[u8]) -> Result<()> {
+        if data.len() > policy::MAX_NULL_DATA_SIZE {
+            return Err(Error::NonStandardDataSize(data.len()));
+        }
+
+        self.add_null_data_output_unchecked(data)
+    }
+
+    /// Add output by null data without standard size limit
+    ///
+    /// Outputs with more than `policy::MAX_NULL_DATA_SIZE` bytes are not relayed by default nodes.
+    /// # Arguments
+    /// * `data` - extra data
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::TxBuilder;
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// txb.add_null_data_output_unchecked(&[0; 221])?;
+    /// assert!(!txb.check_standard().is_empty());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn add_null_data_output_unchecked(&mut self, data: &[u8]) -> Result<()> {
         let script = null_data_script(data)?;
-        self.add_output(0, &script);
+        self.add_output(Amount::ZERO, &script);
+        Ok(())
+    }
+
+    /// Add output by null data with multiple pushes
+    /// # Arguments
+    /// * `data` - extra data, each pushed separately
+    /// # Example
+    /// ```
+    /// # use bch_addr::{AddressType, Converter};
+    /// # use cash_tx_builder::TxBuilder;
+    /// # let converter = Converter::new();
+    /// # let parser = |address: &str| {
+    /// #     let parsed = converter.parse(address).ok();
+    /// #     match parsed {
+    /// #         Some((_, _, address_type, hash)) => {
+    /// #             Some((hash, address_type == AddressType::P2PKH))
+    /// #         }
+    /// #         None => None
+    /// #     }
+    /// # };
+    /// # let mut txb = TxBuilder::new(&parser);
+    /// txb.add_null_data_outputs(&[b"hoge", b"fuga"])?;
+    /// assert_eq!(&txb.to_vec()[17..21], b"hoge");
+    /// assert_eq!(&txb.to_vec()[22..26], b"fuga");
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn add_null_data_outputs(&mut self, data: &[&[u8]]) -> Result<()> {
+        let script = null_data_script_multi(data)?;
+        self.add_output(Amount::ZERO, &script);
+        Ok(())
+    }
+
+    /// Add SLP `SEND` output
+    /// # Arguments
+    /// * `token_id` - token id
+    /// * `amounts` - token amounts sent to output 1, 2, ...
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::TxBuilder;
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// let token_id = "959a6818cba5af8aba391d3f7649f5f6a5ceb6cdcd2c2a3dcb5d2fbfc4b08e98";
+    /// txb.add_slp_send_output(token_id, &[100, 200])?;
+    /// assert_eq!(&txb.to_vec()[17..21], b"SLP\x00");
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn add_slp_send_output(&mut self, token_id: &str, amounts: &[u64]) -> Result<()> {
+        let script = slp::send_script(token_id, amounts)?;
+        self.add_output(Amount::ZERO, &script);
         Ok(())
     }
 
     /// Add output by null data
     /// # Arguments
-    /// * `value` - satoshi
+    /// * `value` - value
     /// * `script` - `scriptPubKey`
     /// # Example
     /// ```
     /// # #[macro_use] extern crate hex_literal;
     /// # use bch_addr::{AddressType, Converter};
-    /// # use cash_tx_builder::TxBuilder;
+    /// # use cash_tx_builder::{TxBuilder, Amount};
     /// # let converter = Converter::new();
     /// # let parser = |address: &str| {
     /// #     let parsed = converter.parse(address).ok();
@@ -250,139 +1206,2332 @@ impl<F: Fn(&str) -> Option<(Vec<u8>, bool)>> TxBuilder<F> {
     /// # };
     /// # let mut txb = TxBuilder::new(&parser);
     /// let script = hex!("76a91432b57f34861bcbe33a701be9ac3a50288fbc0a3d88ac");
-    /// txb.add_output(1000, &script);
+    /// txb.add_output(Amount::from_sat(1000), &script);
     /// assert_eq!(&txb.to_vec()[15..40], script);
     /// ```
-    pub fn add_output(&mut self, value: u64, script: &[u8]) {
+    pub fn add_output(&mut self, value: Amount, script: &[u8]) {
         self.tx.outputs.push(Output::new(value, script));
+        self.invalidate_outputs();
     }
 
-    /// Convert to `Vec<u8>`
-    /// # Returns
-    /// * serialized transaction
-    pub fn to_vec(&self) -> Vec<u8> {
-        Vec::from(&self.tx)
+    /// Add output with CashTokens
+    /// # Arguments
+    /// * `value` - value
+    /// * `script` - `scriptPubKey`
+    /// * `token` - token data
+    /// # Example
+    /// ```
+    /// # #[macro_use] extern crate hex_literal;
+    /// # use std::str::FromStr;
+    /// # use cash_tx_builder::{TxBuilder, u256, Amount};
+    /// # use cash_tx_builder::types::transaction::TokenData;
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// let category = u256::from_str("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb")?;
+    /// let script = hex!("76a91432b57f34861bcbe33a701be9ac3a50288fbc0a3d88ac");
+    /// txb.add_token_output(Amount::from_sat(1000), &script, TokenData::new(category, 1, None)?);
+    /// assert_eq!(txb.to_vec()[14], 0x3c);
+    /// assert_eq!(txb.to_vec()[15], 0xef);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn add_token_output(&mut self, value: Amount, script: &[u8], token: TokenData) {
+        self.tx.outputs.push(Output::with_token(value, script, token));
+        self.invalidate_outputs();
     }
 
-    /// Get digest according to bip143  
-    /// [spec](https://github.com/Bitcoin-ABC/bitcoin-abc/blob/master/doc/abc/replay-protected-sighash.md)
+    /// Add output built by `OutputBuilder`
     /// # Arguments
-    /// * `hash_type` - sighash type
-    /// * `index` - input index
-    /// * `prev_value` - (option) previous value
-    /// * `prev_script` - (option) previous script
-    pub fn witness_v0_hash(&self, hash_type: u32, index: u32, prev_value: Option<u64>, prev_script: Option<&[u8]>) -> Result<Vec<u8>> {
-        let hash_prev_outs = if !hash_type.is_set(sig_hash::ANYONECANPAY) {
-            let hasher = self.tx.inputs.iter().fold(Sha256::new(), |hasher, i| {
-                hasher.chain(i.outpoint.txid).chain(i.outpoint.n.to_le_bytes())
-            });
-            hash::hash256(hasher)
-        } else {
-            vec![0; 32]
+    /// * `output` - output
+    /// # Returns
+    /// * index of added output
+    pub fn push_output(&mut self, output: &OutputBuilder) -> Result<usize> {
+        let script = match &output.destination {
+            Destination::Script(script) => script.clone(),
+            Destination::Address(address) => self.address_script(address)?,
         };
+        self.tx.outputs.push(Output {
+            value: output.value,
+            script,
+            token: output.token.clone(),
+        });
+        self.invalidate_outputs();
 
-        let hash_sequence = if !hash_type.is_set(sig_hash::ANYONECANPAY) && 
-                               (hash_type & 0x1f) != sig_hash::SINGLE &&
-                               (hash_type & 0x1f) != sig_hash::NONE {
-            let hasher = self.tx.inputs.iter().fold(Sha256::new(), |hasher, i| {
-                hasher.chain(i.sequence_no.to_le_bytes())
-            });
-            hash::hash256(hasher)
-        } else {
-            vec![0; 32]
-        };
+        Ok(self.tx.outputs.len() - 1)
+    }
 
-        let hash_outputs = if (hash_type & 0x1f) != sig_hash::SINGLE &&
-                              (hash_type & 0x1f) != sig_hash::NONE {
-            let hasher = self.tx.outputs.iter().fold(Sha256::new(), |hasher, o| {
-                hasher.chain(o.to_vec())
-            });
-            hash::hash256(hasher)
-        } else if (hash_type & 0x1f) == sig_hash::SINGLE &&
-                  index < self.tx.outputs.len() as u32 {
-            let hasher = Sha256::new().chain(self.tx.outputs[index as usize].to_vec());
-            hash::hash256(hasher)
-        } else {
-            vec![0; 32]
-        };
+    /// Remove output
+    /// # Arguments
+    /// * `index` - output index
+    pub fn remove_output(&mut self, index: usize) -> Result<()> {
+        if index >= self.tx.outputs.len() {
+            return Err(Error::InvalidIndex(index));
+        }
+        self.tx.outputs.remove(index);
+        self.invalidate_outputs();
+        Ok(())
+    }
 
-        let (prev_value, prev_script) = if prev_value.is_some() && prev_script.is_some() {
-            (prev_value.unwrap(), prev_script.unwrap())
-        } else if let Some(o) = self.prev_outputs.get(&(index as usize)) {
-            (o.value, &o.script[..])
-        } else {
-            return Err(Error::InvalidIndex(index as usize));
+    /// Replace output
+    /// # Arguments
+    /// * `index` - output index
+    /// * `value` - value
+    /// * `script` - `scriptPubKey`
+    pub fn replace_output(&mut self, index: usize, value: Amount, script: &[u8]) -> Result<()> {
+        let output = self.tx.outputs.get_mut(index).ok_or(Error::InvalidIndex(index))?;
+        *output = Output::new(value, script);
+        self.invalidate_outputs();
+        Ok(())
+    }
+
+    /// Move output to another position
+    /// # Arguments
+    /// * `from` - current output index
+    /// * `to` - new output index
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Amount};
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// txb.add_output(Amount::from_sat(1), &[0x51]);
+    /// txb.add_output(Amount::from_sat(2), &[0x52]);
+    /// txb.add_output(Amount::from_sat(3), &[0x53]);
+    /// txb.move_output(2, 0)?;
+    /// assert_eq!(txb.to_vec()[6], 3);
+    /// assert!(txb.move_output(0, 3).is_err());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn move_output(&mut self, from: usize, to: usize) -> Result<()> {
+        if from >= self.tx.outputs.len() {
+            return Err(Error::InvalidIndex(from));
+        }
+        if to >= self.tx.outputs.len() {
+            return Err(Error::InvalidIndex(to));
+        }
+        let output = self.tx.outputs.remove(from);
+        self.tx.outputs.insert(to, output);
+        self.invalidate_outputs();
+        Ok(())
+    }
+
+    /// Sort inputs and outputs by BIP69
+    ///
+    /// Inputs are sorted by previous txid and txout-index,
+    /// outputs are sorted by value and `scriptPubKey`.
+    /// [spec](https://github.com/bitcoin/bips/blob/master/bip-0069.mediawiki)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Amount};
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// txb.add_input("0e53ec5dfb2cb8a71fec32dc9a634a35b7e24799295ddd5278217822e0b31f57".parse()?, 1, None, None, None)?;
+    /// txb.add_input("0e53ec5dfb2cb8a71fec32dc9a634a35b7e24799295ddd5278217822e0b31f57".parse()?, 0, None, None, None)?;
+    /// txb.add_output(Amount::from_sat(2000), &[0x51]);
+    /// txb.add_output(Amount::from_sat(1000), &[0x52]);
+    /// txb.sort_bip69();
+    /// assert_eq!(txb.to_vec()[37], 0);
+    /// assert_eq!(txb.to_vec()[97], 0x52);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn sort_bip69(&mut self) {
+        let mut order = (0..self.tx.inputs.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&self.tx.inputs[a].outpoint, &self.tx.inputs[b].outpoint);
+            a.txid.cmp(&b.txid).then(a.n.cmp(&b.n))
+        });
+        self.reorder_inputs(order);
+
+        self.tx.outputs.sort_by(|a, b| a.value.cmp(&b.value).then_with(|| a.script.cmp(&b.script)));
+        self.invalidate_outputs();
+    }
+
+    /// Shuffle inputs (previous outputs and `redeem script`s follow their inputs)
+    /// # Arguments
+    /// * `rng` - random number generator (seedable one gives reproducible order)
+    pub fn shuffle_inputs<R: RngCore + ?Sized>(&mut self, rng: &mut R) {
+        let order = shuffled_indices(self.tx.inputs.len(), rng);
+        self.reorder_inputs(order);
+    }
+
+    /// Shuffle outputs, e.g. to hide position of change output
+    /// # Arguments
+    /// * `rng` - random number generator (seedable one gives reproducible order)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Amount};
+    /// # use cash_tx_builder::rand_core::SeedableRng;
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// for i in 1..=10 {
+    ///     txb.add_output(Amount::from_sat(1000 * i), &[0x51]);
+    /// }
+    /// let mut other = TxBuilder::from_hex(&txb.to_hex(), |_: &str| None)?;
+    /// txb.shuffle_outputs(&mut rand_pcg::Pcg32::seed_from_u64(1));
+    /// other.shuffle_outputs(&mut rand_pcg::Pcg32::seed_from_u64(1));
+    /// assert_eq!(txb.txid(), other.txid());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn shuffle_outputs<R: RngCore + ?Sized>(&mut self, rng: &mut R) {
+        let order = shuffled_indices(self.tx.outputs.len(), rng);
+        self.tx.outputs = order.into_iter().map(|i| self.tx.outputs[i].clone()).collect();
+        self.invalidate_outputs();
+    }
+
+    /// Reorder inputs with their previous outputs and `redeem script`s
+    /// # Arguments
+    /// * `order` - old index of each new position
+    fn reorder_inputs(&mut self, order: Vec<usize>) {
+        let inputs = order.iter().map(|&i| self.tx.inputs[i].clone()).collect();
+        let mut prev_outputs = HashMap::new();
+        let mut redeem_scripts = HashMap::new();
+        for (new, old) in order.into_iter().enumerate() {
+            if let Some(o) = self.prev_outputs.remove(&old) {
+                prev_outputs.insert(new, o);
+            }
+            if let Some(script) = self.redeem_scripts.remove(&old) {
+                redeem_scripts.insert(new, script);
+            }
+        }
+        self.tx.inputs = inputs;
+        self.prev_outputs = prev_outputs;
+        self.redeem_scripts = redeem_scripts;
+        self.invalidate_inputs();
+    }
+
+    /// Add change output
+    ///
+    /// Dust change (at `policy::DUST_RELAY_FEE_RATE`) is left to fee without adding output.
+    /// # Arguments
+    /// * `address` - bitcoin address to receive change (string or `address::Address`)
+    /// * `fee_rate` - fee rate, e.g. `Network::min_fee_rate`
+    /// # Returns
+    /// * change value (`0` if change output is not added)
+    /// # Example
+    /// ```
+    /// # use bch_addr::{AddressType, Converter};
+    /// # use cash_tx_builder::{TxBuilder, Amount, FeeRate};
+    /// # use cash_tx_builder::script::address_to_script;
+    /// # let converter = Converter::new();
+    /// # let parser = |address: &str| {
+    /// #     let parsed = converter.parse(address).ok();
+    /// #     match parsed {
+    /// #         Some((_, _, address_type, hash)) => {
+    /// #             Some((hash, address_type == AddressType::P2PKH))
+    /// #         }
+    /// #         None => None
+    /// #     }
+    /// # };
+    /// # let mut txb = TxBuilder::new(&parser);
+    /// # let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// # let prev_script = address_to_script("qq6zfutryz9rkem05rkpwq60pu5sxg4z5c330k4w75", &parser)?;
+    /// txb.add_input(prev_txid, 1, Some(100_000), Some(&prev_script), None)?;
+    /// txb.add_address_output(Amount::from_sat(11000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+    /// let change = txb.add_change_output("qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70", FeeRate::MIN_RELAY)?;
+    /// assert_eq!(change, Amount::from_sat(88773));
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn add_change_output<A: ToAddressScript + ?Sized>(&mut self, address: &A, fee_rate: FeeRate) -> Result<Amount> {
+        let script = self.address_script(address)?;
+        let input_value = self.prev_value_sum()?;
+        let output_value = self.output_value_sum()?;
+
+        self.add_output(Amount::ZERO, &script);
+        let size = match self.estimated_size_with_signatures() {
+            Ok(size) => size,
+            Err(e) => {
+                self.tx.outputs.pop();
+                self.invalidate_outputs();
+                return Err(e);
+            },
+        };
+        let required = fee_rate.fee(size).checked_add(output_value);
+        let change = match required.and_then(|required| input_value.checked_sub(required)) {
+            Some(change) => change,
+            None => {
+                self.tx.outputs.pop();
+                self.invalidate_outputs();
+                let required = output_value.to_sat().saturating_add(fee_rate.fee(size).to_sat());
+                return Err(Error::InsufficientFunds(input_value.to_sat(), required));
+            },
         };
+        if let Some(o) = self.tx.outputs.last_mut() {
+            o.value = change;
+        }
+        let is_dust = self.tx.outputs.last().is_some_and(|o| policy::is_dust(o, policy::DUST_RELAY_FEE_RATE));
+        if is_dust {
+            self.tx.outputs.pop();
+        }
+        self.invalidate_outputs();
 
-        let input = self.tx.inputs.get(index as usize).ok_or_else(|| Error::InvalidIndex(index as usize))?;
+        Ok(if is_dust { Amount::ZERO } else { change })
+    }
 
-        let hasher = Sha256::new()
-            .chain(self.tx.version.to_le_bytes())
-            .chain(hash_prev_outs)
-            .chain(hash_sequence)
-            .chain(input.outpoint.txid)
-            .chain(input.outpoint.n.to_le_bytes())
-            .chain(encode(&[Script::Data(&prev_script)])?)
-            .chain(prev_value.to_le_bytes())
-            .chain(input.sequence_no.to_le_bytes())
-            .chain(hash_outputs)
-            .chain(self.tx.lock_time.to_le_bytes())
-            .chain(((self.fork_id << 8) | hash_type).to_le_bytes());
+    /// Sweep unspent outputs of the same `scriptPubKey` into one output
+    ///
+    /// Unspent outputs are added in the given order while the signed transaction
+    /// stays within `policy::MAX_STANDARD_TX_SIZE`, then the rest after fee is sent to `destination`.
+    /// Nothing is added on error.
+    /// # Arguments
+    /// * `utxos` - unspent outputs to sweep
+    /// * `prev_script` - `scriptPubKey` of `utxos`
+    /// * `destination` - address to receive swept value (string or `address::Address`)
+    /// * `fee_rate` - fee rate
+    /// # Returns
+    /// * unspent outputs left for a follow-up transaction
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Amount, FeeRate};
+    /// # use cash_tx_builder::provider::Utxo;
+    /// # use cash_tx_builder::script::p2pkh;
+    /// # let mut txb = TxBuilder::new(|_: &str| Some((vec![0x02; 20], true)));
+    /// let prev_script = p2pkh::script_pub_key(&[0x01; 20])?;
+    /// let utxos = (0..1000).map(|i| Utxo { txid: [0x01; 32].into(), vout: i, value: 1000 }).collect::<Vec<_>>();
+    /// let rest = txb.consolidate(&utxos, &prev_script, "destination", FeeRate::MIN_RELAY)?;
+    /// assert_eq!(txb.input_count() + rest.len(), 1000);
+    /// assert!(txb.estimated_size_with_signatures()? <= 100_000);
+    /// assert_eq!(txb.output_count(), 1);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn consolidate<A>(&mut self, utxos: &[Utxo], prev_script: &[u8], destination: &A, fee_rate: FeeRate) -> Result<Vec<Utxo>>
+        where A: ToAddressScript + ?Sized {
+        let script = self.address_script(destination)?;
+        let outputs = self.tx.outputs.len() as u64;
+        let output_size = Output::new(Amount::ZERO, &script).to_vec().len()
+            + VarInt::from(outputs + 1).len() - VarInt::from(outputs).len();
 
-        Ok(hash::hash256(hasher))
+        let input_count = self.tx.inputs.len();
+        let result = self.add_inputs_within_size(utxos, prev_script, output_size)
+            .and_then(|added| self.add_change_output(destination, fee_rate).map(|_| added));
+        match result {
+            Ok(added) => Ok(utxos[added..].to_vec()),
+            Err(e) => {
+                while self.tx.inputs.len() > input_count {
+                    self.remove_input(self.tx.inputs.len() - 1)?;
+                }
+                Err(e)
+            },
+        }
     }
 
-    /// Get txid
+    /// Add inputs while signed transaction with an output of `output_size` bytes stays standard
     /// # Returns
-    /// * txid
-    pub fn txid(&self) -> String {
-        let hash = hash::hash256(Sha256::new().chain(self.to_vec()));
-        u256::from(&hash[..]).into()
+    /// * number of added inputs
+    fn add_inputs_within_size(&mut self, utxos: &[Utxo], prev_script: &[u8], output_size: usize) -> Result<usize> {
+        let mut added = 0;
+        for utxo in utxos {
+            self.add_input(utxo.txid, utxo.vout, Some(utxo.value), Some(prev_script), None)?;
+            if self.estimated_size_with_signatures()? + output_size > policy::MAX_STANDARD_TX_SIZE {
+                self.remove_input(self.tx.inputs.len() - 1)?;
+                break;
+            }
+            added += 1;
+        }
+
+        Ok(added)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use super::super::script::p2pkh;
-    use bch_addr::{AddressType, Converter};
+    /// Split into a chain of transactions each within `max_bytes` after signing
+    ///
+    /// Inputs are moved in order into intermediate transactions, each paying everything
+    /// after its own fee to one output of `change`, which is spent by input 0 of the next one.
+    /// The last transaction has the remaining inputs and all outputs, and pays what is left of the original fee
+    /// (at least `fee_rate`), so split before adding change and add it to the last one.
+    /// Inputs with tokens are always kept in the last transaction.
+    /// A transaction already within `max_bytes` is returned as is.
+    ///
+    /// Txids of unsigned parents are used, so sign in order and update each child
+    /// with `set_input_txid(0, parent.txid())` before signing it.
+    /// # Arguments
+    /// * `max_bytes` - maximum estimated size of each transaction, e.g. `policy::MAX_STANDARD_TX_SIZE`
+    /// * `change` - address receiving intermediate outputs (string or `address::Address`)
+    /// * `fee_rate` - fee rate of intermediate transactions, e.g. `Network::min_fee_rate`
+    /// # Returns
+    /// * transactions in spending order (`Error::TxTooLarge` if inputs can not be split further)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Amount, FeeRate};
+    /// # use cash_tx_builder::script::p2pkh;
+    /// let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+    /// let mut txb = TxBuilder::new(&parser);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// let prev_script = p2pkh::script_pub_key(&[0x01; 20])?;
+    /// for i in 0..20 {
+    ///     txb.add_input(prev_txid, i, Some(10_000), Some(&prev_script), None)?;
+    /// }
+    ///
+    /// let mut txs = txb.split_to_max_size(1000, &hex::encode([0x03; 20]), FeeRate::MIN_RELAY)?;
+    /// assert_eq!(txs.len(), 4);
+    /// txs[3].add_change_output(&hex::encode([0x02; 20]), FeeRate::MIN_RELAY)?;
+    /// assert!(txs.iter().all(|tx| tx.estimated_size_with_signatures().unwrap() <= 1000));
+    ///
+    /// let parent = txs[0].txid();
+    /// txs[1].set_input_txid(0, parent)?;
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn split_to_max_size<A>(&self, max_bytes: usize, change: &A, fee_rate: FeeRate) -> Result<Vec<TxBuilder<F>>>
+        where F: Clone,
+              A: ToAddressScript + ?Sized {
+        let script = self.address_script(change)?;
+        let input_value = self.prev_value_sum()?;
+        let (plain, tokens): (Vec<usize>, Vec<usize>) = (0..self.tx.inputs.len())
+            .partition(|i| self.prev_outputs[i].token.is_none());
 
-    #[test]
-    fn get_digest() -> Result<()> {
-        let converter = Converter::new();
-        let parser = |address: &str| {
-            let parsed = converter.parse(address).ok();
-            match parsed {
-                Some((_, _, address_type, hash)) => {
-                    Some((hash, address_type == AddressType::P2PKH))
+        let mut txs = Vec::new();
+        let mut carry: Option<(Txid, Output)> = None;
+        let mut next = 0;
+        loop {
+            let mut last = self.clone_outputs_only();
+            last.push_carried_input(&carry)?;
+            for &i in tokens.iter().chain(&plain[next..]) {
+                last.push_input_of(self, i);
+            }
+            let size = last.estimated_size_with_signatures()?;
+            if size <= max_bytes {
+                let required = fee_rate.fee(size);
+                if last.fee()? < required {
+                    let output_value = self.output_value_sum()?.to_sat();
+                    return Err(Error::InsufficientFunds(input_value.to_sat(), output_value.saturating_add(required.to_sat())));
                 }
-                None => None
+                txs.push(last);
+                return Ok(txs);
             }
-        };
 
-        let mut txb = TxBuilder::new(&parser);
-        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c";
-        let prev_index = 1;
-        let prev_script = address_to_script("qq6zfutryz9rkem05rkpwq60pu5sxg4z5c330k4w75", &parser)?;
-        let prev_value = 100_000;
+            let mut txb = self.clone_outputs_only();
+            txb.tx.outputs.clear();
+            txb.push_carried_input(&carry)?;
+            txb.add_output(Amount::ZERO, &script);
+            let start = next;
+            while next < plain.len() {
+                txb.push_input_of(self, plain[next]);
+                if txb.estimated_size_with_signatures()? > max_bytes {
+                    txb.remove_input(txb.tx.inputs.len() - 1)?;
+                    break;
+                }
+                next += 1;
+            }
+            if next == start {
+                return Err(Error::TxTooLarge(size, max_bytes));
+            }
 
-        txb.add_input(prev_txid, prev_index, Some(prev_value), Some(&prev_script), None)?;
-        txb.add_address_output(11000, "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
-        txb.add_address_output(88757, "qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70")?;
+            let value = txb.prev_value_sum()?;
+            let fee = fee_rate.fee(txb.estimated_size_with_signatures()?);
+            let value = value.checked_sub(fee)
+                .ok_or(Error::InsufficientFunds(value.to_sat(), fee.to_sat()))?;
+            txb.tx.outputs[0].value = value;
+            txb.invalidate_outputs();
+            carry = Some((txb.txid(), txb.tx.outputs[0].clone()));
+            txs.push(txb);
+        }
+    }
 
-        let script_sig = p2pkh::script_sig(
-            &hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036"),
-            &hex!("304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b3041")
-        )?;
-        txb.set_script_sig(0, &script_sig)?;
+    /// Add input spending output 0 of previous transaction in a chain
+    fn push_carried_input(&mut self, carry: &Option<(Txid, Output)>) -> Result<()> {
+        if let Some((txid, output)) = carry {
+            self.add_input(*txid, 0, None, None, None)?;
+            self.set_prev_output(self.tx.inputs.len() - 1, output.clone())?;
+        }
 
-        let hash_type = sig_hash::ALL | sig_hash::FORKID;
-        let sighash = txb.witness_v0_hash(hash_type, 0, None, None)?;
+        Ok(())
+    }
 
-        let txid = txb.txid();
+    /// Add input `index` of `other` with its previous output and `redeem script`
+    fn push_input_of<G>(&mut self, other: &TxBuilder<G>, index: usize)
+        where G: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        let new = self.tx.inputs.len();
+        self.tx.inputs.push(other.tx.inputs[index].clone());
+        if let Some(o) = other.prev_outputs.get(&index) {
+            self.prev_outputs.insert(new, o.clone());
+        }
+        if let Some(script) = other.redeem_scripts.get(&index) {
+            self.redeem_scripts.insert(new, script.clone());
+        }
+        self.invalidate_inputs();
+    }
 
-        assert_eq!(sighash, hex!("2b492e7c4c8a3d670fd7fe324a87e3c55df1802c9a100f4006f8fff7c0913dd4"));
-        assert_eq!(txid, "ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda");
+    fn prev_value_sum(&self) -> Result<Amount> {
+        (0..self.tx.inputs.len()).try_fold(Amount::ZERO, |sum, i| {
+            let o = self.prev_outputs.get(&i).ok_or(Error::MissingPrevOutput(i))?;
+            sum.checked_add(o.value).ok_or_else(|| Error::InvalidAmount(format!("sum of input values exceeds {}", Amount::MAX_MONEY)))
+        })
+    }
+
+    fn output_value_sum(&self) -> Result<Amount> {
+        self.tx.outputs.iter().try_fold(Amount::ZERO, |sum, o| {
+            sum.checked_add(o.value).ok_or_else(|| Error::InvalidAmount(format!("sum of output values exceeds {}", Amount::MAX_MONEY)))
+        })
+    }
+
+    /// Sum of input values
+    /// # Returns
+    /// * total (`None` if any previous output is unknown or sum exceeds `Amount::MAX_MONEY`)
+    pub fn input_value(&self) -> Option<Amount> {
+        self.prev_value_sum().ok()
+    }
+
+    /// Sum of output values
+    /// # Returns
+    /// * total (`None` if sum exceeds `Amount::MAX_MONEY`)
+    pub fn output_value(&self) -> Option<Amount> {
+        self.output_value_sum().ok()
+    }
+
+    /// Input value left for fee
+    /// # Returns
+    /// * input value minus output value (`None` if unknown or outputs exceed inputs)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Amount};
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// txb.add_input(prev_txid, 0, Some(50_000), Some(&[0x51]), None)?;
+    /// txb.add_output(Amount::from_sat(49_000), &[0x51]);
+    /// assert_eq!(txb.balance(), Some(Amount::from_sat(1000)));
+    /// txb.add_output(Amount::from_sat(2000), &[0x51]);
+    /// assert_eq!(txb.balance(), None);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn balance(&self) -> Option<Amount> {
+        self.input_value()?.checked_sub(self.output_value()?)
+    }
+
+    /// Values of each input and output with totals
+    pub fn balance_report(&self) -> BalanceReport {
+        BalanceReport {
+            inputs: (0..self.tx.inputs.len()).map(|i| self.prev_outputs.get(&i).map(|o| o.value)).collect(),
+            outputs: self.tx.outputs.iter().map(|o| o.value).collect(),
+            input_total: self.input_value(),
+            output_total: self.output_value(),
+            fee: self.balance(),
+        }
+    }
+
+    /// Number of inputs
+    pub fn input_count(&self) -> usize {
+        self.tx.inputs.len()
+    }
+
+    /// Number of outputs
+    pub fn output_count(&self) -> usize {
+        self.tx.outputs.len()
+    }
+
+    /// Get serialized size
+    /// # Returns
+    /// * size in bytes
+    pub fn size(&self) -> usize {
+        self.to_vec().len()
+    }
+
+    /// Get estimated size after signing
+    ///
+    /// Unsigned P2PKH and P2SH inputs are counted at their worst-case signed size.
+    /// # Returns
+    /// * size in bytes
+    /// # Example
+    /// ```
+    /// # use bch_addr::{AddressType, Converter};
+    /// # use cash_tx_builder::{TxBuilder, Amount};
+    /// # use cash_tx_builder::script::address_to_script;
+    /// # let converter = Converter::new();
+    /// # let parser = |address: &str| {
+    /// #     let parsed = converter.parse(address).ok();
+    /// #     match parsed {
+    /// #         Some((_, _, address_type, hash)) => {
+    /// #             Some((hash, address_type == AddressType::P2PKH))
+    /// #         }
+    /// #         None => None
+    /// #     }
+    /// # };
+    /// # let mut txb = TxBuilder::new(&parser);
+    /// # let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// # let prev_script = address_to_script("qq6zfutryz9rkem05rkpwq60pu5sxg4z5c330k4w75", &parser)?;
+    /// # txb.add_input(prev_txid, 1, Some(100_000), Some(&prev_script), None)?;
+    /// # txb.add_address_output(Amount::from_sat(11000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+    /// assert_eq!(txb.size(), 85);
+    /// assert_eq!(txb.estimated_size_with_signatures()?, 85 + 108);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn estimated_size_with_signatures(&self) -> Result<usize> {
+        self.tx.inputs.iter().enumerate().try_fold(self.size(), |size, (i, input)| {
+            match self.prev_outputs.get(&i) {
+                Some(o) if input.script.is_empty() => {
+                    let redeem_script = self.redeem_scripts.get(&i).map(Vec::as_slice);
+                    let len = estimated_script_sig_len(&o.script, redeem_script).ok_or(Error::MissingRedeemScript(i))?;
+                    Ok(size + len + VarInt::from(len as u64).len() - 1)
+                },
+                _ => Ok(size),
+            }
+        })
+    }
+
+    /// Measure transaction at its estimated size after signing
+    ///
+    /// `redeem scripts` of unsigned P2SH inputs are not counted as signature operations.
+    /// # Returns
+    /// * metrics (fee and sigops are `None` if any previous output is unknown)
+    pub fn metrics(&self) -> Result<Metrics> {
+        let size = self.estimated_size_with_signatures()?;
+        let prev_outputs = (0..self.tx.inputs.len())
+            .map(|i| self.prev_outputs.get(&i).cloned())
+            .collect::<Option<Vec<_>>>();
+
+        Ok(Metrics::with_size(&self.tx, prev_outputs.as_deref(), size))
+    }
+
+    /// Get fee (sum of input values minus sum of output values)
+    /// # Returns
+    /// * fee
+    /// # Example
+    /// ```
+    /// # use bch_addr::{AddressType, Converter};
+    /// # use cash_tx_builder::{TxBuilder, Amount};
+    /// # use cash_tx_builder::script::address_to_script;
+    /// # let converter = Converter::new();
+    /// # let parser = |address: &str| {
+    /// #     let parsed = converter.parse(address).ok();
+    /// #     match parsed {
+    /// #         Some((_, _, address_type, hash)) => {
+    /// #             Some((hash, address_type == AddressType::P2PKH))
+    /// #         }
+    /// #         None => None
+    /// #     }
+    /// # };
+    /// # let mut txb = TxBuilder::new(&parser);
+    /// # let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// # let prev_script = address_to_script("qq6zfutryz9rkem05rkpwq60pu5sxg4z5c330k4w75", &parser)?;
+    /// # txb.add_input(prev_txid, 1, Some(100_000), Some(&prev_script), None)?;
+    /// # txb.add_address_output(Amount::from_sat(11000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+    /// assert_eq!(txb.fee()?, Amount::from_sat(89000));
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn fee(&self) -> Result<Amount> {
+        let input_value = self.prev_value_sum()?;
+        let output_value = self.output_value_sum()?;
+
+        input_value.checked_sub(output_value).ok_or(Error::InsufficientFunds(input_value.to_sat(), output_value.to_sat()))
+    }
+
+    /// Check consensus-level sanity of transaction
+    /// # Returns
+    /// * list of violations (empty if none)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Amount};
+    /// # use cash_tx_builder::verify::Violation;
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// txb.add_input(prev_txid, 0, Some(1000), Some(&[0x51]), None)?;
+    /// txb.add_input(prev_txid, 0, Some(1000), Some(&[0x51]), None)?;
+    /// txb.add_output(Amount::from_sat(1000), &[0x51]);
+    /// assert_eq!(txb.verify(), vec![Violation::DuplicateOutPoint(1)]);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn verify(&self) -> Vec<Violation> {
+        verify::verify(&self.tx, &self.prev_outputs)
+    }
+
+    /// Check standardness policy of default node
+    ///
+    /// Always empty if the network does not require standardness (see `Network::require_standard`).
+    /// # Returns
+    /// * list of violations (empty if none)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Amount};
+    /// # use cash_tx_builder::policy::Violation;
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// txb.add_output(Amount::from_sat(1000), &[0x51]);
+    /// assert_eq!(txb.check_standard(), vec![Violation::NonStandardScript(0)]);
+    /// ```
+    pub fn check_standard(&self) -> Vec<policy::Violation> {
+        if !self.network.require_standard() {
+            return vec![];
+        }
+
+        policy::check_standard(&self.tx)
+    }
+
+    /// Iterate over inputs of draft
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Amount};
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// txb.add_input(prev_txid, 1, Some(1000), Some(&[0x51]), Some(0))?;
+    /// txb.add_output(Amount::from_sat(900), &[0x52]);
+    ///
+    /// let input = txb.inputs().next().unwrap();
+    /// assert_eq!((input.outpoint.n, input.sequence_no), (1, 0));
+    /// assert_eq!(txb.prev_output(0).map(|o| o.value), Some(Amount::from_sat(1000)));
+    /// assert!(txb.outputs().all(|o| o.script == [0x52]));
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn inputs(&self) -> std::slice::Iter<'_, Input> {
+        self.tx.inputs.iter()
+    }
+
+    /// Iterate over outputs of draft
+    pub fn outputs(&self) -> std::slice::Iter<'_, Output> {
+        self.tx.outputs.iter()
+    }
+
+    /// Previous output spent by input
+    /// # Arguments
+    /// * `index` - input index
+    /// # Returns
+    /// * previous output (`None` if not set)
+    pub fn prev_output(&self, index: usize) -> Option<&Output> {
+        self.prev_outputs.get(&index)
+    }
+
+    /// Outpoints spent by inputs
+    pub fn outpoints(&self) -> Vec<OutPoint> {
+        self.tx.outpoints().cloned().collect()
+    }
+
+    /// Find inputs spending the same outpoints as `other`
+    /// # Arguments
+    /// * `other` - other builder
+    /// # Returns
+    /// * indexes of conflicting inputs of this builder
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::TxBuilder;
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// let mut txb = TxBuilder::new(|_: &str| None);
+    /// txb.add_input(prev_txid, 0, None, None, None)?;
+    /// txb.add_input(prev_txid, 1, None, None, None)?;
+    /// let mut other = TxBuilder::new(|_: &str| None);
+    /// other.add_input(prev_txid, 1, None, None, None)?;
+    /// assert_eq!(txb.conflicts_with(&other), [1]);
+    /// assert_eq!(other.conflicts_with(&txb), [0]);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn conflicts_with<G>(&self, other: &TxBuilder<G>) -> Vec<usize>
+        where G: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        let others = other.outpoints().into_iter().collect::<HashSet<_>>();
+
+        self.tx.inputs.iter().enumerate()
+            .filter(|(_, input)| others.contains(&input.outpoint))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Signing state of input
+    /// # Arguments
+    /// * `index` - input index
+    pub fn input_state(&self, index: usize) -> Result<InputState> {
+        let input = self.tx.inputs.get(index).ok_or(Error::InvalidIndex(index))?;
+        let prev_script = self.prev_outputs.get(&index).map(|o| o.script.as_slice());
+
+        Ok(match prev_script {
+            _ if !input.script.is_empty() => script_sig_state(prev_script, &input.script),
+            Some(_) => InputState::Unsigned,
+            None => InputState::MissingPrevOutput,
+        })
+    }
+
+    /// Indexes of inputs which are not finalized
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, InputState};
+    /// # use cash_tx_builder::script::{p2pkh, multisig};
+    /// let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// let redeem_script = multisig::redeem_script(2, &[&[0x02; 33], &[0x03; 33]])?;
+    /// txb.add_input(prev_txid, 0, Some(1000), Some(&p2pkh::script_pub_key(&[0x01; 20])?), None)?;
+    /// txb.add_input(prev_txid, 1, Some(1000), Some(&multisig::script_pub_key(&redeem_script)?), None)?;
+    /// txb.add_input(prev_txid, 2, None, None, None)?;
+    /// assert_eq!(txb.unsigned_inputs(), [0, 1, 2]);
+    ///
+    /// txb.set_script_sig(0, &p2pkh::script_sig(&[0x02; 33], &[0x30; 72])?)?;
+    /// txb.set_script_sig(1, &multisig::script_sig(&[&[0x30; 72], &[]], &redeem_script)?)?;
+    /// assert_eq!(txb.input_state(0)?, InputState::Finalized);
+    /// assert_eq!(txb.input_state(1)?, InputState::PartiallySigned);
+    /// assert_eq!(txb.input_state(2)?, InputState::MissingPrevOutput);
+    /// assert_eq!(txb.unsigned_inputs(), [1, 2]);
+    /// assert!(!txb.is_complete());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn unsigned_inputs(&self) -> Vec<usize> {
+        (0..self.tx.inputs.len())
+            .filter(|&i| self.input_state(i).ok() != Some(InputState::Finalized))
+            .collect()
+    }
+
+    /// Check if all inputs are finalized
+    pub fn is_complete(&self) -> bool {
+        self.unsigned_inputs().is_empty()
+    }
+
+    /// Convert to `Vec<u8>`
+    ///
+    /// Incomplete transaction is serialized as is, use `to_complete_vec` to refuse it.
+    /// # Returns
+    /// * serialized transaction
+    pub fn to_vec(&self) -> Vec<u8> {
+        Vec::from(&self.tx)
+    }
+
+    /// Convert to `Vec<u8>` if all inputs are finalized
+    /// # Returns
+    /// * serialized transaction (`Error::IncompleteInput` with the first input not finalized)
+    pub fn to_complete_vec(&self) -> Result<Vec<u8>> {
+        match self.unsigned_inputs().first() {
+            Some(&index) => Err(Error::IncompleteInput(index)),
+            None => Ok(self.to_vec()),
+        }
+    }
+
+    /// Convert to hex string
+    /// # Returns
+    /// * serialized transaction in hex
+    pub fn to_hex(&self) -> String {
+        self.tx.to_hex()
+    }
+
+    fn invalidate_inputs(&mut self) {
+        let cache = self.sighash_cache.get_mut();
+        cache.hash_prev_outs = None;
+        cache.hash_sequence = None;
+        cache.hash_utxos = None;
+    }
+
+    fn invalidate_outputs(&mut self) {
+        self.sighash_cache.get_mut().hash_outputs = None;
+    }
+
+    fn cached<G, H>(&self, field: G, compute: H) -> Vec<u8>
+        where G: Fn(&mut SighashCache) -> &mut Option<Vec<u8>>,
+              H: FnOnce() -> Vec<u8> {
+        let mut cache = self.sighash_cache.borrow_mut();
+        field(&mut cache).get_or_insert_with(compute).clone()
+    }
+
+    /// Get digest according to bip143  
+    /// [spec](https://github.com/Bitcoin-ABC/bitcoin-abc/blob/master/doc/abc/replay-protected-sighash.md)
+    /// # Arguments
+    /// * `hash_type` - sighash type
+    /// * `index` - input index
+    /// * `prev_value` - (option) previous value (known previous output's if `None`)
+    /// * `script_code` - (option) `scriptCode`, e.g. `redeem script` of P2SH input or script
+    ///   after the last executed `OP_CODESEPARATOR` (known previous `scriptPubKey` if `None`)
+    pub fn witness_v0_hash(&self, hash_type: SigHashType, index: u32, prev_value: Option<u64>, script_code: Option<&[u8]>) -> Result<SigHash> {
+        let mut w = Hash256Writer::new();
+        self.write_witness_v0_preimage(&mut w, hash_type, index, prev_value, script_code)?;
+
+        Ok(w.finalize().into())
+    }
+
+    /// Get digest of P2SH input, using `redeem script` as `scriptCode`
+    /// # Arguments
+    /// * `index` - input index
+    /// * `hash_type` - sighash type
+    /// * `redeem_script` - `redeem script`
+    /// # Example
+    /// ```
+    /// # #[macro_use] extern crate hex_literal;
+    /// # use cash_tx_builder::{TxBuilder, sig_hash};
+    /// # use cash_tx_builder::{hash, script::p2sh};
+    /// let redeem_script = hex!("51");
+    /// let prev_script = p2sh::script_pub_key(&hash::hash160(&redeem_script))?;
+    /// let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// txb.add_input(prev_txid, 0, Some(1000), Some(&prev_script), None)?;
+    ///
+    /// let hash_type = sig_hash::ALL | sig_hash::FORKID;
+    /// let sighash = txb.p2sh_sighash(0, hash_type, &redeem_script)?;
+    /// assert_eq!(sighash, txb.witness_v0_hash(hash_type, 0, None, Some(&redeem_script))?);
+    ///
+    /// txb.set_script_sig(0, &p2sh::script_sig(&[], &redeem_script)?)?;
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn p2sh_sighash(&self, index: usize, hash_type: SigHashType, redeem_script: &[u8]) -> Result<SigHash> {
+        let prev_output = self.prev_outputs.get(&index).ok_or(Error::MissingPrevOutput(index))?;
+        if prev_output.script != p2sh::script_pub_key(&hash::hash160(redeem_script))? {
+            return Err(Error::InvalidRedeemScript(index));
+        }
+
+        self.witness_v0_hash(hash_type, index as u32, None, Some(redeem_script))
+    }
+
+    /// Get serialized (unhashed) preimage of `witness_v0_hash`
+    /// for signers which hash it internally
+    ///
+    /// With `sig_hash::UTXOS`, all previous outputs are committed and must be known.
+    /// # Arguments
+    /// * `hash_type` - sighash type
+    /// * `index` - input index
+    /// * `prev_value` - (option) previous value (known previous output's if `None`)
+    /// * `script_code` - (option) `scriptCode` (known previous `scriptPubKey` if `None`)
+    /// # Returns
+    /// * preimage
+    pub fn witness_v0_preimage(&self, hash_type: SigHashType, index: u32, prev_value: Option<u64>, script_code: Option<&[u8]>) -> Result<Vec<u8>> {
+        let mut preimage = vec![];
+        self.write_witness_v0_preimage(&mut preimage, hash_type, index, prev_value, script_code)?;
+
+        Ok(preimage)
+    }
+
+    /// Get SHA-256 midstate of `witness_v0_preimage` without its trailing fields
+    ///
+    /// The trailing fields are `sequence`, `hashOutputs`, `lock time` and sighash type,
+    /// which can be ground over by `Midstate::hash256` without rehashing the rest.
+    /// # Arguments
+    /// * `hash_type` - sighash type
+    /// * `index` - input index
+    /// * `prev_value` - (option) previous value (known previous output's if `None`)
+    /// * `script_code` - (option) `scriptCode` (known previous `scriptPubKey` if `None`)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, sig_hash, Amount};
+    /// # use cash_tx_builder::hash::SigHash;
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// txb.add_input(prev_txid, 0, Some(1000), Some(&[0x51]), None)?;
+    /// txb.add_output(Amount::from_sat(900), &[0x51]);
+    ///
+    /// let hash_type = sig_hash::ALL | sig_hash::FORKID;
+    /// let midstate = txb.witness_v0_midstate(hash_type, 0, None, None)?;
+    /// let preimage = txb.witness_v0_preimage(hash_type, 0, None, None)?;
+    /// let tail = &preimage[preimage.len() - 44..];
+    /// assert_eq!(SigHash::from(midstate.hash256(tail)), txb.witness_v0_hash(hash_type, 0, None, None)?);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn witness_v0_midstate(&self, hash_type: SigHashType, index: u32, prev_value: Option<u64>, script_code: Option<&[u8]>) -> Result<Midstate> {
+        let preimage = self.witness_v0_preimage(hash_type, index, prev_value, script_code)?;
+
+        Ok(hash::sha256_midstate(&preimage[..preimage.len() - WITNESS_V0_TAIL_LEN]))
+    }
+
+    /// Write preimage of `witness_v0_hash` to `w`
+    fn write_witness_v0_preimage<W: Write>(&self, w: &mut W, hash_type: SigHashType, index: u32, prev_value: Option<u64>, script_code: Option<&[u8]>) -> Result<()> {
+        hash_type.validate()?;
+
+        let hash_prev_outs = if !hash_type.is_set(sig_hash::ANYONECANPAY) {
+            self.cached(|c| &mut c.hash_prev_outs, || {
+                let hasher = self.tx.inputs.iter().fold(Sha256::new(), |hasher, i| {
+                    hasher.chain_update(i.outpoint.txid).chain_update(i.outpoint.n.to_le_bytes())
+                });
+                hash::finalize_hash256(hasher).to_vec()
+            })
+        } else {
+            vec![0; 32]
+        };
+
+        let hash_utxos = if hash_type.is_set(sig_hash::UTXOS) {
+            let prev_outputs = (0..self.tx.inputs.len())
+                .map(|i| self.prev_outputs.get(&i).ok_or(Error::MissingPrevOutput(i)))
+                .collect::<Result<Vec<_>>>()?;
+            self.cached(|c| &mut c.hash_utxos, || {
+                let hasher = prev_outputs.iter().fold(Sha256::new(), |hasher, o| {
+                    hasher.chain_update(o.to_vec())
+                });
+                hash::finalize_hash256(hasher).to_vec()
+            })
+        } else {
+            vec![]
+        };
+
+        let hash_sequence = if !hash_type.is_set(sig_hash::ANYONECANPAY) && 
+                               hash_type.base() != sig_hash::SINGLE &&
+                               hash_type.base() != sig_hash::NONE {
+            self.cached(|c| &mut c.hash_sequence, || {
+                let hasher = self.tx.inputs.iter().fold(Sha256::new(), |hasher, i| {
+                    hasher.chain_update(i.sequence_no.to_le_bytes())
+                });
+                hash::finalize_hash256(hasher).to_vec()
+            })
+        } else {
+            vec![0; 32]
+        };
+
+        let hash_outputs = if hash_type.base() != sig_hash::SINGLE &&
+                              hash_type.base() != sig_hash::NONE {
+            self.cached(|c| &mut c.hash_outputs, || {
+                let hasher = self.tx.outputs.iter().fold(Sha256::new(), |hasher, o| {
+                    hasher.chain_update(o.to_vec())
+                });
+                hash::finalize_hash256(hasher).to_vec()
+            })
+        } else if hash_type.base() == sig_hash::SINGLE &&
+                  index < self.tx.outputs.len() as u32 {
+            let hasher = Sha256::new().chain_update(self.tx.outputs[index as usize].to_vec());
+            hash::finalize_hash256(hasher).to_vec()
+        } else {
+            vec![0; 32]
+        };
+
+        let prev_output = self.prev_outputs.get(&(index as usize));
+        let prev_value = prev_value.or_else(|| prev_output.map(|o| o.value.to_sat()))
+            .ok_or(Error::InvalidIndex(index as usize))?;
+        let script_code = script_code.or_else(|| prev_output.map(|o| &o.script[..]))
+            .ok_or(Error::InvalidIndex(index as usize))?;
+
+        // spent token is committed before scriptCode (CHIP-2022-02)
+        let token_prefix = prev_output.and_then(|o| o.token.as_ref()).map(TokenData::to_vec).unwrap_or_default();
+
+        let input = self.tx.inputs.get(index as usize).ok_or(Error::InvalidIndex(index as usize))?;
+
+        let parts = [
+            &self.tx.version.to_le_bytes()[..],
+            &hash_prev_outs,
+            &hash_utxos,
+            &hash_sequence,
+            input.outpoint.txid.as_ref(),
+            &input.outpoint.n.to_le_bytes(),
+            &token_prefix,
+            &Vec::from(VarInt::from(script_code.len() as u64)),
+            script_code,
+            &prev_value.to_le_bytes(),
+            &input.sequence_no.to_le_bytes(),
+            &hash_outputs,
+            &self.tx.lock_time.to_le_bytes(),
+            &((self.fork_id << 8) | hash_type.to_u32()).to_le_bytes(),
+        ];
+        for part in &parts {
+            w.write_all(part)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get digest according to original (pre-fork) algorithm
+    ///
+    /// Any raw sighash type is accepted, as in pre-fork transactions.
+    /// # Arguments
+    /// * `hash_type` - sighash type
+    /// * `index` - input index
+    /// * `prev_script` - (option) previous script
+    /// # Returns
+    /// * digest (`1` if `SIGHASH_SINGLE` without corresponding output)
+    pub fn legacy_hash<H: Into<u32>>(&self, hash_type: H, index: u32, prev_script: Option<&[u8]>) -> Result<SigHash> {
+        let hash_type = hash_type.into();
+        let index = index as usize;
+        if index >= self.tx.inputs.len() {
+            return Err(Error::InvalidIndex(index));
+        }
+
+        let base_type = hash_type & 0x1f;
+        if base_type == sig_hash::SINGLE.to_u32() && index >= self.tx.outputs.len() {
+            let mut one = [0; 32];
+            one[0] = 1;
+            return Ok(one.into());
+        }
+
+        let prev_script = match prev_script {
+            Some(script) => script,
+            None => &self.prev_outputs.get(&index).ok_or(Error::MissingPrevOutput(index))?.script,
+        };
+
+        let mut tx = self.tx.clone();
+        for (i, input) in tx.inputs.iter_mut().enumerate() {
+            if i == index {
+                input.script = remove_code_separators(prev_script);
+            } else {
+                input.script.clear();
+                if base_type == sig_hash::NONE.to_u32() || base_type == sig_hash::SINGLE.to_u32() {
+                    input.sequence_no = 0;
+                }
+            }
+        }
+        if hash_type.is_set(sig_hash::ANYONECANPAY.to_u32()) {
+            tx.inputs = vec![tx.inputs.swap_remove(index)];
+        }
+
+        if base_type == sig_hash::NONE.to_u32() {
+            tx.outputs.clear();
+        } else if base_type == sig_hash::SINGLE.to_u32() {
+            tx.outputs.truncate(index + 1);
+            for o in &mut tx.outputs[..index] {
+                *o = Output::new(Amount::from_sat(0xffff_ffff_ffff_ffff), &[]);
+            }
+        }
+
+        let mut w = Hash256Writer::new();
+        tx.write_to(&mut w)?;
+        w.write_all(&hash_type.to_le_bytes())?;
+
+        Ok(w.finalize().into())
+    }
+
+    /// Sign P2PKH (or P2PK) input and set `scriptSig`
+    ///
+    /// Other inputs are rejected, use `p2sh_sighash` to sign P2SH input.
+    /// # Arguments
+    /// * `index` - input index
+    /// * `signer` - signer of the input
+    /// * `hash_type` - sighash type
+    /// # Example
+    /// ```
+    /// # use bch_addr::{AddressType, Converter};
+    /// # use cash_tx_builder::{TxBuilder, sig_hash, Amount};
+    /// # use cash_tx_builder::script::address_to_script;
+    /// # use cash_tx_builder::secp256k1::SecretKey;
+    /// # let converter = Converter::new();
+    /// # let parser = |address: &str| {
+    /// #     let parsed = converter.parse(address).ok();
+    /// #     match parsed {
+    /// #         Some((_, _, address_type, hash)) => {
+    /// #             Some((hash, address_type == AddressType::P2PKH))
+    /// #         }
+    /// #         None => None
+    /// #     }
+    /// # };
+    /// # let mut txb = TxBuilder::new(&parser);
+    /// # let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// # let prev_script = address_to_script("qq6zfutryz9rkem05rkpwq60pu5sxg4z5c330k4w75", &parser)?;
+    /// # txb.add_input(prev_txid, 1, Some(100_000), Some(&prev_script), None)?;
+    /// # txb.add_address_output(Amount::from_sat(99_000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+    /// let key = SecretKey::from_slice(&[0x01; 32])?;
+    /// txb.sign_input(0, &key, sig_hash::ALL | sig_hash::FORKID)?;
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    #[cfg(feature = "sign")]
+    pub fn sign_input<S: Signer>(&mut self, index: usize, signer: &S, hash_type: SigHashType) -> Result<()> {
+        if index >= self.tx.inputs.len() {
+            return Err(Error::InvalidIndex(index));
+        }
+        let prev_script_type = self.single_sig_script_type(index)?;
+        let sighash = self.witness_v0_hash(hash_type, index as u32, None, None)?;
+        let mut sig = signer.sign(&sighash)?;
+        sig.push(hash_type.to_u8());
+
+        let script_sig = match prev_script_type {
+            ScriptType::PubKey => p2pk::script_sig(&sig)?,
+            _ => p2pkh::script_sig(&signer.public_key(), &sig)?,
+        };
+        self.set_script_sig(index, &script_sig)
+    }
+
+    /// Type of previous `scriptPubKey` signed by single signature (P2PKH or P2PK)
+    /// # Returns
+    /// * script type (`Error::MissingRedeemScript` for P2SH without `redeem script`,
+    ///   `Error::UnsupportedScript` for other types)
+    fn single_sig_script_type(&self, index: usize) -> Result<ScriptType> {
+        let prev_output = self.prev_outputs.get(&index).ok_or(Error::MissingPrevOutput(index))?;
+        match script_type(&prev_output.script) {
+            t @ ScriptType::PubKeyHash | t @ ScriptType::PubKey => Ok(t),
+            ScriptType::ScriptHash if !self.redeem_scripts.contains_key(&index) => Err(Error::MissingRedeemScript(index)),
+            _ => Err(Error::UnsupportedScript(index)),
+        }
+    }
+
+    /// Sign all P2PKH (or P2PK) inputs and set `scriptSig`s
+    ///
+    /// Previous outputs of all inputs are required.
+    /// Errors of `signer` and other inputs (`Error::UnsupportedScript`, `Error::MissingRedeemScript`)
+    /// are wrapped in `Error::InputError` with input index.
+    /// # Arguments
+    /// * `hash_type` - sighash type
+    /// * `signer` - closure which receives input index, sighash and previous `scriptPubKey`,
+    ///   and returns `signature` (without sighash type) and `public key`
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, sig_hash, Amount};
+    /// # use cash_tx_builder::script::p2pkh;
+    /// # let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// let prev_script = p2pkh::script_pub_key(&[0x01; 20])?;
+    /// txb.add_input(prev_txid, 0, Some(50_000), Some(&prev_script), None)?;
+    /// txb.add_input(prev_txid, 1, Some(50_000), Some(&prev_script), None)?;
+    /// txb.add_output(Amount::from_sat(99_000), &prev_script);
+    /// txb.sign_all(sig_hash::ALL | sig_hash::FORKID, |_index, _sighash, _prev_script| {
+    ///     // sign `_sighash` with the key of `_prev_script`
+    ///     Ok((vec![0x30; 71], vec![0x02; 33]))
+    /// })?;
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn sign_all<S>(&mut self, hash_type: SigHashType, mut signer: S) -> Result<()>
+        where S: FnMut(usize, &[u8], &[u8]) -> Result<(Vec<u8>, Vec<u8>)>
+    {
+        hash_type.validate()?;
+        let prev_scripts = (0..self.tx.inputs.len())
+            .map(|i| self.prev_outputs.get(&i).map(|o| o.script.clone()).ok_or(Error::MissingPrevOutput(i)))
+            .collect::<Result<Vec<_>>>()?;
+
+        for (index, prev_script) in prev_scripts.into_iter().enumerate() {
+            let prev_script_type = self.single_sig_script_type(index)
+                .map_err(|e| Error::InputError(index, Box::new(e)))?;
+            let sighash = self.witness_v0_hash(hash_type, index as u32, None, None)?;
+            let (mut sig, pubkey) = signer(index, &sighash, &prev_script)
+                .map_err(|e| Error::InputError(index, Box::new(e)))?;
+            sig.push(hash_type.to_u8());
+
+            let script_sig = match prev_script_type {
+                ScriptType::PubKey => p2pk::script_sig(&sig)?,
+                _ => p2pkh::script_sig(&pubkey, &sig)?,
+            };
+            self.set_script_sig(index, &script_sig)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sign all P2PKH inputs with keys derived from extended private key
+    /// # Arguments
+    /// * `xpriv` - extended private key
+    /// * `paths` - derivation path from `xpriv` for each input
+    /// * `hash_type` - sighash type
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Signer, sig_hash, hash, Amount};
+    /// # use cash_tx_builder::hd::Xpriv;
+    /// # use cash_tx_builder::script::p2pkh;
+    /// let xpriv = Xpriv::new_master(&[0x01; 32])?;
+    /// let path = "m/44'/145'/0'/0/0".parse()?;
+    /// let pubkey = Signer::public_key(&xpriv.derive_path(&path)?);
+    /// let prev_script = p2pkh::script_pub_key(&hash::hash160(&pubkey))?;
+    ///
+    /// let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// txb.add_input(prev_txid, 0, Some(50_000), Some(&prev_script), None)?;
+    /// txb.add_output(Amount::from_sat(49_000), &prev_script);
+    /// txb.sign_with_xpriv(&xpriv, &[path], sig_hash::ALL | sig_hash::FORKID)?;
+    /// txb.validate_input(0)?;
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    #[cfg(feature = "hd")]
+    pub fn sign_with_xpriv(&mut self, xpriv: &Xpriv, paths: &[DerivationPath], hash_type: SigHashType) -> Result<()> {
+        if paths.len() != self.tx.inputs.len() {
+            return Err(Error::InvalidIndex(paths.len()));
+        }
+
+        self.sign_all(hash_type, |index, sighash, _| {
+            let key = xpriv.derive_path(&paths[index])?;
+            Ok((key.sign(sighash)?, Signer::public_key(&key)))
+        })
+    }
+
+    /// Validate `scriptSig` of input by script interpreter
+    /// # Arguments
+    /// * `index` - input index
+    #[cfg(feature = "sign")]
+    pub fn validate_input(&self, index: usize) -> Result<()> {
+        let input = self.tx.inputs.get(index).ok_or(Error::InvalidIndex(index))?;
+        let prev_output = self.prev_outputs.get(&index).ok_or(Error::MissingPrevOutput(index))?;
+        let checker = InputChecker { txb: self, index };
+
+        interpreter::verify_script(&input.script, &prev_output.script, &checker)
+    }
+
+    /// Verify signature of input against its BIP143 digest without running scripts,
+    /// e.g. a counterparty's partial signature before finalizing
+    ///
+    /// `redeem script` set by `set_redeem_script` is used as `scriptCode` of P2SH input.
+    /// # Arguments
+    /// * `index` - input index
+    /// * `pubkey` - serialized `public key`
+    /// * `sig` - ECDSA or Schnorr signature (without sighash type)
+    /// * `hash_type` - sighash type
+    /// # Returns
+    /// * `true` if signature is valid, `false` if invalid or malformed
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{TxBuilder, Signer, sig_hash, hash, Amount};
+    /// # use cash_tx_builder::script::p2pkh;
+    /// # use cash_tx_builder::signer::schnorr::SchnorrSigner;
+    /// # use cash_tx_builder::secp256k1::SecretKey;
+    /// let key = SecretKey::from_slice(&[0x01; 32])?;
+    /// let pubkey = Signer::public_key(&key);
+    /// let prev_script = p2pkh::script_pub_key(&hash::hash160(&pubkey))?;
+    /// let mut txb = TxBuilder::new(|_: &str| None);
+    /// let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// txb.add_input(prev_txid, 0, Some(50_000), Some(&prev_script), None)?;
+    /// txb.add_output(Amount::from_sat(49_000), &prev_script);
+    ///
+    /// let hash_type = sig_hash::ALL | sig_hash::FORKID;
+    /// let sighash = txb.witness_v0_hash(hash_type, 0, None, None)?;
+    /// let sig = SchnorrSigner(key).sign(&sighash)?;
+    /// assert!(txb.verify_input_signature(0, &pubkey, &sig, hash_type)?);
+    /// assert!(!txb.verify_input_signature(0, &pubkey, &sig, sig_hash::NONE | sig_hash::FORKID)?);
+    /// assert!(!txb.verify_input_signature(0, &[0x02; 33], &sig, hash_type)?);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    #[cfg(feature = "sign")]
+    pub fn verify_input_signature(&self, index: usize, pubkey: &[u8], sig: &[u8], hash_type: SigHashType) -> Result<bool> {
+        if index >= self.tx.inputs.len() {
+            return Err(Error::InvalidIndex(index));
+        }
+        let prev_output = self.prev_outputs.get(&index).ok_or(Error::MissingPrevOutput(index))?;
+        let sighash = match self.redeem_scripts.get(&index) {
+            Some(redeem_script) => self.p2sh_sighash(index, hash_type, redeem_script)?,
+            None if script_type(&prev_output.script) == ScriptType::ScriptHash => {
+                return Err(Error::MissingRedeemScript(index));
+            },
+            None => self.witness_v0_hash(hash_type, index as u32, None, None)?,
+        };
+
+        Ok(signer::verify(pubkey, &sighash, sig))
+    }
+
+    /// Get txid
+    /// # Returns
+    /// * txid
+    pub fn txid(&self) -> Txid {
+        self.tx.txid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use super::super::types::u256;
+    use super::super::script::p2pkh;
+    use super::super::types::transaction::OutPoint;
+    use bch_addr::{AddressType, Converter};
+
+    #[test]
+    fn get_digest() -> Result<()> {
+        let converter = Converter::new();
+        let parser = |address: &str| {
+            let parsed = converter.parse(address).ok();
+            parsed.map(|(_, _, address_type, hash)| (hash, address_type == AddressType::P2PKH))
+        };
+
+        let mut txb = TxBuilder::new(&parser);
+        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        let prev_index = 1;
+        let prev_script = address_to_script("qq6zfutryz9rkem05rkpwq60pu5sxg4z5c330k4w75", &parser)?;
+        let prev_value = 100_000;
+
+        txb.add_input(prev_txid, prev_index, Some(prev_value), Some(&prev_script), None)?;
+        txb.add_address_output(Amount::from_sat(11000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+        txb.add_address_output(Amount::from_sat(88757), "qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70")?;
+
+        let script_sig = p2pkh::script_sig(
+            &hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036"),
+            &hex!("304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b3041")
+        )?;
+        txb.set_script_sig(0, &script_sig)?;
+
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        let sighash = txb.witness_v0_hash(hash_type, 0, None, None)?;
+
+        let txid = txb.txid();
+
+        assert_eq!(sighash, hex!("2b492e7c4c8a3d670fd7fe324a87e3c55df1802c9a100f4006f8fff7c0913dd4"));
+        assert_eq!(txid.to_string(), "ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda");
+        assert_eq!(txb.to_string(), txb.to_hex());
+        assert_eq!(format!("{:x}", txb), txb.to_hex());
+        assert_eq!(txb.as_ref().txid(), txid);
+
+        let preimage = txb.witness_v0_preimage(hash_type, 0, None, None)?;
+        assert_eq!(SigHash::from(hash::hash256(&preimage)), sighash);
+        assert_eq!(preimage.len(), 4 + 32 + 32 + 36 + 1 + prev_script.len() + 8 + 4 + 32 + 4 + 4);
+        assert_eq!(preimage[preimage.len() - 4..], hash_type.to_u32().to_le_bytes());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn sign_input() -> Result<()> {
+        use secp256k1::{ecdsa, Message, PublicKey, SecretKey, SECP256K1};
+        use super::super::script::decode;
+
+        let converter = Converter::new();
+        let parser = |address: &str| {
+            let parsed = converter.parse(address).ok();
+            parsed.map(|(_, _, address_type, hash)| (hash, address_type == AddressType::P2PKH))
+        };
+
+        let mut txb = TxBuilder::new(&parser);
+        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        let prev_script = address_to_script("qq6zfutryz9rkem05rkpwq60pu5sxg4z5c330k4w75", &parser)?;
+        txb.add_input(prev_txid, 1, Some(100_000), Some(&prev_script), None)?;
+        txb.add_address_output(Amount::from_sat(99_000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+
+        let key = SecretKey::from_slice(&[0x01; 32])?;
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        txb.sign_input(0, &key, hash_type)?;
+
+        let sighash = txb.witness_v0_hash(hash_type, 0, None, None)?;
+        let script_sig = txb.tx.inputs[0].script.clone();
+        let scripts = decode(&script_sig)?;
+        let (sig, pubkey) = match &scripts[..] {
+            [Script::Data(sig), Script::Data(pubkey)] => (sig, pubkey),
+            _ => panic!("unexpected scriptSig"),
+        };
+
+        assert_eq!(sig.last(), Some(&hash_type.to_u8()));
+        assert_eq!(pubkey.to_vec(), PublicKey::from_secret_key(SECP256K1, &key).serialize().to_vec());
+
+        let sig = ecdsa::Signature::from_der(&sig[..sig.len() - 1])?;
+        let msg = Message::from_digest_slice(&sighash)?;
+        SECP256K1.verify_ecdsa(&msg, &sig, &PublicKey::from_slice(pubkey)?)?;
+
+        // only P2PKH and P2PK inputs are signed
+        let redeem_script = multisig::redeem_script(1, &[&PublicKey::from_secret_key(SECP256K1, &key).serialize()])?;
+        txb.add_input(prev_txid, 2, Some(1000), Some(&p2sh::script_pub_key(&hash::hash160(&redeem_script))?), None)?;
+        txb.add_input(prev_txid, 3, Some(1000), Some(&redeem_script), None)?;
+        match txb.sign_input(1, &key, hash_type) {
+            Err(Error::MissingRedeemScript(1)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match txb.sign_input(2, &key, hash_type) {
+            Err(Error::UnsupportedScript(2)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        txb.set_redeem_script(1, &redeem_script)?;
+        match txb.sign_input(1, &key, hash_type) {
+            Err(Error::UnsupportedScript(1)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(txb.tx.inputs[1..].iter().all(|i| i.script.is_empty()));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "sign")]
+    fn validate_input() -> Result<()> {
+        use secp256k1::SecretKey;
+        use super::super::interpreter::ScriptError;
+        use super::super::signer::schnorr::SchnorrSigner;
+
+        let keys = [SecretKey::from_slice(&[0x01; 32])?, SecretKey::from_slice(&[0x02; 32])?];
+        let pubkeys = keys.iter().map(Signer::public_key).collect::<Vec<_>>();
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+
+        // P2PKH
+        let mut txb = TxBuilder::new(|_: &str| None);
+        let prev_script = p2pkh::script_pub_key(&hash::hash160(&pubkeys[0]))?;
+        txb.add_input(prev_txid, 0, Some(100_000), Some(&prev_script), None)?;
+        txb.add_output(Amount::from_sat(99_000), &prev_script);
+        assert!(txb.validate_input(0).is_err());
+        txb.sign_input(0, &SchnorrSigner(keys[0]), hash_type)?;
+        txb.validate_input(0)?;
+
+        txb.replace_output(0, Amount::from_sat(98_000), &prev_script)?;
+        match txb.validate_input(0) {
+            Err(Error::ScriptError(ScriptError::NullFail)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        // P2SH 2-of-2 multisig
+        let redeem_script = multisig::redeem_script(2, &[&pubkeys[0], &pubkeys[1]])?;
+        let mut txb = TxBuilder::new(|_: &str| None);
+        txb.add_input(prev_txid, 1, Some(100_000), Some(&multisig::script_pub_key(&redeem_script)?), None)?;
+        txb.add_output(Amount::from_sat(99_000), &prev_script);
+        let sighash = txb.witness_v0_hash(hash_type, 0, Some(100_000), Some(&redeem_script))?;
+        let sigs = keys.iter()
+            .map(|key| Ok([key.sign(&sighash)?, vec![hash_type.to_u8()]].concat()))
+            .collect::<Result<Vec<_>>>()?;
+        let script_sig = multisig::script_sig(&[&sigs[0], &sigs[1]], &redeem_script)?;
+        txb.set_script_sig(0, &script_sig)?;
+        txb.validate_input(0)?;
+
+        let script_sig = multisig::script_sig(&[&sigs[1], &sigs[0]], &redeem_script)?;
+        txb.set_script_sig(0, &script_sig)?;
+        assert!(txb.validate_input(0).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn verify_input_signature() -> Result<()> {
+        use secp256k1::SecretKey;
+
+        let keys = [SecretKey::from_slice(&[0x01; 32])?, SecretKey::from_slice(&[0x02; 32])?];
+        let pubkeys = keys.iter().map(Signer::public_key).collect::<Vec<_>>();
+        let redeem_script = multisig::redeem_script(2, &[&pubkeys[0], &pubkeys[1]])?;
+        let mut txb = TxBuilder::new(|_: &str| None);
+        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        txb.add_input(prev_txid, 0, Some(50_000), Some(&multisig::script_pub_key(&redeem_script)?), None)?;
+        txb.add_output(Amount::from_sat(49_000), &[0x51]);
+
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        match txb.verify_input_signature(0, &pubkeys[0], &[], hash_type) {
+            Err(Error::MissingRedeemScript(0)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        txb.set_redeem_script(0, &redeem_script)?;
+        let sig = keys[0].sign(&txb.p2sh_sighash(0, hash_type, &redeem_script)?)?;
+        assert!(txb.verify_input_signature(0, &pubkeys[0], &sig, hash_type)?);
+        assert!(!txb.verify_input_signature(0, &pubkeys[1], &sig, hash_type)?);
+        assert!(!txb.verify_input_signature(0, &pubkeys[0], &sig[1..], hash_type)?);
+
+        txb.add_output(Amount::from_sat(500), &[0x51]);
+        assert!(!txb.verify_input_signature(0, &pubkeys[0], &sig, hash_type)?);
+        assert!(txb.verify_input_signature(1, &pubkeys[0], &sig, hash_type).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "sign")]
+    #[test]
+    fn sign_all() -> Result<()> {
+        use secp256k1::SecretKey;
+
+        let keys = [SecretKey::from_slice(&[0x01; 32])?, SecretKey::from_slice(&[0x02; 32])?];
+        let prev_scripts = keys.iter()
+            .map(|key| p2pkh::script_pub_key(&hash::hash160(&Signer::public_key(key))))
+            .collect::<Result<Vec<_>>>()?;
+        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+
+        let mut txb = TxBuilder::new(|_: &str| None);
+        txb.add_input(prev_txid, 0, Some(50_000), Some(&prev_scripts[0]), None)?;
+        txb.add_input(prev_txid, 1, None, None, None)?;
+        txb.add_output(Amount::from_sat(99_000), &prev_scripts[0]);
+        match txb.sign_all(sig_hash::ALL | sig_hash::FORKID, |_, _, _| unreachable!()) {
+            Err(Error::MissingPrevOutput(1)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        txb.remove_input(1)?;
+        txb.add_input(prev_txid, 1, Some(50_000), Some(&prev_scripts[1]), None)?;
+        txb.sign_all(sig_hash::ALL | sig_hash::FORKID, |index, sighash, prev_script| {
+            assert_eq!(prev_script, &prev_scripts[index][..]);
+            Ok((keys[index].sign(sighash)?, Signer::public_key(&keys[index])))
+        })?;
+        txb.validate_input(0)?;
+        txb.validate_input(1)?;
+
+        let result = txb.sign_all(sig_hash::ALL | sig_hash::FORKID, |index, sighash, _| {
+            match index {
+                0 => Ok((keys[0].sign(sighash)?, Signer::public_key(&keys[0]))),
+                _ => Err(Error::MissingRedeemScript(index)),
+            }
+        });
+        match &result {
+            Err(Error::InputError(1, e)) => assert!(matches!(**e, Error::MissingRedeemScript(1))),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(result.unwrap_err().to_string(), "input 1: Missing redeem script: 1");
+
+        // bare multisig input is not signed by single signature
+        let redeem_script = multisig::redeem_script(1, &[&Signer::public_key(&keys[0])])?;
+        txb.add_input(prev_txid, 2, Some(1000), Some(&redeem_script), None)?;
+        match txb.sign_all(sig_hash::ALL | sig_hash::FORKID, |index, sighash, _| Ok((keys[index].sign(sighash)?, Signer::public_key(&keys[index])))) {
+            Err(Error::InputError(2, e)) => assert!(matches!(*e, Error::UnsupportedScript(2))),
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert!(txb.tx.inputs[2].script.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn change_output() -> Result<()> {
+        let converter = Converter::new();
+        let parser = |address: &str| {
+            let parsed = converter.parse(address).ok();
+            parsed.map(|(_, _, address_type, hash)| (hash, address_type == AddressType::P2PKH))
+        };
+
+        let mut txb = TxBuilder::new(&parser);
+        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        let prev_script = address_to_script("qq6zfutryz9rkem05rkpwq60pu5sxg4z5c330k4w75", &parser)?;
+        txb.add_input(prev_txid, 1, Some(100_000), Some(&prev_script), None)?;
+        txb.add_address_output(Amount::from_sat(11000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+
+        let change = txb.add_change_output("qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70", FeeRate::from_sat_per_byte(2))?;
+        assert_eq!(change, Amount::from_sat(100_000 - 11000 - 227 * 2));
+        assert_eq!(txb.tx.outputs.len(), 2);
+        assert_eq!(txb.tx.outputs[1].value, change);
+
+        let mut half = TxBuilder::new(&parser);
+        half.add_input(prev_txid, 1, Some(100_000), Some(&prev_script), None)?;
+        half.add_address_output(Amount::from_sat(11000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+        let change = half.add_change_output("qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70", "0.5".parse()?)?;
+        assert_eq!(change, Amount::from_sat(100_000 - 11000 - 114));
+
+        match txb.add_change_output("qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70", FeeRate::from_sat_per_byte(1000)) {
+            Err(Error::InsufficientFunds(..)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(txb.tx.outputs.len(), 2);
+
+        // dust change is left to fee
+        let mut dust = TxBuilder::new(&parser);
+        dust.add_input(prev_txid, 1, Some(11_500), Some(&prev_script), None)?;
+        dust.add_address_output(Amount::from_sat(11_000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+        let change = dust.add_change_output("qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70", FeeRate::MIN_RELAY)?;
+        assert_eq!(change, Amount::ZERO);
+        assert_eq!(dust.tx.outputs.len(), 1);
+        assert_eq!(dust.fee()?, Amount::from_sat(500));
+        assert!(dust.check_standard().is_empty());
+
+        txb.add_input(prev_txid, 0, None, None, None)?;
+        match txb.add_change_output("qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70", FeeRate::MIN_RELAY) {
+            Err(Error::MissingPrevOutput(1)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn coinbase() -> Result<()> {
+        let rewards = [Output::new(Amount::from_sat(625_000_000), &p2pkh::script_pub_key(&[0x01; 20])?)];
+
+        let txb = TxBuilder::new_coinbase(1, &[], &rewards, |_: &str| None)?;
+        assert_eq!(txb.tx.inputs[0].script, [0x51, 0x00]);
+        assert!(txb.tx.is_coinbase());
+        assert!(txb.verify().is_empty());
+
+        let txb = TxBuilder::new_coinbase(227_931, &[0xff; 94], &rewards, |_: &str| None)?;
+        assert_eq!(txb.tx.inputs[0].script.len(), 100);
+        assert_eq!(txb.tx.inputs[0].script[..4], [0x03, 0x5b, 0x7a, 0x03]);
+        match TxBuilder::new_coinbase(227_931, &[0xff; 95], &rewards, |_: &str| None) {
+            Err(Error::InvalidLengthData(101)) => {},
+            r => panic!("unexpected result: {:?}", r.map(|txb| txb.tx)),
+        }
+
+        let mut txb = TxBuilder::new(|_: &str| None);
+        txb.add_input("427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?, 0, None, None, None)?;
+        match txb.add_input(Txid::default(), 0xffff_ffff, None, None, None) {
+            Err(Error::NullOutPoint(1)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(txb.input_count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cpfp() -> Result<()> {
+        let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+        let prev_txid: Txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        let prev_script = p2pkh::script_pub_key(&[0x01; 20])?;
+        let token = TokenData::new(prev_txid.into(), 100, None)?;
+
+        let mut parent = TxBuilder::new(&parser);
+        parent.add_input(prev_txid, 0, Some(100_000), Some(&prev_script), None)?;
+        parent.add_output(Amount::from_sat(50_000), &prev_script);
+        parent.add_token_output(Amount::from_sat(1000), &prev_script, token.clone());
+
+        let child = TxBuilder::cpfp_from(&parent, 0, FeeRate::MIN_RELAY, &hex::encode([0x02; 20]))?;
+        assert_eq!(child.tx.inputs[0].outpoint.txid, parent.txid());
+        assert_eq!(child.tx.inputs[0].outpoint.n, 0);
+        // parent overpays, so child pays for itself only
+        assert_eq!(child.fee()?, Amount::from_sat(child.estimated_size_with_signatures()? as u64));
+
+        let child = TxBuilder::cpfp_from(&parent, 1, FeeRate::from_sat_per_byte(500), &hex::encode([0x02; 20]));
+        match child {
+            Err(Error::InsufficientFunds(1000, _)) => {},
+            r => panic!("unexpected result: {:?}", r.map(|txb| txb.tx)),
+        }
+        let child = TxBuilder::cpfp_from(&parent, 1, FeeRate::MIN_RELAY, &hex::encode([0x02; 20]))?;
+        assert_eq!(child.tx.outputs[0].token, Some(token));
+        assert!(TxBuilder::cpfp_from(&parent, 2, FeeRate::MIN_RELAY, &hex::encode([0x02; 20])).is_err());
+
+        let mut parent = TxBuilder::new(&parser);
+        parent.add_input(prev_txid, 0, Some(100_000), Some(&prev_script), None)?;
+        parent.add_output(Amount::from_sat(100_000), &prev_script);
+        let child = TxBuilder::cpfp_from(&parent, 0, FeeRate::from_sat_per_byte(3), &hex::encode([0x02; 20]))?;
+        let package_size = parent.estimated_size_with_signatures()? + child.estimated_size_with_signatures()?;
+        assert_eq!(child.fee()?, Amount::from_sat(package_size as u64 * 3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_digest() -> Result<()> {
+        use std::convert::TryFrom;
+
+        // from bitcoin core's sighash.json
+        let set: &[(&str, &str, u32, i32, &str)] = &[
+            ("73107cbd025c22ebc8c3e0a47b2a760739216a528de8d4dab5d45cbeb3051cebae73b01ca10200000007ab6353656a636affffffffe26816dffc670841e6a6c8c61c586da401df1261a330a6c6b3dd9f9a0789bc9e000000000800ac6552ac6aac51ffffffff0174a8f0010000000004ac52515100000000", "5163ac63635151ac", 1, 1190874345, "06e328de263a87b09beabe222a21627a6ea5c7f560030da31610c4611f4a46bc"),
+            ("50818f4c01b464538b1e7e7f5ae4ed96ad23c68c830e78da9a845bc19b5c3b0b20bb82e5e9030000000763526a63655352ffffffff023b3f9c040000000008630051516a6a5163a83caf01000000000553ab65510000000000", "6aac", 0, 946795545, "746306f322de2b4b58ffe7faae83f6a72433c22f88062cdde881d4dd8a5a4e2d"),
+            ("a93e93440250f97012d466a6cc24839f572def241c814fe6ae94442cf58ea33eb0fdd9bcc1030000000600636a0065acffffffff5dee3a6e7e5ad6310dea3e5b3ddda1a56bf8de7d3b75889fc024b5e233ec10f80300000007ac53635253ab53ffffffff0160468b04000000000800526a5300ac526a00000000", "ac00636a53", 1, 1773442520, "5c9d3a2ce9365bb72cfabbaa4579c843bb8abf200944612cf8ae4b56a908bcbd"),
+            ("d3b7421e011f4de0f1cea9ba7458bf3486bee722519efab711a963fa8c100970cf7488b7bb0200000003525352dcd61b300148be5d05000000000000000000", "535251536aac536a", 0, -1960128125, "29aa6d2d752d3310eba20442770ad345b7f6a35f96161ede5f07b33e92053e2a"),
+            ("c363a70c01ab174230bbe4afe0c3efa2d7f2feaf179431359adedccf30d1f69efe0c86ed390200000002ab51558648fe0231318b04000000000151662170000000000008ac5300006a63acac00000000", "", 0, 2146479410, "191ab180b0d753763671717d051f138d4866b7cb0d1d4811472e64de595d2c70"),
+        ];
+
+        for (raw_tx, script, index, hash_type, expected) in set {
+            let tx = Transaction::try_from(&hex::decode(raw_tx).unwrap()[..])?;
+            let txb = TxBuilder::from_tx(&tx, |_: &str| None)?;
+            let script = hex::decode(script).unwrap();
+
+            let digest = txb.legacy_hash(*hash_type as u32, *index, Some(&script))?;
+            assert_eq!(digest.as_ref(), u256::from_str(expected)?.as_ref());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn pst_round_trip() -> Result<()> {
+        let converter = Converter::new();
+        let parser = |address: &str| {
+            let parsed = converter.parse(address).ok();
+            parsed.map(|(_, _, address_type, hash)| (hash, address_type == AddressType::P2PKH))
+        };
+
+        let mut txb = TxBuilder::new(&parser);
+        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        let prev_script = address_to_script("qq6zfutryz9rkem05rkpwq60pu5sxg4z5c330k4w75", &parser)?;
+        txb.add_input(prev_txid, 1, Some(100_000), Some(&prev_script), None)?;
+        txb.add_address_output(Amount::from_sat(11000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+        txb.add_address_output(Amount::from_sat(88757), "qqny0aeaayxca8d4khmh68xp44d0aqwk3sk3zpzs70")?;
+
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        let mut pst = txb.to_pst();
+        let signer = TxBuilder::from_pst(&pst, &parser)?;
+        assert_eq!(signer.witness_v0_hash(hash_type, 0, None, None)?, hex!("2b492e7c4c8a3d670fd7fe324a87e3c55df1802c9a100f4006f8fff7c0913dd4"));
+
+        pst.add_signature(
+            0,
+            &hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036"),
+            &hex!("304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b3041"),
+        )?;
+        let tx = pst.finalize()?;
+        let txb = TxBuilder::from_tx(&tx, &parser)?;
+        assert_eq!(txb.txid().to_string(), "ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda");
+
+        Ok(())
+    }
+
+    #[test]
+    fn size_and_fee() -> Result<()> {
+        let converter = Converter::new();
+        let parser = |address: &str| {
+            let parsed = converter.parse(address).ok();
+            parsed.map(|(_, _, address_type, hash)| (hash, address_type == AddressType::P2PKH))
+        };
+
+        let pubkeys = [
+            &hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036")[..],
+            &hex!("030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1de")[..],
+        ];
+        let redeem_script = multisig::redeem_script(2, &pubkeys)?;
+
+        let mut txb = TxBuilder::new(&parser);
+        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        txb.add_input(prev_txid, 1, Some(100_000), Some(&multisig::script_pub_key(&redeem_script)?), None)?;
+        txb.add_address_output(Amount::from_sat(110_000), "qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3")?;
+
+        match txb.estimated_size_with_signatures() {
+            Err(Error::MissingRedeemScript(0)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        txb.set_redeem_script(0, &redeem_script)?;
+        let script_sig_len = 1 + 2 * 74 + 1 + redeem_script.len();
+        assert_eq!(txb.estimated_size_with_signatures()?, txb.size() + script_sig_len);
+
+        match txb.fee() {
+            Err(Error::InsufficientFunds(100_000, 110_000)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        txb.add_input(prev_txid, 0, None, None, None)?;
+        match txb.fee() {
+            Err(Error::MissingPrevOutput(1)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let mut txb = TxBuilder::new(&parser);
+        txb.add_input(prev_txid, 2, Some(100_000), Some(&p2pk::script_pub_key(pubkeys[0])?), None)?;
+        assert_eq!(txb.estimated_size_with_signatures()?, txb.size() + 1 + 73);
+
+        // sums beyond MAX_MONEY are rejected instead of wrapping
+        txb.add_input(prev_txid, 3, Some(u64::MAX), Some(&[0x51]), None)?;
+        match txb.fee() {
+            Err(Error::InvalidAmount(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        txb.remove_input(1)?;
+        txb.add_output(Amount::MAX_MONEY, &[0x51]);
+        txb.add_output(Amount::from_sat(1), &[0x51]);
+        match txb.add_change_output("qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3", FeeRate::MIN_RELAY) {
+            Err(Error::InvalidAmount(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn sighash_cache() -> Result<()> {
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        let fresh = |txb: &TxBuilder<_>| -> Result<Vec<SigHash>> {
+            let mut fresh = TxBuilder::from_tx(&txb.tx, |_: &str| None)?;
+            fresh.prev_outputs = txb.prev_outputs.clone();
+            (0..txb.tx.inputs.len()).map(|i| fresh.witness_v0_hash(hash_type, i as u32, None, None)).collect()
+        };
+        let hashes = |txb: &TxBuilder<_>| -> Result<Vec<SigHash>> {
+            (0..txb.tx.inputs.len()).map(|i| txb.witness_v0_hash(hash_type, i as u32, None, None)).collect()
+        };
+
+        let mut txb = TxBuilder::new(|_: &str| None);
+        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        txb.add_input(prev_txid, 0, Some(1000), Some(&[0x51]), None)?;
+        txb.add_output(Amount::from_sat(500), &[0x51]);
+        let before = hashes(&txb)?;
+
+        // each check fills the cache before the next edit
+        let check = |txb: &TxBuilder<_>| -> Result<()> {
+            assert_eq!(hashes(txb)?, fresh(txb)?);
+            Ok(())
+        };
+        txb.add_input(prev_txid, 1, Some(2000), Some(&[0x52]), None)?;
+        check(&txb)?;
+        txb.set_input_sequence(1, Sequence::from_blocks(1))?;
+        check(&txb)?;
+        txb.add_output(Amount::from_sat(300), &[0x52]);
+        check(&txb)?;
+        txb.replace_output(0, Amount::from_sat(400), &[0x51])?;
+        check(&txb)?;
+        txb.move_output(1, 0)?;
+        check(&txb)?;
+        txb.sort_bip69();
+        check(&txb)?;
+        txb.remove_output(1)?;
+        check(&txb)?;
+        txb.remove_input(0)?;
+        check(&txb)?;
+        assert_ne!(hashes(&txb)?, before);
+
+        Ok(())
+    }
+
+    #[test]
+    fn script_code() -> Result<()> {
+        let pubkey = hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036");
+        let redeem_script = multisig::redeem_script(2, &[&pubkey[..]; 3])?;
+        assert!(redeem_script.len() > 0x4b);
+
+        let mut txb = TxBuilder::new(|_: &str| None);
+        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        txb.add_input(prev_txid, 0, Some(1000), Some(&multisig::script_pub_key(&redeem_script)?), None)?;
+        txb.add_output(Amount::from_sat(500), &[0x51]);
+
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        let preimage = txb.witness_v0_preimage(hash_type, 0, None, Some(&redeem_script))?;
+        assert_eq!(preimage[104], redeem_script.len() as u8);
+        assert_eq!(preimage[105..105 + redeem_script.len()], redeem_script[..]);
+        assert_eq!(preimage[105 + redeem_script.len()..113 + redeem_script.len()], 1000u64.to_le_bytes());
+        assert_eq!(
+            txb.witness_v0_hash(hash_type, 0, None, Some(&redeem_script))?,
+            txb.witness_v0_hash(hash_type, 0, Some(1000), Some(&redeem_script))?
+        );
+        assert_ne!(
+            txb.witness_v0_hash(hash_type, 0, None, Some(&redeem_script))?,
+            txb.witness_v0_hash(hash_type, 0, None, None)?
+        );
+
+        assert_eq!(txb.p2sh_sighash(0, hash_type, &redeem_script)?, SigHash::from(hash::hash256(&preimage)));
+        match txb.p2sh_sighash(0, hash_type, &[0x51]) {
+            Err(Error::InvalidRedeemScript(0)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn sighash_utxos() -> Result<()> {
+        let mut txb = TxBuilder::new(|_: &str| None);
+        let prev_txid: Txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        txb.add_input(prev_txid, 0, Some(1000), Some(&[0x51]), None)?;
+        txb.add_input(prev_txid, 1, None, None, None)?;
+        txb.add_output(Amount::from_sat(500), &[0x51]);
+
+        let hash_type = sig_hash::ALL | sig_hash::FORKID;
+        match txb.witness_v0_preimage(hash_type | sig_hash::UTXOS, 0, None, None) {
+            Err(Error::MissingPrevOutput(1)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match txb.witness_v0_preimage(hash_type | sig_hash::UTXOS | sig_hash::ANYONECANPAY, 0, None, None) {
+            Err(Error::InvalidSigHashType(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        let token = TokenData::new(prev_txid.into(), 100, None)?;
+        txb.set_prev_output(1, Output::with_token(Amount::from_sat(2000), &[0x52], token))?;
+        let preimage = txb.witness_v0_preimage(hash_type, 0, None, None)?;
+        let with_utxos = txb.witness_v0_preimage(hash_type | sig_hash::UTXOS, 0, None, None)?;
+
+        let utxos = [txb.prev_outputs[&0].to_vec(), txb.prev_outputs[&1].to_vec()].concat();
+        assert_eq!(with_utxos[..36], preimage[..36]);
+        assert_eq!(with_utxos[36..68], hash::hash256(&utxos)[..]);
+        assert_eq!(with_utxos[68..with_utxos.len() - 4], preimage[36..preimage.len() - 4]);
+
+        // token input commits its token prefix before scriptCode
+        let expected = [
+            &hex!("02000000")[..],
+            &hex!("8147f19876c306ca96a1f0ffa830dcba956103fe723b04b7ca3c747b85e78ddc"),
+            &hex!("752adad0a7b9ceca853768aebb6965eca126a62965f698a0c1bc43d83db632ad"),
+            &hex!("4ceee1213404c7a96b80b86f85487220599dbefcbc192c55336a0e968afc7c4201000000"),
+            &hex!("ef4ceee1213404c7a96b80b86f85487220599dbefcbc192c55336a0e968afc7c421064"),
+            &hex!("0152"),
+            &hex!("d007000000000000"),
+            &hex!("ffffffff"),
+            &hex!("7d68cff2a3ebb9abf9303b7e94d6b48ee62c0af7eaef5ec74de227e0875d720f"),
+            &hex!("00000000"),
+            &hex!("41000000"),
+        ].concat();
+        assert_eq!(txb.witness_v0_preimage(hash_type, 1, None, None)?, expected);
+        assert_eq!(txb.witness_v0_hash(hash_type, 1, None, None)?.to_string(), "35c0946acf74356c9317b7ad540fe6bcd41a57432f1e034964c80d26f6a421ca");
+        let midstate = txb.witness_v0_midstate(hash_type, 1, None, None)?;
+        assert_eq!(SigHash::from(midstate.hash256(&expected[expected.len() - 44..])), txb.witness_v0_hash(hash_type, 1, None, None)?);
+
+        // cached hashUtxos follows updated previous output
+        txb.set_prev_output(1, Output::new(Amount::from_sat(2000), &[0x52]))?;
+        let utxos = [txb.prev_outputs[&0].to_vec(), txb.prev_outputs[&1].to_vec()].concat();
+        let with_utxos = txb.witness_v0_preimage(hash_type | sig_hash::UTXOS, 0, None, None)?;
+        assert_eq!(with_utxos[36..68], hash::hash256(&utxos)[..]);
+        assert!(txb.set_prev_output(2, Output::new(Amount::from_sat(0), &[])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn sighash_type() -> Result<()> {
+        use self::sig_hash::SigHashType;
+
+        let hash_type = sig_hash::SINGLE | sig_hash::ANYONECANPAY | sig_hash::FORKID;
+        assert_eq!(hash_type.to_u8(), 0xc3);
+        assert_eq!(hash_type.base(), sig_hash::SINGLE);
+        assert!(hash_type.is_set(sig_hash::ANYONECANPAY));
+        assert!(!hash_type.is_set(sig_hash::UTXOS));
+        assert_eq!(SigHashType::from_u32(0xc3)?, hash_type);
+
+        for &invalid in &[0x01, 0x40, 0x44, 0x50, 0x141, 0xe1] {
+            match SigHashType::from_u32(invalid) {
+                Err(Error::InvalidSigHashType(v)) => assert_eq!(v, invalid),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        let mut txb = TxBuilder::new(|_: &str| None);
+        let prev_txid: Txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        txb.add_input(prev_txid, 0, Some(1000), Some(&[0x51]), None)?;
+        txb.add_output(Amount::from_sat(500), &[0x51]);
+        // forgetting FORKID must not produce a digest
+        match txb.witness_v0_hash(sig_hash::ALL, 0, None, None) {
+            Err(Error::InvalidSigHashType(0x01)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match txb.sign_all(sig_hash::ALL, |_, _, _| unreachable!()) {
+            Err(Error::InvalidSigHashType(0x01)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn network() -> Result<()> {
+        let parser = |address: &str| Some((address.as_bytes()[address.len() - 20..].to_vec(), true));
+        let mut txb = TxBuilder::new(parser);
+        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        txb.add_input(prev_txid, 0, Some(10_000), Some(&p2pkh::script_pub_key(&[0x01; 20])?), None)?;
+        assert_eq!(txb.network(), Network::Mainnet);
+        match txb.add_address_output(Amount::from_sat(1000), "bchreg:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq") {
+            Err(Error::NetworkMismatch(Network::Mainnet)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        // unknown prefix and no prefix are left to address parser
+        txb.add_address_output(Amount::from_sat(1000), "custom:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq")?;
+
+        // fee rate below minimum relay fee rate is charged as given
+        let change = txb.add_change_output("qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq", "0.5".parse()?)?;
+        let size = txb.estimated_size_with_signatures()? as u64;
+        assert_eq!(txb.fee()?, Amount::from_sat(size.div_ceil(2)));
+        assert!(txb.fee()? < policy::MIN_RELAY_FEE_RATE.fee(size as usize));
+        assert_eq!(change, Amount::from_sat(9000).checked_sub(txb.fee()?).unwrap());
+
+        txb.add_output(Amount::ZERO, &[0x51]);
+        assert_eq!(txb.check_standard().len(), 1);
+        txb.set_network(Network::Regtest);
+        assert!(txb.check_standard().is_empty());
+        txb.add_address_output(Amount::from_sat(1000), "bchreg:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq")?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "address")]
+    #[test]
+    fn network_of_address() -> Result<()> {
+        use super::super::address::{self, Address, AddressNetwork};
+
+        let mut txb = TxBuilder::new(address::parse);
+        let hash = [0x01; 20];
+        let legacy = Address::P2PKH(AddressNetwork::Testnet, hash).to_legacy();
+        match txb.add_address_output(Amount::from_sat(1000), &legacy) {
+            Err(Error::NetworkMismatch(Network::Mainnet)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match txb.add_address_output(Amount::from_sat(1000), &Address::P2SH(AddressNetwork::Regtest, hash)) {
+            Err(Error::NetworkMismatch(Network::Mainnet)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        txb.set_network(Network::Testnet4);
+        txb.add_address_output(Amount::from_sat(1000), &legacy)?;
+        txb.set_network(Network::Regtest);
+        txb.add_address_output(Amount::from_sat(1000), &legacy)?;
+        txb.add_address_output(Amount::from_sat(1000), &Address::P2SH(AddressNetwork::Regtest, hash))?;
+        assert_eq!(txb.output_count(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn edit_inputs_and_outputs() -> Result<()> {
+        let mut txb = TxBuilder::new(|_: &str| None);
+        let prev_txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        for i in 0..3 {
+            txb.add_input(prev_txid, i, Some(1000 * (u64::from(i) + 1)), Some(&[0x51]), None)?;
+        }
+        txb.set_redeem_script(2, &[0x52])?;
+        txb.add_output(Amount::from_sat(100), &[0x51]);
+        txb.add_output(Amount::from_sat(200), &[0x52]);
+
+        txb.remove_input(1)?;
+        assert_eq!(txb.tx.inputs.len(), 2);
+        assert_eq!(txb.tx.inputs[1].outpoint.n, 2);
+        assert_eq!(txb.prev_outputs[&1].value, Amount::from_sat(3000));
+        assert_eq!(txb.redeem_scripts[&1], vec![0x52]);
+        assert_eq!(txb.fee()?, Amount::from_sat(4000 - 300));
+
+        txb.replace_output(1, Amount::from_sat(500), &[0x53])?;
+        txb.move_output(1, 0)?;
+        assert_eq!(txb.tx.outputs[0], Output::new(Amount::from_sat(500), &[0x53]));
+        txb.remove_output(1)?;
+        assert_eq!(txb.tx.outputs.len(), 1);
+
+        assert!(txb.remove_input(2).is_err());
+        assert!(txb.remove_output(1).is_err());
+        assert!(txb.replace_output(1, Amount::from_sat(0), &[]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn bip69() -> Result<()> {
+        let mut txb = TxBuilder::new(|_: &str| None);
+        // txids differ only in the first byte of the internal (reversed) order
+        let txid_a: Txid = "01000000000000000000000000000000000000000000000000000000000000ff".parse()?;
+        let txid_b: Txid = "ff00000000000000000000000000000000000000000000000000000000000001".parse()?;
+        txb.add_input(txid_b, 0, Some(3000), Some(&[0x53]), None)?;
+        txb.add_input(txid_a, 1, None, None, None)?;
+        txb.add_input(txid_a, 0, Some(1000), Some(&[0x51]), None)?;
+        txb.set_script_sig(0, &[0x00])?;
+        txb.set_redeem_script(2, &[0x52])?;
+        txb.add_output(Amount::from_sat(1000), &[0x52, 0x00]);
+        txb.add_output(Amount::from_sat(1000), &[0x52]);
+        txb.add_output(Amount::from_sat(500), &[0x53]);
+
+        txb.sort_bip69();
+
+        let inputs = &txb.tx.inputs;
+        assert_eq!(inputs[0].outpoint, OutPoint { txid: txid_a, n: 0 });
+        assert_eq!(inputs[1].outpoint, OutPoint { txid: txid_a, n: 1 });
+        assert_eq!(inputs[2].outpoint, OutPoint { txid: txid_b, n: 0 });
+        assert_eq!(inputs[2].script, vec![0x00]);
+        assert_eq!(txb.prev_outputs[&0].value, Amount::from_sat(1000));
+        assert_eq!(txb.prev_outputs[&2].value, Amount::from_sat(3000));
+        assert!(!txb.prev_outputs.contains_key(&1));
+        assert_eq!(txb.redeem_scripts[&0], vec![0x52]);
+        assert_eq!(txb.redeem_scripts.len(), 1);
+
+        let outputs = txb.tx.outputs.iter().map(|o| (o.value.to_sat(), o.script.clone())).collect::<Vec<_>>();
+        assert_eq!(outputs, vec![(500, vec![0x53]), (1000, vec![0x52]), (1000, vec![0x52, 0x00])]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn shuffle() -> Result<()> {
+        use rand_core::SeedableRng;
+        type Builder = TxBuilder<fn(&str) -> Option<(Vec<u8>, bool)>>;
+
+        let build = || -> Result<Builder> {
+            let mut txb = Builder::new(|_| None);
+            for i in 0..8u8 {
+                let value = u64::from(i) * 1000;
+                txb.add_input(Txid::from([i; 32]), 0, Some(value), Some(&[0x51]), None)?;
+                txb.set_redeem_script(i as usize, &[i])?;
+                txb.add_output(Amount::from_sat(value), &[i]);
+            }
+            Ok(txb)
+        };
+
+        let mut txb = build()?;
+        let mut other = build()?;
+        txb.shuffle_inputs(&mut rand_pcg::Pcg32::seed_from_u64(7));
+        txb.shuffle_outputs(&mut rand_pcg::Pcg32::seed_from_u64(7));
+        other.shuffle_inputs(&mut rand_pcg::Pcg32::seed_from_u64(7));
+        other.shuffle_outputs(&mut rand_pcg::Pcg32::seed_from_u64(7));
+        assert_eq!(txb.to_vec(), other.to_vec());
+        assert_ne!(txb.to_vec(), build()?.to_vec());
+
+        for (index, input) in txb.tx.inputs.iter().enumerate() {
+            let i = input.outpoint.txid.as_ref()[0];
+            assert_eq!(txb.prev_outputs[&index].value, Amount::from_sat(u64::from(i) * 1000));
+            assert_eq!(txb.redeem_scripts[&index], vec![i]);
+        }
+        let mut scripts = txb.tx.outputs.iter().map(|o| o.script[0]).collect::<Vec<_>>();
+        scripts.sort_unstable();
+        assert_eq!(scripts, (0..8).collect::<Vec<_>>());
+
+        Ok(())
+    }
+
+    #[test]
+    fn null_data_size() -> Result<()> {
+        let mut txb = TxBuilder::new(|_: &str| None);
+        txb.add_null_data_output(&[0; policy::MAX_NULL_DATA_SIZE])?;
+        assert!(txb.check_standard().is_empty());
+        match txb.add_null_data_output(&[0; policy::MAX_NULL_DATA_SIZE + 1]) {
+            Err(Error::NonStandardDataSize(221)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(txb.output_count(), 1);
+
+        txb.add_null_data_output_unchecked(&[0; policy::MAX_NULL_DATA_SIZE + 1])?;
+        assert_eq!(txb.output_count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn clone_outputs_only() -> Result<()> {
+        let mut txb = TxBuilder::new(|_: &str| None);
+        txb.set_version(1);
+        txb.set_lock_time(LockTime::Height(600_000));
+        txb.set_network(Network::Chipnet);
+        let prev_txid: Txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        txb.add_input(prev_txid, 0, Some(50_000), Some(&[0x51]), None)?;
+        txb.set_redeem_script(0, &[0x52])?;
+        txb.add_output(Amount::from_sat(49_000), &[0x51]);
+
+        let template = txb.clone_outputs_only();
+        assert!(template.prev_outputs.is_empty());
+        assert!(template.redeem_scripts.is_empty());
+        assert_eq!(template.network(), Network::Chipnet);
+        assert_eq!(template.tx, Transaction { inputs: vec![], ..txb.tx.clone() });
+
+        Ok(())
+    }
+
+    #[test]
+    fn consolidate() -> Result<()> {
+        let prev_script = p2pkh::script_pub_key(&[0x01; 20])?;
+        let utxos = (0..3).map(|i| Utxo { txid: [0x01; 32].into(), vout: i, value: 1000 }).collect::<Vec<_>>();
+
+        let mut txb = TxBuilder::new(|_: &str| Some((vec![0x02; 20], true)));
+        assert!(txb.consolidate(&utxos, &prev_script, "destination", FeeRate::MIN_RELAY)?.is_empty());
+        assert_eq!(txb.input_count(), 3);
+        let size = txb.estimated_size_with_signatures()? as u64;
+        assert_eq!(txb.fee()?, Amount::from_sat(size));
+        assert_eq!(txb.tx.outputs[0].value, Amount::from_sat(3000 - size));
+
+        let mut txb = TxBuilder::new(|_: &str| Some((vec![0x02; 20], true)));
+        match txb.consolidate(&utxos, &prev_script, "destination", FeeRate::from_sat_per_byte(10)) {
+            Err(Error::InsufficientFunds(3000, _)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(txb.input_count(), 0);
+        assert_eq!(txb.output_count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn anti_fee_sniping_locktime() -> Result<()> {
+        use rand_core::SeedableRng;
+
+        let mut txb = TxBuilder::new(|_: &str| None);
+        let prev_txid: Txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+        txb.add_input(prev_txid, 0, None, None, None)?;
+        txb.add_input(prev_txid, 1, None, None, Some(10))?;
+
+        let mut rng = rand_pcg::Pcg32::seed_from_u64(0);
+        let heights = (0..1000)
+            .map(|_| u32::from(txb.set_anti_fee_sniping_locktime(50, &mut rng)))
+            .collect::<Vec<_>>();
+        assert!(heights.iter().all(|&h| h <= 50));
+        let moved = heights.iter().filter(|&&h| h < 50).count();
+        assert!(moved > 50 && moved < 150, "{}", moved);
+        assert!(heights.contains(&0));
+
+        assert_eq!(txb.tx.inputs[0].sequence_no, u32::from(Sequence::MAX_NON_FINAL));
+        assert_eq!(txb.tx.inputs[1].sequence_no, 10);
+
+        Ok(())
+    }
+
+    #[test]
+    fn split_to_max_size() -> Result<()> {
+        let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+        let change = hex::encode([0x03; 20]);
+        let prev_script = p2pkh::script_pub_key(&[0x01; 20])?;
+        let mut txb = TxBuilder::new(parser);
+        for i in 0..10 {
+            txb.add_input([0x01; 32].into(), i, Some(10_000), Some(&prev_script), None)?;
+        }
+        let category = u256::from_str("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb")?;
+        let mut token_output = Output::new(Amount::from_sat(1000), &prev_script);
+        token_output.token = Some(TokenData::new(category, 1, None)?);
+        txb.set_prev_output(0, token_output.clone())?;
+        txb.add_token_output(Amount::from_sat(1000), &prev_script, token_output.token.clone().unwrap());
+
+        assert_eq!(txb.split_to_max_size(100_000, &change, FeeRate::MIN_RELAY)?.len(), 1);
+
+        let mut txs = txb.split_to_max_size(600, &change, FeeRate::MIN_RELAY)?;
+        assert_eq!(txs.len(), 5);
+        for (parent, child) in txs.iter().zip(&txs[1..]) {
+            assert_eq!(child.tx.inputs[0].outpoint, OutPoint { txid: parent.txid(), n: 0 });
+            assert_eq!(child.prev_outputs[&0], parent.tx.outputs[0]);
+        }
+        assert_eq!(txs[4].tx.outputs, txb.tx.outputs);
+        assert_eq!(txs[4].prev_outputs[&1], token_output);
+        assert_eq!(txs.iter().map(|tx| tx.input_count()).sum::<usize>(), 10 + 4);
+        let fees = txs.iter().try_fold(Amount::ZERO, |sum, tx| Ok::<_, Error>(sum.checked_add(tx.fee()?).unwrap()))?;
+        assert_eq!(fees, txb.fee()?);
+        txs[4].add_change_output(&change, FeeRate::MIN_RELAY)?;
+        for tx in &txs {
+            assert_eq!(tx.fee()?, Amount::from_sat(tx.estimated_size_with_signatures()? as u64));
+        }
+
+        txb.add_change_output(&change, FeeRate::MIN_RELAY)?;
+
+        match txb.split_to_max_size(200, &change, FeeRate::MIN_RELAY) {
+            Err(Error::TxTooLarge(_, 200)) => {},
+            r => panic!("unexpected result: {:?}", r.map(|txs| txs.len())),
+        }
+        match txb.split_to_max_size(600, &change, FeeRate::from_sat_per_byte(100)) {
+            Err(Error::InsufficientFunds(_, _)) => {},
+            r => panic!("unexpected result: {:?}", r.map(|txs| txs.len())),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_outputs_from() -> Result<()> {
+        let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+        let mut txb = TxBuilder::new(parser);
+        let address = hex::encode([0x01; 20]);
+        let rows = vec![
+            (address.clone(), 1000),
+            ("bchtest:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq".to_string(), 1000),
+            (address.clone(), u64::MAX),
+            (address.clone(), Amount::MAX_MONEY.to_sat()),
+        ];
+        match txb.add_outputs_from(rows, true) {
+            Err(Error::BatchError(rows)) => {
+                let rows = rows.iter().map(|(i, e)| (*i, e.to_string())).collect::<Vec<_>>();
+                assert_eq!(rows.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![1, 2, 3]);
+                assert!(rows[0].1.contains("network"));
+            },
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(txb.output_count(), 0);
+
+        txb.add_outputs_from(vec![(address.clone(), 1000), (address, 1000)], false)?;
+        assert_eq!(txb.output_count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn input_state() -> Result<()> {
+        use super::super::script::bare_multisig;
+
+        let mut txb = TxBuilder::new(|_: &str| None);
+        let bare = bare_multisig::script_pub_key(2, &[&[0x02; 33], &[0x03; 33], &[0x02; 33]])?;
+        txb.add_input([0x01; 32].into(), 0, Some(1000), Some(&bare), None)?;
+        txb.add_input([0x01; 32].into(), 1, None, None, None)?;
+        txb.add_output(Amount::from_sat(500), &bare);
+        match txb.to_complete_vec() {
+            Err(Error::IncompleteInput(0)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        txb.set_script_sig(0, &bare_multisig::script_sig(&[&[0x30; 72], &[]])?)?;
+        assert_eq!(txb.input_state(0)?, InputState::PartiallySigned);
+        txb.set_script_sig(0, &bare_multisig::script_sig(&[&[0x30; 72], &[0x30; 72]])?)?;
+        assert_eq!(txb.input_state(0)?, InputState::Finalized);
+
+        // signed input is finalized without previous output
+        txb.set_script_sig(1, &p2pkh::script_sig(&[0x02; 33], &[0x30; 72])?)?;
+        assert_eq!(txb.input_state(1)?, InputState::Finalized);
+        assert!(txb.is_complete());
+        assert_eq!(txb.to_complete_vec()?, txb.to_vec());
+        assert!(txb.input_state(2).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn hydrate_prevout() -> Result<()> {
+        use std::convert::TryFrom;
+
+        let prev_script = p2pkh::script_pub_key(&[0x01; 20])?;
+        let mut txb = TxBuilder::new(|_: &str| None);
+        txb.add_input([0x01; 32].into(), 0, Some(2000), Some(&prev_script), None)?;
+        txb.add_input([0x01; 32].into(), 1, Some(3000), Some(&prev_script), None)?;
+        txb.add_output(Amount::from_sat(4000), &prev_script);
+        let tx = Transaction::try_from(&txb.to_vec()[..])?;
+
+        let prevouts = [(2000, prev_script.clone()), (3000, prev_script.clone())];
+        let hydrated = TxBuilder::from_tx_with_prevouts(&tx, &prevouts, |_: &str| None)?;
+        assert_eq!(hydrated.fee()?, Amount::from_sat(1000));
+        assert_eq!(hydrated.witness_v0_hash(sig_hash::ALL | sig_hash::FORKID, 1, None, None)?,
+            txb.witness_v0_hash(sig_hash::ALL | sig_hash::FORKID, 1, None, None)?);
+
+        match TxBuilder::from_tx_with_prevouts(&tx, &prevouts[..1], |_: &str| None) {
+            Err(Error::MissingPrevOutput(1)) => {},
+            r => panic!("unexpected result: {:?}", r.map(|txb| txb.tx)),
+        }
+        let mut extra = prevouts.to_vec();
+        extra.push((1000, prev_script.clone()));
+        match TxBuilder::from_tx_with_prevouts(&tx, &extra, |_: &str| None) {
+            Err(Error::InvalidIndex(2)) => {},
+            r => panic!("unexpected result: {:?}", r.map(|txb| txb.tx)),
+        }
+
+        let mut txb = TxBuilder::from_tx(&tx, |_: &str| None)?;
+        assert!(txb.fee().is_err());
+        match txb.hydrate_prevout(0, Amount::MAX_MONEY.to_sat() + 1, &prev_script) {
+            Err(Error::InvalidAmount(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        txb.hydrate_prevout(0, 2000, &prev_script)?;
+        txb.hydrate_prevout(1, 3000, &prev_script)?;
+        assert_eq!(txb.fee()?, Amount::from_sat(1000));
+
+        Ok(())
+    }
+
+    #[test]
+    fn push_input_output() -> Result<()> {
+        let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+        let mut txb = TxBuilder::new(&parser);
+        let outpoint: OutPoint = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c:1".parse()?;
+        let redeem_script = [0x51];
+        let token = TokenData::new(outpoint.txid.into(), 100, None)?;
+
+        assert_eq!(txb.push_input(InputBuilder::new(outpoint.clone()).value(Amount::from_sat(1000)))?, 0);
+        assert!(!txb.prev_outputs.contains_key(&0));
+        let index = txb.push_input(InputBuilder::new(OutPoint { n: 2, ..outpoint.clone() })
+            .value(Amount::from_sat(2000))
+            .script(&p2sh::script_pub_key(&hash::hash160(&redeem_script))?)
+            .token(token.clone())
+            .redeem_script(&redeem_script)
+            .sequence(0))?;
+        assert_eq!(index, 1);
+        assert_eq!(txb.prev_outputs[&1].token, Some(token.clone()));
+        assert_eq!(txb.redeem_scripts[&1], redeem_script);
+        assert_eq!(txb.tx.inputs[1].sequence_no, 0);
+        match txb.push_input(&InputBuilder::new(OutPoint { txid: Txid::default(), n: 0xffff_ffff })) {
+            Err(Error::NullOutPoint(2)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        txb.push_output(OutputBuilder::to_script(&[0x52]).value(Amount::from_sat(1000)).token(token.clone()))?;
+        txb.push_output(OutputBuilder::to_address(&hex::encode([0x01; 20])).value(Amount::from_sat(500)))?;
+        assert_eq!(txb.tx.outputs[0], Output::with_token(Amount::from_sat(1000), &[0x52], token));
+        assert_eq!(txb.tx.outputs[1].script, p2pkh::script_pub_key(&[0x01; 20])?);
+        match txb.push_output(&OutputBuilder::to_address("zz")) {
+            Err(Error::InvalidAddress(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(txb.output_count(), 2);
 
         Ok(())
     }
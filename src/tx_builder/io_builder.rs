@@ -0,0 +1,141 @@
+use super::super::types::Amount;
+use super::super::types::transaction::{OutPoint, Output, TokenData};
+
+/// Input to be added by `TxBuilder::push_input`
+/// # Example
+/// ```
+/// # use cash_tx_builder::{TxBuilder, Amount, InputBuilder, Sequence};
+/// # use cash_tx_builder::script::p2pkh;
+/// let mut txb = TxBuilder::new(|_: &str| None);
+/// let outpoint = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c:1".parse()?;
+/// let prev_script = p2pkh::script_pub_key(&[0x01; 20])?;
+/// let index = txb.push_input(InputBuilder::new(outpoint)
+///     .value(Amount::from_sat(100_000))
+///     .script(&prev_script)
+///     .sequence(Sequence::MAX_NON_FINAL.into()))?;
+/// assert_eq!(index, 0);
+/// assert_eq!(txb.input_value(), Some(Amount::from_sat(100_000)));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputBuilder {
+    pub(super) outpoint: OutPoint,
+    pub(super) value: Option<Amount>,
+    pub(super) script: Option<Vec<u8>>,
+    pub(super) token: Option<TokenData>,
+    pub(super) sequence_no: Option<u32>,
+    pub(super) redeem_script: Option<Vec<u8>>,
+}
+
+impl InputBuilder {
+    /// Construct input spending `outpoint`
+    pub fn new(outpoint: OutPoint) -> InputBuilder {
+        InputBuilder {
+            outpoint,
+            value: None,
+            script: None,
+            token: None,
+            sequence_no: None,
+            redeem_script: None,
+        }
+    }
+
+    /// Set value of previous output
+    pub fn value(&mut self, value: Amount) -> &mut InputBuilder {
+        self.value = Some(value);
+        self
+    }
+
+    /// Set `scriptPubKey` of previous output
+    pub fn script(&mut self, script: &[u8]) -> &mut InputBuilder {
+        self.script = Some(script.to_vec());
+        self
+    }
+
+    /// Set token data of previous output
+    pub fn token(&mut self, token: TokenData) -> &mut InputBuilder {
+        self.token = Some(token);
+        self
+    }
+
+    /// Set sequence number (default: `0xffffffff`)
+    pub fn sequence(&mut self, sequence_no: u32) -> &mut InputBuilder {
+        self.sequence_no = Some(sequence_no);
+        self
+    }
+
+    /// Set `redeem script` of P2SH input
+    pub fn redeem_script(&mut self, script: &[u8]) -> &mut InputBuilder {
+        self.redeem_script = Some(script.to_vec());
+        self
+    }
+
+    /// Previous output if both value and `scriptPubKey` are set
+    pub(super) fn prev_output(&self) -> Option<Output> {
+        let (value, script) = (self.value?, self.script.as_ref()?);
+
+        Some(Output {
+            value,
+            script: script.clone(),
+            token: self.token.clone(),
+        })
+    }
+}
+
+/// Destination of output
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Destination {
+    Script(Vec<u8>),
+    Address(String),
+}
+
+/// Output to be added by `TxBuilder::push_output`
+/// # Example
+/// ```
+/// # use cash_tx_builder::{TxBuilder, Amount, OutputBuilder};
+/// let parser = |address: &str| hex::decode(address).ok().map(|hash| (hash, true));
+/// let mut txb = TxBuilder::new(&parser);
+/// txb.push_output(OutputBuilder::to_address(&hex::encode([0x01; 20])).value(Amount::from_sat(1000)))?;
+/// let index = txb.push_output(&OutputBuilder::to_script(&[0x51]))?;
+/// assert_eq!(index, 1);
+/// assert_eq!(txb.output_value(), Some(Amount::from_sat(1000)));
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputBuilder {
+    pub(super) destination: Destination,
+    pub(super) value: Amount,
+    pub(super) token: Option<TokenData>,
+}
+
+impl OutputBuilder {
+    /// Construct output paying to `scriptPubKey`
+    pub fn to_script(script: &[u8]) -> OutputBuilder {
+        OutputBuilder::new(Destination::Script(script.to_vec()))
+    }
+
+    /// Construct output paying to address, parsed by `TxBuilder`'s address parser
+    pub fn to_address(address: &str) -> OutputBuilder {
+        OutputBuilder::new(Destination::Address(address.to_string()))
+    }
+
+    fn new(destination: Destination) -> OutputBuilder {
+        OutputBuilder {
+            destination,
+            value: Amount::ZERO,
+            token: None,
+        }
+    }
+
+    /// Set value (default: `0`)
+    pub fn value(&mut self, value: Amount) -> &mut OutputBuilder {
+        self.value = value;
+        self
+    }
+
+    /// Set token data
+    pub fn token(&mut self, token: TokenData) -> &mut OutputBuilder {
+        self.token = Some(token);
+        self
+    }
+}
@@ -0,0 +1,277 @@
+//! Sighash test vectors
+//!
+//! Legacy vectors are taken from Bitcoin Core's `sighash.json` (with hash types as raw `u32`).
+//!
+//! Replay-protected vectors are not taken from node test data.
+//! They were computed by a standalone script written from the BCH sighash spec
+//! (`replay-protected-sighash.md`, CashTokens and `SIGHASH_UTXOS` CHIPs),
+//! over the reference transaction of this crate's examples, the Core transactions above
+//! and a minting transaction, covering `ANYONECANPAY`, `NONE`, `SINGLE` without matching output,
+//! `UTXOS` and token prefix of spent output.
+//! Signers computing digests on their own can run them with `check_with`.
+//! # Example
+//! ```
+//! # use cash_tx_builder::sig_hash::test_vectors::{self, Algorithm};
+//! assert!(test_vectors::check().is_empty());
+//!
+//! // e.g. digest computed by external signer
+//! let failures = test_vectors::check_with(|v| match v.algorithm {
+//!     Algorithm::Legacy => v.compute(),
+//!     Algorithm::ReplayProtected => Ok([0; 32].into()),
+//! });
+//! assert_eq!(failures, (5..test_vectors::VECTORS.len()).collect::<Vec<_>>());
+//! ```
+
+use std::convert::TryFrom;
+use super::SigHashType;
+use super::super::TxBuilder;
+use crate::hash::SigHash;
+use crate::types::transaction::{Output, Transaction};
+use crate::error::Result;
+
+/// Digest algorithm of vector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// original (pre-fork) algorithm, `TxBuilder::legacy_hash`
+    Legacy,
+    /// replay-protected (BIP143 style) algorithm, `TxBuilder::witness_v0_hash`
+    ReplayProtected,
+}
+
+/// Test vector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vector {
+    /// digest algorithm
+    pub algorithm: Algorithm,
+    /// serialized transaction in hex
+    pub raw_tx: &'static str,
+    /// `scriptCode` in hex
+    pub script_code: &'static str,
+    /// input index
+    pub index: u32,
+    /// raw sighash type
+    pub hash_type: u32,
+    /// previous value (ignored by legacy algorithm)
+    pub prev_value: u64,
+    /// serialized previous outputs of all inputs in hex (empty if not committed)
+    pub utxos: &'static [&'static str],
+    /// digest in hex (in byte order, as `SigHash` is displayed)
+    pub expected: &'static str,
+}
+
+/// All vectors
+pub const VECTORS: &[Vector] = &[
+    Vector {
+        algorithm: Algorithm::Legacy,
+        raw_tx: "73107cbd025c22ebc8c3e0a47b2a760739216a528de8d4dab5d45cbeb3051cebae73b01ca10200000007ab6353656a636affffffffe26816dffc670841e6a6c8c61c586da401df1261a330a6c6b3dd9f9a0789bc9e000000000800ac6552ac6aac51ffffffff0174a8f0010000000004ac52515100000000",
+        script_code: "5163ac63635151ac",
+        index: 1,
+        hash_type: 1_190_874_345,
+        prev_value: 0,
+        utxos: &[],
+        expected: "bc464a1f61c41016a30d0360f5c7a56e7a62212a22beea9bb0873a26de28e306",
+    },
+    Vector {
+        algorithm: Algorithm::Legacy,
+        raw_tx: "50818f4c01b464538b1e7e7f5ae4ed96ad23c68c830e78da9a845bc19b5c3b0b20bb82e5e9030000000763526a63655352ffffffff023b3f9c040000000008630051516a6a5163a83caf01000000000553ab65510000000000",
+        script_code: "6aac",
+        index: 0,
+        hash_type: 946_795_545,
+        prev_value: 0,
+        utxos: &[],
+        expected: "2d4e5a8addd481e8dd2c06882fc23324a7f683aefae7ff584b2bde22f3066374",
+    },
+    Vector {
+        algorithm: Algorithm::Legacy,
+        raw_tx: "a93e93440250f97012d466a6cc24839f572def241c814fe6ae94442cf58ea33eb0fdd9bcc1030000000600636a0065acffffffff5dee3a6e7e5ad6310dea3e5b3ddda1a56bf8de7d3b75889fc024b5e233ec10f80300000007ac53635253ab53ffffffff0160468b04000000000800526a5300ac526a00000000",
+        script_code: "ac00636a53",
+        index: 1,
+        hash_type: 1_773_442_520,
+        prev_value: 0,
+        utxos: &[],
+        expected: "bdbc08a9564baef82c61440920bf8abb43c87945aabbfa2cb75b36e92c3a9d5c",
+    },
+    Vector {
+        algorithm: Algorithm::Legacy,
+        raw_tx: "d3b7421e011f4de0f1cea9ba7458bf3486bee722519efab711a963fa8c100970cf7488b7bb0200000003525352dcd61b300148be5d05000000000000000000",
+        script_code: "535251536aac536a",
+        index: 0,
+        hash_type: 2_334_839_171,
+        prev_value: 0,
+        utxos: &[],
+        expected: "2a3e05923eb3075fde1e16965fa3f6b745d30a774204a2eb10332d752d6daa29",
+    },
+    Vector {
+        algorithm: Algorithm::Legacy,
+        raw_tx: "c363a70c01ab174230bbe4afe0c3efa2d7f2feaf179431359adedccf30d1f69efe0c86ed390200000002ab51558648fe0231318b04000000000151662170000000000008ac5300006a63acac00000000",
+        script_code: "",
+        index: 0,
+        hash_type: 2_146_479_410,
+        prev_value: 0,
+        utxos: &[],
+        expected: "702c5d59de642e4711481d0dcbb766488d131f057d7171367653d7b080b11a19",
+    },
+    Vector {
+        algorithm: Algorithm::ReplayProtected,
+        raw_tx: "02000000014ceee1213404c7a96b80b86f85487220599dbefcbc192c55336a0e968afc7c42010000006a47304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b3041210366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036ffffffff02f82a0000000000001976a91426b61031a0fdf3cb9f17da8b9c1fc54a638c369188acb55a0100000000001976a9142647f73de90d8e9db5b5f77d1cc1ad5afe81d68c88ac00000000",
+        script_code: "76a9143424f163208a3b676fa0ec17034f0f290322a2a688ac",
+        index: 0,
+        hash_type: 0x41,
+        prev_value: 100_000,
+        utxos: &[],
+        expected: "2b492e7c4c8a3d670fd7fe324a87e3c55df1802c9a100f4006f8fff7c0913dd4",
+    },
+    Vector {
+        algorithm: Algorithm::ReplayProtected,
+        raw_tx: "73107cbd025c22ebc8c3e0a47b2a760739216a528de8d4dab5d45cbeb3051cebae73b01ca10200000007ab6353656a636affffffffe26816dffc670841e6a6c8c61c586da401df1261a330a6c6b3dd9f9a0789bc9e000000000800ac6552ac6aac51ffffffff0174a8f0010000000004ac52515100000000",
+        script_code: "5163ac63635151ac",
+        index: 1,
+        hash_type: 0x41,
+        prev_value: 50_000,
+        utxos: &[],
+        expected: "ae8c178feab3f53aecfdfefdb04e3b4cca4b9f5d20bd96188f3646fcb5c1d8d4",
+    },
+    Vector {
+        algorithm: Algorithm::ReplayProtected,
+        raw_tx: "a93e93440250f97012d466a6cc24839f572def241c814fe6ae94442cf58ea33eb0fdd9bcc1030000000600636a0065acffffffff5dee3a6e7e5ad6310dea3e5b3ddda1a56bf8de7d3b75889fc024b5e233ec10f80300000007ac53635253ab53ffffffff0160468b04000000000800526a5300ac526a00000000",
+        script_code: "ac00636a53",
+        index: 1,
+        hash_type: 0x43,
+        prev_value: 1_000,
+        utxos: &[],
+        expected: "6303895f1f3e18367db9c20c2f8f425f986f8447a52697b351b5cfb634440f3c",
+    },
+    Vector {
+        algorithm: Algorithm::ReplayProtected,
+        raw_tx: "50818f4c01b464538b1e7e7f5ae4ed96ad23c68c830e78da9a845bc19b5c3b0b20bb82e5e9030000000763526a63655352ffffffff023b3f9c040000000008630051516a6a5163a83caf01000000000553ab65510000000000",
+        script_code: "6aac",
+        index: 0,
+        hash_type: 0xc2,
+        prev_value: 70_000_000,
+        utxos: &[],
+        expected: "4ba7dd1d7747a4464e42cbc922c4462513ccd92024eaccd405dda834b4ae4bc5",
+    },
+    Vector {
+        algorithm: Algorithm::ReplayProtected,
+        raw_tx: "c363a70c01ab174230bbe4afe0c3efa2d7f2feaf179431359adedccf30d1f69efe0c86ed390200000002ab51558648fe0231318b04000000000151662170000000000008ac5300006a63acac00000000",
+        script_code: "",
+        index: 0,
+        hash_type: 0xc3,
+        prev_value: 12_345,
+        utxos: &[],
+        expected: "7e209c6f8cdf2f0d70900d00e2e71bef527dfe8ff2b947c6ffb725f586ba48c3",
+    },
+    Vector {
+        algorithm: Algorithm::ReplayProtected,
+        raw_tx: "73107cbd025c22ebc8c3e0a47b2a760739216a528de8d4dab5d45cbeb3051cebae73b01ca10200000007ab6353656a636affffffffe26816dffc670841e6a6c8c61c586da401df1261a330a6c6b3dd9f9a0789bc9e000000000800ac6552ac6aac51ffffffff0174a8f0010000000004ac52515100000000",
+        script_code: "5163ac63635151ac",
+        index: 0,
+        hash_type: 0x61,
+        prev_value: 10_000,
+        utxos: &[
+            "10270000000000000151",
+            "50c30000000000002befbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb10645163ac63635151ac",
+        ],
+        expected: "cfcb67867a611f504f1ded80a7baf5738022d211e77435258f5ffe3e8f575d9f",
+    },
+    Vector {
+        algorithm: Algorithm::ReplayProtected,
+        raw_tx: "73107cbd025c22ebc8c3e0a47b2a760739216a528de8d4dab5d45cbeb3051cebae73b01ca10200000007ab6353656a636affffffffe26816dffc670841e6a6c8c61c586da401df1261a330a6c6b3dd9f9a0789bc9e000000000800ac6552ac6aac51ffffffff0174a8f0010000000004ac52515100000000",
+        script_code: "5163ac63635151ac",
+        index: 1,
+        hash_type: 0x61,
+        prev_value: 50_000,
+        utxos: &[
+            "10270000000000000151",
+            "50c30000000000002befbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb10645163ac63635151ac",
+        ],
+        expected: "924fac7c54d2a9048a7f19029aa1fd72a0dbc102f13ac0805777a1d5689fb706",
+    },
+    Vector {
+        algorithm: Algorithm::ReplayProtected,
+        raw_tx: "02000000014ceee1213404c7a96b80b86f85487220599dbefcbc192c55336a0e968afc7c420000000000ffffffff02e8030000000000003bef4ceee1213404c7a96b80b86f85487220599dbefcbc192c55336a0e968afc7c422276a914751e76e8199196d454941c45d1b3a323f1433bd688ac200300000000000025ef4ceee1213404c7a96b80b86f85487220599dbefcbc192c55336a0e968afc7c426001cc5200000000",
+        script_code: "76a914751e76e8199196d454941c45d1b3a323f1433bd688ac",
+        index: 0,
+        hash_type: 0x41,
+        prev_value: 3_000,
+        utxos: &[
+            "b80b0000000000003bef4ceee1213404c7a96b80b86f85487220599dbefcbc192c55336a0e968afc7c422276a914751e76e8199196d454941c45d1b3a323f1433bd688ac",
+        ],
+        expected: "ac3cbac8bae3aca8e9b02a2ea63caa43b0826eb30adc91f77e399fd0fba4ae5c",
+    },
+    Vector {
+        algorithm: Algorithm::ReplayProtected,
+        raw_tx: "02000000014ceee1213404c7a96b80b86f85487220599dbefcbc192c55336a0e968afc7c420000000000ffffffff02e8030000000000003bef4ceee1213404c7a96b80b86f85487220599dbefcbc192c55336a0e968afc7c422276a914751e76e8199196d454941c45d1b3a323f1433bd688ac200300000000000025ef4ceee1213404c7a96b80b86f85487220599dbefcbc192c55336a0e968afc7c426001cc5200000000",
+        script_code: "76a914751e76e8199196d454941c45d1b3a323f1433bd688ac",
+        index: 0,
+        hash_type: 0x61,
+        prev_value: 3_000,
+        utxos: &[
+            "b80b0000000000003bef4ceee1213404c7a96b80b86f85487220599dbefcbc192c55336a0e968afc7c422276a914751e76e8199196d454941c45d1b3a323f1433bd688ac",
+        ],
+        expected: "34e5c5c513b92aa7492d4b8d2ccb2c2553ee08676471f5d6203a05a5297acdea",
+    },
+];
+
+impl Vector {
+    /// Parsed transaction
+    pub fn tx(&self) -> Result<Transaction> {
+        Transaction::try_from(&hex::decode(self.raw_tx)?[..])
+    }
+
+    /// `scriptCode`
+    pub fn script_code(&self) -> Vec<u8> {
+        hex::decode(self.script_code).expect("valid hex")
+    }
+
+    /// Parsed previous outputs
+    pub fn utxos(&self) -> Result<Vec<Output>> {
+        self.utxos.iter()
+            .map(|o| Output::try_from(&hex::decode(o)?[..]))
+            .collect()
+    }
+
+    /// Expected digest
+    pub fn expected(&self) -> SigHash {
+        let mut v = [0; 32];
+        v.copy_from_slice(&hex::decode(self.expected).expect("valid hex"));
+        v.into()
+    }
+
+    /// Digest computed by this crate
+    pub fn compute(&self) -> Result<SigHash> {
+        let mut txb = TxBuilder::from_tx(&self.tx()?, |_: &str| None)?;
+        for (index, output) in self.utxos()?.into_iter().enumerate() {
+            txb.set_prev_output(index, output)?;
+        }
+        let script_code = self.script_code();
+
+        match self.algorithm {
+            Algorithm::Legacy => txb.legacy_hash(self.hash_type, self.index, Some(&script_code)),
+            Algorithm::ReplayProtected => {
+                let hash_type = SigHashType::from_u32(self.hash_type)?;
+                txb.witness_v0_hash(hash_type, self.index, Some(self.prev_value), Some(&script_code))
+            },
+        }
+    }
+}
+
+/// Run all vectors with `digest`
+/// # Arguments
+/// * `digest` - computes digest of vector
+/// # Returns
+/// * indexes of vectors whose digest is wrong or failed
+pub fn check_with<F>(mut digest: F) -> Vec<usize>
+    where F: FnMut(&Vector) -> Result<SigHash> {
+    VECTORS.iter().enumerate()
+        .filter(|(_, v)| digest(v).ok() != Some(v.expected()))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Run all vectors with this crate
+/// # Returns
+/// * indexes of failed vectors (empty if all passed)
+pub fn check() -> Vec<usize> {
+    check_with(Vector::compute)
+}
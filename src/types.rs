@@ -1,9 +1,20 @@
 mod error;
 /// Transaction structures
 pub mod transaction;
+/// Block structures
+pub mod block;
 mod uint256;
 mod var_int;
+mod lock_time;
+mod txid;
+mod amount;
+mod fee_rate;
 
+/// Alias of `Error` kept for compatibility
 pub use error::Error as TypeError;
 pub use var_int::*;
 pub use uint256::uint256 as u256;
+pub use txid::Txid;
+pub use amount::{Amount, COIN};
+pub use fee_rate::FeeRate;
+pub use lock_time::{LockTime, Sequence, LOCKTIME_THRESHOLD};
@@ -0,0 +1,139 @@
+use std::fmt;
+use super::error::{Error, Result};
+
+/// Satoshis per BCH
+pub const COIN: u64 = 100_000_000;
+
+/// Value in satoshis
+///
+/// Arithmetic is checked against overflow and `MAX_MONEY`.
+/// Decimal BCH amounts are parsed from strings, never from floats.
+/// # Example
+/// ```
+/// # use cash_tx_builder::Amount;
+/// let amount = Amount::from_bch("0.0001")?;
+/// assert_eq!(amount, Amount::from_sat(10_000));
+/// assert_eq!(amount.to_bch(), "0.00010000");
+/// assert_eq!(amount.checked_add(Amount::MAX_MONEY), None);
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Amount(u64);
+
+impl Amount {
+    /// zero
+    pub const ZERO: Amount = Amount(0);
+    /// maximum valid value (21 million BCH)
+    pub const MAX_MONEY: Amount = Amount(21_000_000 * COIN);
+
+    /// Construct from satoshis
+    ///
+    /// Any `u64` is accepted as serialized transactions can carry it,
+    /// use `is_valid` to check range.
+    pub const fn from_sat(sat: u64) -> Amount {
+        Amount(sat)
+    }
+
+    /// Satoshis
+    pub const fn to_sat(self) -> u64 {
+        self.0
+    }
+
+    /// Parse decimal BCH amount, e.g. `"0.0001"`
+    /// # Arguments
+    /// * `s` - BCH amount with up to 8 decimal places
+    /// # Returns
+    /// * amount (error if malformed or exceeds `MAX_MONEY`)
+    pub fn from_bch(s: &str) -> Result<Amount> {
+        let invalid = || Error::InvalidAmount(s.to_string());
+        let (int, frac) = match s.find('.') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => (s, ""),
+        };
+        let is_digits = |v: &str| v.bytes().all(|b| b.is_ascii_digit());
+        if (int.is_empty() && frac.is_empty()) || frac.len() > 8 || !is_digits(int) || !is_digits(frac) {
+            return Err(invalid());
+        }
+
+        let int = if int.is_empty() { 0 } else { int.parse::<u64>().map_err(|_| invalid())? };
+        let frac = format!("{:0<8}", frac).parse::<u64>().map_err(|_| invalid())?;
+        int.checked_mul(COIN)
+            .and_then(|sat| sat.checked_add(frac))
+            .map(Amount)
+            .filter(|amount| amount.is_valid())
+            .ok_or_else(invalid)
+    }
+
+    /// Format as decimal BCH amount with 8 decimal places
+    pub fn to_bch(self) -> String {
+        format!("{}.{:08}", self.0 / COIN, self.0 % COIN)
+    }
+
+    /// `true` if not greater than `MAX_MONEY`
+    pub fn is_valid(self) -> bool {
+        self <= Amount::MAX_MONEY
+    }
+
+    /// Add, `None` if result exceeds `MAX_MONEY`
+    pub fn checked_add(self, rhs: Amount) -> Option<Amount> {
+        self.0.checked_add(rhs.0).map(Amount).filter(|amount| amount.is_valid())
+    }
+
+    /// Subtract, `None` if result is negative
+    pub fn checked_sub(self, rhs: Amount) -> Option<Amount> {
+        self.0.checked_sub(rhs.0).map(Amount)
+    }
+
+    /// Multiply, `None` if result exceeds `MAX_MONEY`
+    pub fn checked_mul(self, rhs: u64) -> Option<Amount> {
+        self.0.checked_mul(rhs).map(Amount).filter(|amount| amount.is_valid())
+    }
+}
+
+impl From<Amount> for u64 {
+    fn from(amount: Amount) -> u64 {
+        amount.0
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} BCH", self.to_bch())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bch_conversion() -> Result<()> {
+        assert_eq!(Amount::from_bch("1")?, Amount::from_sat(COIN));
+        assert_eq!(Amount::from_bch("0.00000001")?, Amount::from_sat(1));
+        assert_eq!(Amount::from_bch(".5")?, Amount::from_sat(50_000_000));
+        assert_eq!(Amount::from_bch("21000000")?, Amount::MAX_MONEY);
+        for invalid in &["", ".", "-1", "1e-8", "0.000000001", "21000000.00000001", "184467440737.09551616", "1.2.3"] {
+            match Amount::from_bch(invalid) {
+                Err(Error::InvalidAmount(_)) => {},
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        assert_eq!(Amount::from_sat(123_456_789).to_bch(), "1.23456789");
+        assert_eq!(Amount::from_sat(1).to_string(), "0.00000001 BCH");
+
+        Ok(())
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        let one = Amount::from_sat(1);
+        assert_eq!(Amount::MAX_MONEY.checked_add(one), None);
+        assert_eq!(Amount::from_sat(u64::MAX).checked_add(one), None);
+        assert_eq!(Amount::ZERO.checked_sub(one), None);
+        assert_eq!(one.checked_mul(u64::MAX), None);
+        assert_eq!(one.checked_add(one).and_then(|v| v.checked_mul(3)), Some(Amount::from_sat(6)));
+        assert!(!Amount::from_sat(u64::MAX).is_valid());
+    }
+}
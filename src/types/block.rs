@@ -0,0 +1,258 @@
+use std::convert::TryFrom;
+use super::transaction::Transaction;
+use super::var_int::VarInt;
+use super::txid::Txid;
+use super::uint256::uint256;
+use crate::hash;
+use super::error::{Error, Result};
+
+/// Size of serialized block header
+pub const HEADER_SIZE: usize = 80;
+
+/// Block header
+///
+/// Hashes are `Txid` to share reversed hex representation.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct BlockHeader {
+    /// version
+    pub version: u32,
+    /// hash of previous block
+    pub prev_block: Txid,
+    /// merkle root of transactions
+    pub merkle_root: Txid,
+    /// timestamp
+    pub time: u32,
+    /// compact target
+    pub bits: u32,
+    /// nonce
+    pub nonce: u32,
+}
+
+/// Block
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Block {
+    /// header
+    pub header: BlockHeader,
+    /// list of transactions (coinbase first)
+    pub transactions: Vec<Transaction>,
+}
+
+fn hash256(v: &[u8]) -> Txid {
+    Txid::from(<[u8; 32]>::from(hash::hash256(v)))
+}
+
+/// Compute merkle root
+/// # Arguments
+/// * `txids` - txids in block order
+/// # Returns
+/// * merkle root (all zero if `txids` is empty)
+/// # Example
+/// ```
+/// # use cash_tx_builder::Txid;
+/// # use cash_tx_builder::types::block::merkle_root;
+/// let txid: Txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b".parse()?;
+/// assert_eq!(merkle_root(&[txid]), txid);
+/// # Ok::<(), cash_tx_builder::TypeError>(())
+/// ```
+pub fn merkle_root(txids: &[Txid]) -> Txid {
+    let mut hashes = txids.to_vec();
+    while hashes.len() > 1 {
+        // odd hash is paired with itself
+        hashes = hashes.chunks(2)
+            .map(|pair| {
+                let (left, right) = (&pair[0], &pair[pair.len() - 1]);
+                hash256(&[left.as_ref(), right.as_ref()].concat())
+            })
+            .collect();
+    }
+
+    hashes.first().copied().unwrap_or_default()
+}
+
+impl From<&BlockHeader> for Vec<u8> {
+    fn from(h: &BlockHeader) -> Vec<u8> {
+        [
+            &h.version.to_le_bytes()[..],
+            h.prev_block.as_ref(),
+            h.merkle_root.as_ref(),
+            &h.time.to_le_bytes(),
+            &h.bits.to_le_bytes(),
+            &h.nonce.to_le_bytes(),
+        ].concat()
+    }
+}
+
+impl TryFrom<&[u8]> for BlockHeader {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<BlockHeader> {
+        if bytes.len() != HEADER_SIZE {
+            return Err(Error::BlockParseError(bytes.len().min(HEADER_SIZE)));
+        }
+        let u32_at = |i: usize| {
+            let mut v = [0; 4];
+            v.copy_from_slice(&bytes[i..i + 4]);
+            u32::from_le_bytes(v)
+        };
+        let hash_at = |i: usize| {
+            let mut v = [0; 32];
+            v.copy_from_slice(&bytes[i..i + 32]);
+            Txid::from(v)
+        };
+
+        Ok(BlockHeader {
+            version: u32_at(0),
+            prev_block: hash_at(4),
+            merkle_root: hash_at(36),
+            time: u32_at(68),
+            bits: u32_at(72),
+            nonce: u32_at(76),
+        })
+    }
+}
+
+impl BlockHeader {
+    /// Get block hash
+    pub fn hash(&self) -> Txid {
+        hash256(&Vec::from(self))
+    }
+
+    /// Target decoded from `bits`
+    /// # Returns
+    /// * target (`None` if `bits` is negative or overflows)
+    pub fn target(&self) -> Option<uint256> {
+        uint256::from_compact(self.bits)
+    }
+
+    /// Check that block hash is not above target
+    pub fn check_proof_of_work(&self) -> bool {
+        match self.target() {
+            Some(target) if !target.is_zero() => uint256::from(self.hash()) <= target,
+            _ => false,
+        }
+    }
+}
+
+impl From<&Block> for Vec<u8> {
+    fn from(b: &Block) -> Vec<u8> {
+        [
+            Vec::from(&b.header),
+            Vec::from(VarInt::from(b.transactions.len() as u64)),
+            b.transactions.iter().flat_map(Vec::from).collect(),
+        ].concat()
+    }
+}
+
+impl TryFrom<&[u8]> for Block {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Block> {
+        let header = BlockHeader::try_from(bytes.get(..HEADER_SIZE).unwrap_or(bytes))?;
+        let count = VarInt::try_from(&bytes[HEADER_SIZE..])
+            .map_err(|_| Error::BlockParseError(HEADER_SIZE))?;
+
+        let mut r = &bytes[HEADER_SIZE + Vec::from(count).len()..];
+        let transactions = (0..u64::from(count))
+            .map(|_| Transaction::read_from(&mut r))
+            .collect::<Result<Vec<_>>>()?;
+        if !r.is_empty() {
+            return Err(Error::BlockParseError(bytes.len() - r.len()));
+        }
+
+        Ok(Block {
+            header,
+            transactions,
+        })
+    }
+}
+
+impl Block {
+    /// Parse hex string
+    /// # Arguments
+    /// * `s` - serialized block in hex
+    pub fn from_hex(s: &str) -> Result<Block> {
+        Block::try_from(&hex::decode(s)?[..])
+    }
+
+    /// Convert to hex string
+    pub fn to_hex(&self) -> String {
+        hex::encode(Vec::from(self))
+    }
+
+    /// Get block hash
+    pub fn hash(&self) -> Txid {
+        self.header.hash()
+    }
+
+    /// Iterate transactions
+    pub fn iter(&self) -> impl Iterator<Item = &Transaction> {
+        self.transactions.iter()
+    }
+
+    /// Compute merkle root of transactions
+    pub fn merkle_root(&self) -> Txid {
+        merkle_root(&self.iter().map(Transaction::txid).collect::<Vec<_>>())
+    }
+
+    /// Check if merkle root in header matches transactions
+    pub fn check_merkle_root(&self) -> bool {
+        self.header.merkle_root == self.merkle_root()
+    }
+}
+
+impl<'a> IntoIterator for &'a Block {
+    type Item = &'a Transaction;
+    type IntoIter = std::slice::Iter<'a, Transaction>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.transactions.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c0101000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+
+    #[test]
+    fn genesis() -> Result<()> {
+        let block = Block::from_hex(GENESIS)?;
+        assert_eq!(block.hash().to_string(), "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+        assert_eq!(block.header.merkle_root.to_string(), "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b");
+        assert_eq!(block.header.time, 1_231_006_505);
+        assert_eq!(block.iter().count(), 1);
+        assert!(block.transactions[0].is_coinbase());
+        assert!(block.check_merkle_root());
+        assert_eq!(block.to_hex(), GENESIS);
+        assert_eq!(block.header.target(), Some(uint256::from(0xffff) << 208));
+        assert!(block.header.check_proof_of_work());
+        let header = BlockHeader { nonce: 0, ..block.header };
+        assert!(!header.check_proof_of_work());
+
+        let raw = hex::decode(GENESIS)?;
+        for len in 0..raw.len() {
+            assert!(Block::try_from(&raw[..len]).is_err());
+        }
+        match Block::try_from(&[&raw[..], &[0x00]].concat()[..]) {
+            Err(Error::BlockParseError(285)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn merkle() -> Result<()> {
+        let txids = ["01", "02", "03"].iter()
+            .map(|s| format!("{:0>64}", s).parse())
+            .collect::<Result<Vec<Txid>>>()?;
+        let h = |a: &Txid, b: &Txid| hash256(&[a.as_ref(), b.as_ref()].concat());
+
+        assert_eq!(merkle_root(&[]), Txid::default());
+        assert_eq!(merkle_root(&txids[..2]), h(&txids[0], &txids[1]));
+        assert_eq!(merkle_root(&txids), h(&h(&txids[0], &txids[1]), &h(&txids[2], &txids[2])));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+use std::str::FromStr;
+use super::amount::Amount;
+use super::error::{Error, Result};
+
+/// Fee rate in satoshis per 1000 bytes
+///
+/// Rates below 1 satoshi per byte (e.g. `0.5`) are kept exactly,
+/// fees are rounded up so that the rate is never undershot.
+/// # Example
+/// ```
+/// # use cash_tx_builder::{Amount, FeeRate};
+/// let rate: FeeRate = "0.5".parse()?;
+/// assert_eq!(rate, FeeRate::from_sat_per_kb(500));
+/// assert_eq!(rate.fee(1001), Amount::from_sat(501));
+/// assert_eq!(FeeRate::from_fee(Amount::from_sat(226), 226), FeeRate::MIN_RELAY);
+/// assert_eq!(rate.to_string(), "0.5 sat/B");
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct FeeRate(u64);
+
+impl FeeRate {
+    /// zero
+    pub const ZERO: FeeRate = FeeRate(0);
+    /// minimum rate relayed by default node (1 satoshi per byte)
+    pub const MIN_RELAY: FeeRate = FeeRate::from_sat_per_byte(1);
+
+    /// Construct from satoshis per 1000 bytes
+    pub const fn from_sat_per_kb(sat: u64) -> FeeRate {
+        FeeRate(sat)
+    }
+
+    /// Construct from satoshis per byte
+    pub const fn from_sat_per_byte(sat: u64) -> FeeRate {
+        FeeRate(sat.saturating_mul(1000))
+    }
+
+    /// Rate of `fee` paid for `size` bytes (rounded down)
+    pub fn from_fee(fee: Amount, size: usize) -> FeeRate {
+        match size {
+            0 => FeeRate::ZERO,
+            _ => FeeRate((u128::from(fee.to_sat()) * 1000 / size as u128).min(u128::from(u64::MAX)) as u64),
+        }
+    }
+
+    /// Satoshis per 1000 bytes
+    pub const fn to_sat_per_kb(self) -> u64 {
+        self.0
+    }
+
+    /// Fee of `size` bytes (rounded up, saturating)
+    pub fn fee(self, size: usize) -> Amount {
+        let fee = (u128::from(self.0) * size as u128).div_ceil(1000);
+        Amount::from_sat(fee.min(u128::from(u64::MAX)) as u64)
+    }
+}
+
+/// Parse decimal satoshis per byte with up to 3 decimal places, e.g. `"0.5"` (`" sat/B"` suffix is allowed)
+impl FromStr for FeeRate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<FeeRate> {
+        let invalid = || Error::InvalidFeeRate(s.to_string());
+        let v = s.strip_suffix(" sat/B").unwrap_or(s);
+        let (int, frac) = v.split_once('.').unwrap_or((v, ""));
+        let is_digits = |v: &str| v.bytes().all(|b| b.is_ascii_digit());
+        if (int.is_empty() && frac.is_empty()) || frac.len() > 3 || !is_digits(int) || !is_digits(frac) {
+            return Err(invalid());
+        }
+
+        let int = if int.is_empty() { 0 } else { int.parse::<u64>().map_err(|_| invalid())? };
+        let frac = format!("{:0<3}", frac).parse::<u64>().map_err(|_| invalid())?;
+        int.checked_mul(1000)
+            .and_then(|rate| rate.checked_add(frac))
+            .map(FeeRate)
+            .ok_or_else(invalid)
+    }
+}
+
+/// Satoshis per byte without trailing zeros, e.g. `0.5 sat/B`
+impl fmt::Display for FeeRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 % 1000 {
+            0 => write!(f, "{} sat/B", self.0 / 1000),
+            frac => {
+                let frac = format!("{:03}", frac);
+                write!(f, "{}.{} sat/B", self.0 / 1000, frac.trim_end_matches('0'))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fee() {
+        let rate = FeeRate::from_sat_per_kb(1500);
+        assert_eq!(rate.fee(0), Amount::ZERO);
+        assert_eq!(rate.fee(1), Amount::from_sat(2));
+        assert_eq!(rate.fee(1000), Amount::from_sat(1500));
+        assert_eq!(FeeRate::from_sat_per_kb(u64::MAX).fee(usize::MAX), Amount::from_sat(u64::MAX));
+        assert_eq!(FeeRate::from_fee(Amount::from_sat(1501), 1001), FeeRate::from_sat_per_kb(1499));
+        assert_eq!(FeeRate::from_fee(Amount::from_sat(1), 0), FeeRate::ZERO);
+        assert!(FeeRate::from_sat_per_kb(999) < FeeRate::MIN_RELAY);
+    }
+
+    #[test]
+    fn string_conversion() -> Result<()> {
+        assert_eq!("1".parse::<FeeRate>()?, FeeRate::MIN_RELAY);
+        assert_eq!(".25".parse::<FeeRate>()?, FeeRate::from_sat_per_kb(250));
+        assert_eq!("1.001 sat/B".parse::<FeeRate>()?, FeeRate::from_sat_per_kb(1001));
+        for invalid in &["", ".", "-1", "0.0001", "1e3", "18446744073709552", "1 sat/kB"] {
+            match invalid.parse::<FeeRate>() {
+                Err(Error::InvalidFeeRate(_)) => {},
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+
+        for rate in &[FeeRate::ZERO, FeeRate::MIN_RELAY, FeeRate::from_sat_per_kb(1010), FeeRate::from_sat_per_kb(5)] {
+            assert_eq!(rate.to_string().parse::<FeeRate>()?, *rate);
+        }
+        assert_eq!(FeeRate::from_sat_per_kb(1010).to_string(), "1.01 sat/B");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+/// `lock_time` below this value is a block height, otherwise a unix timestamp
+pub const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+/// Transaction `lock_time`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LockTime {
+    /// block height
+    Height(u32),
+    /// unix timestamp
+    Time(u32),
+}
+
+impl LockTime {
+    /// Returns `true` if `self` is a block height
+    pub fn is_height(self) -> bool {
+        match self {
+            LockTime::Height(_) => true,
+            LockTime::Time(_) => false,
+        }
+    }
+}
+
+impl Default for LockTime {
+    fn default() -> LockTime {
+        LockTime::Height(0)
+    }
+}
+
+impl From<u32> for LockTime {
+    fn from(n: u32) -> LockTime {
+        if n < LOCKTIME_THRESHOLD {
+            LockTime::Height(n)
+        } else {
+            LockTime::Time(n)
+        }
+    }
+}
+
+impl From<LockTime> for u32 {
+    fn from(lock_time: LockTime) -> u32 {
+        match lock_time {
+            LockTime::Height(n) | LockTime::Time(n) => n,
+        }
+    }
+}
+
+/// Input `sequence_no` with BIP68 relative lock-time semantics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Sequence(u32);
+
+impl Sequence {
+    /// final sequence, disables `lock_time` of the transaction
+    pub const FINAL: Sequence = Sequence(0xffff_ffff);
+    /// largest non-final sequence, enables `lock_time` of the transaction without relative lock-time
+    pub const MAX_NON_FINAL: Sequence = Sequence(0xffff_fffe);
+    /// relative lock-time is disabled if set
+    pub const DISABLE_FLAG: u32 = 1 << 31;
+    /// relative lock-time is in units of 512 seconds if set, otherwise in blocks
+    pub const TYPE_FLAG: u32 = 1 << 22;
+    /// mask of relative lock-time value
+    pub const MASK: u32 = 0x0000_ffff;
+    /// granularity of time based relative lock-time
+    pub const GRANULARITY: u32 = 512;
+
+    /// Relative lock-time in blocks
+    /// # Arguments
+    /// * `blocks` - number of blocks
+    pub fn from_blocks(blocks: u16) -> Sequence {
+        Sequence(u32::from(blocks))
+    }
+
+    /// Relative lock-time in units of 512 seconds
+    /// # Arguments
+    /// * `intervals` - number of 512 seconds intervals
+    pub fn from_512_seconds(intervals: u16) -> Sequence {
+        Sequence(Sequence::TYPE_FLAG | u32::from(intervals))
+    }
+
+    /// Relative lock-time in seconds, rounded up to 512 seconds granularity
+    /// # Arguments
+    /// * `seconds` - seconds
+    /// # Returns
+    /// * `None` if `seconds` exceeds the maximum relative lock-time
+    pub fn from_seconds(seconds: u32) -> Option<Sequence> {
+        let intervals = seconds.div_ceil(Sequence::GRANULARITY);
+        if intervals > Sequence::MASK {
+            return None;
+        }
+        Some(Sequence::from_512_seconds(intervals as u16))
+    }
+
+    /// Returns `true` if `self` is final
+    pub fn is_final(self) -> bool {
+        self == Sequence::FINAL
+    }
+
+    /// Returns `true` if relative lock-time is enabled
+    pub fn is_relative_lock_time(self) -> bool {
+        self.0 & Sequence::DISABLE_FLAG == 0
+    }
+
+    /// Relative lock-time in blocks
+    /// # Returns
+    /// * `None` if relative lock-time is disabled or in time
+    pub fn blocks(self) -> Option<u16> {
+        if self.is_relative_lock_time() && self.0 & Sequence::TYPE_FLAG == 0 {
+            Some((self.0 & Sequence::MASK) as u16)
+        } else {
+            None
+        }
+    }
+
+    /// Relative lock-time in seconds
+    /// # Returns
+    /// * `None` if relative lock-time is disabled or in blocks
+    pub fn seconds(self) -> Option<u32> {
+        if self.is_relative_lock_time() && self.0 & Sequence::TYPE_FLAG != 0 {
+            Some((self.0 & Sequence::MASK) * Sequence::GRANULARITY)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Sequence {
+    fn default() -> Sequence {
+        Sequence::FINAL
+    }
+}
+
+impl From<u32> for Sequence {
+    fn from(n: u32) -> Sequence {
+        Sequence(n)
+    }
+}
+
+impl From<Sequence> for u32 {
+    fn from(sequence: Sequence) -> u32 {
+        sequence.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_time() {
+        assert_eq!(LockTime::from(600_000), LockTime::Height(600_000));
+        assert_eq!(LockTime::from(1_600_000_000), LockTime::Time(1_600_000_000));
+        assert_eq!(u32::from(LockTime::Time(1_600_000_000)), 1_600_000_000);
+        assert!(!LockTime::Time(1_600_000_000).is_height());
+    }
+
+    #[test]
+    fn sequence() {
+        assert_eq!(u32::from(Sequence::from_blocks(10)), 10);
+        assert_eq!(Sequence::from_blocks(10).blocks(), Some(10));
+        assert_eq!(Sequence::from_blocks(10).seconds(), None);
+
+        let sequence = Sequence::from_seconds(1000).unwrap();
+        assert_eq!(u32::from(sequence), 0x0040_0002);
+        assert_eq!(sequence.seconds(), Some(1024));
+        assert_eq!(sequence.blocks(), None);
+        assert_eq!(Sequence::from_seconds(0x1_0000 * 512), None);
+
+        assert!(Sequence::FINAL.is_final());
+        assert!(!Sequence::MAX_NON_FINAL.is_final());
+        assert!(!Sequence::MAX_NON_FINAL.is_relative_lock_time());
+        assert!(!Sequence::FINAL.is_relative_lock_time());
+        assert_eq!(Sequence::FINAL.blocks(), None);
+    }
+}
@@ -4,16 +4,41 @@ pub mod outpoint;
 pub mod input;
 /// Transaction output
 pub mod output;
+/// CashTokens
+pub mod token;
+/// Borrowed transaction view
+pub mod transaction_ref;
+/// Transaction comparison
+pub mod diff;
+mod stream;
+#[cfg(feature = "serde")]
+mod json;
+/// Verbose transaction representation
+#[cfg(feature = "serde")]
+pub mod verbose;
 
 use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
 pub use outpoint::OutPoint;
 pub use input::Input;
 pub use output::Output;
+pub use token::TokenData;
+pub use transaction_ref::TransactionRef;
+pub use diff::TxDiff;
 use super::var_int::VarInt;
+use super::txid::Txid;
+use super::amount::Amount;
+use crate::hash;
+use crate::script::{self, p2sh, ScriptType};
+use crate::interpreter::MAX_SCRIPT_SIZE;
 use super::error::{Error, Result};
 
 /// Bitcoin Cash transaction format
 #[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", serde(into = "json::TransactionJson", from = "json::TransactionJson"))]
 pub struct Transaction {
     /// version no
     pub version: u32,
@@ -43,6 +68,10 @@ fn read_var_int(v: &[u8]) -> Option<(u64, &[u8])> {
     Some((vi.into(), &v[size..]))
 }
 
+fn txid(raw: &[u8]) -> Txid {
+    Txid::from(<[u8; 32]>::from(hash::hash256(raw)))
+}
+
 impl From<&Transaction> for Vec<u8> {
     fn from(tx: &Transaction) -> Vec<u8> {
         [
@@ -106,7 +135,7 @@ impl TryFrom<&[u8]> for Transaction {
         for _ in 0..out_counter {
             let (value, p) = read_bytes(read_pointer)
                     .ok_or_else(|| Error::TxParseError(len - read_pointer.len(), read_pointer.to_vec()))?;
-            let value = u64::from_le_bytes(value);
+            let value = Amount::from_sat(u64::from_le_bytes(value));
 
             let (script_len, p) = read_var_int(p)
                     .ok_or_else(|| Error::TxParseError(len - p.len(), p.to_vec()))?;
@@ -116,7 +145,14 @@ impl TryFrom<&[u8]> for Transaction {
             }
             let (script, p) = p.split_at(script_len as usize);
 
-            let output = Output::new(value, script);
+            let output = match script.first() {
+                Some(&token::PREFIX_TOKEN) => {
+                    let (token, script) = TokenData::parse(script)
+                            .ok_or_else(|| Error::TxParseError(len - p.len() - script.len(), script.to_vec()))?;
+                    Output::with_token(value, script, token)
+                },
+                _ => Output::new(value, script),
+            };
             tx.outputs.push(output);
 
             read_pointer = p;
@@ -132,6 +168,29 @@ impl TryFrom<&[u8]> for Transaction {
     }
 }
 
+/// Parse serialized transaction in hex, same as `Transaction::from_hex`
+impl FromStr for Transaction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Transaction> {
+        Transaction::from_hex(s)
+    }
+}
+
+/// Serialized transaction in hex
+impl fmt::Display for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+/// Serialized transaction in hex
+impl fmt::LowerHex for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Vec::from(self).iter().try_for_each(|v| write!(f, "{:02x}", v))
+    }
+}
+
 impl Transaction {
     /// Construct new `Transaction`
     pub fn new() -> Transaction {
@@ -142,6 +201,275 @@ impl Transaction {
             lock_time: 0,
         }
     }
+
+    /// Parse hex string
+    /// # Arguments
+    /// * `s` - serialized transaction in hex
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::types::transaction::Transaction;
+    /// let hex = "0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000";
+    /// let tx = Transaction::from_hex(hex)?;
+    /// assert_eq!(tx.outputs.len(), 2);
+    /// assert_eq!(tx.to_hex(), hex);
+    /// assert_eq!(hex.parse::<Transaction>()?, tx);
+    /// assert_eq!(tx.to_string(), hex);
+    /// # Ok::<(), cash_tx_builder::TypeError>(())
+    /// ```
+    pub fn from_hex(s: &str) -> Result<Transaction> {
+        Transaction::try_from(&hex::decode(s)?[..])
+    }
+
+    /// Convert to hex string
+    pub fn to_hex(&self) -> String {
+        hex::encode(Vec::from(self))
+    }
+
+    /// Get txid
+    pub fn txid(&self) -> Txid {
+        let mut w = hash::Hash256Writer::new();
+        self.write_to(&mut w).expect("hashing never fails");
+        Txid::from(<[u8; 32]>::from(w.finalize()))
+    }
+
+    /// Check if transaction is coinbase (single input spending null outpoint)
+    pub fn is_coinbase(&self) -> bool {
+        self.inputs.len() == 1 && self.inputs[0].outpoint.is_null()
+    }
+
+    /// Outpoints spent by inputs, in input order
+    pub fn outpoints(&self) -> impl Iterator<Item = &OutPoint> {
+        self.inputs.iter().map(|i| &i.outpoint)
+    }
+
+    /// Find input spending `outpoint`
+    /// # Arguments
+    /// * `outpoint` - outpoint
+    /// # Returns
+    /// * input index (`None` if not spent by this transaction)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::types::transaction::{Transaction, OutPoint};
+    /// let tx = Transaction::from_hex("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000")?;
+    /// let outpoint: OutPoint = "695538649751ffdb1a28c4c8bf9dca9afe5b65a3dbaea25770105aa2154b9a33:1".parse()?;
+    /// assert_eq!(tx.spends(&outpoint), Some(0));
+    /// assert_eq!(tx.outpoints().next(), Some(&outpoint));
+    ///
+    /// let other = OutPoint { n: 0, ..outpoint };
+    /// assert_eq!(tx.spends(&other), None);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn spends(&self, outpoint: &OutPoint) -> Option<usize> {
+        self.outpoints().position(|o| o == outpoint)
+    }
+
+    /// Copy transaction without inputs
+    ///
+    /// Version, outputs and lock time are kept, so that the output set can be funded again.
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::types::transaction::Transaction;
+    /// let tx = Transaction::from_hex("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000")?;
+    /// let template = tx.strip_inputs();
+    /// assert!(template.inputs.is_empty());
+    /// assert_eq!(template.outputs, tx.outputs);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn strip_inputs(&self) -> Transaction {
+        Transaction {
+            version: self.version,
+            inputs: vec![],
+            outputs: self.outputs.clone(),
+            lock_time: self.lock_time,
+        }
+    }
+
+    /// Append output
+    /// # Arguments
+    /// * `output` - output (value and sum of output values up to `Amount::MAX_MONEY`,
+    ///   `scriptPubKey` up to `MAX_SCRIPT_SIZE`)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::{Amount, Error};
+    /// # use cash_tx_builder::types::transaction::{Transaction, Output};
+    /// let mut tx = Transaction::new();
+    /// tx.push_output(Output::new(Amount::from_sat(1000), &[0x51]))?;
+    /// tx.set_output_value(0, Amount::from_sat(900))?;
+    /// assert_eq!(tx.outputs[0].value, Amount::from_sat(900));
+    ///
+    /// assert!(tx.push_output(Output::new(Amount::MAX_MONEY, &[0x51])).is_err());
+    /// assert!(tx.set_output_value(1, Amount::from_sat(900)).is_err());
+    /// assert_eq!(tx.outputs.len(), 1);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn push_output(&mut self, output: Output) -> Result<()> {
+        self.check_output(None, output.value, &output.script)?;
+        self.outputs.push(output);
+
+        Ok(())
+    }
+
+    /// Set value of output
+    /// # Arguments
+    /// * `index` - output index
+    /// * `value` - value (value and sum of output values up to `Amount::MAX_MONEY`)
+    pub fn set_output_value(&mut self, index: usize, value: Amount) -> Result<()> {
+        let output = self.outputs.get(index).ok_or(Error::InvalidIndex(index))?;
+        self.check_output(Some(index), value, &output.script)?;
+        self.outputs[index].value = value;
+
+        Ok(())
+    }
+
+    /// Replace `scriptSig` of input
+    /// # Arguments
+    /// * `index` - input index
+    /// * `script` - `scriptSig` (up to `MAX_SCRIPT_SIZE`)
+    pub fn replace_input_script(&mut self, index: usize, script: &[u8]) -> Result<()> {
+        let input = self.inputs.get_mut(index).ok_or(Error::InvalidIndex(index))?;
+        if script.len() > MAX_SCRIPT_SIZE {
+            return Err(Error::ScriptTooLarge(script.len(), MAX_SCRIPT_SIZE));
+        }
+        input.script = script.to_vec();
+
+        Ok(())
+    }
+
+    /// Check output replacing output at `index` (or appended if `None`)
+    fn check_output(&self, index: Option<usize>, value: Amount, script: &[u8]) -> Result<()> {
+        if !value.is_valid() {
+            return Err(Error::InvalidAmount(value.to_sat().to_string()));
+        }
+        if script.len() > MAX_SCRIPT_SIZE {
+            return Err(Error::ScriptTooLarge(script.len(), MAX_SCRIPT_SIZE));
+        }
+        self.outputs.iter().enumerate()
+            .filter(|(i, _)| Some(*i) != index)
+            .try_fold(value, |sum, (_, o)| sum.checked_add(o.value))
+            .ok_or_else(|| Error::InvalidAmount(format!("sum of output values exceeds {}", Amount::MAX_MONEY)))?;
+
+        Ok(())
+    }
+
+    /// Compare with other transaction field by field
+    /// # Arguments
+    /// * `other` - transaction to compare, e.g. built by reference implementation
+    /// # Returns
+    /// * differences (`is_empty()` if identical)
+    pub fn diff(&self, other: &Transaction) -> TxDiff {
+        TxDiff::new(self, other)
+    }
+
+    /// Count signature operations as consensus does
+    ///
+    /// `scriptSig`s and `scriptPubKey`s are counted inaccurately,
+    /// `redeem scripts` of inputs spending P2SH outputs accurately.
+    /// # Arguments
+    /// * `prev_scripts` - `scriptPubKey`s of previous outputs, in input order (ignored for coinbase)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::types::transaction::{Transaction, Input, Output};
+    /// # use cash_tx_builder::script::{p2pkh, p2sh, bare_multisig};
+    /// # use cash_tx_builder::{hash, Amount};
+    /// let redeem_script = bare_multisig::script_pub_key(2, &[&[0x02; 33], &[0x03; 33]])?;
+    /// let p2sh_script = p2sh::script_pub_key(&hash::hash160(&redeem_script))?;
+    ///
+    /// let mut tx = Transaction::new();
+    /// tx.inputs.push(Input::new(&[0x01; 32], 0, None));
+    /// tx.inputs[0].script = p2sh::script_sig(&[&[], &[0x30; 72], &[0x30; 72]], &redeem_script)?;
+    /// tx.outputs.push(Output::new(Amount::from_sat(1000), &p2pkh::script_pub_key(&[0x01; 20])?));
+    /// // 2 public keys of `redeem script` and `OP_CHECKSIG` of P2PKH output
+    /// assert_eq!(tx.sigop_count(&[&p2sh_script])?, 3);
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn sigop_count(&self, prev_scripts: &[&[u8]]) -> Result<usize> {
+        let mut count = self.inputs.iter().map(|i| script::sigop_count(&i.script, false)).sum::<usize>()
+            + self.outputs.iter().map(|o| script::sigop_count(&o.script, false)).sum::<usize>();
+        if self.is_coinbase() {
+            return Ok(count);
+        }
+        if prev_scripts.len() < self.inputs.len() {
+            return Err(Error::MissingPrevOutput(prev_scripts.len()));
+        }
+        if prev_scripts.len() > self.inputs.len() {
+            return Err(Error::InvalidIndex(self.inputs.len()));
+        }
+
+        for (input, prev_script) in self.inputs.iter().zip(prev_scripts) {
+            if !p2sh::is_p2sh(prev_script) || !script::is_push_only(&input.script) {
+                continue;
+            }
+            let redeem_script = script::decode(&input.script)?.last().and_then(|s| s.data().map(|d| d.to_vec()));
+            if let Some(redeem_script) = redeem_script {
+                count += script::sigop_count(&redeem_script, true);
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Find outputs paying to `scriptPubKey`
+    /// # Arguments
+    /// * `script` - `scriptPubKey`
+    /// # Returns
+    /// * output indexes and values
+    pub fn find_outputs_to_script(&self, script: &[u8]) -> Vec<(usize, Amount)> {
+        self.outputs.iter().enumerate()
+            .filter(|(_, o)| o.script == script)
+            .map(|(i, o)| (i, o.value))
+            .collect()
+    }
+
+    /// Find outputs paying to hash160, e.g. to watch addresses
+    /// # Arguments
+    /// * `hash` - hash160 of `public key` or `redeem script`
+    /// * `kind` - `ScriptType::PubKeyHash`, `ScriptType::ScriptHash`,
+    ///   or `ScriptType::PubKey` to match hash160 of `public key` (other types never match)
+    /// # Returns
+    /// * output indexes and values
+    /// # Example
+    /// ```
+    /// # #[macro_use] extern crate hex_literal;
+    /// # use cash_tx_builder::Amount;
+    /// # use cash_tx_builder::script::ScriptType;
+    /// # use cash_tx_builder::types::transaction::Transaction;
+    /// let tx = Transaction::from_hex("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000")?;
+    /// let hash = hex!("92fc13573caf1bd38bd65738428406f4af80793a");
+    /// assert_eq!(tx.find_outputs_to_hash160(&hash, ScriptType::PubKeyHash), [(1, Amount::from_sat(10_000))]);
+    /// assert!(tx.find_outputs_to_hash160(&hash, ScriptType::ScriptHash).is_empty());
+    /// # Ok::<(), cash_tx_builder::Error>(())
+    /// ```
+    pub fn find_outputs_to_hash160(&self, hash: &[u8], kind: ScriptType) -> Vec<(usize, Amount)> {
+        self.outputs.iter().enumerate()
+            .filter(|(_, o)| match (kind, script::script_type(&o.script)) {
+                (ScriptType::PubKeyHash, ScriptType::PubKeyHash) => o.script[3..23] == *hash,
+                (ScriptType::ScriptHash, ScriptType::ScriptHash) => o.script[2..22] == *hash,
+                (ScriptType::PubKey, ScriptType::PubKey) => hash::hash160(&o.script[1..o.script.len() - 1]) == hash,
+                _ => false,
+            })
+            .map(|(i, o)| (i, o.value))
+            .collect()
+    }
+
+    /// Convert to verbose representation like bitcoind's `decoderawtransaction`
+    /// # Arguments
+    /// * `formatter` - address formatter, receives hash and `true` if P2PKH
+    /// # Example
+    /// ```
+    /// # use std::convert::TryFrom;
+    /// # use cash_tx_builder::types::transaction::Transaction;
+    /// let raw = hex::decode("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000")?;
+    /// let tx = Transaction::try_from(&raw[..])?;
+    /// let verbose = tx.to_json_verbose(&|hash: &[u8], _| Some(hex::encode(hash)));
+    /// assert_eq!(verbose.vout[0].script_pub_key.script_type, "pubkeyhash");
+    /// assert_eq!(verbose.vout[0].script_pub_key.addresses, ["214ffcd3e7668da243cc4006759f6fe5f3c60bfe"]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn to_json_verbose<F>(&self, formatter: &F) -> verbose::VerboseTransaction
+        where F: Fn(&[u8], bool) -> Option<String> {
+        verbose::verbose_transaction(self, formatter)
+    }
 }
 
 #[cfg(test)]
@@ -158,6 +486,162 @@ mod tests {
         let tx_hex: Vec<u8> = (&tx).into();
 
         assert_eq!(tx_hex, hex.to_vec());
+        assert_eq!(Transaction::from_hex(&tx.to_hex())?, tx);
+        assert!(Transaction::from_hex("zz").is_err());
+        assert!(Transaction::from_hex(&tx.to_hex()[..20]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn find_outputs() -> Result<()> {
+        let pubkey = [0x02; 33];
+        let redeem_script = hex!("51");
+        let mut tx = Transaction::new();
+        tx.outputs.push(Output::new(Amount::from_sat(1000), &script::p2pk::script_pub_key(&pubkey)?));
+        tx.outputs.push(Output::new(Amount::from_sat(2000), &script::p2pkh::script_pub_key(&hash::hash160(&pubkey))?));
+        tx.outputs.push(Output::new(Amount::from_sat(3000), &p2sh::script_pub_key(&hash::hash160(&redeem_script))?));
+        tx.outputs.push(Output::new(Amount::from_sat(4000), &script::p2pk::script_pub_key(&pubkey)?));
+
+        let p2pk = tx.outputs[0].script.clone();
+        assert_eq!(tx.find_outputs_to_script(&p2pk), [(0, Amount::from_sat(1000)), (3, Amount::from_sat(4000))]);
+        assert!(tx.find_outputs_to_script(&redeem_script).is_empty());
+
+        let pkh = hash::hash160(&pubkey);
+        assert_eq!(tx.find_outputs_to_hash160(&pkh, ScriptType::PubKeyHash), [(1, Amount::from_sat(2000))]);
+        assert_eq!(tx.find_outputs_to_hash160(&pkh, ScriptType::PubKey), [(0, Amount::from_sat(1000)), (3, Amount::from_sat(4000))]);
+        assert!(tx.find_outputs_to_hash160(&pkh, ScriptType::ScriptHash).is_empty());
+        assert!(tx.find_outputs_to_hash160(&pkh, ScriptType::Multisig).is_empty());
+        let sh = hash::hash160(&redeem_script);
+        assert_eq!(tx.find_outputs_to_hash160(&sh, ScriptType::ScriptHash), [(2, Amount::from_sat(3000))]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn sigops() -> Result<()> {
+        let hex = hex!("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000");
+        let mut tx = Transaction::try_from(&hex[..])?;
+        let p2pkh = tx.outputs[0].script.clone();
+        assert_eq!(tx.sigop_count(&[&p2pkh])?, 2);
+
+        // `redeem script` is counted only if previous output is P2SH
+        let redeem_script = hex!("ad51ae");
+        tx.inputs[0].script = script::encode(&[script::Script::Data(&redeem_script)])?;
+        assert_eq!(tx.sigop_count(&[&p2pkh])?, 2);
+        let p2sh = p2sh::script_pub_key(&hash::hash160(&redeem_script))?;
+        assert_eq!(tx.sigop_count(&[&p2sh])?, 4);
+
+        match tx.sigop_count(&[]) {
+            Err(Error::MissingPrevOutput(0)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match tx.sigop_count(&[&p2sh, &p2sh]) {
+            Err(Error::InvalidIndex(1)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn mutate() -> Result<()> {
+        let mut tx = Transaction::new();
+        tx.inputs.push(Input::new(&[0x01; 32], 0, None));
+        tx.push_output(Output::new(Amount::from_sat(1000), &[0x51]))?;
+        tx.push_output(Output::new(Amount::MAX_MONEY.checked_sub(Amount::from_sat(1000)).unwrap(), &[0x51]))?;
+        match tx.push_output(Output::new(Amount::from_sat(1), &[0x51])) {
+            Err(Error::InvalidAmount(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match tx.set_output_value(0, Amount::from_sat(1001)) {
+            Err(Error::InvalidAmount(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        tx.set_output_value(1, Amount::from_sat(1))?;
+        match tx.push_output(Output::new(Amount::from_sat(1), &[0x51; MAX_SCRIPT_SIZE + 1])) {
+            Err(Error::ScriptTooLarge(_, MAX_SCRIPT_SIZE)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+        assert_eq!(tx.outputs.len(), 2);
+
+        tx.replace_input_script(0, &[0x51])?;
+        assert_eq!(tx.inputs[0].script, [0x51]);
+        assert!(tx.replace_input_script(0, &[0x51; MAX_SCRIPT_SIZE + 1]).is_err());
+        match tx.replace_input_script(1, &[]) {
+            Err(Error::InvalidIndex(1)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn token_output() -> Result<()> {
+        let hex = hex!("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000");
+        let mut tx = Transaction::try_from(&hex[..])?;
+
+        let token = TokenData::new(tx.inputs[0].outpoint.txid.into(), 1000, None)?;
+        tx.outputs[1].token = Some(token);
+
+        let parsed = Transaction::try_from(&Vec::from(&tx)[..])?;
+        assert_eq!(parsed, tx);
+
+        Ok(())
+    }
+
+    #[test]
+    fn diff() -> Result<()> {
+        let tx = Transaction::from_hex("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000")?;
+        assert!(tx.diff(&tx).is_empty());
+        assert_eq!(tx.diff(&tx).to_string(), "");
+
+        let mut other = tx.clone();
+        other.inputs[0].script.clear();
+        other.outputs[1].value = Amount::from_sat(9999);
+        other.outputs.pop();
+        other.lock_time = 100;
+        let diff = tx.diff(&other);
+        assert_eq!(diff.version, None);
+        assert_eq!(diff.inputs.len(), 1);
+        assert_eq!(diff.inputs[0].script, Some((tx.inputs[0].script.clone(), vec![])));
+        assert_eq!(diff.inputs[0].sequence_no, None);
+        assert_eq!(diff.output_count, Some((2, 1)));
+        assert!(diff.outputs.is_empty());
+        assert_eq!(diff.lock_time, Some((0, 100)));
+        assert_eq!(diff.to_string().lines().count(), 3);
+
+        other.outputs = tx.outputs.clone();
+        other.outputs[1].value = Amount::from_sat(9999);
+        let diff = tx.diff(&other);
+        assert_eq!(diff.outputs[0].index, 1);
+        assert_eq!(diff.outputs[0].value, Some((Amount::from_sat(10000), Amount::from_sat(9999))));
+        assert!(diff.to_string().contains("output 1 value: 10000 != 9999"));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_round_trip() -> Result<()> {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        // xorshift for reproducible input
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..200 {
+            let data = (0..4096).map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            }).collect::<Vec<_>>();
+
+            let tx = Transaction::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            let raw = Vec::from(&tx);
+            assert_eq!(Transaction::try_from(&raw[..])?, tx);
+            assert_eq!(Transaction::read_from(&raw[..])?, tx);
+            assert_eq!(TransactionRef::parse(&raw)?.to_transaction()?, tx);
+        }
 
         Ok(())
     }
@@ -0,0 +1,158 @@
+use std::fmt;
+use super::{Transaction, Input, Output, OutPoint, TokenData};
+use super::super::amount::Amount;
+
+/// Pair of differing values, `(self, other)`
+pub type Change<T> = Option<(T, T)>;
+
+fn change<T: PartialEq + Clone>(a: &T, b: &T) -> Change<T> {
+    if a == b { None } else { Some((a.clone(), b.clone())) }
+}
+
+/// Differences of input at the same index
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputDiff {
+    /// input index
+    pub index: usize,
+    /// previous outpoint
+    pub outpoint: Change<OutPoint>,
+    /// `scriptSig`
+    pub script: Change<Vec<u8>>,
+    /// sequence no
+    pub sequence_no: Change<u32>,
+}
+
+/// Differences of output at the same index
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputDiff {
+    /// output index
+    pub index: usize,
+    /// value
+    pub value: Change<Amount>,
+    /// `scriptPubKey`
+    pub script: Change<Vec<u8>>,
+    /// token data
+    pub token: Change<Option<TokenData>>,
+}
+
+/// Differences of two transactions
+///
+/// Inputs and outputs are compared index by index up to the shorter list,
+/// extra entries are reported by `input_count` and `output_count`.
+/// # Example
+/// ```
+/// # use cash_tx_builder::types::transaction::Transaction;
+/// let tx = Transaction::from_hex("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000")?;
+/// let mut other = tx.clone();
+/// other.version = 2;
+/// other.inputs[0].sequence_no = 0xffff_fffe;
+///
+/// let diff = tx.diff(&other);
+/// assert_eq!(diff.version, Some((1, 2)));
+/// assert_eq!(diff.inputs[0].sequence_no, Some((0xffff_ffff, 0xffff_fffe)));
+/// assert!(diff.outputs.is_empty());
+/// assert!(tx.diff(&tx).is_empty());
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxDiff {
+    /// version
+    pub version: Change<u32>,
+    /// number of inputs
+    pub input_count: Change<usize>,
+    /// differing inputs
+    pub inputs: Vec<InputDiff>,
+    /// number of outputs
+    pub output_count: Change<usize>,
+    /// differing outputs
+    pub outputs: Vec<OutputDiff>,
+    /// lock time
+    pub lock_time: Change<u32>,
+}
+
+impl TxDiff {
+    pub(crate) fn new(a: &Transaction, b: &Transaction) -> TxDiff {
+        TxDiff {
+            version: change(&a.version, &b.version),
+            input_count: change(&a.inputs.len(), &b.inputs.len()),
+            inputs: a.inputs.iter().zip(&b.inputs).enumerate()
+                .filter_map(|(index, (a, b))| input_diff(index, a, b))
+                .collect(),
+            output_count: change(&a.outputs.len(), &b.outputs.len()),
+            outputs: a.outputs.iter().zip(&b.outputs).enumerate()
+                .filter_map(|(index, (a, b))| output_diff(index, a, b))
+                .collect(),
+            lock_time: change(&a.lock_time, &b.lock_time),
+        }
+    }
+
+    /// `true` if transactions are identical
+    pub fn is_empty(&self) -> bool {
+        self.version.is_none() && self.input_count.is_none() && self.inputs.is_empty()
+            && self.output_count.is_none() && self.outputs.is_empty() && self.lock_time.is_none()
+    }
+}
+
+fn input_diff(index: usize, a: &Input, b: &Input) -> Option<InputDiff> {
+    let diff = InputDiff {
+        index,
+        outpoint: change(&a.outpoint, &b.outpoint),
+        script: change(&a.script, &b.script),
+        sequence_no: change(&a.sequence_no, &b.sequence_no),
+    };
+
+    if diff.outpoint.is_none() && diff.script.is_none() && diff.sequence_no.is_none() { None } else { Some(diff) }
+}
+
+fn output_diff(index: usize, a: &Output, b: &Output) -> Option<OutputDiff> {
+    let diff = OutputDiff {
+        index,
+        value: change(&a.value, &b.value),
+        script: change(&a.script, &b.script),
+        token: change(&a.token, &b.token),
+    };
+
+    if diff.value.is_none() && diff.script.is_none() && diff.token.is_none() { None } else { Some(diff) }
+}
+
+/// One line per differing field, `self != other`
+impl fmt::Display for TxDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((a, b)) = self.version {
+            writeln!(f, "version: {} != {}", a, b)?;
+        }
+        if let Some((a, b)) = self.input_count {
+            writeln!(f, "input count: {} != {}", a, b)?;
+        }
+        for input in &self.inputs {
+            if let Some((a, b)) = &input.outpoint {
+                writeln!(f, "input {} outpoint: {}:{} != {}:{}", input.index, a.txid, a.n, b.txid, b.n)?;
+            }
+            if let Some((a, b)) = &input.script {
+                writeln!(f, "input {} script: {} != {}", input.index, hex::encode(a), hex::encode(b))?;
+            }
+            if let Some((a, b)) = input.sequence_no {
+                writeln!(f, "input {} sequence: {:#010x} != {:#010x}", input.index, a, b)?;
+            }
+        }
+        if let Some((a, b)) = self.output_count {
+            writeln!(f, "output count: {} != {}", a, b)?;
+        }
+        for output in &self.outputs {
+            if let Some((a, b)) = output.value {
+                writeln!(f, "output {} value: {} != {}", output.index, a.to_sat(), b.to_sat())?;
+            }
+            if let Some((a, b)) = &output.script {
+                writeln!(f, "output {} script: {} != {}", output.index, hex::encode(a), hex::encode(b))?;
+            }
+            if let Some((a, b)) = &output.token {
+                writeln!(f, "output {} token: {:?} != {:?}", output.index, a, b)?;
+            }
+        }
+        if let Some((a, b)) = self.lock_time {
+            writeln!(f, "lock time: {} != {}", a, b)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,9 +1,12 @@
-use crate::types::{VarInt, u256};
+use crate::types::{VarInt, Txid};
 use super::OutPoint;
 
 /// Transaction input
 #[allow(missing_docs)]
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", serde(into = "super::json::InputJson", from = "super::json::InputJson"))]
 pub struct Input {
     pub outpoint: OutPoint,
     pub script: Vec<u8>,
@@ -29,7 +32,7 @@ impl Input {
     /// * sequence_no - (option) sequence number
     pub fn new(txid: &[u8; 32], index: u32, sequence_no: Option<u32>) -> Input {
         Input {
-            outpoint: OutPoint {txid: u256(*txid), n: index},
+            outpoint: OutPoint {txid: Txid::from(*txid), n: index},
             script: vec![],
             sequence_no: sequence_no.unwrap_or(0xffff_ffff),
         }
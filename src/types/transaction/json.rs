@@ -0,0 +1,224 @@
+//! JSON representation compatible with bitcoind's `decoderawtransaction`
+//! (values are in satoshis)
+
+use std::convert::TryFrom;
+use crate::types::{u256, Txid, Amount};
+use super::{Transaction, Input, Output, OutPoint, TokenData};
+use super::token::Nft;
+use super::super::error::Error;
+
+pub(super) mod hex_bytes {
+    use serde::{Serializer, Deserializer, Deserialize, de};
+
+    pub fn serialize<S: Serializer>(v: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(v))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        hex::decode(&s)
+        .map_err(|_| {
+            de::Error::invalid_value(
+                de::Unexpected::Str(&s),
+                &"hex string",
+            )
+        })
+    }
+}
+
+// token amount is serialized as string to keep 64 bit precision
+mod amount_string {
+    use serde::{Serializer, Deserializer, Deserialize, de};
+
+    pub fn serialize<S: Serializer>(v: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&v.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        s.parse()
+        .map_err(|_| {
+            de::Error::invalid_value(
+                de::Unexpected::Str(&s),
+                &"decimal string",
+            )
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScriptJson {
+    #[serde(with = "hex_bytes")]
+    hex: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct InputJson {
+    #[serde(flatten)]
+    outpoint: OutPoint,
+    #[serde(rename = "scriptSig")]
+    script_sig: ScriptJson,
+    sequence: u32,
+}
+
+impl From<Input> for InputJson {
+    fn from(i: Input) -> InputJson {
+        InputJson {
+            outpoint: i.outpoint,
+            script_sig: ScriptJson { hex: i.script },
+            sequence: i.sequence_no,
+        }
+    }
+}
+
+impl From<InputJson> for Input {
+    fn from(i: InputJson) -> Input {
+        Input {
+            outpoint: i.outpoint,
+            script: i.script_sig.hex,
+            sequence_no: i.sequence,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct OutputJson {
+    value: u64,
+    #[serde(rename = "scriptPubKey")]
+    script_pub_key: ScriptJson,
+    #[serde(rename = "tokenData", default, skip_serializing_if = "Option::is_none")]
+    token_data: Option<TokenData>,
+}
+
+impl From<Output> for OutputJson {
+    fn from(o: Output) -> OutputJson {
+        OutputJson {
+            value: o.value.to_sat(),
+            script_pub_key: ScriptJson { hex: o.script },
+            token_data: o.token,
+        }
+    }
+}
+
+impl From<OutputJson> for Output {
+    fn from(o: OutputJson) -> Output {
+        Output {
+            value: Amount::from_sat(o.value),
+            script: o.script_pub_key.hex,
+            token: o.token_data,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct TokenJson {
+    category: u256,
+    #[serde(with = "amount_string")]
+    amount: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nft: Option<Nft>,
+}
+
+impl From<TokenData> for TokenJson {
+    fn from(t: TokenData) -> TokenJson {
+        TokenJson {
+            category: t.category,
+            amount: t.amount,
+            nft: t.nft,
+        }
+    }
+}
+
+impl TryFrom<TokenJson> for TokenData {
+    type Error = Error;
+
+    fn try_from(t: TokenJson) -> Result<TokenData, Error> {
+        TokenData::new(t.category, t.amount, t.nft)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct TransactionJson {
+    #[serde(skip_deserializing)]
+    txid: Txid,
+    version: u32,
+    #[serde(skip_deserializing)]
+    size: usize,
+    locktime: u32,
+    vin: Vec<Input>,
+    vout: Vec<Output>,
+}
+
+impl From<Transaction> for TransactionJson {
+    fn from(tx: Transaction) -> TransactionJson {
+        TransactionJson {
+            txid: tx.txid(),
+            version: tx.version,
+            size: Vec::from(&tx).len(),
+            locktime: tx.lock_time,
+            vin: tx.inputs,
+            vout: tx.outputs,
+        }
+    }
+}
+
+impl From<TransactionJson> for Transaction {
+    fn from(tx: TransactionJson) -> Transaction {
+        Transaction {
+            version: tx.version,
+            inputs: tx.vin,
+            outputs: tx.vout,
+            lock_time: tx.locktime,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{self, json, Value};
+
+    #[test]
+    fn serde() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let hex = hex!("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000");
+        let mut tx = Transaction::try_from(&hex[..])?;
+
+        let value = serde_json::to_value(&tx)?;
+        assert_eq!(value["txid"], "7bdc016701e4c5d7ec34e99954ec3921140728d2c58b1da3cf6aa34c760d8a47");
+        assert_eq!(value["version"], 1);
+        assert_eq!(value["size"], hex.len());
+        assert_eq!(value["locktime"], 0);
+        assert_eq!(value["vin"][0], json!({
+            "txid": "695538649751ffdb1a28c4c8bf9dca9afe5b65a3dbaea25770105aa2154b9a33",
+            "vout": 1,
+            "scriptSig": {
+                "hex": "47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1de",
+            },
+            "sequence": 0xffff_ffffu32,
+        }));
+        assert_eq!(value["vout"][1], json!({
+            "value": 10000,
+            "scriptPubKey": { "hex": "76a91492fc13573caf1bd38bd65738428406f4af80793a88ac" },
+        }));
+        assert_eq!(serde_json::from_value::<Transaction>(value)?, tx);
+
+        let nft = Nft { capability: super::super::token::Capability::Mutable, commitment: vec![0xcc] };
+        tx.outputs[1].token = Some(TokenData::new(tx.inputs[0].outpoint.txid.into(), 1000, Some(nft))?);
+        let value = serde_json::to_value(&tx)?;
+        assert_eq!(value["vout"][1]["tokenData"], json!({
+            "category": "695538649751ffdb1a28c4c8bf9dca9afe5b65a3dbaea25770105aa2154b9a33",
+            "amount": "1000",
+            "nft": { "capability": "mutable", "commitment": "cc" },
+        }));
+        assert_eq!(serde_json::from_value::<Transaction>(value.clone())?, tx);
+
+        let mut invalid = value;
+        invalid["vout"][1]["tokenData"]["amount"] = Value::from("0");
+        invalid["vout"][1]["tokenData"].as_object_mut().map(|o| o.remove("nft"));
+        assert!(serde_json::from_value::<Transaction>(invalid).is_err());
+
+        Ok(())
+    }
+}
@@ -1,10 +1,16 @@
-use crate::types::u256;
+use std::fmt;
+use std::str::FromStr;
+use crate::types::Txid;
+use crate::types::error::{Error, Result};
 
 /// Outpoint
 #[allow(missing_docs)]
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct OutPoint {
-    pub txid: u256,
+    pub txid: Txid,
+    #[cfg_attr(feature = "serde", serde(rename = "vout"))]
     pub n: u32,
 }
 
@@ -18,3 +24,50 @@ impl From<&OutPoint> for Vec<u8> {
         ret.to_vec()
     }
 }
+
+impl OutPoint {
+    /// Null outpoint spent by coinbase input
+    pub fn null() -> OutPoint {
+        OutPoint {
+            txid: Txid::default(),
+            n: 0xffff_ffff,
+        }
+    }
+
+    /// Check if outpoint is null
+    pub fn is_null(&self) -> bool {
+        *self == OutPoint::null()
+    }
+}
+
+/// `txid:vout`
+impl fmt::Display for OutPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.txid, self.n)
+    }
+}
+
+impl FromStr for OutPoint {
+    type Err = Error;
+
+    /// Parse `txid:vout`
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::types::transaction::OutPoint;
+    /// let s = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c:1";
+    /// let outpoint: OutPoint = s.parse()?;
+    /// assert_eq!(outpoint.n, 1);
+    /// assert_eq!(outpoint.to_string(), s);
+    /// assert!("427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse::<OutPoint>().is_err());
+    /// # Ok::<(), cash_tx_builder::TypeError>(())
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidOutPoint(s.to_string());
+        let (txid, n) = s.rsplit_once(':').ok_or_else(invalid)?;
+
+        Ok(OutPoint {
+            txid: txid.parse().map_err(|_| invalid())?,
+            n: n.parse().map_err(|_| invalid())?,
+        })
+    }
+}
@@ -1,32 +1,99 @@
+use std::convert::TryFrom;
 use super::super::var_int::VarInt;
+use super::super::amount::Amount;
+use super::super::error::{Error, Result};
+use super::token::{self, TokenData};
 
 /// Transaction output
 #[allow(missing_docs)]
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "super::json::OutputJson", from = "super::json::OutputJson"))]
 pub struct Output {
-    pub value: u64,
+    pub value: Amount,
     pub script: Vec<u8>,
+    pub token: Option<TokenData>,
 }
 
 impl From<&Output> for Vec<u8> {
     fn from(o: &Output) -> Vec<u8> {
+        let token = o.token.as_ref().map(TokenData::to_vec).unwrap_or_default();
         [
-            &o.value.to_le_bytes()[..],
-            &Vec::from(VarInt::from(o.script.len() as u64)),
+            &o.value.to_sat().to_le_bytes()[..],
+            &Vec::from(VarInt::from((token.len() + o.script.len()) as u64)),
+            &token,
             &o.script,
         ].concat()
     }
 }
 
+/// Generate output which survives serialization round trip
+/// (`scriptPubKey` does not start with token prefix)
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Output {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Output> {
+        let mut script: Vec<u8> = u.arbitrary()?;
+        if script.first() == Some(&token::PREFIX_TOKEN) {
+            script.remove(0);
+        }
+
+        Ok(Output {
+            value: u.arbitrary()?,
+            script,
+            token: u.arbitrary()?,
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for Output {
+    type Error = Error;
+
+    fn try_from(v: &[u8]) -> Result<Output> {
+        let parse_error = || Error::TxParseError(0, v.to_vec());
+
+        let mut value = [0; 8];
+        value.copy_from_slice(v.get(..8).ok_or_else(parse_error)?);
+        let value = Amount::from_sat(u64::from_le_bytes(value));
+
+        let script_len = VarInt::try_from(&v[8..]).map_err(|_| parse_error())?;
+        let script = &v[8 + script_len.len()..];
+        if script.len() as u64 != u64::from(script_len) {
+            return Err(parse_error());
+        }
+
+        match script.first() {
+            Some(&token::PREFIX_TOKEN) => {
+                let (token, script) = TokenData::parse(script).ok_or_else(parse_error)?;
+                Ok(Output::with_token(value, script, token))
+            },
+            _ => Ok(Output::new(value, script)),
+        }
+    }
+}
+
 impl Output {
     /// Construct `Output`
     /// # Arguments
-    /// * `value` - satoshi
+    /// * `value` - value
     /// * `script` - `scriptPubKey`
-    pub fn new(value: u64, script: &[u8]) -> Output {
+    pub fn new(value: Amount, script: &[u8]) -> Output {
         Output {
             value,
             script: script.to_vec(),
+            token: None,
+        }
+    }
+
+    /// Construct `Output` with token
+    /// # Arguments
+    /// * `value` - value
+    /// * `script` - `scriptPubKey`
+    /// * `token` - token data
+    pub fn with_token(value: Amount, script: &[u8], token: TokenData) -> Output {
+        Output {
+            value,
+            script: script.to_vec(),
+            token: Some(token),
         }
     }
 
@@ -39,10 +106,12 @@ impl Output {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::str::FromStr;
+    use crate::types::u256;
 
     #[test]
     fn construct_test() {
-        let value = 10000;
+        let value = Amount::from_sat(10000);
         let script = hex!("76a91492fc13573caf1bd38bd65738428406f4af80793a88ac");
 
         let output = Output::new(value, &script);
@@ -51,4 +120,22 @@ mod tests {
         assert_eq!(output.script, script);
         assert_eq!(output.to_vec(), hex!("10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac").to_vec());
     }
+
+    #[test]
+    fn token_output() -> Result<()> {
+        let category = u256::from_str("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb")?;
+        let token = TokenData::new(category, 1, None)?;
+        let script = hex!("76a91492fc13573caf1bd38bd65738428406f4af80793a88ac");
+
+        let output = Output::with_token(Amount::from_sat(1000), &script, token.clone());
+        let v = output.to_vec();
+        assert_eq!(v[8] as usize, 35 + script.len());
+        assert_eq!(v[9..44], token.to_vec()[..]);
+        assert_eq!(v[44..], script);
+
+        assert_eq!(Output::try_from(&v[..])?, output);
+        assert!(Output::try_from(&v[..40]).is_err());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,160 @@
+//! streaming (de)serialization over `std::io`
+
+use std::io::{self, Read, Write};
+use super::{Transaction, Input, Output, TokenData, token};
+use super::super::var_int::VarInt;
+use super::super::amount::Amount;
+use super::super::error::{Error, Result};
+
+struct Reader<R> {
+    inner: R,
+    pos: usize,
+}
+
+impl<R: Read> Reader<R> {
+    fn read_array<T: Default + AsMut<[u8]>>(&mut self) -> Result<T> {
+        let mut ret = T::default();
+        self.inner.read_exact(ret.as_mut())?;
+        self.pos += ret.as_mut().len();
+
+        Ok(ret)
+    }
+
+    fn read_var_int(&mut self) -> Result<u64> {
+        let [prefix] = self.read_array::<[u8; 1]>()?;
+        let n = match prefix {
+            0xfd => u16::from_le_bytes(self.read_array()?).into(),
+            0xfe => u32::from_le_bytes(self.read_array()?).into(),
+            0xff => u64::from_le_bytes(self.read_array()?),
+            n => n.into(),
+        };
+
+        Ok(n)
+    }
+
+    fn read_script(&mut self) -> Result<Vec<u8>> {
+        let len = self.read_var_int()?;
+
+        // do not trust `len` for allocation
+        let mut script = vec![];
+        (&mut self.inner).take(len).read_to_end(&mut script)?;
+        self.pos += script.len();
+        if script.len() as u64 != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        Ok(script)
+    }
+}
+
+impl Transaction {
+    /// Parse transaction from reader incrementally
+    /// # Arguments
+    /// * `r` - reader positioned at the head of a serialized transaction
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::types::transaction::Transaction;
+    /// let raw = hex::decode("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000")?;
+    /// let stream = [&raw[..], &raw[..]].concat();
+    /// let mut r = &stream[..];
+    /// let first = Transaction::read_from(&mut r)?;
+    /// let second = Transaction::read_from(&mut r)?;
+    /// assert_eq!(first, second);
+    /// assert!(r.is_empty());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn read_from<R: Read>(r: R) -> Result<Transaction> {
+        let mut r = Reader { inner: r, pos: 0 };
+        let mut tx = Transaction::new();
+
+        tx.version = u32::from_le_bytes(r.read_array()?);
+
+        let in_counter = r.read_var_int()?;
+        for _ in 0..in_counter {
+            let txid = r.read_array()?;
+            let index = u32::from_le_bytes(r.read_array()?);
+            let script = r.read_script()?;
+            let sequence_no = u32::from_le_bytes(r.read_array()?);
+
+            let mut input = Input::new(&txid, index, Some(sequence_no));
+            input.script = script;
+            tx.inputs.push(input);
+        }
+
+        let out_counter = r.read_var_int()?;
+        for _ in 0..out_counter {
+            let value = Amount::from_sat(u64::from_le_bytes(r.read_array()?));
+            let script = r.read_script()?;
+
+            let output = match script.first() {
+                Some(&token::PREFIX_TOKEN) => {
+                    let (token, script) = TokenData::parse(&script)
+                            .ok_or_else(|| Error::TxParseError(r.pos - script.len(), script.clone()))?;
+                    Output::with_token(value, script, token)
+                },
+                _ => Output { value, script, token: None },
+            };
+            tx.outputs.push(output);
+        }
+
+        tx.lock_time = u32::from_le_bytes(r.read_array()?);
+
+        Ok(tx)
+    }
+
+    /// Serialize transaction to writer incrementally
+    /// # Arguments
+    /// * `w` - writer
+    /// # Returns
+    /// * written size
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<usize> {
+        let mut size = 0;
+        let mut write = |v: &[u8]| -> io::Result<()> {
+            w.write_all(v)?;
+            size += v.len();
+            Ok(())
+        };
+
+        write(&self.version.to_le_bytes())?;
+        write(&Vec::from(VarInt::from(self.inputs.len() as u64)))?;
+        for input in &self.inputs {
+            write(&input.to_vec())?;
+        }
+        write(&Vec::from(VarInt::from(self.outputs.len() as u64)))?;
+        for output in &self.outputs {
+            write(&output.to_vec())?;
+        }
+        write(&self.lock_time.to_le_bytes())?;
+
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use super::*;
+
+    #[test]
+    fn stream() -> Result<()> {
+        let hex = hex!("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000");
+        let mut tx = Transaction::try_from(&hex[..])?;
+        tx.outputs[1].token = Some(TokenData::new(tx.inputs[0].outpoint.txid.into(), 1000, None)?);
+
+        let mut v = vec![];
+        let size = tx.write_to(&mut v)?;
+        assert_eq!(size, v.len());
+        assert_eq!(v, Vec::from(&tx));
+        assert_eq!(Transaction::read_from(&v[..])?, tx);
+
+        for len in 0..v.len() {
+            assert!(Transaction::read_from(&v[..len]).is_err());
+        }
+
+        // huge script length must not be allocated up front
+        let v = [&hex[..5], &[0xff; 40], &[0xff; 9]].concat();
+        assert!(Transaction::read_from(&v[..]).is_err());
+
+        Ok(())
+    }
+}
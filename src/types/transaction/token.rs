@@ -0,0 +1,264 @@
+use std::convert::TryFrom;
+use crate::types::{VarInt, u256};
+use super::super::error::{Error, Result};
+
+/// Prefix byte of token data in output
+pub const PREFIX_TOKEN: u8 = 0xef;
+
+/// Maximum length of NFT commitment
+pub const MAX_COMMITMENT_LENGTH: usize = 40;
+
+const HAS_COMMITMENT_LENGTH: u8 = 0x40;
+const HAS_NFT: u8 = 0x20;
+const HAS_AMOUNT: u8 = 0x10;
+const RESERVED_BIT: u8 = 0x80;
+const CAPABILITY_MASK: u8 = 0x0f;
+
+/// NFT capability
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Capability {
+    /// immutable NFT
+    None = 0x00,
+    /// mutable NFT
+    Mutable = 0x01,
+    /// minting NFT
+    Minting = 0x02,
+}
+
+/// Non-fungible token
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Nft {
+    pub capability: Capability,
+    #[cfg_attr(feature = "serde", serde(with = "super::json::hex_bytes"))]
+    pub commitment: Vec<u8>,
+}
+
+/// Token data of output
+/// [spec](https://github.com/bitjson/cashtokens)
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "super::json::TokenJson", try_from = "super::json::TokenJson"))]
+pub struct TokenData {
+    pub category: u256,
+    pub amount: u64,
+    pub nft: Option<Nft>,
+}
+
+/// Generate NFT with valid commitment length
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Nft {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Nft> {
+        let len = u.int_in_range(0..=MAX_COMMITMENT_LENGTH)?;
+
+        Ok(Nft {
+            capability: u.arbitrary()?,
+            commitment: u.bytes(len)?.to_vec(),
+        })
+    }
+}
+
+/// Generate valid token data
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for TokenData {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<TokenData> {
+        let nft: Option<Nft> = u.arbitrary()?;
+        let min_amount = if nft.is_some() { 0 } else { 1 };
+
+        Ok(TokenData {
+            category: u.arbitrary()?,
+            amount: u.int_in_range(min_amount..=i64::MAX as u64)?,
+            nft,
+        })
+    }
+}
+
+impl From<&TokenData> for Vec<u8> {
+    fn from(t: &TokenData) -> Vec<u8> {
+        let mut v = vec![PREFIX_TOKEN];
+        v.extend(t.category.as_ref());
+        v.push(t.bitfield());
+        if let Some(nft) = t.nft.as_ref().filter(|nft| !nft.commitment.is_empty()) {
+            v.extend(Vec::from(VarInt::from(nft.commitment.len() as u64)));
+            v.extend(&nft.commitment);
+        }
+        if t.amount > 0 {
+            v.extend(Vec::from(VarInt::from(t.amount)));
+        }
+
+        v
+    }
+}
+
+fn read_var_int(v: &[u8]) -> Option<(u64, &[u8])> {
+    let vi = VarInt::try_from(v).ok()?;
+    let size = vi.len();
+
+    // must be minimally encoded
+    if Vec::from(vi)[..] != v[..size] {
+        return None;
+    }
+
+    Some((vi.into(), &v[size..]))
+}
+
+impl TokenData {
+    /// Construct `TokenData`
+    /// # Arguments
+    /// * `category` - token category id
+    /// * `amount` - fungible token amount (`0` if no fungible token)
+    /// * `nft` - (option) non-fungible token
+    /// # Example
+    /// ```
+    /// # use std::str::FromStr;
+    /// # use cash_tx_builder::u256;
+    /// # use cash_tx_builder::types::transaction::token::{TokenData, Nft, Capability};
+    /// let category = u256::from_str("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb")?;
+    /// let nft = Nft { capability: Capability::Minting, commitment: vec![0xcc] };
+    /// let token = TokenData::new(category, 100, Some(nft))?;
+    /// assert_eq!(token.to_vec()[33], 0x72);
+    /// assert!(TokenData::new(category, 0, None).is_err());
+    /// # Ok::<(), cash_tx_builder::TypeError>(())
+    /// ```
+    pub fn new(category: u256, amount: u64, nft: Option<Nft>) -> Result<TokenData> {
+        let token = TokenData {
+            category,
+            amount,
+            nft,
+        };
+        if !token.is_valid() {
+            return Err(Error::InvalidTokenData(token.to_vec()));
+        }
+
+        Ok(token)
+    }
+
+    fn bitfield(&self) -> u8 {
+        let mut bitfield = 0;
+        if let Some(nft) = &self.nft {
+            bitfield |= HAS_NFT | nft.capability as u8;
+            if !nft.commitment.is_empty() {
+                bitfield |= HAS_COMMITMENT_LENGTH;
+            }
+        }
+        if self.amount > 0 {
+            bitfield |= HAS_AMOUNT;
+        }
+
+        bitfield
+    }
+
+    fn is_valid(&self) -> bool {
+        let commitment_len = self.nft.as_ref().map_or(0, |nft| nft.commitment.len());
+
+        (self.amount > 0 || self.nft.is_some()) &&
+        self.amount <= i64::MAX as u64 &&
+        commitment_len <= MAX_COMMITMENT_LENGTH
+    }
+
+    /// Parse token prefix from the head of `scriptPubKey` field
+    /// # Returns
+    /// * token data and remaining bytes (`scriptPubKey`)
+    pub(crate) fn parse(v: &[u8]) -> Option<(TokenData, &[u8])> {
+        if *v.first()? != PREFIX_TOKEN {
+            return None;
+        }
+
+        let category = u256::from_le_slice(v.get(1..33)?).ok()?;
+        let bitfield = *v.get(33)?;
+        let mut rest = v.get(34..)?;
+
+        let capability = match bitfield & CAPABILITY_MASK {
+            0x00 => Capability::None,
+            0x01 => Capability::Mutable,
+            0x02 => Capability::Minting,
+            _ => return None,
+        };
+        if bitfield & RESERVED_BIT != 0 {
+            return None;
+        }
+        if bitfield & HAS_NFT == 0 && (bitfield & HAS_COMMITMENT_LENGTH != 0 || capability != Capability::None) {
+            return None;
+        }
+
+        let mut commitment = vec![];
+        if bitfield & HAS_COMMITMENT_LENGTH != 0 {
+            let (len, r) = read_var_int(rest)?;
+            if len == 0 || r.len() < len as usize {
+                return None;
+            }
+            let (c, r) = r.split_at(len as usize);
+            commitment = c.to_vec();
+            rest = r;
+        }
+
+        let mut amount = 0;
+        if bitfield & HAS_AMOUNT != 0 {
+            let (a, r) = read_var_int(rest)?;
+            if a == 0 {
+                return None;
+            }
+            amount = a;
+            rest = r;
+        }
+
+        let nft = if bitfield & HAS_NFT != 0 {
+            Some(Nft { capability, commitment })
+        } else {
+            None
+        };
+
+        let token = TokenData { category, amount, nft };
+        if !token.is_valid() {
+            return None;
+        }
+
+        Some((token, rest))
+    }
+
+    /// Convert to `Vec<u8>`
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let category = u256::from_str("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb")?;
+        let set = [
+            (TokenData::new(category, 1, None)?, "10" , "01"),
+            (TokenData::new(category, 0, Some(Nft { capability: Capability::None, commitment: vec![] }))?, "20", ""),
+            (TokenData::new(category, 0xfd, Some(Nft { capability: Capability::Mutable, commitment: vec![0xcc] }))?, "71", "01ccfdfd00"),
+        ];
+
+        for (token, bitfield, rest) in set.iter() {
+            let v = token.to_vec();
+            assert_eq!(v[0], PREFIX_TOKEN);
+            assert_eq!(hex::encode(&v[33..34]), *bitfield);
+            assert_eq!(hex::encode(&v[34..]), *rest);
+
+            let script = [&v[..], &[0x51]].concat();
+            let (parsed, remain) = TokenData::parse(&script).unwrap();
+            assert_eq!(parsed, *token);
+            assert_eq!(remain, [0x51]);
+        }
+
+        // no token, commitment without NFT, zero amount and non-minimal amount
+        for bitfield in &["00", "40", "1000", "10fd0100"] {
+            let v = hex::decode(format!("ef{}{}", "bb".repeat(32), bitfield)).unwrap();
+            assert!(TokenData::parse(&v).is_none());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+use std::convert::TryFrom;
+use crate::types::{Txid, Amount};
+use super::{Transaction, TokenData, token, read_bytes, read_var_int};
+use super::super::error::{Error, Result};
+
+/// Borrowed view of transaction input
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputRef<'a> {
+    pub txid: Txid,
+    pub n: u32,
+    pub script: &'a [u8],
+    pub sequence_no: u32,
+}
+
+/// Borrowed view of transaction output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputRef<'a> {
+    /// satoshi
+    pub value: Amount,
+    /// `scriptPubKey` (without token prefix)
+    pub script: &'a [u8],
+    token_prefix: &'a [u8],
+}
+
+impl OutputRef<'_> {
+    /// Parse token data
+    /// # Returns
+    /// * token data (`None` if output has no token)
+    pub fn token(&self) -> Option<TokenData> {
+        TokenData::parse(self.token_prefix).map(|(token, _)| token)
+    }
+}
+
+/// Transaction view over serialized bytes
+///
+/// Only offsets of inputs and outputs are recorded on parse,
+/// and scripts are borrowed from the original bytes on access.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionRef<'a> {
+    raw: &'a [u8],
+    inputs: Vec<usize>,
+    outputs: Vec<usize>,
+}
+
+fn parse_input(v: &[u8]) -> Option<(InputRef<'_>, &[u8])> {
+    let (txid, v) = read_bytes::<[u8; 32]>(v)?;
+    let (n, v) = read_bytes(v)?;
+    let (script_len, v) = read_var_int(v)?;
+    if (v.len() as u64) < script_len {
+        return None;
+    }
+    let (script, v) = v.split_at(script_len as usize);
+    let (sequence_no, v) = read_bytes(v)?;
+
+    let input = InputRef {
+        txid: Txid::from(txid),
+        n: u32::from_le_bytes(n),
+        script,
+        sequence_no: u32::from_le_bytes(sequence_no),
+    };
+
+    Some((input, v))
+}
+
+fn parse_output(v: &[u8]) -> Option<(OutputRef<'_>, &[u8])> {
+    let (value, v) = read_bytes(v)?;
+    let (script_len, v) = read_var_int(v)?;
+    if (v.len() as u64) < script_len {
+        return None;
+    }
+    let (script, v) = v.split_at(script_len as usize);
+
+    let (token_prefix, script) = match script.first() {
+        Some(&token::PREFIX_TOKEN) => {
+            let (_, rest) = TokenData::parse(script)?;
+            script.split_at(script.len() - rest.len())
+        },
+        _ => (&[][..], script),
+    };
+    let output = OutputRef {
+        value: Amount::from_sat(u64::from_le_bytes(value)),
+        script,
+        token_prefix,
+    };
+
+    Some((output, v))
+}
+
+impl<'a> TransactionRef<'a> {
+    /// Parse transaction at the head of `v`
+    /// # Arguments
+    /// * `v` - serialized transaction (trailing bytes are ignored)
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::Amount;
+    /// # use cash_tx_builder::types::transaction::TransactionRef;
+    /// let raw = hex::decode("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000")?;
+    /// let tx = TransactionRef::parse(&raw)?;
+    /// assert_eq!(tx.input_count(), 1);
+    /// assert_eq!(tx.output(1).map(|o| o.value), Some(Amount::from_sat(10000)));
+    /// assert_eq!(tx.as_bytes().len(), raw.len());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn parse(v: &'a [u8]) -> Result<TransactionRef<'a>> {
+        let len = v.len();
+        let parse_error = |rest: &[u8]| Error::TxParseError(len - rest.len(), rest.to_vec());
+
+        let (_, rest) = read_bytes::<[u8; 4]>(v).ok_or_else(|| parse_error(v))?;
+
+        let (in_counter, mut rest) = read_var_int(rest).ok_or_else(|| parse_error(rest))?;
+        let mut inputs = vec![];
+        for _ in 0..in_counter {
+            inputs.push(len - rest.len());
+            rest = parse_input(rest).ok_or_else(|| parse_error(rest))?.1;
+        }
+
+        let (out_counter, mut rest) = read_var_int(rest).ok_or_else(|| parse_error(rest))?;
+        let mut outputs = vec![];
+        for _ in 0..out_counter {
+            outputs.push(len - rest.len());
+            rest = parse_output(rest).ok_or_else(|| parse_error(rest))?.1;
+        }
+
+        let (_, rest) = read_bytes::<[u8; 4]>(rest).ok_or_else(|| parse_error(rest))?;
+
+        Ok(TransactionRef {
+            raw: &v[..len - rest.len()],
+            inputs,
+            outputs,
+        })
+    }
+
+    /// Serialized transaction
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// Version no
+    pub fn version(&self) -> u32 {
+        let mut v = [0; 4];
+        v.copy_from_slice(&self.raw[..4]);
+        u32::from_le_bytes(v)
+    }
+
+    /// lock_time
+    pub fn lock_time(&self) -> u32 {
+        let mut v = [0; 4];
+        v.copy_from_slice(&self.raw[self.raw.len() - 4..]);
+        u32::from_le_bytes(v)
+    }
+
+    /// Number of inputs
+    pub fn input_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Number of outputs
+    pub fn output_count(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Get input
+    /// # Arguments
+    /// * `index` - input index
+    pub fn input(&self, index: usize) -> Option<InputRef<'a>> {
+        let offset = *self.inputs.get(index)?;
+        parse_input(&self.raw[offset..]).map(|(input, _)| input)
+    }
+
+    /// Get output
+    /// # Arguments
+    /// * `index` - output index
+    pub fn output(&self, index: usize) -> Option<OutputRef<'a>> {
+        let offset = *self.outputs.get(index)?;
+        parse_output(&self.raw[offset..]).map(|(output, _)| output)
+    }
+
+    /// Iterate inputs
+    pub fn inputs(&self) -> impl Iterator<Item = InputRef<'a>> + '_ {
+        (0..self.input_count()).filter_map(move |i| self.input(i))
+    }
+
+    /// Iterate outputs
+    pub fn outputs(&self) -> impl Iterator<Item = OutputRef<'a>> + '_ {
+        (0..self.output_count()).filter_map(move |i| self.output(i))
+    }
+
+    /// Get txid
+    pub fn txid(&self) -> Txid {
+        super::txid(self.raw)
+    }
+
+    /// Convert to owned `Transaction`
+    pub fn to_transaction(&self) -> Result<Transaction> {
+        Transaction::try_from(self.raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transaction_ref() -> Result<()> {
+        let hex = hex!("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000");
+        let mut tx = Transaction::try_from(&hex[..])?;
+        tx.lock_time = 600_000;
+        tx.outputs[1].token = Some(TokenData::new(tx.inputs[0].outpoint.txid.into(), 1000, None)?);
+        let raw = [Vec::from(&tx), vec![0xff; 3]].concat();
+
+        let tx_ref = TransactionRef::parse(&raw)?;
+        assert_eq!(tx_ref.as_bytes(), &raw[..raw.len() - 3]);
+        assert_eq!(tx_ref.version(), tx.version);
+        assert_eq!(tx_ref.lock_time(), tx.lock_time);
+        assert_eq!(tx_ref.txid(), tx.txid());
+        assert_eq!(tx_ref.to_transaction()?, tx);
+
+        for (input, expected) in tx_ref.inputs().zip(&tx.inputs) {
+            assert_eq!(input.txid, expected.outpoint.txid);
+            assert_eq!(input.n, expected.outpoint.n);
+            assert_eq!(input.script, &expected.script[..]);
+            assert_eq!(input.sequence_no, expected.sequence_no);
+        }
+        assert_eq!(tx_ref.outputs().count(), 2);
+        for (output, expected) in tx_ref.outputs().zip(&tx.outputs) {
+            assert_eq!(output.value, expected.value);
+            assert_eq!(output.script, &expected.script[..]);
+            assert_eq!(output.token(), expected.token);
+        }
+        assert!(tx_ref.input(1).is_none());
+
+        for len in 0..raw.len() - 3 {
+            assert!(TransactionRef::parse(&raw[..len]).is_err());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+//! Verbose transaction representation like bitcoind's `decoderawtransaction`
+//! (values are in satoshis)
+
+use crate::types::Txid;
+use crate::script::{self, multisig, ScriptType};
+use crate::hash;
+use super::{Transaction, Input, Output, TokenData};
+
+/// Script with disassembly
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VerboseScript {
+    pub asm: String,
+    pub hex: String,
+}
+
+/// `scriptPubKey` with disassembly and decoded addresses
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VerboseScriptPubKey {
+    pub asm: String,
+    pub hex: String,
+    #[serde(rename = "reqSigs", skip_serializing_if = "Option::is_none")]
+    pub req_sigs: Option<usize>,
+    #[serde(rename = "type")]
+    pub script_type: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<String>,
+}
+
+/// Verbose transaction input (`coinbase` is set instead of outpoint and `scriptSig` for coinbase input)
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VerboseInput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coinbase: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub txid: Option<Txid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vout: Option<u32>,
+    #[serde(rename = "scriptSig", skip_serializing_if = "Option::is_none")]
+    pub script_sig: Option<VerboseScript>,
+    pub sequence: u32,
+}
+
+/// Verbose transaction output
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VerboseOutput {
+    pub value: u64,
+    pub n: usize,
+    #[serde(rename = "scriptPubKey")]
+    pub script_pub_key: VerboseScriptPubKey,
+    #[serde(rename = "tokenData", skip_serializing_if = "Option::is_none")]
+    pub token_data: Option<TokenData>,
+}
+
+/// Verbose transaction
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VerboseTransaction {
+    pub txid: Txid,
+    pub hash: Txid,
+    pub version: u32,
+    pub size: usize,
+    pub locktime: u32,
+    pub vin: Vec<VerboseInput>,
+    pub vout: Vec<VerboseOutput>,
+}
+
+fn verbose_input(input: &Input) -> VerboseInput {
+    if input.outpoint.is_null() {
+        return VerboseInput {
+            coinbase: Some(hex::encode(&input.script)),
+            txid: None,
+            vout: None,
+            script_sig: None,
+            sequence: input.sequence_no,
+        };
+    }
+
+    VerboseInput {
+        coinbase: None,
+        txid: Some(input.outpoint.txid),
+        vout: Some(input.outpoint.n),
+        script_sig: Some(VerboseScript {
+            asm: script::to_asm(&input.script),
+            hex: hex::encode(&input.script),
+        }),
+        sequence: input.sequence_no,
+    }
+}
+
+fn verbose_script_pub_key<F>(script: &[u8], formatter: &F) -> VerboseScriptPubKey
+    where F: Fn(&[u8], bool) -> Option<String> {
+    let script_type = script::script_type(script);
+    let (req_sigs, addresses) = match script_type {
+        ScriptType::Multisig => match multisig::parse_redeem_script(script) {
+            Ok((m, pubkeys)) => (
+                Some(m),
+                pubkeys.iter().filter_map(|pubkey| formatter(&hash::hash160(pubkey), true)).collect(),
+            ),
+            Err(_) => (None, vec![]),
+        },
+        ScriptType::NullData | ScriptType::NonStandard => (None, vec![]),
+        _ => (Some(1), script::script_to_address(script, formatter).into_iter().collect()),
+    };
+
+    VerboseScriptPubKey {
+        asm: script::to_asm(script),
+        hex: hex::encode(script),
+        req_sigs,
+        script_type: script_type.name(),
+        addresses,
+    }
+}
+
+fn verbose_output<F>(n: usize, output: &Output, formatter: &F) -> VerboseOutput
+    where F: Fn(&[u8], bool) -> Option<String> {
+    VerboseOutput {
+        value: output.value.to_sat(),
+        n,
+        script_pub_key: verbose_script_pub_key(&output.script, formatter),
+        token_data: output.token.clone(),
+    }
+}
+
+pub(super) fn verbose_transaction<F>(tx: &Transaction, formatter: &F) -> VerboseTransaction
+    where F: Fn(&[u8], bool) -> Option<String> {
+    let txid = tx.txid();
+
+    VerboseTransaction {
+        txid,
+        hash: txid,
+        version: tx.version,
+        size: Vec::from(tx).len(),
+        locktime: tx.lock_time,
+        vin: tx.inputs.iter().map(verbose_input).collect(),
+        vout: tx.outputs.iter().enumerate().map(|(n, o)| verbose_output(n, o, formatter)).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use serde_json::{self, json};
+    use super::*;
+    use super::super::super::amount::Amount;
+
+    #[test]
+    fn verbose() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let formatter = |hash: &[u8], is_pkh: bool| {
+            Some(format!("{}:{}", if is_pkh { "pkh" } else { "sh" }, hex::encode(hash)))
+        };
+        let hex = hex!("0100000001339a4b15a25a107057a2aedba3655bfe9aca9dbfc8c4281adbff519764385569010000006a47304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d44121030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1deffffffff02d7f52d01000000001976a914214ffcd3e7668da243cc4006759f6fe5f3c60bfe88ac10270000000000001976a91492fc13573caf1bd38bd65738428406f4af80793a88ac00000000");
+        let mut tx = Transaction::try_from(&hex[..])?;
+        tx.outputs.push(Output::new(Amount::from_sat(0), &hex!("6a04686f6765")));
+
+        let value = serde_json::to_value(tx.to_json_verbose(&formatter))?;
+        assert_eq!(value["vin"][0]["scriptSig"]["asm"], "304402204bdde4960e3733c64b8debc7c2ce609699e418de91e055594a7fd53f07e618b90220066f02e1f9a3e26e76ff4220de3b2b17dab63684c1fb9ef567ed2056ba3a96d441 030a7decd850db8d31c819bd34a0f9934f9c51e1f78718f59c886a3c8389c0d1de");
+        assert_eq!(value["vout"][1], json!({
+            "value": 10000,
+            "n": 1,
+            "scriptPubKey": {
+                "asm": "OP_DUP OP_HASH160 92fc13573caf1bd38bd65738428406f4af80793a OP_EQUALVERIFY OP_CHECKSIG",
+                "hex": "76a91492fc13573caf1bd38bd65738428406f4af80793a88ac",
+                "reqSigs": 1,
+                "type": "pubkeyhash",
+                "addresses": ["pkh:92fc13573caf1bd38bd65738428406f4af80793a"],
+            },
+        }));
+        assert_eq!(value["vout"][2]["scriptPubKey"], json!({
+            "asm": "OP_RETURN 686f6765",
+            "hex": "6a04686f6765",
+            "type": "nulldata",
+        }));
+        assert_eq!(value["txid"], value["hash"]);
+
+        let mut coinbase = Input::new(&[0; 32], 0xffff_ffff, None);
+        coinbase.script = vec![0x03, 0x01, 0x02, 0x03];
+        tx.inputs = vec![coinbase];
+        let value = serde_json::to_value(tx.to_json_verbose(&formatter))?;
+        assert_eq!(value["vin"][0], json!({ "coinbase": "03010203", "sequence": 0xffff_ffffu32 }));
+
+        Ok(())
+    }
+}
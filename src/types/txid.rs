@@ -0,0 +1,153 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use hex;
+
+use super::uint256::uint256;
+use super::error::{Error, Result};
+
+#[cfg(feature = "serde")]
+use serde::{Serializer, Serialize, Deserializer, Deserialize, de};
+
+/// Transaction id
+///
+/// Bytes are stored in serialized (internal) order,
+/// and displayed/parsed in reversed hex like block explorers.
+/// Ordering follows the displayed order (BIP69).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Txid([u8; 32]);
+
+impl From<[u8; 32]> for Txid {
+    fn from(v: [u8; 32]) -> Txid {
+        Txid(v)
+    }
+}
+
+impl From<Txid> for [u8; 32] {
+    fn from(v: Txid) -> [u8; 32] {
+        v.0
+    }
+}
+
+impl From<uint256> for Txid {
+    fn from(v: uint256) -> Txid {
+        Txid(v.0)
+    }
+}
+
+impl From<Txid> for uint256 {
+    fn from(v: Txid) -> uint256 {
+        uint256(v.0)
+    }
+}
+
+impl AsRef<[u8]> for Txid {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Ord for Txid {
+    fn cmp(&self, other: &Txid) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for Txid {
+    fn partial_cmp(&self, other: &Txid) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Txid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().rev().try_for_each(|v| write!(f, "{:02x}", v))
+    }
+}
+
+impl FromStr for Txid {
+    type Err = Error;
+
+    /// Parse reversed hex
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::Txid;
+    /// let txid: Txid = "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c".parse()?;
+    /// assert_eq!(<[u8; 32]>::from(txid)[0], 0x4c);
+    /// assert_eq!(txid.to_string(), "427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c");
+    /// assert!("427cfc8a".parse::<Txid>().is_err());
+    /// # Ok::<(), cash_tx_builder::TypeError>(())
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let v = hex::decode(s)?;
+        if v.len() != 32 {
+            return Err(Error::InvalidTxid(s.to_string()));
+        }
+
+        let mut txid = [0; 32];
+        txid.iter_mut().zip(v.iter().rev()).for_each(|(d, s)| *d = *s);
+
+        Ok(Txid(txid))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Txid {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Txid {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        let s = String::deserialize(deserializer)?;
+
+        Txid::from_str(&s)
+        .map_err(|_| {
+            de::Error::invalid_value(
+                de::Unexpected::Str(&s),
+                &"32 bytes hex string",
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert() -> Result<()> {
+        let txid = Txid::from_str("ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda")?;
+        let v = uint256::from_str("ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda")?;
+        assert_eq!(txid, Txid::from(v));
+        assert_eq!(txid.to_string(), String::from(v));
+        assert_eq!(txid.as_ref()[31], 0xec);
+
+        let smaller = Txid::from_str("eb225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcdb")?;
+        assert!(smaller < txid);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let txid = Txid::from_str("ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda")?;
+
+        let serialized = serde_json::to_string(&txid)?;
+        assert_eq!(serialized, "\"ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda\"");
+        assert_eq!(serde_json::from_str::<Txid>(&serialized)?, txid);
+        assert!(serde_json::from_str::<Txid>("\"ec22\"").is_err());
+
+        Ok(())
+    }
+}
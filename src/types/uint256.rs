@@ -1,3 +1,6 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr};
 use std::str::FromStr;
 use hex;
 
@@ -7,28 +10,226 @@ use super::error::{Error, Result};
 use serde::{Serializer, Serialize, Deserializer, Deserialize, de};
 
 /// 256 bit unsigned value
+///
+/// Bytes are kept in little endian (serialized order),
+/// ordering and operators treat it as a number.
+/// # Example
+/// ```
+/// # use cash_tx_builder::u256;
+/// let target = u256::from_compact(0x1d00ffff).unwrap();
+/// assert_eq!(target, u256::from(0xffff) << 208);
+/// assert_eq!(target.to_compact(), 0x1d00ffff);
+/// assert!(target < u256::MAX >> 32);
+/// assert_eq!(!u256::ZERO, u256::MAX);
+/// ```
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct uint256(pub [u8; 32]);
 
-// TODO: use AsRef<[u8]>
-impl From<&[u8]> for uint256 {
-    fn from(v: &[u8]) -> uint256 {
-        let mut array = [0; 32];
+impl TryFrom<&[u8]> for uint256 {
+    type Error = Error;
 
-        let src = if v.len() > 32 {
-            &v[0..31]
-        } else {
-            v
-        };
-        let dest = if v.len() < 32 {
-            &mut array[0..v.len()]
-        } else {
-            &mut array
+    /// Same as `uint256::from_le_slice`
+    fn try_from(v: &[u8]) -> Result<uint256> {
+        uint256::from_le_slice(v)
+    }
+}
+
+impl uint256 {
+    /// Construct from 32 bytes in little endian (serialized order)
+    /// # Arguments
+    /// * `v` - 32 bytes
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::u256;
+    /// let v = u256::from_le_slice(&[0x01; 32])?;
+    /// assert_eq!(v.0, [0x01; 32]);
+    /// assert!(u256::from_le_slice(&[0x01; 31]).is_err());
+    /// assert!(u256::from_le_slice(&[0x01; 33]).is_err());
+    /// # Ok::<(), cash_tx_builder::TypeError>(())
+    /// ```
+    pub fn from_le_slice(v: &[u8]) -> Result<uint256> {
+        let array = <[u8; 32]>::try_from(v).map_err(|_| Error::InvalidUint256Length(v.len()))?;
+
+        Ok(uint256(array))
+    }
+
+    /// Construct from 32 bytes in big endian (displayed order)
+    /// # Arguments
+    /// * `v` - 32 bytes
+    /// # Example
+    /// ```
+    /// # use cash_tx_builder::u256;
+    /// let v = u256::from_be_slice(&[&[0x00; 31][..], &[0x01]].concat())?;
+    /// assert_eq!(v.0[0], 0x01);
+    /// assert_eq!(String::from(v), format!("{:0>64}", "01"));
+    /// # Ok::<(), cash_tx_builder::TypeError>(())
+    /// ```
+    pub fn from_be_slice(v: &[u8]) -> Result<uint256> {
+        let mut ret = uint256::from_le_slice(v)?;
+        ret.0.reverse();
+
+        Ok(ret)
+    }
+
+    /// zero
+    pub const ZERO: uint256 = uint256([0; 32]);
+    /// maximum value (`2^256 - 1`)
+    pub const MAX: uint256 = uint256([0xff; 32]);
+
+    /// Construct from bytes in big endian (displayed order)
+    pub fn from_be_bytes(mut v: [u8; 32]) -> uint256 {
+        v.reverse();
+        uint256(v)
+    }
+
+    /// Bytes in big endian (displayed order)
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        let mut v = self.0;
+        v.reverse();
+        v
+    }
+
+    /// `true` if zero
+    pub fn is_zero(self) -> bool {
+        self == uint256::ZERO
+    }
+
+    /// Number of significant bits (`0` for zero)
+    pub fn bits(self) -> u32 {
+        match self.0.iter().rposition(|&b| b != 0) {
+            Some(i) => i as u32 * 8 + (8 - self.0[i].leading_zeros()),
+            None => 0,
+        }
+    }
+
+    /// Lowest 64 bits
+    pub fn low_u64(self) -> u64 {
+        let mut v = [0; 8];
+        v.copy_from_slice(&self.0[..8]);
+        u64::from_le_bytes(v)
+    }
+
+    /// Byte at `i` (`0` if out of range)
+    fn byte(&self, i: i64) -> u8 {
+        usize::try_from(i).ok().and_then(|i| self.0.get(i)).copied().unwrap_or(0)
+    }
+
+    /// Decode compact representation (`nBits` of block header)
+    /// # Arguments
+    /// * `bits` - compact value
+    /// # Returns
+    /// * value (`None` if negative or overflows 256 bits)
+    pub fn from_compact(bits: u32) -> Option<uint256> {
+        let size = bits >> 24;
+        let word = bits & 0x007f_ffff;
+        if word != 0 && bits & 0x0080_0000 != 0 {
+            return None;
+        }
+        if word != 0 && (size > 34 || (word > 0xff && size > 33) || (word > 0xffff && size > 32)) {
+            return None;
+        }
+
+        Some(match size {
+            0..=3 => uint256::from(u64::from(word >> (8 * (3 - size)))),
+            _ => uint256::from(u64::from(word)) << (8 * (size - 3)),
+        })
+    }
+
+    /// Encode to compact representation (`nBits` of block header)
+    pub fn to_compact(self) -> u32 {
+        let mut size = self.bits().div_ceil(8);
+        let mut compact = match size {
+            0..=3 => (self.low_u64() << (8 * (3 - size))) as u32,
+            _ => (self >> (8 * (size - 3))).low_u64() as u32,
         };
-        dest.copy_from_slice(src);
+        // the sign bit must not be set
+        if compact & 0x0080_0000 != 0 {
+            compact >>= 8;
+            size += 1;
+        }
+
+        compact | size << 24
+    }
+}
 
-        uint256(array)
+impl From<u64> for uint256 {
+    fn from(n: u64) -> uint256 {
+        let mut v = [0; 32];
+        v[..8].copy_from_slice(&n.to_le_bytes());
+        uint256(v)
+    }
+}
+
+/// Numerical order
+impl Ord for uint256 {
+    fn cmp(&self, other: &uint256) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl PartialOrd for uint256 {
+    fn partial_cmp(&self, other: &uint256) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+macro_rules! bit_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait for uint256 {
+            type Output = uint256;
+
+            fn $method(self, rhs: uint256) -> uint256 {
+                let mut v = self.0;
+                v.iter_mut().zip(rhs.0.iter()).for_each(|(a, b)| *a $op *b);
+                uint256(v)
+            }
+        }
+    };
+}
+
+bit_op!(BitAnd, bitand, &=);
+bit_op!(BitOr, bitor, |=);
+bit_op!(BitXor, bitxor, ^=);
+
+impl Not for uint256 {
+    type Output = uint256;
+
+    fn not(self) -> uint256 {
+        uint256(self.0.map(|b| !b))
+    }
+}
+
+/// Shift to higher bits (bits shifted out are dropped)
+impl Shl<u32> for uint256 {
+    type Output = uint256;
+
+    fn shl(self, n: u32) -> uint256 {
+        let (bytes, bits) = (i64::from(n / 8), n % 8);
+        uint256(std::array::from_fn(|i| {
+            let src = i as i64 - bytes;
+            match bits {
+                0 => self.byte(src),
+                _ => self.byte(src) << bits | self.byte(src - 1) >> (8 - bits),
+            }
+        }))
+    }
+}
+
+/// Shift to lower bits
+impl Shr<u32> for uint256 {
+    type Output = uint256;
+
+    fn shr(self, n: u32) -> uint256 {
+        let (bytes, bits) = (i64::from(n / 8), n % 8);
+        uint256(std::array::from_fn(|i| {
+            let src = i as i64 + bytes;
+            match bits {
+                0 => self.byte(src),
+                _ => self.byte(src) >> bits | self.byte(src + 1) << (8 - bits),
+            }
+        }))
     }
 }
 
@@ -54,9 +255,7 @@ impl FromStr for uint256 {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let v = hex::decode(s)?.into_iter().rev().collect::<Vec<u8>>();
-
-        Ok(v[..].into())
+        uint256::from_be_slice(&hex::decode(s)?)
     }
 }
 
@@ -78,13 +277,13 @@ impl<'de> Deserialize<'de> for uint256 {
     {
         let s = String::deserialize(deserializer)?;
 
-        Ok(uint256::from_str(&s)
-        .or_else(|_| {
-            Err(de::Error::invalid_value(
+        uint256::from_str(&s)
+        .map_err(|_| {
+            de::Error::invalid_value(
                 de::Unexpected::Str(&s),
                 &"hex string",
-            ))
-        })?)
+            )
+        })
     }
 }
 
@@ -99,12 +298,70 @@ mod tests {
         let mut arr = [0; 32];
         arr.copy_from_slice(v_str.as_ref());
 
-        let v_arr = uint256::from(arr.as_ref());
+        let v_arr = uint256::try_from(arr.as_ref())?;
         assert_eq!(v_str, v_arr);
 
+        assert!(uint256::try_from(&arr[..31]).is_err());
+        assert!(uint256::try_from(&[&arr[..], &[0]].concat()[..]).is_err());
+        assert!(uint256::from_str("ec225c44").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn ordering_and_bits() -> Result<()> {
+        let one = uint256::from(1);
+        let mut be = [0; 32];
+        be[0] = 0x01;
+        let high = uint256::from_be_bytes(be);
+        assert!(one < high);
+        assert!(uint256::from(0x100) > uint256::from(0xff));
+        assert_eq!(high.to_be_bytes()[0], 0x01);
+        assert_eq!(one << 248, high);
+        assert_eq!(high >> 248, one);
+        assert_eq!(one << 256, uint256::ZERO);
+        assert_eq!(uint256::MAX >> 300, uint256::ZERO);
+        assert_eq!(uint256::from(0x81) << 7, uint256::from(0x4080));
+        assert_eq!(uint256::from(0x4080) >> 7, uint256::from(0x81));
+        assert_eq!((uint256::MAX >> 1).bits(), 255);
+        assert_eq!(uint256::ZERO.bits(), 0);
+        assert_eq!(uint256::from(0xf0f0) & uint256::from(0xff00), uint256::from(0xf000));
+        assert_eq!(uint256::from(0xf0f0) | uint256::from(0x0f00), uint256::from(0xfff0));
+        assert_eq!(uint256::from(0xf0f0) ^ uint256::from(0xffff), uint256::from(0x0f0f));
+        assert_eq!(uint256::from_str(&String::from(uint256::from(0x1234)))?.low_u64(), 0x1234);
+
         Ok(())
     }
 
+    #[test]
+    fn compact() {
+        // vectors of Bitcoin Core's arith_uint256_tests
+        for (bits, value, normalized) in &[
+            (0x0000_0000u32, 0u64, 0x0000_0000u32),
+            (0x0012_3456, 0, 0x0000_0000),
+            (0x0100_3456, 0, 0x0000_0000),
+            (0x0300_0000, 0, 0x0000_0000),
+            (0x0112_3456, 0x12, 0x0112_0000),
+            (0x0200_8000, 0x80, 0x0200_8000),
+            (0x0212_3456, 0x1234, 0x0212_3400),
+            (0x0312_3456, 0x12_3456, 0x0312_3456),
+            (0x0412_3456, 0x1234_5600, 0x0412_3456),
+            (0x0500_9234, 0x9234_0000, 0x0500_9234),
+        ] {
+            let v = uint256::from_compact(*bits).unwrap();
+            assert_eq!(v, uint256::from(*value), "{:#x}", bits);
+            assert_eq!(v.to_compact(), *normalized, "{:#x}", bits);
+        }
+
+        let v = uint256::from_compact(0x2012_3456).unwrap();
+        assert_eq!(v, uint256::from(0x12_3456) << 232);
+        assert_eq!(v.to_compact(), 0x2012_3456);
+
+        for bits in &[0x0192_3456, 0x0492_3456, 0x2180_0000 | 0x1234, 0xff12_3456u32, 0x2301_0000] {
+            assert_eq!(uint256::from_compact(*bits), None, "{:#x}", bits);
+        }
+    }
+
     #[cfg(feature = "serde")]
     use serde_json;
 
@@ -112,6 +369,7 @@ mod tests {
     mod error {
         use crate::TypeError;
 
+        #[allow(dead_code)]
         #[derive(Debug)]
         pub enum SerdeTestError {
             TypeError(TypeError),
@@ -5,6 +5,7 @@ use super::error::{Result, Error};
 /// Variable length integer
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Ord, PartialOrd, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct VarInt(u64);
 
 impl From<VarInt> for Vec<u8> {
@@ -28,7 +29,7 @@ impl From<VarInt> for Vec<u8> {
             n => {
                 let mut v = [0; 9];
                 v[0] = 0xffu8;
-                v[1..].copy_from_slice(&(n as u64).to_le_bytes());
+                v[1..].copy_from_slice(&n.to_le_bytes());
                 v.to_vec()
             }
         }
@@ -0,0 +1,228 @@
+//! Payment URI (BIP21 style), e.g. `bitcoincash:qxxx?amount=0.1&label=shop`
+//!
+//! Scheme is the cashaddr prefix, so the address part is kept together with it.
+
+use std::fmt;
+use std::str::FromStr;
+use super::script::ToAddressScript;
+use super::network::Network;
+use super::types::Amount;
+use super::error::{Error, Result};
+
+/// Parsed payment request
+/// # Example
+/// ```
+/// # use cash_tx_builder::{TxBuilder, Amount};
+/// # use cash_tx_builder::uri::PaymentUri;
+/// let uri: PaymentUri = "bitcoincash:qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3?amount=0.1&label=Coffee%20shop".parse()?;
+/// assert_eq!(uri.address, "bitcoincash:qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3");
+/// assert_eq!(uri.amount, Some(Amount::from_sat(10_000_000)));
+/// assert_eq!(uri.label.as_deref(), Some("Coffee shop"));
+///
+/// let parser = |address: &str| match address {
+///     "bitcoincash:qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3" => Some((vec![0; 20], true)),
+///     _ => None,
+/// };
+/// let mut txb = TxBuilder::new(&parser);
+/// txb.add_address_output(uri.amount.unwrap_or_default(), &uri)?;
+///
+/// let mut uri = PaymentUri::new("bitcoincash:qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3");
+/// uri.amount = Some(Amount::from_sat(150_000));
+/// uri.message = Some("order #1".to_string());
+/// assert_eq!(uri.to_string(), "bitcoincash:qqntvyp35r7l8julzldgh8qlc49x8rpkjyh4nz5ty3?amount=0.0015&message=order%20%231");
+/// # Ok::<(), cash_tx_builder::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PaymentUri {
+    /// address with cashaddr prefix, e.g. `bitcoincash:qxxx`
+    pub address: String,
+    /// requested amount
+    pub amount: Option<Amount>,
+    /// label for the recipient
+    pub label: Option<String>,
+    /// message describing the payment
+    pub message: Option<String>,
+    /// other (decoded) parameters in URI order
+    pub params: Vec<(String, String)>,
+}
+
+impl PaymentUri {
+    /// Payment request to `address` without parameters
+    /// # Arguments
+    /// * `address` - address with cashaddr prefix
+    pub fn new(address: &str) -> PaymentUri {
+        PaymentUri {
+            address: address.to_string(),
+            ..PaymentUri::default()
+        }
+    }
+
+    /// Value of other parameter
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+impl FromStr for PaymentUri {
+    type Err = Error;
+
+    /// Parse URI
+    ///
+    /// Scheme must be a known cashaddr prefix.
+    /// Unknown `req-` parameters and duplicated known parameters are rejected.
+    fn from_str(s: &str) -> Result<PaymentUri> {
+        let invalid = || Error::InvalidUri(s.to_string());
+        let (scheme, rest) = s.split_once(':').ok_or_else(invalid)?;
+        let scheme = Network::ALL.iter()
+            .map(|n| n.cashaddr_prefix())
+            .find(|prefix| prefix.eq_ignore_ascii_case(scheme))
+            .ok_or_else(invalid)?;
+        let (path, query) = match rest.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (rest, None),
+        };
+        if path.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut uri = PaymentUri::new(&format!("{}:{}", scheme, path));
+        for pair in query.into_iter().flat_map(|q| q.split('&')).filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let key = percent_decode(key).ok_or_else(invalid)?;
+            let value = percent_decode(value).ok_or_else(invalid)?;
+            let slot = match key.as_str() {
+                "amount" => {
+                    if uri.amount.replace(Amount::from_bch(&value)?).is_some() {
+                        return Err(invalid());
+                    }
+                    continue;
+                },
+                "label" => &mut uri.label,
+                "message" => &mut uri.message,
+                _ if key.starts_with("req-") => return Err(invalid()),
+                _ => {
+                    uri.params.push((key, value));
+                    continue;
+                },
+            };
+            if slot.replace(value).is_some() {
+                return Err(invalid());
+            }
+        }
+
+        Ok(uri)
+    }
+}
+
+/// Amount is written without trailing zeros, other values are percent-encoded
+impl fmt::Display for PaymentUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.address)?;
+
+        let amount = self.amount.map(|amount| {
+            let bch = amount.to_bch();
+            bch.trim_end_matches('0').trim_end_matches('.').to_string()
+        });
+        let params = amount.iter().map(|v| ("amount", v.as_str()))
+            .chain(self.label.iter().map(|v| ("label", v.as_str())))
+            .chain(self.message.iter().map(|v| ("message", v.as_str())))
+            .chain(self.params.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        for (i, (key, value)) in params.enumerate() {
+            let separator = if i == 0 { '?' } else { '&' };
+            write!(f, "{}{}={}", separator, percent_encode(key), percent_encode(value))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ToAddressScript for PaymentUri {
+    fn to_address_script<F>(&self, parser: &F) -> Result<Vec<u8>>
+        where F: Fn(&str) -> Option<(Vec<u8>, bool)> {
+        self.address.to_address_script(parser)
+    }
+
+    fn is_for_network(&self, network: Network) -> bool {
+        self.address.is_for_network(network)
+    }
+}
+
+/// Decode `%XX` escapes, `None` if malformed or not UTF-8
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Escape all but unreserved characters of RFC 3986
+fn percent_encode(s: &str) -> String {
+    s.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "bitcoincash:qph5kuz78czq00e3t85ugpgd7xmer5kr7c5f6jdpwk";
+
+    #[test]
+    fn parse() -> Result<()> {
+        let uri: PaymentUri = "BCHTEST:qph5kuz78czq00e3t85ugpgd7xmer5kr7c5f6jdpwk".parse()?;
+        assert_eq!(uri, PaymentUri::new("bchtest:qph5kuz78czq00e3t85ugpgd7xmer5kr7c5f6jdpwk"));
+
+        let uri: PaymentUri = format!("{}?label=%E3%81%82&foo=bar&message=a+b", ADDRESS).parse()?;
+        assert_eq!(uri.label.as_deref(), Some("あ"));
+        assert_eq!(uri.message.as_deref(), Some("a+b"));
+        assert_eq!(uri.param("foo"), Some("bar"));
+        assert_eq!(uri.amount, None);
+
+        for invalid in &[
+            "bitcoin:qph5kuz78czq00e3t85ugpgd7xmer5kr7c5f6jdpwk",
+            "bitcoincash:",
+            "bitcoincash:?amount=1",
+            "bitcoincash:qph5kuz78czq00e3t85ugpgd7xmer5kr7c5f6jdpwk?req-foo=1",
+            "bitcoincash:qph5kuz78czq00e3t85ugpgd7xmer5kr7c5f6jdpwk?amount=1&amount=2",
+            "bitcoincash:qph5kuz78czq00e3t85ugpgd7xmer5kr7c5f6jdpwk?label=%zz",
+        ] {
+            match invalid.parse::<PaymentUri>() {
+                Err(Error::InvalidUri(_)) => {},
+                r => panic!("unexpected result: {:?}", r),
+            }
+        }
+        match format!("{}?amount=0.000000001", ADDRESS).parse::<PaymentUri>() {
+            Err(Error::InvalidAmount(_)) => {},
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let uri = PaymentUri {
+            address: ADDRESS.to_string(),
+            amount: Some(Amount::from_sat(100_000_000)),
+            label: Some("a&b=c".to_string()),
+            message: None,
+            params: vec![("r".to_string(), "https://example.com/i/1".to_string())],
+        };
+        let s = uri.to_string();
+        assert_eq!(s, format!("{}?amount=1&label=a%26b%3Dc&r=https%3A%2F%2Fexample.com%2Fi%2F1", ADDRESS));
+        assert_eq!(s.parse::<PaymentUri>()?, uri);
+        assert_eq!(PaymentUri::new(ADDRESS).to_string(), ADDRESS);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+//! sanity check of transaction
+
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+use super::script::{is_push_only, p2sh};
+use super::types::Amount;
+use super::types::transaction::Transaction;
+use super::types::transaction::output::Output;
+
+/// Maximum amount of satoshi
+pub const MAX_MONEY: u64 = Amount::MAX_MONEY.to_sat();
+
+/// Maximum size of transaction
+pub const MAX_TX_SIZE: usize = 1_000_000;
+
+/// Minimum size of coinbase `scriptSig`
+pub const MIN_COINBASE_SCRIPT_SIG_SIZE: usize = 2;
+
+/// Maximum size of coinbase `scriptSig`
+pub const MAX_COINBASE_SCRIPT_SIG_SIZE: usize = 100;
+
+/// Violation of consensus rule
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum Violation {
+    /// Transaction has no input.
+    #[error("No inputs")]
+    NoInputs,
+
+    /// Transaction has no output.
+    #[error("No outputs")]
+    NoOutputs,
+
+    /// Outpoint is spent more than once.
+    /// # Arguments
+    /// * input index
+    #[error("Duplicate outpoint: {0}")]
+    DuplicateOutPoint(usize),
+
+    /// Non-coinbase input spends null outpoint.
+    /// # Arguments
+    /// * input index
+    #[error("Null outpoint: {0}")]
+    NullOutPoint(usize),
+
+    /// Coinbase `scriptSig` size is out of range.
+    /// # Arguments
+    /// * size in bytes
+    #[error("Bad coinbase scriptSig length: {0}")]
+    BadCoinbaseLength(usize),
+
+    /// Output value exceeds `MAX_MONEY`.
+    /// # Arguments
+    /// * output index
+    #[error("Output value out of range: {0}")]
+    OutputValueOutOfRange(usize),
+
+    /// Sum of output values exceeds `MAX_MONEY`.
+    /// # Arguments
+    /// * sum of output values
+    #[error("Total output value out of range: {0}")]
+    TotalOutputValueOutOfRange(u64),
+
+    /// Sum of input values is less than sum of output values.
+    /// # Arguments
+    /// * sum of input values
+    /// * sum of output values
+    #[error("Input value {0} is less than output value {1}")]
+    InsufficientInputValue(u64, u64),
+
+    /// Serialized transaction exceeds `MAX_TX_SIZE`.
+    /// # Arguments
+    /// * size in bytes
+    #[error("Oversized transaction: {0}")]
+    OversizedTransaction(usize),
+
+    /// `scriptSig` spending P2SH contains non-push operation.
+    /// # Arguments
+    /// * input index
+    #[error("Non push-only scriptSig: {0}")]
+    NonPushOnlyScriptSig(usize),
+}
+
+/// Check consensus-level sanity of transaction
+///
+/// Checks which need previous outputs are skipped for inputs whose previous output is unknown.
+/// # Arguments
+/// * `tx` - transaction
+/// * `prev_outputs` - previous outputs keyed by input index
+/// # Returns
+/// * list of violations (empty if none)
+/// # Example
+/// ```
+/// # use std::collections::HashMap;
+/// # use cash_tx_builder::types::transaction::Transaction;
+/// # use cash_tx_builder::verify::{verify, Violation};
+/// let tx = Transaction::default();
+/// assert_eq!(verify(&tx, &HashMap::new()), vec![Violation::NoInputs, Violation::NoOutputs]);
+/// ```
+pub fn verify(tx: &Transaction, prev_outputs: &HashMap<usize, Output>) -> Vec<Violation> {
+    let mut violations = vec![];
+
+    if tx.inputs.is_empty() {
+        violations.push(Violation::NoInputs);
+    }
+    if tx.outputs.is_empty() {
+        violations.push(Violation::NoOutputs);
+    }
+
+    let mut outpoints = HashSet::new();
+    for (i, input) in tx.inputs.iter().enumerate() {
+        if !outpoints.insert((input.outpoint.txid, input.outpoint.n)) {
+            violations.push(Violation::DuplicateOutPoint(i));
+        }
+    }
+
+    if tx.is_coinbase() {
+        let len = tx.inputs[0].script.len();
+        if !(MIN_COINBASE_SCRIPT_SIG_SIZE..=MAX_COINBASE_SCRIPT_SIG_SIZE).contains(&len) {
+            violations.push(Violation::BadCoinbaseLength(len));
+        }
+    } else {
+        for (i, input) in tx.inputs.iter().enumerate() {
+            if input.outpoint.is_null() {
+                violations.push(Violation::NullOutPoint(i));
+            }
+        }
+    }
+
+    let mut output_value = 0u64;
+    for (i, output) in tx.outputs.iter().enumerate() {
+        if output.value.to_sat() > MAX_MONEY {
+            violations.push(Violation::OutputValueOutOfRange(i));
+        }
+        output_value = output_value.saturating_add(output.value.to_sat());
+    }
+    if output_value > MAX_MONEY {
+        violations.push(Violation::TotalOutputValueOutOfRange(output_value));
+    }
+
+    let prev_values = (0..tx.inputs.len())
+        .map(|i| prev_outputs.get(&i).map(|o| o.value.to_sat()))
+        .collect::<Option<Vec<_>>>();
+    if let Some(prev_values) = prev_values {
+        let input_value = prev_values.into_iter().fold(0u64, u64::saturating_add);
+        if !tx.inputs.is_empty() && input_value < output_value {
+            violations.push(Violation::InsufficientInputValue(input_value, output_value));
+        }
+    }
+
+    let size = Vec::from(tx).len();
+    if size > MAX_TX_SIZE {
+        violations.push(Violation::OversizedTransaction(size));
+    }
+
+    for (i, input) in tx.inputs.iter().enumerate() {
+        let spends_p2sh = matches!(prev_outputs.get(&i), Some(o) if p2sh::is_p2sh(&o.script));
+        if spends_p2sh && !is_push_only(&input.script) {
+            violations.push(Violation::NonPushOnlyScriptSig(i));
+        }
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::types::transaction::input::Input;
+
+    #[test]
+    fn violations() {
+        let p2sh = hex!("a914023a723c9e8b8297d84f6ab7dc08784c36b0729a87");
+        let mut tx = Transaction::default();
+        tx.inputs.push(Input::new(&[0x01; 32], 0, None));
+        tx.inputs.push(Input::new(&[0x01; 32], 0, None));
+        tx.inputs[1].script = vec![0x00, 0x76];
+        tx.outputs.push(Output::new(Amount::from_sat(MAX_MONEY + 1), &[0x51]));
+        tx.outputs.push(Output::new(Amount::from_sat(1000), &[0x51]));
+
+        let mut prev_outputs = HashMap::new();
+        prev_outputs.insert(0, Output::new(Amount::from_sat(1000), &[0x51]));
+        assert_eq!(verify(&tx, &prev_outputs), vec![
+            Violation::DuplicateOutPoint(1),
+            Violation::OutputValueOutOfRange(0),
+            Violation::TotalOutputValueOutOfRange(MAX_MONEY + 1001),
+        ]);
+
+        prev_outputs.insert(1, Output::new(Amount::from_sat(1000), &p2sh));
+        assert_eq!(verify(&tx, &prev_outputs)[3..], [
+            Violation::InsufficientInputValue(2000, MAX_MONEY + 1001),
+            Violation::NonPushOnlyScriptSig(1),
+        ]);
+
+        tx.inputs[1] = Input::new(&[0x02; 32], 0, None);
+        tx.outputs = vec![Output::new(Amount::from_sat(1500), &[0x6a; MAX_TX_SIZE])];
+        let size = Vec::from(&tx).len();
+        assert_eq!(verify(&tx, &prev_outputs), vec![Violation::OversizedTransaction(size)]);
+
+        let mut coinbase = Input::new(&[0; 32], 0xffff_ffff, None);
+        tx.inputs.push(coinbase.clone());
+        tx.outputs = vec![Output::new(Amount::from_sat(1000), &[0x51])];
+        assert_eq!(verify(&tx, &prev_outputs), vec![Violation::NullOutPoint(2)]);
+
+        coinbase.script = vec![0x51];
+        tx.inputs = vec![coinbase];
+        assert_eq!(verify(&tx, &HashMap::new()), vec![Violation::BadCoinbaseLength(1)]);
+        tx.inputs[0].script.push(0x51);
+        assert_eq!(verify(&tx, &HashMap::new()), vec![]);
+    }
+}
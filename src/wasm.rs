@@ -0,0 +1,199 @@
+//! JavaScript bindings by `wasm_bindgen`
+//!
+//! Binary data is passed as `Uint8Array`, txids and transactions as hex strings,
+//! and satoshi values as `BigInt`.
+//! Addresses are not parsed, use `scriptPubKey` instead.
+
+use std::fmt::Display;
+use wasm_bindgen::prelude::*;
+use super::tx_builder;
+use super::tx_builder::sig_hash::SigHashType;
+use super::script::{self, p2pkh, p2sh};
+use super::hash;
+use super::types::Amount;
+
+type AddressParser = fn(&str) -> Option<(Vec<u8>, bool)>;
+
+fn js_error<E: Display>(err: E) -> JsValue {
+    JsValue::from_str(&err.to_string())
+}
+
+fn no_address(_: &str) -> Option<(Vec<u8>, bool)> {
+    None
+}
+
+/// Transaction builder for JavaScript
+#[wasm_bindgen(js_name = TxBuilder)]
+#[derive(Debug)]
+pub struct TxBuilder {
+    inner: tx_builder::TxBuilder<AddressParser>,
+}
+
+#[wasm_bindgen(js_class = TxBuilder)]
+impl TxBuilder {
+    /// Construct new transaction builder
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> TxBuilder {
+        TxBuilder {
+            inner: tx_builder::TxBuilder::new(no_address as AddressParser),
+        }
+    }
+
+    /// Construct transaction builder from hex string
+    #[wasm_bindgen(js_name = fromHex)]
+    pub fn from_hex(hex: &str) -> Result<TxBuilder, JsValue> {
+        Ok(TxBuilder {
+            inner: tx_builder::TxBuilder::from_hex(hex, no_address as AddressParser).map_err(js_error)?,
+        })
+    }
+
+    /// Set fork id
+    #[wasm_bindgen(js_name = setForkId)]
+    pub fn set_fork_id(&mut self, id: u32) {
+        self.inner.set_fork_id(id);
+    }
+
+    /// Add input
+    #[wasm_bindgen(js_name = addInput)]
+    pub fn add_input(&mut self, txid: &str, index: u32, value: Option<u64>, script: Option<Vec<u8>>, sequence_no: Option<u32>) -> Result<(), JsValue> {
+        let txid = txid.parse().map_err(js_error)?;
+        self.inner.add_input(txid, index, value, script.as_deref(), sequence_no).map_err(js_error)
+    }
+
+    /// Set `scriptSig` of input
+    #[wasm_bindgen(js_name = setScriptSig)]
+    pub fn set_script_sig(&mut self, index: usize, script: &[u8]) -> Result<(), JsValue> {
+        self.inner.set_script_sig(index, script).map_err(js_error)
+    }
+
+    /// Set `redeem script` of P2SH input
+    #[wasm_bindgen(js_name = setRedeemScript)]
+    pub fn set_redeem_script(&mut self, index: usize, script: &[u8]) -> Result<(), JsValue> {
+        self.inner.set_redeem_script(index, script).map_err(js_error)
+    }
+
+    /// Add output
+    #[wasm_bindgen(js_name = addOutput)]
+    pub fn add_output(&mut self, value: u64, script: &[u8]) {
+        self.inner.add_output(Amount::from_sat(value), script);
+    }
+
+    /// Add null data (`OP_RETURN`) output
+    #[wasm_bindgen(js_name = addNullDataOutput)]
+    pub fn add_null_data_output(&mut self, data: &[u8]) -> Result<(), JsValue> {
+        self.inner.add_null_data_output(data).map_err(js_error)
+    }
+
+    /// Get BIP143 sighash (`prevValue` and `scriptCode` default to previous output)
+    #[wasm_bindgen(js_name = sighash)]
+    pub fn witness_v0_hash(&self, hash_type: u32, index: u32, prev_value: Option<u64>, script_code: Option<Vec<u8>>) -> Result<Vec<u8>, JsValue> {
+        let hash_type = SigHashType::from_u32(hash_type).map_err(js_error)?;
+        self.inner.witness_v0_hash(hash_type, index, prev_value, script_code.as_deref())
+            .map(|hash| hash.to_vec())
+            .map_err(js_error)
+    }
+
+    /// Get fee
+    pub fn fee(&self) -> Result<u64, JsValue> {
+        self.inner.fee().map(u64::from).map_err(js_error)
+    }
+
+    /// Get estimated size after signing
+    #[wasm_bindgen(js_name = estimatedSize)]
+    pub fn estimated_size(&self) -> Result<usize, JsValue> {
+        self.inner.estimated_size_with_signatures().map_err(js_error)
+    }
+
+    /// Get txid
+    pub fn txid(&self) -> String {
+        self.inner.txid().to_string()
+    }
+
+    /// Get serialized transaction
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.inner.to_vec()
+    }
+
+    /// Get serialized transaction in hex
+    #[wasm_bindgen(js_name = toHex)]
+    pub fn to_hex(&self) -> String {
+        self.inner.to_hex()
+    }
+}
+
+/// P2PKH `scriptPubKey` from public key hash
+#[wasm_bindgen(js_name = p2pkhScriptPubKey)]
+pub fn p2pkh_script_pub_key(hash: &[u8]) -> Result<Vec<u8>, JsValue> {
+    p2pkh::script_pub_key(hash).map_err(js_error)
+}
+
+/// P2PKH `scriptSig` from public key and signature
+#[wasm_bindgen(js_name = p2pkhScriptSig)]
+pub fn p2pkh_script_sig(pubkey: &[u8], sig: &[u8]) -> Result<Vec<u8>, JsValue> {
+    p2pkh::script_sig(pubkey, sig).map_err(js_error)
+}
+
+/// P2SH `scriptPubKey` from script hash
+#[wasm_bindgen(js_name = p2shScriptPubKey)]
+pub fn p2sh_script_pub_key(hash: &[u8]) -> Result<Vec<u8>, JsValue> {
+    p2sh::script_pub_key(hash).map_err(js_error)
+}
+
+/// Null data `scriptPubKey`
+#[wasm_bindgen(js_name = nullDataScript)]
+pub fn null_data_script(data: &[u8]) -> Result<Vec<u8>, JsValue> {
+    script::null_data_script(data).map_err(js_error)
+}
+
+/// Convert script to ASM
+#[wasm_bindgen(js_name = scriptToAsm)]
+pub fn script_to_asm(script: &[u8]) -> String {
+    script::to_asm(script)
+}
+
+/// Convert ASM to script
+#[wasm_bindgen(js_name = scriptFromAsm)]
+pub fn script_from_asm(asm: &str) -> Result<Vec<u8>, JsValue> {
+    script::from_asm(asm).map_err(js_error)
+}
+
+/// RIPEMD-160 of SHA-256
+#[wasm_bindgen]
+pub fn hash160(v: &[u8]) -> Vec<u8> {
+    hash::hash160(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // error paths need JavaScript runtime
+    #[test]
+    fn builder() -> Result<(), JsValue> {
+        let hash = hex!("3424f163208a3b676fa0ec17034f0f290322a2a6");
+        let prev_script = p2pkh_script_pub_key(&hash)?;
+        let mut txb = TxBuilder::new();
+        txb.add_input("427cfc8a960e6a33552c19bcfcbe9d59207248856fb8806ba9c7043421e1ee4c", 1, Some(100_000), Some(prev_script.clone()), None)?;
+        txb.add_output(11000, &p2pkh_script_pub_key(&hex!("26b61031a0fdf3cb9f17da8b9c1fc54a638c3691"))?);
+        txb.add_output(88757, &p2pkh_script_pub_key(&hex!("2647f73de90d8e9db5b5f77d1cc1ad5afe81d68c"))?);
+        assert_eq!(txb.fee()?, 243);
+
+        let hash_type = (tx_builder::sig_hash::ALL | tx_builder::sig_hash::FORKID).to_u32();
+        assert_eq!(txb.witness_v0_hash(hash_type, 0, None, None)?, hex!("2b492e7c4c8a3d670fd7fe324a87e3c55df1802c9a100f4006f8fff7c0913dd4"));
+
+        let script_sig = p2pkh_script_sig(
+            &hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036"),
+            &hex!("304402202dacf747f6ddc911b755938a07232cfa34057f7a336f72346c438c04f4d5dbc502206a7915ce8569ab5832dae89275bdc13f2467a69684643704f1a9a38b34d55b3041"),
+        )?;
+        txb.set_script_sig(0, &script_sig)?;
+        assert_eq!(txb.txid(), "ec225c44df97f7573583c17f5b3fa55cc7bf4cc6b916ee88fd7cd3284e0dfcda");
+        assert_eq!(TxBuilder::from_hex(&txb.to_hex())?.to_bytes(), txb.to_bytes());
+
+        assert_eq!(script_from_asm(&script_to_asm(&prev_script))?, prev_script);
+        assert_eq!(hash160(&hex!("0366be8427eddf9341141e5bb10486e41b1f3b33101ab3d5e816c37f30f2ddb036")), hash);
+
+        Ok(())
+    }
+}